@@ -178,10 +178,13 @@ fn demo_04_create_tables(conn_url: &str, results: &mut TestResults) {
         }
     };
 
-    // Drop existing tables (ignore errors)
-    let _ = conn.execute_batch("DROP TABLE users");
-    let _ = conn.execute_batch("DROP TABLE all_types");
-    let _ = conn.execute_batch("DROP TABLE logs");
+    // Drop existing tables
+    for table in ["users", "all_types", "logs"] {
+        if let Err(e) = conn.drop_table_if_exists(table) {
+            results.fail(&format!("Drop {}", table), &format!("{:?}", e));
+            return;
+        }
+    }
 
     // Create users table
     match conn.execute_batch(
@@ -567,6 +570,7 @@ fn demo_09_connection_pooling(conn_url: &str, results: &mut TestResults) {
         connection_lifetime: 3600,
         validate: true,
         acquire_timeout: 10, // seconds
+        ..PoolOptions::default()
     };
 
     match ConnectionPool::new(conn_url, options) {
@@ -0,0 +1,13 @@
+#![no_main]
+
+use firebirust::xdr::{self, Decoder};
+use libfuzzer_sys::fuzz_target;
+
+// cargo +nightly fuzz run decode_response
+fuzz_target!(|data: &[u8]| {
+    let mut d = Decoder::new(data);
+    if let Ok(response) = xdr::decode_response(&mut d) {
+        let _ = response.error();
+    }
+    let _ = xdr::decode_fetch_header(&mut Decoder::new(data));
+});
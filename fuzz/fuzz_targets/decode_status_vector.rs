@@ -0,0 +1,11 @@
+#![no_main]
+
+use firebirust::xdr::{self, Decoder};
+use libfuzzer_sys::fuzz_target;
+
+// cargo +nightly fuzz run decode_status_vector
+fuzz_target!(|data: &[u8]| {
+    if let Ok(args) = xdr::decode_status_vector(&mut Decoder::new(data)) {
+        let _ = xdr::status_error(&args);
+    }
+});
@@ -45,19 +45,19 @@ print("""// generated by misc/make_tz_map.py
 use maplit::hashmap;
 
 #[allow(dead_code)]
-pub fn timezone_id_by_name(s: &str) -> u16 {
+pub fn timezone_id_by_name(s: &str) -> Option<u16> {
     let map = hashmap! {""")
 for k, v in timezone_id_by_name.items():
     print('        "{}" => {},'.format(k, v))
 print("""    };
-    map[s]
+    map.get(s).copied()
 }""")
 
 print("""
-pub fn timezone_name_by_id(id: u16) -> &'static str {
+pub fn timezone_name_by_id(id: u16) -> Option<&'static str> {
     let map = hashmap! {""")
 for k, v in timezone_name_by_id.items():
     print('        {} => "{}",'.format(k, v))
 print("""    };
-    map[&id]
+    map.get(&id).copied()
 }""")
@@ -31,6 +31,7 @@ use std::sync::Arc;
 use std::thread::{self, JoinHandle};
 
 use super::error::Error;
+use super::utils;
 use super::Connection;
 
 /// Maximum number of events per alerter (Firebird limit)
@@ -175,7 +176,7 @@ impl Drop for EventAlerter {
 /// - For each event:
 ///   - name_length (1 byte)
 ///   - name (name_length bytes)
-///   - count (4 bytes, little-endian, initially 0)
+///   - count (4 bytes, VAX byte order, initially 0)
 fn build_event_buffer(events: &[String]) -> Vec<u8> {
     let mut buf = Vec::new();
     buf.push(EPB_VERSION1);
@@ -184,7 +185,7 @@ fn build_event_buffer(events: &[String]) -> Vec<u8> {
         let name = event.trim();
         buf.push(name.len() as u8);
         buf.extend_from_slice(name.as_bytes());
-        // Initial count = 0 (4 bytes little-endian)
+        // Initial count = 0 (4 bytes VAX byte order)
         buf.extend_from_slice(&[0u8; 4]);
     }
 
@@ -213,19 +214,9 @@ fn parse_event_counts(events: &[String], event_buffer: &[u8], result_buffer: &[u
             break;
         }
 
-        let old_count = u32::from_le_bytes([
-            event_buffer[pos],
-            event_buffer[pos + 1],
-            event_buffer[pos + 2],
-            event_buffer[pos + 3],
-        ]);
-
-        let new_count = u32::from_le_bytes([
-            result_buffer[pos],
-            result_buffer[pos + 1],
-            result_buffer[pos + 2],
-            result_buffer[pos + 3],
-        ]);
+        // Counts are in VAX (little endian) order whatever the host
+        let old_count = utils::bytes_to_uint32(&event_buffer[pos..]);
+        let new_count = utils::bytes_to_uint32(&result_buffer[pos..]);
 
         if new_count > old_count {
             fired.push((event.clone(), new_count - old_count));
@@ -329,6 +320,17 @@ mod tests {
         assert_eq!(fired[0].1, 3); // 8 - 5 = 3 new events
     }
 
+    #[test]
+    fn test_parse_event_counts_vax_order() {
+        let events = vec!["e".to_string()];
+        let old_buf = vec![EPB_VERSION1, 1, b'e', 0xff, 0x00, 0x00, 0x00];
+        let new_buf = vec![EPB_VERSION1, 1, b'e', 0x01, 0x01, 0x00, 0x00];
+
+        // 0x00ff -> 0x0101, whatever the host byte order
+        let fired = parse_event_counts(&events, &old_buf, &new_buf);
+        assert_eq!(fired, vec![("e".to_string(), 2)]);
+    }
+
     #[test]
     fn test_register_too_many_events() {
         let mut alerter = EventAlerter::new("firebird://test");
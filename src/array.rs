@@ -0,0 +1,639 @@
+// MIT License
+//
+// Copyright (c) 2021-2024 Hajime Nakagami<nakagami@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! ARRAY columns
+//!
+//! Like a blob, an ARRAY value is stored apart from its row: the row holds
+//! an array id, and the elements are read (op_get_slice) and written
+//! (op_put_slice) as a slice described by an SDL string, built from the
+//! element type and bounds of the column in the system tables. `Statement`
+//! fetches the slice of each ARRAY column with the rows and stores an
+//! `Array` parameter before executing, so an `Array` is used like any other
+//! value. Elements are kept in row-major order (the last index varies
+//! fastest), as Firebird stores them.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::Array;
+//!
+//! conn.execute_batch("CREATE TABLE matrix (id INTEGER, m INTEGER[2, 3])")?;
+//! let m = Array::new(&[(1, 2), (1, 3)], vec![1, 2, 3, 4, 5, 6])?;
+//! conn.execute("INSERT INTO matrix (id, m) VALUES (1, ?)", (&m,))?;
+//!
+//! let mut stmt = conn.prepare("SELECT m FROM matrix WHERE id = 1")?;
+//! let m: Array = stmt.query_row((), |row| row.get(0))?;
+//! assert_eq!(m.get::<i32>(&[2, 1])?, 4);
+//! assert_eq!(m.to_vec::<i32>()?, vec![1, 2, 3, 4, 5, 6]);
+//! ```
+
+use super::cellvalue::{CellValue, CellValueToVal};
+use super::connection::Connection;
+use super::ddl::object_name;
+use super::error::{Error, ValueError};
+use super::param::{Param, ToSqlParam};
+use super::xsqlvar::XSQLVar;
+use super::*;
+use chrono::{Datelike, Timelike};
+use rust_decimal::Decimal;
+
+// element types of RDB$FIELDS.RDB$FIELD_TYPE, as written in SDL
+const BLR_SHORT: u8 = 7;
+const BLR_LONG: u8 = 8;
+const BLR_FLOAT: u8 = 10;
+const BLR_DATE: u8 = 12;
+const BLR_TIME: u8 = 13;
+const BLR_TEXT: u8 = 14;
+const BLR_INT64: u8 = 16;
+const BLR_BOOL: u8 = 23;
+const BLR_DOUBLE: u8 = 27;
+const BLR_TIMESTAMP: u8 = 35;
+const BLR_VARYING: u8 = 37;
+
+const SDL_VERSION1: u8 = 1;
+const SDL_RELATION: u8 = 2;
+const SDL_FIELD: u8 = 4;
+const SDL_STRUCT: u8 = 6;
+const SDL_VARIABLE: u8 = 7;
+const SDL_SCALAR: u8 = 8;
+const SDL_TINY_INTEGER: u8 = 9;
+const SDL_SHORT_INTEGER: u8 = 10;
+const SDL_LONG_INTEGER: u8 = 11;
+const SDL_DO2: u8 = 34;
+const SDL_DO1: u8 = 35;
+const SDL_ELEMENT: u8 = 36;
+const SDL_EOC: u8 = 255;
+
+const MAX_DIMENSIONS: usize = 16;
+
+const ARRAY_DESC_SQL: &str = "
+    SELECT F.RDB$FIELD_TYPE, F.RDB$FIELD_SCALE, F.RDB$FIELD_LENGTH,
+        D.RDB$LOWER_BOUND, D.RDB$UPPER_BOUND
+    FROM RDB$RELATION_FIELDS RF
+    JOIN RDB$FIELDS F ON F.RDB$FIELD_NAME = RF.RDB$FIELD_SOURCE
+    JOIN RDB$FIELD_DIMENSIONS D ON D.RDB$FIELD_NAME = F.RDB$FIELD_NAME
+    WHERE RF.RDB$RELATION_NAME = ? AND RF.RDB$FIELD_NAME = ?
+    ORDER BY D.RDB$DIMENSION";
+
+/// Value of an ARRAY column: its bounds and its elements in row-major order
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array {
+    bounds: Vec<(i32, i32)>,
+    elements: Vec<CellValue>,
+    // table and field to store into, when the parameter doesn't tell
+    column: Option<(String, String)>,
+    // id of a fetched array whose slice is not loaded yet
+    id: Option<Vec<u8>>,
+}
+
+impl Array {
+    /// Array with the (lower, upper) bounds of each dimension, and its
+    /// elements in row-major order. Elements can't be NULL.
+    pub fn new<T: Into<Param>>(bounds: &[(i32, i32)], elements: Vec<T>) -> Result<Array, Error> {
+        if bounds.is_empty() || bounds.len() > MAX_DIMENSIONS {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "ARRAY needs 1 to {} dimensions, not {}",
+                MAX_DIMENSIONS,
+                bounds.len()
+            ))));
+        }
+        if let Some((lower, upper)) = bounds.iter().find(|(lower, upper)| lower > upper) {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "ARRAY bounds {}:{} are reversed",
+                lower, upper
+            ))));
+        }
+        let elements = elements
+            .into_iter()
+            .map(|e| param_cell(e.into()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let array = Array {
+            bounds: bounds.to_vec(),
+            elements,
+            column: None,
+            id: None,
+        };
+        if array.elements.len() != count(bounds) {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "ARRAY {:?} has {} elements, not {}",
+                bounds,
+                count(bounds),
+                array.elements.len()
+            ))));
+        }
+        Ok(array)
+    }
+
+    /// Store into `field` of `table`, for a parameter Firebird doesn't
+    /// describe with its column (e.g. an expression or a procedure input)
+    pub fn column(mut self, table: &str, field: &str) -> Self {
+        self.column = Some((object_name(table), object_name(field)));
+        self
+    }
+
+    /// (lower, upper) bounds of each dimension
+    pub fn bounds(&self) -> &[(i32, i32)] {
+        &self.bounds
+    }
+
+    pub fn dimensions(&self) -> usize {
+        self.bounds.len()
+    }
+
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Element at `index`, one subscript per dimension within its bounds
+    pub fn get<T>(&self, index: &[i32]) -> Result<T, Error>
+    where
+        CellValue: CellValueToVal<T>,
+    {
+        let offset = self.offset(index)?;
+        self.elements[offset].clone().to_val()
+    }
+
+    /// All elements in row-major order
+    pub fn to_vec<T>(&self) -> Result<Vec<T>, Error>
+    where
+        CellValue: CellValueToVal<T>,
+    {
+        self.elements.iter().map(|e| e.clone().to_val()).collect()
+    }
+
+    fn offset(&self, index: &[i32]) -> Result<usize, Error> {
+        if index.len() != self.bounds.len() {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "ARRAY has {} dimensions, not {}",
+                self.bounds.len(),
+                index.len()
+            ))));
+        }
+        let mut offset = 0;
+        for (&i, &(lower, upper)) in index.iter().zip(self.bounds.iter()) {
+            if i < lower || i > upper {
+                return Err(Error::ValueError(ValueError::new(&format!(
+                    "ARRAY index {:?} is out of bounds {:?}",
+                    index, self.bounds
+                ))));
+            }
+            offset = offset * (upper - lower + 1) as usize + (i - lower) as usize;
+        }
+        Ok(offset)
+    }
+
+    /// Fetched array, loaded by `Statement` once its column is described
+    pub(crate) fn unloaded(id: Vec<u8>) -> Array {
+        Array {
+            bounds: Vec::new(),
+            elements: Vec::new(),
+            column: None,
+            id: Some(id),
+        }
+    }
+
+    pub(crate) fn id(&self) -> Option<&Vec<u8>> {
+        self.id.as_ref()
+    }
+
+    pub(crate) fn elements(&self) -> &[CellValue] {
+        &self.elements
+    }
+
+    pub(crate) fn column_name(&self) -> Option<&(String, String)> {
+        self.column.as_ref()
+    }
+}
+
+impl CellValueToVal<Array> for CellValue {
+    fn to_val(self) -> Result<Array, Error> {
+        match self {
+            CellValue::Array(v) if v.id.is_none() => Ok(v),
+            CellValue::Array(_) => Err(Error::ValueError(ValueError::new(
+                "ARRAY columns are only loaded by the blocking Connection",
+            ))),
+            _ => Err(Error::ValueError(ValueError::new("Can't convert to ARRAY"))),
+        }
+    }
+}
+
+impl ToSqlParam for Array {
+    /// Placeholder, replaced by the id of the stored slice before executing
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        (vec![0; 8], vec![9, 0], false)
+    }
+
+    fn array(&self) -> Option<&Array> {
+        Some(self)
+    }
+}
+
+impl ToSqlParam for &Array {
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        (*self).to_value_blr_isnull()
+    }
+
+    fn array(&self) -> Option<&Array> {
+        Some(self)
+    }
+}
+
+fn count(bounds: &[(i32, i32)]) -> usize {
+    bounds
+        .iter()
+        .map(|(lower, upper)| (upper - lower + 1) as usize)
+        .product()
+}
+
+fn param_cell(param: Param) -> Result<CellValue, Error> {
+    Ok(match param {
+        Param::Null => {
+            return Err(Error::ValueError(ValueError::new(
+                "ARRAY elements can't be NULL",
+            )));
+        }
+        Param::Text(v) => CellValue::Varying(v),
+        Param::Short(v) => CellValue::Short(v),
+        Param::Long(v) => CellValue::Long(v),
+        Param::Int64(v) => CellValue::Int64(v),
+        Param::Int128(v) => CellValue::Int128(v),
+        Param::Time(v) => CellValue::Time(v),
+        Param::Date(v) => CellValue::Date(v),
+        Param::TimeStamp(v) => CellValue::TimeStamp(v),
+        Param::Float(v) => CellValue::Float(v),
+        Param::Double(v) => CellValue::Double(v),
+        Param::Blob(v) => CellValue::BlobBinary(v),
+        Param::TimeStampTZ(v) => CellValue::TimeStampTz(v),
+        Param::TimeTz(t, tz) => CellValue::TimeTz((t, tz)),
+        Param::Decimal(v) => CellValue::Decimal(v),
+        Param::Boolean(v) => CellValue::Boolean(v),
+    })
+}
+
+/// Element type and bounds of an ARRAY column, from the system tables
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ArrayDesc {
+    relation: String,
+    field: String,
+    dtype: u8,
+    scale: i32,
+    // bytes of a CHAR / VARCHAR element
+    length: usize,
+    bounds: Vec<(i32, i32)>,
+}
+
+impl ArrayDesc {
+    pub(crate) fn lookup(
+        conn: &Connection,
+        trans_handle: i32,
+        relation: &str,
+        field: &str,
+    ) -> Result<ArrayDesc, Error> {
+        let mut stmt = conn._prepare(ARRAY_DESC_SQL, trans_handle, false)?;
+        let mut desc = ArrayDesc {
+            relation: relation.to_string(),
+            field: field.to_string(),
+            dtype: 0,
+            scale: 0,
+            length: 0,
+            bounds: Vec::new(),
+        };
+        for row in stmt.query((relation, field))? {
+            desc.dtype = row.get::<i32>(0)? as u8;
+            desc.scale = row.get::<i32>(1)?;
+            desc.length = row.get::<i32>(2)? as usize;
+            desc.bounds.push((row.get::<i32>(3)?, row.get::<i32>(4)?));
+        }
+        if desc.bounds.is_empty() {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "{}.{} is not an ARRAY column",
+                relation, field
+            ))));
+        }
+        Ok(desc)
+    }
+
+    /// Column of a fetched element, decoded like a row value
+    pub(crate) fn element(&self) -> Result<XSQLVar, Error> {
+        let mut x = XSQLVar::new();
+        x.sqltype = match self.dtype {
+            BLR_SHORT => SQL_TYPE_SHORT,
+            BLR_LONG => SQL_TYPE_LONG,
+            BLR_INT64 => SQL_TYPE_INT64,
+            BLR_FLOAT => SQL_TYPE_FLOAT,
+            BLR_DOUBLE => SQL_TYPE_DOUBLE,
+            BLR_DATE => SQL_TYPE_DATE,
+            BLR_TIME => SQL_TYPE_TIME,
+            BLR_TIMESTAMP => SQL_TYPE_TIMESTAMP,
+            BLR_TEXT => SQL_TYPE_TEXT,
+            BLR_VARYING => SQL_TYPE_VARYING,
+            BLR_BOOL => SQL_TYPE_BOOLEAN,
+            dtype => {
+                return Err(Error::ValueError(ValueError::new(&format!(
+                    "ARRAY of {}.{} has unsupported element type {}",
+                    self.relation, self.field, dtype
+                ))));
+            }
+        };
+        x.sqlscale = self.scale;
+        x.sqllen = self.length as i32;
+        x.relname = self.relation.clone();
+        x.fieldname = self.field.clone();
+        Ok(x)
+    }
+
+    /// Slice description of the whole array
+    pub(crate) fn sdl(&self) -> Vec<u8> {
+        let mut sdl = vec![SDL_VERSION1, SDL_STRUCT, 1, self.dtype];
+        match self.dtype {
+            BLR_SHORT | BLR_LONG | BLR_INT64 => sdl.push(self.scale as u8),
+            BLR_TEXT | BLR_VARYING => sdl.extend_from_slice(&(self.length as u16).to_le_bytes()),
+            _ => {}
+        }
+        sdl.push(SDL_RELATION);
+        sdl_literal(&mut sdl, &self.relation);
+        sdl.push(SDL_FIELD);
+        sdl_literal(&mut sdl, &self.field);
+        for (n, &(lower, upper)) in self.bounds.iter().enumerate() {
+            if lower == 1 {
+                sdl.extend_from_slice(&[SDL_DO1, n as u8]);
+            } else {
+                sdl.extend_from_slice(&[SDL_DO2, n as u8]);
+                sdl_number(&mut sdl, lower);
+            }
+            sdl_number(&mut sdl, upper);
+        }
+        sdl.extend_from_slice(&[SDL_ELEMENT, 1, SDL_SCALAR, 0, self.bounds.len() as u8]);
+        for n in 0..self.bounds.len() {
+            sdl.extend_from_slice(&[SDL_VARIABLE, n as u8]);
+        }
+        sdl.push(SDL_EOC);
+        sdl
+    }
+
+    /// Bytes of the slice in the server's native layout
+    pub(crate) fn slice_length(&self) -> Result<usize, Error> {
+        Ok(count(&self.bounds) * element_length(&self.element()?))
+    }
+
+    /// Array of the elements of a fetched slice
+    pub(crate) fn read(&self, elements: Vec<CellValue>) -> Result<Array, Error> {
+        if elements.len() != count(&self.bounds) {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "ARRAY slice of {}.{} has {} elements, not {}",
+                self.relation,
+                self.field,
+                elements.len(),
+                count(&self.bounds)
+            ))));
+        }
+        Ok(Array {
+            bounds: self.bounds.clone(),
+            elements,
+            column: None,
+            id: None,
+        })
+    }
+
+    /// Elements of `array` as sent by op_put_slice
+    pub(crate) fn encode(&self, array: &Array) -> Result<Vec<u8>, Error> {
+        if array.bounds != self.bounds {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "ARRAY bounds {:?} don't match {:?} of {}.{}",
+                array.bounds, self.bounds, self.relation, self.field
+            ))));
+        }
+        self.element()?;
+        let mut buf: Vec<u8> = Vec::new();
+        for e in &array.elements {
+            self.encode_element(e, &mut buf)?;
+        }
+        Ok(buf)
+    }
+
+    fn encode_element(&self, e: &CellValue, buf: &mut Vec<u8>) -> Result<(), Error> {
+        let e = e.clone();
+        match self.dtype {
+            BLR_SHORT => {
+                let v = self.integer(e, i16::MIN.into(), i16::MAX.into())?;
+                buf.extend_from_slice(&utils::bint32_to_bytes(v as i32));
+            }
+            BLR_LONG => {
+                let v = self.integer(e, i32::MIN.into(), i32::MAX.into())?;
+                buf.extend_from_slice(&utils::bint32_to_bytes(v as i32));
+            }
+            BLR_INT64 => {
+                let v = self.integer(e, i64::MIN, i64::MAX)?;
+                buf.extend_from_slice(&utils::bint64_to_bytes(v));
+            }
+            BLR_FLOAT => buf.extend_from_slice(&utils::f32_to_bytes(e.to_val()?)),
+            BLR_DOUBLE => buf.extend_from_slice(&utils::f64_to_bytes(e.to_val()?)),
+            BLR_DATE => {
+                let d: chrono::NaiveDate = e.to_val()?;
+                utils::check_date_range(&d)?;
+                buf.extend_from_slice(&utils::convert_date(d.year(), d.month(), d.day()));
+            }
+            BLR_TIME => {
+                let t: chrono::NaiveTime = e.to_val()?;
+                buf.extend_from_slice(&time_bytes(&t));
+            }
+            BLR_TIMESTAMP => {
+                let dt: chrono::NaiveDateTime = e.to_val()?;
+                utils::check_date_range(&dt.date())?;
+                buf.extend_from_slice(&utils::convert_date(dt.year(), dt.month(), dt.day()));
+                buf.extend_from_slice(&time_bytes(&dt.time()));
+            }
+            BLR_TEXT | BLR_VARYING => {
+                let s: String = e.to_val()?;
+                let b = s.as_bytes();
+                if b.len() > self.length {
+                    return Err(Error::ValueError(ValueError::new(&format!(
+                        "'{}' is longer than the {} bytes of an element of {}.{}",
+                        s, self.length, self.relation, self.field
+                    ))));
+                }
+                let ln = if self.dtype == BLR_TEXT {
+                    self.length
+                } else {
+                    buf.extend_from_slice(&utils::bint32_to_bytes(b.len() as i32));
+                    b.len()
+                };
+                buf.extend_from_slice(b);
+                buf.resize(buf.len() + ln - b.len(), b' ');
+                buf.resize(buf.len() + xdr::pad_len(ln), 0);
+            }
+            BLR_BOOL => {
+                let b: bool = e.to_val()?;
+                buf.extend_from_slice(&[b as u8, 0, 0, 0]);
+            }
+            // rejected by `element`
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Unscaled value of an integer element, checked against `min..=max`
+    fn integer(&self, e: CellValue, min: i64, max: i64) -> Result<i64, Error> {
+        let v: i128 = if self.scale < 0 {
+            let mut d: Decimal = e.to_val()?;
+            d.rescale((-self.scale) as u32);
+            d.mantissa()
+        } else {
+            CellValueToVal::<i128>::to_val(e)?
+        };
+        if v < min.into() || v > max.into() {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "{} is out of range for an element of {}.{}",
+                v, self.relation, self.field
+            ))));
+        }
+        Ok(v as i64)
+    }
+}
+
+/// Bytes of an element in the server's native layout, which sizes a slice
+pub(crate) fn element_length(x: &XSQLVar) -> usize {
+    match x.sqltype {
+        SQL_TYPE_TEXT => x.sqllen as usize,
+        SQL_TYPE_VARYING => x.sqllen as usize + 2,
+        SQL_TYPE_SHORT => 2,
+        SQL_TYPE_BOOLEAN => 1,
+        SQL_TYPE_INT64 | SQL_TYPE_DOUBLE | SQL_TYPE_TIMESTAMP => 8,
+        _ => 4,
+    }
+}
+
+fn time_bytes(t: &chrono::NaiveTime) -> [u8; 4] {
+    utils::convert_time(t.hour(), t.minute(), t.second(), t.nanosecond())
+}
+
+fn sdl_literal(sdl: &mut Vec<u8>, s: &str) {
+    sdl.push(s.len() as u8);
+    sdl.extend_from_slice(s.as_bytes());
+}
+
+fn sdl_number(sdl: &mut Vec<u8>, n: i32) {
+    if (-128..=127).contains(&n) {
+        sdl.extend_from_slice(&[SDL_TINY_INTEGER, n as u8]);
+    } else if (-32768..=32767).contains(&n) {
+        sdl.push(SDL_SHORT_INTEGER);
+        sdl.extend_from_slice(&(n as i16).to_le_bytes());
+    } else {
+        sdl.push(SDL_LONG_INTEGER);
+        sdl.extend_from_slice(&n.to_le_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn desc(dtype: u8, length: usize, bounds: &[(i32, i32)]) -> ArrayDesc {
+        ArrayDesc {
+            relation: "T".to_string(),
+            field: "A".to_string(),
+            dtype,
+            scale: 0,
+            length,
+            bounds: bounds.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_index() {
+        let a = Array::new(&[(1, 2), (0, 2)], vec![1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(a.len(), 6);
+        assert_eq!(a.dimensions(), 2);
+        assert_eq!(a.get::<i32>(&[1, 0]).unwrap(), 1);
+        assert_eq!(a.get::<i32>(&[2, 1]).unwrap(), 5);
+        assert_eq!(a.to_vec::<i64>().unwrap(), vec![1, 2, 3, 4, 5, 6]);
+        assert!(a.get::<i32>(&[3, 0]).is_err());
+        assert!(a.get::<i32>(&[1]).is_err());
+
+        assert!(Array::new(&[(1, 3)], vec![1, 2]).is_err());
+        assert!(Array::new(&[(2, 1)], Vec::<i32>::new()).is_err());
+        assert!(Array::new(&[(1, 2)], vec![Some(1), None]).is_err());
+    }
+
+    #[test]
+    fn test_sdl() {
+        let d = desc(BLR_LONG, 4, &[(1, 3), (-1, 1000)]);
+        assert_eq!(
+            d.sdl(),
+            vec![
+                1, 6, 1, 8, 0, // struct of one LONG, scale 0
+                2, 1, b'T', 4, 1, b'A', // relation, field
+                35, 0, 9, 3, // do1 0, upper 3
+                34, 1, 9, 255, 10, 232, 3, // do2 1, lower -1, upper 1000
+                36, 1, 8, 0, 2, 7, 0, 7, 1, 255,
+            ]
+        );
+        let d = desc(BLR_VARYING, 10, &[(1, 70000)]);
+        assert_eq!(d.sdl()[..7], [1, 6, 1, 37, 10, 0, 2]);
+        assert_eq!(d.sdl()[12..19], [35, 0, 11, 0x70, 0x11, 1, 0]);
+        assert_eq!(d.slice_length().unwrap(), 70000 * 12);
+    }
+
+    #[test]
+    fn test_encode() {
+        let a = Array::new(&[(1, 2)], vec!["ab", "c"]).unwrap();
+        assert_eq!(
+            desc(BLR_VARYING, 5, &[(1, 2)]).encode(&a).unwrap(),
+            vec![0, 0, 0, 2, b'a', b'b', 0, 0, 0, 0, 0, 1, b'c', 0, 0, 0]
+        );
+        assert_eq!(
+            desc(BLR_TEXT, 3, &[(1, 2)]).encode(&a).unwrap(),
+            vec![b'a', b'b', b' ', 0, b'c', b' ', b' ', 0]
+        );
+        assert!(desc(BLR_TEXT, 1, &[(1, 2)]).encode(&a).is_err());
+        assert!(desc(BLR_TEXT, 3, &[(0, 1)]).encode(&a).is_err());
+
+        let a = Array::new(&[(1, 2)], vec![Decimal::new(125, 2), Decimal::new(-3, 0)]).unwrap();
+        let mut d = desc(BLR_SHORT, 2, &[(1, 2)]);
+        d.scale = -2;
+        assert_eq!(
+            d.encode(&a).unwrap(),
+            vec![0, 0, 0, 125, 255, 255, 254, 212]
+        );
+        let a = Array::new(&[(1, 1)], vec![40000]).unwrap();
+        assert!(desc(BLR_SHORT, 2, &[(1, 1)]).encode(&a).is_err());
+    }
+
+    #[test]
+    fn test_read() {
+        let d = desc(BLR_SHORT, 2, &[(0, 1)]);
+        let x = d.element().unwrap();
+        assert_eq!(x.sqltype, SQL_TYPE_SHORT);
+        assert_eq!(element_length(&x), 2);
+        let a = d
+            .read(vec![CellValue::Short(7), CellValue::Short(8)])
+            .unwrap();
+        assert_eq!(a.get::<i16>(&[1]).unwrap(), 8);
+        assert!(d.read(vec![CellValue::Short(7)]).is_err());
+        assert!(desc(BLR_DOUBLE + 100, 0, &[(1, 1)]).element().is_err());
+    }
+}
@@ -0,0 +1,407 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! BLOB parameters
+//!
+//! `BlobOptions` builds the BLOB parameter buffer (BPB) used when a blob is
+//! opened. Setting a target sub_type makes the server pass the blob through
+//! the BLOB filter registered for the source/target pair, so filtered data
+//! (e.g. compressed text) is returned already converted.
+//!
+//! A column with `BlobOptions::streamed` is not loaded with the row: the row
+//! holds a `BlobId`, and `Connection::blob_reader` reads the blob segment by
+//! segment through `std::io::Read`, so it never has to fit in memory.
+//! `BlobWriter` does the opposite through `std::io::Write`: its `BlobId` is
+//! bound as the parameter of a BLOB column.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::BlobOptions;
+//!
+//! let mut stmt = conn.prepare("SELECT id, packed_notes FROM docs")?;
+//! // read column 1 through the filter from sub_type -10 to TEXT
+//! stmt.set_blob_options(1, BlobOptions::new().target_subtype(1));
+//! for row in stmt.query(())? {
+//!     let notes: String = row.get(1)?;
+//! }
+//!
+//! let mut stmt = conn.prepare("SELECT name, content FROM files")?;
+//! stmt.set_blob_options(1, BlobOptions::new().streamed(true));
+//! for row in stmt.query(())? {
+//!     let name: String = row.get(0)?;
+//!     let content: BlobId = row.get(1)?;
+//!     let mut reader = conn.blob_reader(&content)?;
+//!     std::io::copy(&mut reader, &mut std::fs::File::create(name)?)?;
+//! }
+//!
+//! let mut writer = conn.blob_writer()?;
+//! std::io::copy(&mut std::fs::File::open("video.mp4")?, &mut writer)?;
+//! let content = writer.finish()?;
+//! conn.execute("INSERT INTO files (name, content) VALUES (?, ?)", ("video.mp4", &content))?;
+//! ```
+
+use super::cellvalue::{CellValue, CellValueToVal};
+use super::xsqlvar::XSQLVar;
+use super::*;
+use std::collections::HashMap;
+use std::io;
+
+// bytes sent per op_put_segment by BlobWriter
+const SEGMENT_SIZE: usize = 32000;
+
+/// Options for opening a blob
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlobOptions {
+    /// Sub_type of the stored blob (None = sub_type of the column)
+    pub source_subtype: Option<i16>,
+    /// Sub_type to convert to through a BLOB filter (None = no conversion)
+    pub target_subtype: Option<i16>,
+    /// Character set id of the stored text
+    pub source_charset: Option<i16>,
+    /// Character set id to transliterate text to
+    pub target_charset: Option<i16>,
+    /// Leave the blob on the server, see `BlobId` (blocking Connection only)
+    pub streamed: bool,
+}
+
+impl BlobOptions {
+    /// Create blob options without conversion
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the sub_type of the stored blob
+    pub fn source_subtype(mut self, subtype: i16) -> Self {
+        self.source_subtype = Some(subtype);
+        self
+    }
+
+    /// Set the sub_type the server filters the blob to
+    pub fn target_subtype(mut self, subtype: i16) -> Self {
+        self.target_subtype = Some(subtype);
+        self
+    }
+
+    /// Set the character set id of the stored text
+    pub fn source_charset(mut self, charset: i16) -> Self {
+        self.source_charset = Some(charset);
+        self
+    }
+
+    /// Set the character set id to transliterate text to
+    pub fn target_charset(mut self, charset: i16) -> Self {
+        self.target_charset = Some(charset);
+        self
+    }
+
+    /// Return a `BlobId` instead of loading the blob with the row
+    pub fn streamed(mut self, streamed: bool) -> Self {
+        self.streamed = streamed;
+        self
+    }
+
+    /// Build the BPB. `column_subtype` is used when no source sub_type is given.
+    /// Returns an empty buffer when no conversion is requested.
+    pub(crate) fn to_bpb(&self, column_subtype: i32) -> Vec<u8> {
+        if self.target_subtype.is_none() && self.target_charset.is_none() {
+            return Vec::new();
+        }
+        let mut bpb: Vec<u8> = vec![ISC_BPB_VERSION1];
+        let source_subtype = self.source_subtype.unwrap_or(column_subtype as i16);
+        let items = [
+            (ISC_BPB_SOURCE_TYPE, Some(source_subtype)),
+            (ISC_BPB_TARGET_TYPE, self.target_subtype),
+            (ISC_BPB_SOURCE_INTERP, self.source_charset),
+            (ISC_BPB_TARGET_INTERP, self.target_charset),
+        ];
+        for (item, value) in items.iter() {
+            if let Some(v) = value {
+                bpb.push(*item);
+                bpb.push(2);
+                bpb.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        bpb
+    }
+}
+
+/// BPB for each column of a result set; empty where no options were set
+pub(crate) fn column_bpbs(
+    xsqlda: &[XSQLVar],
+    blob_options: &HashMap<usize, BlobOptions>,
+) -> Vec<Vec<u8>> {
+    (0..xsqlda.len())
+        .map(|i| match blob_options.get(&i) {
+            Some(options) => options.to_bpb(xsqlda[i].sqlsubtype),
+            None => Vec::new(),
+        })
+        .collect()
+}
+
+/// A BLOB left on the server by `BlobOptions::streamed`, to read with
+/// `Connection::blob_reader`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobId {
+    id: Vec<u8>,
+    // transaction the row was fetched in
+    trans_handle: i32,
+    bpb: Vec<u8>,
+}
+
+impl BlobId {
+    pub(crate) fn new(id: Vec<u8>, trans_handle: i32, bpb: Vec<u8>) -> Self {
+        BlobId {
+            id,
+            trans_handle,
+            bpb,
+        }
+    }
+
+    /// The 8 byte blob id
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.id
+    }
+}
+
+impl ToSqlParam for BlobId {
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        (self.id.clone(), vec![9, 0], false)
+    }
+}
+
+impl ToSqlParam for &BlobId {
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        (*self).to_value_blr_isnull()
+    }
+}
+
+impl CellValueToVal<BlobId> for CellValue {
+    fn to_val(self) -> Result<BlobId, Error> {
+        match self {
+            CellValue::BlobId(v) => Ok(v),
+            CellValue::BlobBinary(_) | CellValue::BlobText(_) => Err(Error::ValueError(
+                ValueError::new("BLOB column was loaded, see BlobOptions::streamed"),
+            )),
+            _ => Err(Error::ValueError(ValueError::new(
+                "Can't convert to BlobId",
+            ))),
+        }
+    }
+}
+
+/// Reads a BLOB segment by segment as it is consumed, see
+/// `Connection::blob_reader`. The blob is closed at its end or on drop.
+pub struct BlobReader<'conn> {
+    conn: &'conn Connection,
+    blob_handle: i32,
+    // segments received and not read yet
+    buf: Vec<u8>,
+    pos: usize,
+    open: bool,
+}
+
+impl BlobReader<'_> {
+    pub(crate) fn open<'a>(conn: &'a Connection, blob: &BlobId) -> Result<BlobReader<'a>, Error> {
+        let blob_handle = conn._open_blob(&blob.id, blob.trans_handle, &blob.bpb)?;
+        Ok(BlobReader {
+            conn,
+            blob_handle,
+            buf: Vec::new(),
+            pos: 0,
+            open: true,
+        })
+    }
+
+    /// Close the blob before its end
+    pub fn close(mut self) -> Result<(), Error> {
+        self.finish()
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if self.open {
+            self.open = false;
+            self.conn._close_blob(self.blob_handle)?;
+        }
+        Ok(())
+    }
+
+    // fetch segments until some data arrives or the blob ends
+    fn fill(&mut self) -> Result<(), Error> {
+        self.buf.clear();
+        self.pos = 0;
+        while self.open && self.buf.is_empty() {
+            if !self.conn._read_blob(self.blob_handle, &mut self.buf)? {
+                self.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl io::Read for BlobReader<'_> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.buf.len() {
+            self.fill().map_err(io_error)?;
+        }
+        let n = out.len().min(self.buf.len() - self.pos);
+        out[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl Drop for BlobReader<'_> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Writes a new BLOB segment by segment, see `Connection::blob_writer`.
+/// Dropped without `finish`, the blob is discarded.
+pub struct BlobWriter<'conn> {
+    conn: &'conn Connection,
+    trans_handle: i32,
+    blob_handle: i32,
+    blob_id: Vec<u8>,
+    // data not sent yet, up to a segment
+    buf: Vec<u8>,
+    open: bool,
+}
+
+impl BlobWriter<'_> {
+    pub(crate) fn create(conn: &Connection, trans_handle: i32) -> Result<BlobWriter<'_>, Error> {
+        let (blob_handle, blob_id) = conn._create_blob(trans_handle)?;
+        Ok(BlobWriter {
+            conn,
+            trans_handle,
+            blob_handle,
+            blob_id,
+            buf: Vec::with_capacity(SEGMENT_SIZE),
+            open: true,
+        })
+    }
+
+    /// Send what is left and close the blob, returns its id to bind as a
+    /// parameter in the same transaction
+    pub fn finish(mut self) -> Result<BlobId, Error> {
+        self.send()?;
+        self.open = false;
+        self.conn._close_blob(self.blob_handle)?;
+        Ok(BlobId::new(
+            std::mem::take(&mut self.blob_id),
+            self.trans_handle,
+            Vec::new(),
+        ))
+    }
+
+    fn send(&mut self) -> Result<(), Error> {
+        if !self.buf.is_empty() {
+            self.conn._write_blob(self.blob_handle, &self.buf)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+}
+
+impl io::Write for BlobWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = data.len().min(SEGMENT_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == SEGMENT_SIZE {
+            self.send().map_err(io_error)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send().map_err(io_error)
+    }
+}
+
+impl Drop for BlobWriter<'_> {
+    fn drop(&mut self) {
+        if self.open {
+            let _ = self.conn._cancel_blob(self.blob_handle);
+        }
+    }
+}
+
+fn io_error(e: Error) -> io::Error {
+    match e {
+        Error::IoError(e) => e,
+        e => io::Error::other(format!("{:?}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bpb() {
+        assert!(BlobOptions::new().to_bpb(1).is_empty());
+
+        let bpb = BlobOptions::new().target_subtype(1).to_bpb(-10);
+        assert_eq!(bpb, vec![1, 1, 2, 0xf6, 0xff, 2, 2, 1, 0]);
+
+        let bpb = BlobOptions::new()
+            .source_subtype(1)
+            .target_subtype(1)
+            .target_charset(4)
+            .to_bpb(0);
+        assert_eq!(bpb, vec![1, 1, 2, 1, 0, 2, 2, 1, 0, 5, 2, 4, 0]);
+    }
+    #[test]
+    fn test_column_bpbs() {
+        let mut xsqlda = vec![XSQLVar::new(), XSQLVar::new()];
+        xsqlda[1].sqlsubtype = -10;
+        let mut options = HashMap::new();
+        options.insert(1, BlobOptions::new().target_subtype(1));
+        let bpbs = column_bpbs(&xsqlda, &options);
+        assert!(bpbs[0].is_empty());
+        assert_eq!(bpbs[1], vec![1, 1, 2, 0xf6, 0xff, 2, 2, 1, 0]);
+    }
+    #[test]
+    fn test_blob_id() {
+        let id = BlobId::new(vec![0, 0, 0, 1, 0, 0, 0, 2], 5, Vec::new());
+        let v: BlobId = CellValue::BlobId(id.clone()).to_val().unwrap();
+        assert_eq!(v, id);
+        assert_eq!(v.as_bytes(), &[0, 0, 0, 1, 0, 0, 0, 2]);
+        let r: Result<BlobId, Error> = CellValue::BlobBinary(b"x".to_vec()).to_val();
+        assert!(r.is_err());
+        assert!(BlobOptions::new().streamed(true).to_bpb(0).is_empty());
+    }
+    #[test]
+    fn test_blob_id_param() {
+        let id = BlobId::new(vec![0, 0, 0, 1, 0, 0, 0, 2], 5, Vec::new());
+        assert_eq!(
+            id.to_value_blr_isnull(),
+            (vec![0, 0, 0, 1, 0, 0, 0, 2], vec![9, 0], false)
+        );
+        assert_eq!(
+            ToSqlParam::to_value_blr_isnull(&&id),
+            id.to_value_blr_isnull()
+        );
+    }
+}
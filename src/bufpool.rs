@@ -0,0 +1,162 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Reusable buffers for packet construction
+//!
+//! Each connection keeps a few `BytesMut` buffers for the temporary parts of
+//! a packet (parameter values, BLR, info items), so steady state encoding
+//! does not allocate.
+
+use bytes::BytesMut;
+
+const INITIAL_CAPACITY: usize = 1024;
+
+pub(crate) struct BufferPool {
+    free: Vec<BytesMut>,
+    max_buffers: usize,
+    // larger buffers (e.g. after a big parameter) are released
+    max_capacity: usize,
+    allocated: u64,
+    reused: u64,
+}
+
+impl BufferPool {
+    pub fn new(max_buffers: usize, max_capacity: usize) -> BufferPool {
+        BufferPool {
+            free: Vec::with_capacity(max_buffers),
+            max_buffers,
+            max_capacity,
+            allocated: 0,
+            reused: 0,
+        }
+    }
+
+    pub fn get(&mut self) -> BytesMut {
+        match self.free.pop() {
+            Some(buf) => {
+                self.reused += 1;
+                buf
+            }
+            None => {
+                self.allocated += 1;
+                BytesMut::with_capacity(INITIAL_CAPACITY)
+            }
+        }
+    }
+
+    pub fn put(&mut self, mut buf: BytesMut) {
+        buf.clear();
+        if buf.capacity() <= self.max_capacity && self.free.len() < self.max_buffers {
+            self.free.push(buf);
+        }
+    }
+
+    /// (buffers allocated, buffers reused)
+    #[allow(dead_code)]
+    pub fn stats(&self) -> (u64, u64) {
+        (self.allocated, self.reused)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{utils, xdr};
+
+    #[test]
+    fn test_buffer_pool() {
+        let mut pool = BufferPool::new(2, 4096);
+        let mut a = pool.get();
+        a.extend_from_slice(b"abc");
+        let b = pool.get();
+        pool.put(a);
+        pool.put(b);
+        let a = pool.get();
+        assert!(a.is_empty());
+        assert_eq!(pool.stats(), (2, 1));
+
+        // oversized buffers are not kept
+        let mut big = pool.get();
+        big.reserve(8192);
+        pool.put(big);
+        pool.put(a);
+        assert_eq!(pool.free.len(), 1);
+    }
+
+    // cargo test --release bench_encode -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_encode() {
+        use std::io::Write;
+        use std::time::Instant;
+        const N: usize = 1_000_000;
+        let query = "select a, b, c from foo where id = ? and name = ?";
+        let values = [0u8; 64];
+
+        // before: a fresh Vec per packet part
+        let start = Instant::now();
+        let mut write_buf: Vec<u8> = Vec::new();
+        for _ in 0..N {
+            let mut blr: Vec<u8> = Vec::new();
+            blr.write_all(&[5, 2, 4, 0, 4, 0, 14, 64, 0, 7, 0, 8, 0, 7, 0, 255, 76])
+                .unwrap();
+            let mut vals: Vec<u8> = Vec::new();
+            vals.write_all(&values).unwrap();
+            write_buf
+                .write_all(&utils::xdr_bytes(query.as_bytes()))
+                .unwrap();
+            write_buf.write_all(&utils::xdr_bytes(&blr)).unwrap();
+            write_buf.write_all(&vals).unwrap();
+            std::hint::black_box(&write_buf);
+            write_buf.clear();
+        }
+        let before = start.elapsed();
+
+        // after: pooled buffers, XDR written in place
+        let start = Instant::now();
+        let mut pool = BufferPool::new(8, 65536);
+        let mut write_buf = BytesMut::new();
+        for _ in 0..N {
+            let mut blr = pool.get();
+            blr.extend_from_slice(&[5, 2, 4, 0, 4, 0, 14, 64, 0, 7, 0, 8, 0, 7, 0, 255, 76]);
+            let mut vals = pool.get();
+            vals.extend_from_slice(&values);
+            xdr::put_opaque(&mut write_buf, query.as_bytes());
+            xdr::put_opaque(&mut write_buf, &blr);
+            write_buf.extend_from_slice(&vals);
+            pool.put(blr);
+            pool.put(vals);
+            std::hint::black_box(&write_buf);
+            write_buf.clear();
+        }
+        let after = start.elapsed();
+        println!(
+            "encode x{}: before {:?} ({} ns/op), after {:?} ({} ns/op)",
+            N,
+            before,
+            before.as_nanos() / N as u128,
+            after,
+            after.as_nanos() / N as u128
+        );
+    }
+}
@@ -20,6 +20,8 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use super::array::Array;
+use super::blob::BlobId;
 use super::error::{Error, ValueError};
 use chrono;
 use chrono_tz;
@@ -46,12 +48,33 @@ pub enum CellValue {
     TimeTz((chrono::NaiveTime, chrono_tz::Tz)),
     Decimal(rust_decimal::Decimal),
     Boolean(bool),
+    Array(Array),
+    /// A BLOB not loaded with the row (`BlobOptions::streamed`)
+    BlobId(BlobId),
+    /// CHAR / VARCHAR CHARACTER SET OCTETS
+    Octets(Vec<u8>),
 }
 
 pub trait CellValueToVal<T> {
     fn to_val(self) -> Result<T, Error>
     where
         Self: std::marker::Sized;
+
+    /// Like `to_val`, but also accepts legacy CHAR(1) / SMALLINT booleans
+    /// (used when the connection has `legacy_bool` enabled)
+    fn to_val_legacy_bool(self) -> Result<T, Error>
+    where
+        Self: std::marker::Sized,
+    {
+        self.to_val()
+    }
+
+    /// Fails when `to_val` would truncate or round the value instead of
+    /// converting it exactly (used when the connection has `strict_types`
+    /// enabled)
+    fn check_strict(&self) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 impl<T> CellValueToVal<Option<T>> for CellValue
@@ -64,6 +87,20 @@ where
             _ => Ok(Some(self.to_val()?)),
         }
     }
+
+    fn to_val_legacy_bool(self) -> Result<Option<T>, Error> {
+        match self {
+            CellValue::Null => Ok(None),
+            _ => Ok(Some(self.to_val_legacy_bool()?)),
+        }
+    }
+
+    fn check_strict(&self) -> Result<(), Error> {
+        match self {
+            CellValue::Null => Ok(()),
+            _ => CellValueToVal::<T>::check_strict(self),
+        }
+    }
 }
 
 impl CellValueToVal<String> for CellValue {
@@ -73,6 +110,7 @@ impl CellValueToVal<String> for CellValue {
             CellValue::Varying(v) => Ok(v.to_string()),
             CellValue::BlobBinary(v) => Ok(String::from_utf8_lossy(&v).to_string()),
             CellValue::BlobText(v) => Ok(String::from_utf8_lossy(&v).to_string()),
+            CellValue::Octets(v) => Ok(String::from_utf8_lossy(&v).to_string()),
             _ => Err(Error::ValueError(ValueError::new("Can't convert string"))),
         }
     }
@@ -92,18 +130,79 @@ impl CellValueToVal<i64> for CellValue {
             _ => Err(Error::ValueError(ValueError::new("Can't convert to int"))),
         }
     }
+
+    fn check_strict(&self) -> Result<(), Error> {
+        check_int(self, i64::MIN.into(), i64::MAX.into(), "i64")
+    }
+}
+
+impl CellValueToVal<i128> for CellValue {
+    fn to_val(self) -> Result<i128, Error> {
+        use rust_decimal::prelude::ToPrimitive;
+        match self {
+            CellValue::Short(v) => Ok(v.into()),
+            CellValue::Long(v) => Ok(v.into()),
+            CellValue::Int64(v) => Ok(v.into()),
+            CellValue::Int128(v) => Ok(v),
+            CellValue::Decimal(v) => v.to_i128().ok_or_else(|| {
+                Error::ValueError(ValueError::new("Can't convert decimal to i128"))
+            }),
+            _ => Err(Error::ValueError(ValueError::new("Can't convert to int"))),
+        }
+    }
+
+    fn check_strict(&self) -> Result<(), Error> {
+        check_int(self, i128::MIN, i128::MAX, "i128")
+    }
 }
 
 impl CellValueToVal<i32> for CellValue {
     fn to_val(self) -> Result<i32, Error> {
         CellValueToVal::<i64>::to_val(self).map(|i| i as i32)
     }
+
+    fn check_strict(&self) -> Result<(), Error> {
+        check_int(self, i32::MIN.into(), i32::MAX.into(), "i32")
+    }
 }
 
 impl CellValueToVal<i16> for CellValue {
     fn to_val(self) -> Result<i16, Error> {
         CellValueToVal::<i64>::to_val(self).map(|i| i as i16)
     }
+
+    fn check_strict(&self) -> Result<(), Error> {
+        check_int(self, i16::MIN.into(), i16::MAX.into(), "i16")
+    }
+}
+
+/// An integer or scale 0 NUMERIC that fits `min..=max`. A NUMERIC with
+/// a scale would lose its fraction, a larger value would wrap.
+fn check_int(cell: &CellValue, min: i128, max: i128, target: &str) -> Result<(), Error> {
+    let v: i128 = match cell {
+        CellValue::Short(v) => (*v).into(),
+        CellValue::Long(v) => (*v).into(),
+        CellValue::Int64(v) => (*v).into(),
+        CellValue::Int128(v) => *v,
+        CellValue::Decimal(v) if v.scale() != 0 => {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "NUMERIC {} has scale {} and would be rounded to {}",
+                v,
+                v.scale(),
+                target
+            ))));
+        }
+        CellValue::Decimal(v) => v.mantissa(),
+        // not an integer, to_val reports it
+        _ => return Ok(()),
+    };
+    if v < min || v > max {
+        return Err(Error::ValueError(ValueError::new(&format!(
+            "{} is out of range for {}",
+            v, target
+        ))));
+    }
+    Ok(())
 }
 
 impl CellValueToVal<f64> for CellValue {
@@ -118,6 +217,7 @@ impl CellValueToVal<f64> for CellValue {
             CellValue::Short(v) => Ok(v as f64),
             CellValue::Long(v) => Ok(v as f64),
             CellValue::Int64(v) => Ok(v as f64),
+            CellValue::Int128(v) => Ok(v as f64),
             _ => Err(Error::ValueError(ValueError::new("Can't convert to f64"))),
         }
     }
@@ -145,11 +245,30 @@ impl CellValueToVal<Vec<u8>> for CellValue {
         match self {
             CellValue::BlobBinary(v) => Ok(v.clone()),
             CellValue::BlobText(v) => Ok(v.clone()),
+            CellValue::Octets(v) => Ok(v),
             _ => Err(Error::ValueError(ValueError::new("Can't convert binary"))),
         }
     }
 }
 
+/// JSON held in a text column or BLOB SUB_TYPE TEXT; NULL reads as `null`
+#[cfg(feature = "json")]
+impl CellValueToVal<serde_json::Value> for CellValue {
+    fn to_val(self) -> Result<serde_json::Value, Error> {
+        let result = match self {
+            CellValue::Text(s) | CellValue::Varying(s) => serde_json::from_str(&s),
+            CellValue::BlobText(b) | CellValue::BlobBinary(b) => serde_json::from_slice(&b),
+            CellValue::Null => Ok(serde_json::Value::Null),
+            _ => {
+                return Err(Error::ValueError(ValueError::new(
+                    "Can't convert to json: not a text column",
+                )));
+            }
+        };
+        result.map_err(|e| Error::ValueError(ValueError::new(&format!("Invalid json: {}", e))))
+    }
+}
+
 impl CellValueToVal<bool> for CellValue {
     fn to_val(self) -> Result<bool, Error> {
         match self {
@@ -157,6 +276,23 @@ impl CellValueToVal<bool> for CellValue {
             _ => Err(Error::ValueError(ValueError::new("Can't convert bool"))),
         }
     }
+
+    fn to_val_legacy_bool(self) -> Result<bool, Error> {
+        let v = match &self {
+            CellValue::Text(s) | CellValue::Varying(s) => {
+                match s.trim_end().to_ascii_uppercase().as_str() {
+                    "T" | "Y" | "1" => Some(true),
+                    "F" | "N" | "0" => Some(false),
+                    _ => None,
+                }
+            }
+            CellValue::Short(0) | CellValue::Long(0) | CellValue::Int64(0) => Some(false),
+            CellValue::Short(1) | CellValue::Long(1) | CellValue::Int64(1) => Some(true),
+            CellValue::Boolean(v) => Some(*v),
+            _ => None,
+        };
+        v.ok_or_else(|| Error::ValueError(ValueError::new("Can't convert bool")))
+    }
 }
 
 impl CellValueToVal<chrono::NaiveTime> for CellValue {
@@ -192,6 +328,12 @@ impl CellValueToVal<rust_decimal::Decimal> for CellValue {
     fn to_val(self) -> Result<rust_decimal::Decimal, Error> {
         match self {
             CellValue::Decimal(v) => Ok(v),
+            // NUMERIC(p, 0) arrives as an integer
+            CellValue::Short(v) => Ok(v.into()),
+            CellValue::Long(v) => Ok(v.into()),
+            CellValue::Int64(v) => Ok(v.into()),
+            CellValue::Int128(v) => rust_decimal::Decimal::try_from_i128_with_scale(v, 0)
+                .map_err(|_| Error::ValueError(ValueError::new("Can't convert i128 to decimal"))),
             _ => Err(Error::ValueError(ValueError::new("Can't convert decimal"))),
         }
     }
@@ -218,3 +360,54 @@ impl CellValueToVal<chrono::DateTime<chrono_tz::Tz>> for CellValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_value() {
+        use crate::param::ToSqlParam;
+
+        let v: serde_json::Value = CellValue::BlobText(br#"{"a": [1, 2]}"#.to_vec())
+            .to_val()
+            .unwrap();
+        assert_eq!(v["a"][1], 2);
+        let v: serde_json::Value = CellValue::Varying("\"x\"".to_string()).to_val().unwrap();
+        assert_eq!(v, "x");
+        let v: serde_json::Value = CellValue::Null.to_val().unwrap();
+        assert!(v.is_null());
+        let v: Option<serde_json::Value> = CellValue::Null.to_val().unwrap();
+        assert_eq!(v, None);
+        let r: Result<serde_json::Value, Error> = CellValue::Text("not json".to_string()).to_val();
+        assert!(r.is_err());
+        let r: Result<serde_json::Value, Error> = CellValue::Long(1).to_val();
+        assert!(r.is_err());
+
+        let (_, blr, isnull) = serde_json::Value::Null.to_value_blr_isnull();
+        assert_eq!(blr, vec![14, 0, 0]);
+        assert!(isnull);
+        let doc = serde_json::json!({"a": 1});
+        let (value, _, isnull) = ToSqlParam::to_value_blr_isnull(&&doc);
+        assert_eq!(&value[..7], br#"{"a":1}"#);
+        assert!(!isnull);
+    }
+
+    #[test]
+    fn test_legacy_bool() {
+        let v: bool = CellValue::Text("T".to_string()).to_val_legacy_bool().unwrap();
+        assert!(v);
+        let v: bool = CellValue::Varying("n".to_string()).to_val_legacy_bool().unwrap();
+        assert!(!v);
+        let v: bool = CellValue::Short(1).to_val_legacy_bool().unwrap();
+        assert!(v);
+        let v: Option<bool> = CellValue::Null.to_val_legacy_bool().unwrap();
+        assert_eq!(v, None);
+        let v: Result<bool, Error> = CellValue::Short(2).to_val_legacy_bool();
+        assert!(v.is_err());
+        // without the shim only BOOLEAN converts
+        let v: Result<bool, Error> = CellValue::Text("T".to_string()).to_val();
+        assert!(v.is_err());
+    }
+}
@@ -0,0 +1,842 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Rust structs generated from the schema
+//!
+//! `StructGenerator` describes tables and views and emits one struct per
+//! relation with a field per column, typed the way `Row::get` reads it
+//! (`Option<_>` for nullable columns), a `SELECT` constant listing the
+//! columns in field order and a `FromRow` impl. Regenerating after a
+//! migration keeps the field types in sync with the schema.
+//!
+//! `ProcedureGenerator` does the same for stored procedures: input and
+//! output structs and a function calling the procedure, so that calls are
+//! type checked against the declared parameters.
+//!
+//! The code can be written to a file kept under version control, or to
+//! `OUT_DIR` from a build script, which then needs a reachable database.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // build.rs
+//! use firebirust::StructGenerator;
+//!
+//! fn main() {
+//!     println!("cargo:rerun-if-env-changed=DATABASE_URL");
+//!     let url = std::env::var("DATABASE_URL").unwrap();
+//!     StructGenerator::new()
+//!         .tables(&["ORDERS", "ORDER_LINES", "V_OPEN_ORDERS"])
+//!         .build_script(&url, "schema.rs")
+//!         .unwrap();
+//! }
+//!
+//! // src/schema.rs
+//! include!(concat!(env!("OUT_DIR"), "/schema.rs"));
+//!
+//! // use
+//! let lines = sp_order_lines(&conn, &SpOrderLinesInput { order_id: Some(42) })?;
+//! let orders = conn
+//!     .prepare(Orders::SELECT)?
+//!     .query_map((), Orders::from_row)?
+//!     .collect::<Result<Vec<_>, _>>()?;
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use super::connection::Connection;
+use super::ddl::{object_name, quote};
+use super::error::{Error, ValueError};
+use super::sqltype::SqlType;
+use super::statement::ColumnInfo;
+
+const RELATIONS_SQL: &str = "SELECT RDB$RELATION_NAME FROM RDB$RELATIONS
+    WHERE (RDB$SYSTEM_FLAG IS NULL OR RDB$SYSTEM_FLAG = 0)
+    ORDER BY RDB$RELATION_NAME";
+
+const PROCEDURES_SQL: &str = "SELECT RDB$PROCEDURE_NAME, RDB$PROCEDURE_TYPE FROM RDB$PROCEDURES
+    WHERE (RDB$SYSTEM_FLAG IS NULL OR RDB$SYSTEM_FLAG = 0)
+      AND RDB$PACKAGE_NAME IS NULL
+    ORDER BY RDB$PROCEDURE_NAME";
+
+const PROCEDURE_PARAMETERS_SQL: &str = "SELECT RDB$PARAMETER_NAME, RDB$PARAMETER_TYPE,
+    RDB$FIELD_SOURCE, RDB$NULL_FLAG
+    FROM RDB$PROCEDURE_PARAMETERS
+    WHERE RDB$PROCEDURE_NAME = ? AND RDB$PACKAGE_NAME IS NULL
+    ORDER BY RDB$PARAMETER_TYPE, RDB$PARAMETER_NUMBER";
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type", "unsafe", "use",
+    "where", "while", "abstract", "become", "do", "final", "macro", "override", "priv", "typeof",
+    "unsized", "virtual", "yield",
+];
+
+/// Generator of structs with `FromRow` impls for tables and views
+#[derive(Debug, Clone)]
+pub struct StructGenerator {
+    tables: Vec<String>,
+    derives: Vec<String>,
+}
+
+impl StructGenerator {
+    /// Generator for all user tables and views, deriving `Debug`, `Clone`
+    /// and `PartialEq`
+    pub fn new() -> StructGenerator {
+        StructGenerator {
+            tables: Vec::new(),
+            derives: vec![
+                "Debug".to_string(),
+                "Clone".to_string(),
+                "PartialEq".to_string(),
+            ],
+        }
+    }
+
+    /// Generate a struct for `table` only (and other tables added). Unquoted
+    /// names are matched in upper case, `"quoted"` names as written
+    pub fn table(mut self, table: &str) -> Self {
+        self.tables.push(object_name(table));
+        self
+    }
+
+    pub fn tables(mut self, tables: &[&str]) -> Self {
+        self.tables.extend(tables.iter().map(|t| object_name(t)));
+        self
+    }
+
+    /// Add a derive to the generated structs, e.g. "serde::Serialize"
+    pub fn derive(mut self, derive: &str) -> Self {
+        self.derives.push(derive.to_string());
+        self
+    }
+
+    /// Source code of the structs, in table order
+    pub fn generate(&self, conn: &Connection) -> Result<String, Error> {
+        let tables = if self.tables.is_empty() {
+            let mut stmt = conn.prepare(RELATIONS_SQL)?;
+            let mut tables = Vec::new();
+            for row in stmt.query(())? {
+                tables.push(row.get::<String>(0)?.trim_end().to_string());
+            }
+            tables
+        } else {
+            self.tables.clone()
+        };
+
+        let mut code = String::from(
+            "// Generated by firebirust::StructGenerator from the database schema.\n\
+             // Do not edit: regenerate after changing the schema.\n",
+        );
+        for table in &tables {
+            let stmt = conn.prepare(&format!("SELECT * FROM {}", quote(table)))?;
+            code.push('\n');
+            code.push_str(&self.rust_struct(table, &stmt.description())?);
+        }
+        Ok(code)
+    }
+
+    /// Write the generated code to `path` unless the file already holds it,
+    /// so that its timestamp only changes with the schema. Returns whether
+    /// the file was written.
+    pub fn write<P: AsRef<Path>>(&self, conn: &Connection, path: P) -> Result<bool, Error> {
+        write_if_changed(path.as_ref(), &self.generate(conn)?)
+    }
+
+    /// For build scripts: connect to `url` and write the code to `file_name`
+    /// in `OUT_DIR`
+    pub fn build_script(&self, url: &str, file_name: &str) -> Result<(), Error> {
+        let path = out_dir_path(file_name)?;
+        self.write(&Connection::connect(url)?, path)?;
+        Ok(())
+    }
+
+    /// Struct, `SELECT` constant and `FromRow` impl of a relation with the
+    /// columns `columns`
+    pub fn rust_struct(&self, table: &str, columns: &[ColumnInfo]) -> Result<String, Error> {
+        let name = struct_name(table);
+        let fields = fields(table, columns)?;
+        let select = format!(
+            "SELECT {} FROM {}",
+            columns
+                .iter()
+                .map(|c| quote(&c.field_name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            quote(table)
+        );
+
+        let mut code = String::new();
+        write_struct(
+            &mut code,
+            &format!("Row of {}", table),
+            &self.derives,
+            &name,
+            columns,
+            &fields,
+        );
+        writeln!(code, "impl {} {{", name).unwrap();
+        writeln!(code, "    /// Query of the columns in field order").unwrap();
+        writeln!(code, "    pub const SELECT: &'static str = {:?};", select).unwrap();
+        writeln!(code, "}}\n").unwrap();
+        write_from_row(&mut code, &name, &fields);
+        Ok(code)
+    }
+}
+
+impl Default for StructGenerator {
+    fn default() -> Self {
+        StructGenerator::new()
+    }
+}
+
+/// An input or output parameter of a procedure
+#[derive(Debug, Clone)]
+struct ProcedureParam {
+    /// Described type, with the parameter name and nullability
+    column: ColumnInfo,
+    /// RDB$FIELD_SOURCE, the domain of the parameter
+    domain: String,
+}
+
+/// Generator of typed wrappers for stored procedures
+///
+/// Each procedure gets an input struct (if it has inputs), an output
+/// struct with a `FromRow` impl (if it has outputs) and a function named
+/// after it. A selectable procedure returns `Vec` of its output rows, an
+/// executable one its single output row, or `()` without outputs.
+#[derive(Debug, Clone)]
+pub struct ProcedureGenerator {
+    procedures: Vec<String>,
+    derives: Vec<String>,
+}
+
+impl ProcedureGenerator {
+    /// Generator for all procedures outside packages, deriving `Debug`,
+    /// `Clone` and `PartialEq`
+    pub fn new() -> ProcedureGenerator {
+        ProcedureGenerator {
+            procedures: Vec::new(),
+            derives: StructGenerator::new().derives,
+        }
+    }
+
+    /// Generate a wrapper for `procedure` only (and other procedures
+    /// added). Unquoted names are matched in upper case, `"quoted"` names
+    /// as written
+    pub fn procedure(mut self, procedure: &str) -> Self {
+        self.procedures.push(object_name(procedure));
+        self
+    }
+
+    pub fn procedures(mut self, procedures: &[&str]) -> Self {
+        self.procedures
+            .extend(procedures.iter().map(|p| object_name(p)));
+        self
+    }
+
+    /// Add a derive to the generated structs
+    pub fn derive(mut self, derive: &str) -> Self {
+        self.derives.push(derive.to_string());
+        self
+    }
+
+    /// Source code of the wrappers, in procedure order
+    pub fn generate(&self, conn: &Connection) -> Result<String, Error> {
+        let mut procedures = Vec::new();
+        let mut stmt = conn.prepare(PROCEDURES_SQL)?;
+        for row in stmt.query(())? {
+            let name = row.get::<String>(0)?.trim_end().to_string();
+            if self.procedures.is_empty() || self.procedures.contains(&name) {
+                // RDB$PROCEDURE_TYPE 1 is selectable, 2 executable
+                procedures.push((name, row.get::<Option<i16>>(1)? == Some(1)));
+            }
+        }
+        drop(stmt);
+        if let Some(missing) = self
+            .procedures
+            .iter()
+            .find(|p| !procedures.iter().any(|(name, _)| name == *p))
+        {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "procedure {} not found",
+                missing
+            ))));
+        }
+        if !self.procedures.is_empty() {
+            procedures.sort_by_key(|(name, _)| self.procedures.iter().position(|p| p == name));
+        }
+
+        let mut code = String::from(
+            "// Generated by firebirust::ProcedureGenerator from the database schema.\n\
+             // Do not edit: regenerate after changing the schema.\n",
+        );
+        for (procedure, selectable) in &procedures {
+            let (inputs, outputs) = procedure_params(conn, procedure)?;
+            code.push('\n');
+            code.push_str(&self.rust_procedure(procedure, *selectable, &inputs, &outputs)?);
+        }
+        Ok(code)
+    }
+
+    /// Write the generated code to `path` unless the file already holds it.
+    /// Returns whether the file was written.
+    pub fn write<P: AsRef<Path>>(&self, conn: &Connection, path: P) -> Result<bool, Error> {
+        write_if_changed(path.as_ref(), &self.generate(conn)?)
+    }
+
+    /// For build scripts: connect to `url` and write the code to `file_name`
+    /// in `OUT_DIR`
+    pub fn build_script(&self, url: &str, file_name: &str) -> Result<(), Error> {
+        let path = out_dir_path(file_name)?;
+        self.write(&Connection::connect(url)?, path)?;
+        Ok(())
+    }
+
+    fn rust_procedure(
+        &self,
+        procedure: &str,
+        selectable: bool,
+        inputs: &[ProcedureParam],
+        outputs: &[ProcedureParam],
+    ) -> Result<String, Error> {
+        let struct_name = struct_name(procedure);
+        let input_name = format!("{}Input", struct_name);
+        let output_name = format!("{}Output", struct_name);
+        let input_columns: Vec<ColumnInfo> = inputs.iter().map(|p| p.column.clone()).collect();
+        let output_columns: Vec<ColumnInfo> = outputs.iter().map(|p| p.column.clone()).collect();
+        let input_fields = fields(procedure, &input_columns)?;
+        let output_fields = fields(procedure, &output_columns)?;
+        let mut code = String::new();
+        if !inputs.is_empty() {
+            let doc = format!("Input parameters of {}", procedure);
+            write_struct(
+                &mut code,
+                &doc,
+                &self.derives,
+                &input_name,
+                &input_columns,
+                &input_fields,
+            );
+        }
+        if !outputs.is_empty() {
+            let doc = format!("Output parameters of {}", procedure);
+            write_struct(
+                &mut code,
+                &doc,
+                &self.derives,
+                &output_name,
+                &output_columns,
+                &output_fields,
+            );
+            write_from_row(&mut code, &output_name, &output_fields);
+            code.push('\n');
+        }
+
+        let (kind, ret) = match (selectable, outputs.is_empty()) {
+            (true, _) => ("Rows of the selectable", format!("Vec<{}>", output_name)),
+            (false, false) => ("Outputs of the executable", output_name.clone()),
+            (false, true) => ("Run the executable", "()".to_string()),
+        };
+        writeln!(code, "/// {} procedure {}", kind, procedure).unwrap();
+        writeln!(code, "pub fn {}(", identifier(field_name(procedure))).unwrap();
+        writeln!(code, "    conn: &firebirust::Connection,").unwrap();
+        if !inputs.is_empty() {
+            writeln!(code, "    input: &{},", input_name).unwrap();
+        }
+        writeln!(code, ") -> Result<{}, firebirust::Error> {{", ret).unwrap();
+
+        let placeholders = vec!["?"; inputs.len()].join(", ");
+        let call = if inputs.is_empty() {
+            quote(procedure)
+        } else {
+            format!("{}({})", quote(procedure), placeholders)
+        };
+        let param = |field: &str| format!("firebirust::Param::from(input.{}.clone())", field);
+        let params = if inputs.is_empty() { "()" } else { "params" };
+        if (selectable || outputs.is_empty()) && !inputs.is_empty() {
+            writeln!(code, "    let params: &[&dyn firebirust::ToSqlParam] = &[").unwrap();
+            for (field, _) in &input_fields {
+                writeln!(code, "        &{},", param(field)).unwrap();
+            }
+            writeln!(code, "    ];").unwrap();
+        }
+        if selectable {
+            let columns: Vec<String> = outputs
+                .iter()
+                .map(|p| quote(&p.column.field_name))
+                .collect();
+            let sql = format!("SELECT {} FROM {}", columns.join(", "), call);
+            writeln!(code, "    conn.prepare({:?})?", sql).unwrap();
+            writeln!(
+                code,
+                "        .query_map({}, <{} as firebirust::FromRow>::from_row)?",
+                params, output_name
+            )
+            .unwrap();
+            writeln!(code, "        .collect()").unwrap();
+        } else if outputs.is_empty() {
+            writeln!(
+                code,
+                "    conn.prepare({:?})?",
+                format!("EXECUTE PROCEDURE {}", call)
+            )
+            .unwrap();
+            writeln!(code, "        .execute({})", params).unwrap();
+        } else {
+            // no EXECUTE PROCEDURE output messages: returned through a block
+            let args: Vec<String> = (1..=inputs.len()).map(|i| format!(":P{}", i)).collect();
+            let results: Vec<String> = (1..=outputs.len()).map(|i| format!(":R{}", i)).collect();
+            let mut body = format!("EXECUTE PROCEDURE {}", quote(procedure));
+            if !args.is_empty() {
+                body.push_str(&format!("({})", args.join(", ")));
+            }
+            body.push_str(&format!(" RETURNING_VALUES {};", results.join(", ")));
+            writeln!(
+                code,
+                "    let mut rows = firebirust::ExecuteBlockBuilder::new()"
+            )
+            .unwrap();
+            for (i, (p, (field, _))) in inputs.iter().zip(&input_fields).enumerate() {
+                let domain = format!("TYPE OF {}", quote(&p.domain));
+                writeln!(
+                    code,
+                    "        .input_typed(\"P{}\", {:?}, {})",
+                    i + 1,
+                    domain,
+                    param(field)
+                )
+                .unwrap();
+            }
+            for (i, p) in outputs.iter().enumerate() {
+                let domain = format!("TYPE OF {}", quote(&p.domain));
+                writeln!(code, "        .output(\"R{}\", {:?})", i + 1, domain).unwrap();
+            }
+            writeln!(code, "        .body({:?})", body).unwrap();
+            writeln!(code, "        .query(conn)?;").unwrap();
+            writeln!(code, "    match rows.next() {{").unwrap();
+            writeln!(
+                code,
+                "        Some(row) => <{} as firebirust::FromRow>::from_row(&row),",
+                output_name
+            )
+            .unwrap();
+            writeln!(
+                code,
+                "        None => Err(firebirust::Error::ValueError(firebirust::ValueError::new({:?}))),",
+                format!("{} returned no outputs", procedure)
+            )
+            .unwrap();
+            writeln!(code, "    }}").unwrap();
+        }
+        writeln!(code, "}}").unwrap();
+        Ok(code)
+    }
+}
+
+impl Default for ProcedureGenerator {
+    fn default() -> Self {
+        ProcedureGenerator::new()
+    }
+}
+
+/// Inputs and outputs of a procedure, named and typed as declared
+fn procedure_params(
+    conn: &Connection,
+    procedure: &str,
+) -> Result<(Vec<ProcedureParam>, Vec<ProcedureParam>), Error> {
+    let mut stmt = conn.prepare(PROCEDURE_PARAMETERS_SQL)?;
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    for row in stmt.query((procedure,))? {
+        let name = row.get::<String>(0)?.trim_end().to_string();
+        let domain = row.get::<String>(2)?.trim_end().to_string();
+        let not_null = row.get::<Option<i16>>(3)? == Some(1);
+        let param = (name, domain, not_null);
+        // RDB$PARAMETER_TYPE 0 is an input, 1 an output
+        if row.get::<i16>(1)? == 0 {
+            inputs.push(param);
+        } else {
+            outputs.push(param);
+        }
+    }
+    drop(stmt);
+
+    // the types as the server describes them
+    let placeholders = vec!["?"; inputs.len()].join(", ");
+    let call = if inputs.is_empty() {
+        quote(procedure)
+    } else {
+        format!("{}({})", quote(procedure), placeholders)
+    };
+    let sql = if outputs.is_empty() {
+        format!("EXECUTE PROCEDURE {}", call)
+    } else {
+        format!("SELECT * FROM {}", call)
+    };
+    let mut stmt = conn.prepare(&sql)?;
+    let output_columns = stmt.description();
+    let input_columns = stmt.describe_input()?;
+    if input_columns.len() != inputs.len() || output_columns.len() != outputs.len() {
+        return Err(Error::ValueError(ValueError::new(&format!(
+            "parameters of {} don't match its description",
+            procedure
+        ))));
+    }
+    let named = |params: Vec<(String, String, bool)>, columns: Vec<ColumnInfo>| {
+        params
+            .into_iter()
+            .zip(columns)
+            .map(|((name, domain, not_null), mut column)| {
+                column.name = name.clone();
+                column.field_name = name;
+                column.nullable = !not_null;
+                ProcedureParam { column, domain }
+            })
+            .collect::<Vec<_>>()
+    };
+    Ok((named(inputs, input_columns), named(outputs, output_columns)))
+}
+
+fn write_if_changed(path: &Path, code: &str) -> Result<bool, Error> {
+    if std::fs::read_to_string(path).is_ok_and(|old| old == code) {
+        return Ok(false);
+    }
+    std::fs::write(path, code)?;
+    Ok(true)
+}
+
+fn out_dir_path(file_name: &str) -> Result<PathBuf, Error> {
+    let out_dir = std::env::var_os("OUT_DIR")
+        .ok_or_else(|| Error::ValueError(ValueError::new("OUT_DIR is not set")))?;
+    Ok(Path::new(&out_dir).join(file_name))
+}
+
+/// (field name, Rust type) of each column, field names made unique
+fn fields(relation: &str, columns: &[ColumnInfo]) -> Result<Vec<(String, String)>, Error> {
+    let mut used = HashSet::new();
+    let mut fields = Vec::with_capacity(columns.len());
+    for col in columns {
+        let mut field = field_name(&col.field_name);
+        while !used.insert(field.clone()) {
+            field.push('_');
+        }
+        fields.push((identifier(field), rust_type(relation, col)?));
+    }
+    Ok(fields)
+}
+
+fn write_struct(
+    code: &mut String,
+    doc: &str,
+    derives: &[String],
+    name: &str,
+    columns: &[ColumnInfo],
+    fields: &[(String, String)],
+) {
+    writeln!(code, "/// {}", doc).unwrap();
+    writeln!(code, "#[derive({})]", derives.join(", ")).unwrap();
+    writeln!(code, "pub struct {} {{", name).unwrap();
+    for (col, (field, ty)) in columns.iter().zip(fields) {
+        let null = if col.nullable { "" } else { " NOT NULL" };
+        writeln!(
+            code,
+            "    /// {} {}{}",
+            col.field_name,
+            sql_type_name(col),
+            null
+        )
+        .unwrap();
+        writeln!(code, "    pub {}: {},", field, ty).unwrap();
+    }
+    writeln!(code, "}}\n").unwrap();
+}
+
+fn write_from_row(code: &mut String, name: &str, fields: &[(String, String)]) {
+    writeln!(code, "impl firebirust::FromRow for {} {{", name).unwrap();
+    writeln!(
+        code,
+        "    fn from_row(row: &firebirust::Row) -> Result<Self, firebirust::Error> {{"
+    )
+    .unwrap();
+    writeln!(code, "        Ok({} {{", name).unwrap();
+    for (i, (field, _)) in fields.iter().enumerate() {
+        writeln!(code, "            {}: row.get({})?,", field, i).unwrap();
+    }
+    writeln!(code, "        }})").unwrap();
+    writeln!(code, "    }}").unwrap();
+    writeln!(code, "}}").unwrap();
+}
+
+/// Rust type `Row::get` reads a column as
+fn rust_type(table: &str, col: &ColumnInfo) -> Result<String, Error> {
+    let scaled = col.scale != 0;
+    let ty = match col.sql_type() {
+        SqlType::Char { .. } | SqlType::Varchar { .. } | SqlType::Blob { sub_type: 1 } => "String",
+        SqlType::Blob { .. } => "Vec<u8>",
+        SqlType::SmallInt | SqlType::Integer | SqlType::BigInt | SqlType::Int128 if scaled => {
+            "rust_decimal::Decimal"
+        }
+        SqlType::SmallInt => "i16",
+        SqlType::Integer => "i32",
+        SqlType::BigInt => "i64",
+        SqlType::Int128 => "i128",
+        SqlType::Float => "f32",
+        SqlType::DoublePrecision => "f64",
+        SqlType::Date => "chrono::NaiveDate",
+        SqlType::Time => "chrono::NaiveTime",
+        SqlType::Timestamp => "chrono::NaiveDateTime",
+        SqlType::TimeWithTimeZone => "(chrono::NaiveTime, chrono_tz::Tz)",
+        SqlType::TimestampWithTimeZone => "chrono::DateTime<chrono_tz::Tz>",
+        SqlType::Boolean => "bool",
+        SqlType::DecFloat16 | SqlType::DecFloat34 | SqlType::DecFixed => "rust_decimal::Decimal",
+        t @ (SqlType::Array | SqlType::Null | SqlType::Unknown(_)) => {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "column {} of {} has type {}, which has no Rust type",
+                col.field_name, table, t
+            ))));
+        }
+    };
+    Ok(if col.nullable {
+        format!("Option<{}>", ty)
+    } else {
+        ty.to_string()
+    })
+}
+
+/// DDL type of a column, with the precision and scale of NUMERIC
+fn sql_type_name(col: &ColumnInfo) -> String {
+    match col.sql_type() {
+        SqlType::SmallInt | SqlType::Integer | SqlType::BigInt | SqlType::Int128
+            if col.scale != 0 =>
+        {
+            format!("NUMERIC({}, {})", col.precision.unwrap_or(18), -col.scale)
+        }
+        t => t.to_string(),
+    }
+}
+
+/// "ORDER_LINES" -> "OrderLines"
+fn struct_name(table: &str) -> String {
+    let mut name = String::new();
+    for word in table
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+    {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.extend(first.to_uppercase());
+            name.extend(chars.flat_map(|c| c.to_lowercase()));
+        }
+    }
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, 'T');
+    }
+    name
+}
+
+/// "ORDER ID" -> "order_id"
+fn field_name(column: &str) -> String {
+    let mut name: String = column
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().next().unwrap()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Field name usable as an identifier: "type" -> "r#type"
+fn identifier(name: String) -> String {
+    match name.as_str() {
+        // not allowed as raw identifiers
+        "self" | "super" | "crate" | "_" => name + "_",
+        n if KEYWORDS.contains(&n) => format!("r#{}", name),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    fn column(name: &str, type_code: u32, scale: i32, nullable: bool) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            type_code,
+            sub_type: 0,
+            display_size: None,
+            internal_size: 8,
+            precision: Some(18),
+            scale,
+            nullable,
+            field_name: name.to_string(),
+            table_name: "ORDER_LINES".to_string(),
+            owner_name: "SYSDBA".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(struct_name("ORDER_LINES"), "OrderLines");
+        assert_eq!(struct_name("v$open orders"), "VOpenOrders");
+        assert_eq!(struct_name("2024"), "T2024");
+        assert_eq!(field_name("ORDER ID"), "order_id");
+        assert_eq!(identifier(field_name("TYPE")), "r#type");
+        assert_eq!(identifier(field_name("SELF")), "self_");
+        assert_eq!(field_name("1ST"), "_1st");
+    }
+
+    #[test]
+    fn test_rust_struct() {
+        let columns = vec![
+            column("ID", SQL_TYPE_LONG, 0, false),
+            column("AMOUNT", SQL_TYPE_INT64, -2, false),
+            column("TYPE", SQL_TYPE_SHORT, 0, true),
+            column("NOTE", SQL_TYPE_BLOB, 0, true),
+            column("type", SQL_TYPE_TIMESTAMP, 0, false),
+        ];
+        let code = StructGenerator::new()
+            .derive("serde::Serialize")
+            .rust_struct("ORDER_LINES", &columns)
+            .unwrap();
+        assert_eq!(
+            code,
+            r##"/// Row of ORDER_LINES
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct OrderLines {
+    /// ID INTEGER NOT NULL
+    pub id: i32,
+    /// AMOUNT NUMERIC(18, 2) NOT NULL
+    pub amount: rust_decimal::Decimal,
+    /// TYPE SMALLINT
+    pub r#type: Option<i16>,
+    /// NOTE BLOB SUB_TYPE BINARY
+    pub note: Option<Vec<u8>>,
+    /// type TIMESTAMP NOT NULL
+    pub type_: chrono::NaiveDateTime,
+}
+
+impl OrderLines {
+    /// Query of the columns in field order
+    pub const SELECT: &'static str = "SELECT \"ID\", \"AMOUNT\", \"TYPE\", \"NOTE\", \"type\" FROM \"ORDER_LINES\"";
+}
+
+impl firebirust::FromRow for OrderLines {
+    fn from_row(row: &firebirust::Row) -> Result<Self, firebirust::Error> {
+        Ok(OrderLines {
+            id: row.get(0)?,
+            amount: row.get(1)?,
+            r#type: row.get(2)?,
+            note: row.get(3)?,
+            type_: row.get(4)?,
+        })
+    }
+}
+"##
+        );
+
+        let array = column("TAGS", SQL_TYPE_ARRAY, 0, true);
+        assert!(StructGenerator::new().rust_struct("T", &[array]).is_err());
+    }
+
+    #[test]
+    fn test_rust_procedure() {
+        let param = |name: &str, type_code, nullable| ProcedureParam {
+            column: column(name, type_code, 0, nullable),
+            domain: format!("RDB${}", name.len()),
+        };
+        let generator = ProcedureGenerator::new();
+        let inputs = [param("ORDER_ID", SQL_TYPE_LONG, false)];
+        let outputs = [
+            param("LINE", SQL_TYPE_SHORT, true),
+            param("PRODUCT", SQL_TYPE_VARYING, true),
+        ];
+
+        let code = generator
+            .rust_procedure("SP_LINES", true, &inputs, &outputs)
+            .unwrap();
+        assert!(code.contains("pub struct SpLinesInput {\n    /// ORDER_ID INTEGER NOT NULL\n    pub order_id: i32,\n}"));
+        assert!(code.contains("impl firebirust::FromRow for SpLinesOutput {"));
+        assert!(code.contains(
+            r#"pub fn sp_lines(
+    conn: &firebirust::Connection,
+    input: &SpLinesInput,
+) -> Result<Vec<SpLinesOutput>, firebirust::Error> {
+    let params: &[&dyn firebirust::ToSqlParam] = &[
+        &firebirust::Param::from(input.order_id.clone()),
+    ];
+    conn.prepare("SELECT \"LINE\", \"PRODUCT\" FROM \"SP_LINES\"(?)")?
+        .query_map(params, <SpLinesOutput as firebirust::FromRow>::from_row)?
+        .collect()
+}"#
+        ));
+
+        let code = generator
+            .rust_procedure("SP_TOTAL", false, &inputs, &outputs[..1])
+            .unwrap();
+        assert!(code.contains(
+            r#"    let mut rows = firebirust::ExecuteBlockBuilder::new()
+        .input_typed("P1", "TYPE OF \"RDB$8\"", firebirust::Param::from(input.order_id.clone()))
+        .output("R1", "TYPE OF \"RDB$4\"")
+        .body("EXECUTE PROCEDURE \"SP_TOTAL\"(:P1) RETURNING_VALUES :R1;")
+        .query(conn)?;"#
+        ));
+
+        let code = generator
+            .rust_procedure("SP_CLEANUP", false, &[], &[])
+            .unwrap();
+        assert_eq!(
+            code,
+            r#"/// Run the executable procedure SP_CLEANUP
+pub fn sp_cleanup(
+    conn: &firebirust::Connection,
+) -> Result<(), firebirust::Error> {
+    conn.prepare("EXECUTE PROCEDURE \"SP_CLEANUP\"")?
+        .execute(())
+}
+"#
+        );
+
+        let tz = [param("AT", SQL_TYPE_TIMESTAMP_TZ, true)];
+        let code = generator.rust_procedure("SP_TZ", false, &tz, &[]).unwrap();
+        assert!(code.contains("pub at: Option<chrono::DateTime<chrono_tz::Tz>>,"));
+        let array = [param("TAGS", SQL_TYPE_ARRAY, true)];
+        assert!(
+            generator
+                .rust_procedure("SP_TAGS", false, &array, &[])
+                .is_err()
+        );
+    }
+}
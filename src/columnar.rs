@@ -0,0 +1,323 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Column oriented result sets
+//!
+//! `Rows::fetch_columnar` moves the values of a result set into one typed
+//! vector per column instead of one `Row` per record, the layout analytics
+//! code (aggregations, dataframes, Arrow arrays) wants. Each column is
+//! converted like `Row::get` would convert it; NULL becomes `None`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{ColumnData, ColumnKind};
+//!
+//! let mut stmt = conn.prepare("select region, amount from sales")?;
+//! let batch = stmt.query(())?.fetch_columnar(&[ColumnKind::Text, ColumnKind::F64])?;
+//! if let (ColumnData::Text(regions), ColumnData::F64(amounts)) = (&batch.columns()[0], &batch.columns()[1]) {
+//!     let total: f64 = amounts.iter().flatten().sum();
+//! }
+//! ```
+
+use super::cellvalue::{CellValue, CellValueToVal};
+use super::error::{ConversionError, Error, ValueError};
+use super::statement::ColumnInfo;
+use std::collections::VecDeque;
+
+/// Rust type a column is decoded into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnKind {
+    I64,
+    F64,
+    Bool,
+    Text,
+    Bytes,
+    Decimal,
+    Date,
+    Time,
+    Timestamp,
+}
+
+impl ColumnKind {
+    fn rust_type(self) -> &'static str {
+        match self {
+            ColumnKind::I64 => "i64",
+            ColumnKind::F64 => "f64",
+            ColumnKind::Bool => "bool",
+            ColumnKind::Text => "String",
+            ColumnKind::Bytes => "Vec<u8>",
+            ColumnKind::Decimal => "rust_decimal::Decimal",
+            ColumnKind::Date => "chrono::NaiveDate",
+            ColumnKind::Time => "chrono::NaiveTime",
+            ColumnKind::Timestamp => "chrono::NaiveDateTime",
+        }
+    }
+}
+
+/// Values of one column, `None` for NULL
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnData {
+    I64(Vec<Option<i64>>),
+    F64(Vec<Option<f64>>),
+    Bool(Vec<Option<bool>>),
+    Text(Vec<Option<String>>),
+    Bytes(Vec<Option<Vec<u8>>>),
+    Decimal(Vec<Option<rust_decimal::Decimal>>),
+    Date(Vec<Option<chrono::NaiveDate>>),
+    Time(Vec<Option<chrono::NaiveTime>>),
+    Timestamp(Vec<Option<chrono::NaiveDateTime>>),
+}
+
+impl ColumnData {
+    fn with_capacity(kind: ColumnKind, n: usize) -> ColumnData {
+        match kind {
+            ColumnKind::I64 => ColumnData::I64(Vec::with_capacity(n)),
+            ColumnKind::F64 => ColumnData::F64(Vec::with_capacity(n)),
+            ColumnKind::Bool => ColumnData::Bool(Vec::with_capacity(n)),
+            ColumnKind::Text => ColumnData::Text(Vec::with_capacity(n)),
+            ColumnKind::Bytes => ColumnData::Bytes(Vec::with_capacity(n)),
+            ColumnKind::Decimal => ColumnData::Decimal(Vec::with_capacity(n)),
+            ColumnKind::Date => ColumnData::Date(Vec::with_capacity(n)),
+            ColumnKind::Time => ColumnData::Time(Vec::with_capacity(n)),
+            ColumnKind::Timestamp => ColumnData::Timestamp(Vec::with_capacity(n)),
+        }
+    }
+
+    fn push(&mut self, cell: CellValue, legacy_bool: bool) -> Result<(), Error> {
+        match self {
+            ColumnData::I64(v) => push(v, cell, legacy_bool),
+            ColumnData::F64(v) => push(v, cell, legacy_bool),
+            ColumnData::Bool(v) => push(v, cell, legacy_bool),
+            ColumnData::Text(v) => push(v, cell, legacy_bool),
+            ColumnData::Bytes(v) => push(v, cell, legacy_bool),
+            ColumnData::Decimal(v) => push(v, cell, legacy_bool),
+            ColumnData::Date(v) => push(v, cell, legacy_bool),
+            ColumnData::Time(v) => push(v, cell, legacy_bool),
+            ColumnData::Timestamp(v) => push(v, cell, legacy_bool),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnData::I64(v) => v.len(),
+            ColumnData::F64(v) => v.len(),
+            ColumnData::Bool(v) => v.len(),
+            ColumnData::Text(v) => v.len(),
+            ColumnData::Bytes(v) => v.len(),
+            ColumnData::Decimal(v) => v.len(),
+            ColumnData::Date(v) => v.len(),
+            ColumnData::Time(v) => v.len(),
+            ColumnData::Timestamp(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of NULL values
+    pub fn null_count(&self) -> usize {
+        fn nulls<T>(v: &[Option<T>]) -> usize {
+            v.iter().filter(|x| x.is_none()).count()
+        }
+        match self {
+            ColumnData::I64(v) => nulls(v),
+            ColumnData::F64(v) => nulls(v),
+            ColumnData::Bool(v) => nulls(v),
+            ColumnData::Text(v) => nulls(v),
+            ColumnData::Bytes(v) => nulls(v),
+            ColumnData::Decimal(v) => nulls(v),
+            ColumnData::Date(v) => nulls(v),
+            ColumnData::Time(v) => nulls(v),
+            ColumnData::Timestamp(v) => nulls(v),
+        }
+    }
+}
+
+fn push<T>(values: &mut Vec<Option<T>>, cell: CellValue, legacy_bool: bool) -> Result<(), Error>
+where
+    CellValue: CellValueToVal<T>,
+{
+    let value = if legacy_bool {
+        CellValueToVal::<Option<T>>::to_val_legacy_bool(cell)?
+    } else {
+        CellValueToVal::<Option<T>>::to_val(cell)?
+    };
+    values.push(value);
+    Ok(())
+}
+
+/// A result set stored column by column
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnarBatch {
+    names: Vec<String>,
+    columns: Vec<ColumnData>,
+    len: usize,
+}
+
+impl ColumnarBatch {
+    /// Decode `rows` with one `ColumnKind` per column
+    pub(crate) fn decode(
+        rows: VecDeque<Vec<CellValue>>,
+        columns: &[ColumnInfo],
+        schema: &[ColumnKind],
+        legacy_bool: bool,
+        // rows already consumed, for the row numbers of errors
+        skipped: usize,
+    ) -> Result<ColumnarBatch, Error> {
+        if schema.len() != columns.len() {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "schema has {} columns, the result set {}",
+                schema.len(),
+                columns.len()
+            ))));
+        }
+        let len = rows.len();
+        let mut data: Vec<ColumnData> = schema
+            .iter()
+            .map(|kind| ColumnData::with_capacity(*kind, len))
+            .collect();
+        for (n, row) in rows.into_iter().enumerate() {
+            for (idx, cell) in row.into_iter().enumerate() {
+                data[idx].push(cell, legacy_bool).map_err(|e| match e {
+                    Error::ValueError(e) => Error::Conversion(ConversionError {
+                        column: columns[idx].name.clone(),
+                        index: idx,
+                        sql_type: columns[idx].type_name(),
+                        rust_type: schema[idx].rust_type(),
+                        row: skipped + n + 1,
+                        message: e.message,
+                    }),
+                    e => e,
+                })?;
+            }
+        }
+        Ok(ColumnarBatch {
+            names: columns.iter().map(|c| c.name.clone()).collect(),
+            columns: data,
+            len,
+        })
+    }
+
+    /// Number of rows
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn columns(&self) -> &[ColumnData] {
+        &self.columns
+    }
+
+    /// The column named `name` (alias if available)
+    pub fn column(&self, name: &str) -> Option<&ColumnData> {
+        self.names
+            .iter()
+            .position(|n| n == name)
+            .map(|idx| &self.columns[idx])
+    }
+
+    /// Column names and values, taking ownership of the vectors
+    pub fn into_columns(self) -> Vec<(String, ColumnData)> {
+        self.names.into_iter().zip(self.columns).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, type_code: u32) -> ColumnInfo {
+        ColumnInfo {
+            name: name.to_string(),
+            type_code,
+            sub_type: 0,
+            display_size: None,
+            internal_size: 8,
+            precision: None,
+            scale: 0,
+            nullable: true,
+            field_name: name.to_string(),
+            table_name: "T".to_string(),
+            owner_name: "SYSDBA".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let columns = [
+            column("ID", crate::SQL_TYPE_INT64),
+            column("NAME", crate::SQL_TYPE_VARYING),
+        ];
+        let rows = VecDeque::from(vec![
+            vec![CellValue::Int64(1), CellValue::Varying("one".to_string())],
+            vec![CellValue::Long(2), CellValue::Null],
+        ]);
+        let batch = ColumnarBatch::decode(
+            rows,
+            &columns,
+            &[ColumnKind::I64, ColumnKind::Text],
+            false,
+            0,
+        )
+        .unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch.names(), ["ID", "NAME"]);
+        assert_eq!(batch.columns()[0], ColumnData::I64(vec![Some(1), Some(2)]));
+        assert_eq!(
+            batch.column("NAME"),
+            Some(&ColumnData::Text(vec![Some("one".to_string()), None]))
+        );
+        assert_eq!(batch.column("NAME").unwrap().null_count(), 1);
+        assert_eq!(batch.column("NOPE"), None);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let columns = [column("ID", crate::SQL_TYPE_INT64)];
+        let rows = || {
+            VecDeque::from(vec![
+                vec![CellValue::Int64(1)],
+                vec![CellValue::Boolean(true)],
+            ])
+        };
+        match ColumnarBatch::decode(rows(), &columns, &[ColumnKind::I64], false, 3) {
+            Err(Error::Conversion(e)) => {
+                assert_eq!((e.column.as_str(), e.row, e.rust_type), ("ID", 5, "i64"));
+            }
+            _ => panic!("boolean is not an i64"),
+        }
+        assert!(matches!(
+            ColumnarBatch::decode(rows(), &columns, &[], false, 0),
+            Err(Error::ValueError(_))
+        ));
+    }
+}
@@ -26,12 +26,87 @@
 //! This module provides zlib-based compression for the Firebird wire protocol.
 //! Compression is negotiated during the connection handshake and, if enabled,
 //! all subsequent packets are compressed using zlib deflate/inflate.
+//!
+//! A zstd codec is available with the `zstd` feature. The Firebird protocol
+//! (up to 5.0) only negotiates zlib, so `compress=zstd` falls back to zlib
+//! until a server advertises zstd.
+//!
+//! Servers with a custom compression plugin are supported by registering a
+//! `WireCodec` and its accept_type flag on `ConnectOptions`.
 
 use flate2::write::{ZlibDecoder, ZlibEncoder};
 use flate2::Compression;
 use std::io::Write;
 
 use super::error::Error;
+use super::*;
+
+/// Wire compression requested with the `compress` URL parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressionKind {
+    Off,
+    Zlib,
+    Zstd,
+}
+
+impl CompressionKind {
+    /// "true", "1" and "zlib" select zlib, "zstd" zstd, anything else is off
+    pub fn from_option(value: &str) -> CompressionKind {
+        match value.to_ascii_lowercase().as_str() {
+            "true" | "1" | "zlib" => CompressionKind::Zlib,
+            "zstd" => CompressionKind::Zstd,
+            _ => CompressionKind::Off,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressionKind::Off => "off",
+            CompressionKind::Zlib => "zlib",
+            CompressionKind::Zstd => "zstd",
+        }
+    }
+}
+
+// accept_type flag for zstd; no Firebird release defines one yet
+const PFLAG_COMPRESS_ZSTD: Option<u32> = None;
+
+/// Compression used on a connection, given what the client asked for and the
+/// server's accept_type. Falls back to zlib, then to no compression.
+pub fn negotiate(requested: CompressionKind, accept_type: u32) -> CompressionKind {
+    let zstd_accepted = PFLAG_COMPRESS_ZSTD.is_some_and(|flag| accept_type & flag != 0);
+    match requested {
+        CompressionKind::Off => CompressionKind::Off,
+        CompressionKind::Zstd if cfg!(feature = "zstd") && zstd_accepted => CompressionKind::Zstd,
+        _ if accept_type & PFLAG_COMPRESS != 0 => CompressionKind::Zlib,
+        _ => CompressionKind::Off,
+    }
+}
+
+/// Codec of a custom compression plugin, see `ConnectOptions::register_compression`
+///
+/// Like zlib, the codec sees the packets in order and may keep its
+/// dictionary across them.
+pub trait WireCodec: Send {
+    fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>, Error>;
+    fn decompress(&mut self, data: &[u8]) -> Result<Vec<u8>, Error>;
+}
+
+enum Codec {
+    Zlib {
+        encoder: ZlibEncoder<Vec<u8>>,
+        decoder: ZlibDecoder<Vec<u8>>,
+    },
+    #[cfg(feature = "zstd")]
+    Zstd {
+        encoder: zstd::stream::write::Encoder<'static, Vec<u8>>,
+        decoder: zstd::stream::write::Decoder<'static, Vec<u8>>,
+    },
+    Custom {
+        name: String,
+        codec: Box<dyn WireCodec>,
+    },
+}
 
 /// Wire compressor using zlib (deflate/inflate)
 ///
@@ -39,16 +114,51 @@ use super::error::Error;
 /// that persists across packets. Each packet is compressed incrementally
 /// and ends with a Z_SYNC_FLUSH marker.
 pub struct WireCompressor {
-    encoder: ZlibEncoder<Vec<u8>>,
-    decoder: ZlibDecoder<Vec<u8>>,
+    codec: Codec,
 }
 
 impl WireCompressor {
     /// Create a new wire compressor with default compression level
     pub fn new() -> Self {
         Self {
-            encoder: ZlibEncoder::new(Vec::new(), Compression::default()),
-            decoder: ZlibDecoder::new(Vec::new()),
+            codec: Codec::Zlib {
+                encoder: ZlibEncoder::new(Vec::new(), Compression::default()),
+                decoder: ZlibDecoder::new(Vec::new()),
+            },
+        }
+    }
+
+    /// Compressor for a negotiated kind (zlib when zstd is not compiled in)
+    pub fn for_kind(kind: CompressionKind) -> Result<Self, Error> {
+        match kind {
+            #[cfg(feature = "zstd")]
+            CompressionKind::Zstd => Ok(Self {
+                codec: Codec::Zstd {
+                    encoder: zstd::stream::write::Encoder::new(Vec::new(), 0)?,
+                    decoder: zstd::stream::write::Decoder::new(Vec::new())?,
+                },
+            }),
+            _ => Ok(Self::new()),
+        }
+    }
+
+    /// Compressor for a registered custom codec
+    pub fn custom(name: &str, codec: Box<dyn WireCodec>) -> Self {
+        Self {
+            codec: Codec::Custom {
+                name: name.to_string(),
+                codec,
+            },
+        }
+    }
+
+    /// "zlib", "zstd" or the name a custom codec was registered with
+    pub fn name(&self) -> &str {
+        match &self.codec {
+            Codec::Zlib { .. } => CompressionKind::Zlib.name(),
+            #[cfg(feature = "zstd")]
+            Codec::Zstd { .. } => CompressionKind::Zstd.name(),
+            Codec::Custom { name, .. } => name,
         }
     }
 
@@ -57,19 +167,30 @@ impl WireCompressor {
     /// The compression maintains state across calls (streaming compression),
     /// which matches Firebird's wire compression behavior.
     pub fn compress(&mut self, data: &[u8]) -> Result<Vec<u8>, Error> {
-        // Reset output buffer
-        self.encoder.get_mut().clear();
+        match &mut self.codec {
+            Codec::Zlib { encoder, .. } => {
+                // Reset output buffer
+                encoder.get_mut().clear();
 
-        // Write data to compressor
-        self.encoder.write_all(data)?;
+                // Write data to compressor
+                encoder.write_all(data)?;
 
-        // Flush with sync flush to get compressed data
-        self.encoder.flush()?;
+                // Flush with sync flush to get compressed data
+                encoder.flush()?;
 
-        // Get compressed data
-        let compressed = self.encoder.get_ref().clone();
-
-        Ok(compressed)
+                // Get compressed data
+                Ok(encoder.get_ref().clone())
+            }
+            #[cfg(feature = "zstd")]
+            Codec::Zstd { encoder, .. } => {
+                encoder.get_mut().clear();
+                encoder.write_all(data)?;
+                // ends the block so the peer can decode the packet now
+                encoder.flush()?;
+                Ok(encoder.get_ref().clone())
+            }
+            Codec::Custom { codec, .. } => codec.compress(data),
+        }
     }
 
     /// Decompress data using zlib inflate
@@ -77,19 +198,29 @@ impl WireCompressor {
     /// The decompression maintains state across calls (streaming decompression),
     /// which matches Firebird's wire compression behavior.
     pub fn decompress(&mut self, data: &[u8]) -> Result<Vec<u8>, Error> {
-        // Reset output buffer
-        self.decoder.get_mut().clear();
+        match &mut self.codec {
+            Codec::Zlib { decoder, .. } => {
+                // Reset output buffer
+                decoder.get_mut().clear();
 
-        // Write compressed data to decoder
-        self.decoder.write_all(data)?;
+                // Write compressed data to decoder
+                decoder.write_all(data)?;
 
-        // Flush to get decompressed data
-        self.decoder.flush()?;
+                // Flush to get decompressed data
+                decoder.flush()?;
 
-        // Get decompressed data
-        let decompressed = self.decoder.get_ref().clone();
-
-        Ok(decompressed)
+                // Get decompressed data
+                Ok(decoder.get_ref().clone())
+            }
+            #[cfg(feature = "zstd")]
+            Codec::Zstd { decoder, .. } => {
+                decoder.get_mut().clear();
+                decoder.write_all(data)?;
+                decoder.flush()?;
+                Ok(decoder.get_ref().clone())
+            }
+            Codec::Custom { codec, .. } => codec.decompress(data),
+        }
     }
 }
 
@@ -139,4 +270,31 @@ mod tests {
 
         assert_eq!(original, decompressed);
     }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(CompressionKind::from_option("true"), CompressionKind::Zlib);
+        assert_eq!(CompressionKind::from_option("zstd"), CompressionKind::Zstd);
+        assert_eq!(CompressionKind::from_option("false"), CompressionKind::Off);
+        assert_eq!(CompressionKind::from_option("off"), CompressionKind::Off);
+
+        let accepted = PTYPE_LAZY_SEND | PFLAG_COMPRESS;
+        assert_eq!(negotiate(CompressionKind::Zlib, accepted), CompressionKind::Zlib);
+        // servers only offer zlib
+        assert_eq!(negotiate(CompressionKind::Zstd, accepted), CompressionKind::Zlib);
+        assert_eq!(negotiate(CompressionKind::Zstd, PTYPE_LAZY_SEND), CompressionKind::Off);
+        assert_eq!(negotiate(CompressionKind::Off, accepted), CompressionKind::Off);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_packets() {
+        let mut compressor = WireCompressor::for_kind(CompressionKind::Zstd).unwrap();
+        let mut decompressor = WireCompressor::for_kind(CompressionKind::Zstd).unwrap();
+        assert_eq!(compressor.name(), "zstd");
+        for packet in [&b"op_prepare select 1 from rdb$database"[..], &[7u8; 5000]] {
+            let compressed = compressor.compress(packet).unwrap();
+            assert_eq!(decompressor.decompress(&compressed).unwrap(), packet);
+        }
+    }
 }
@@ -22,44 +22,67 @@
 
 #![allow(dead_code)]
 
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
 
+use super::array::ArrayDesc;
 use super::cellvalue::CellValue;
 use super::conn_params::ConnParams;
-use super::error::Error;
+use super::deadline::{Deadline, DeadlineOutcome};
+use super::error::{ContentionStats, Error, limit_option};
+use super::kill_timeout::KillWatchdog;
 use super::params::Params;
-use super::statement::Statement;
+use super::resources::{LeakDetection, ResourceStats};
+use super::retry::{self, RetryClass, RetryPolicy};
+use super::statement::{self, Statement};
 use super::transaction::*;
 use super::wireprotocol::*;
+use super::wirechannel::WireStats;
 use super::xsqlvar::XSQLVar;
 use super::*;
 
+/// Rows of one op_fetch and whether more rows remain
+pub(crate) type FetchBatch = (Vec<Vec<CellValue>>, bool);
+
 pub struct Connection {
     wp: RefCell<WireProtocol>,
     trans_handle: i32, // transaction for operating from connection methods
     conn_params: ConnParams,
     conn_options: HashMap<String, String>,
+    contention_stats: RefCell<ContentionStats>,
+    // used by transaction() and the other transactions the connection starts
+    default_tx_options: Option<TransactionOptions>,
+    slow_query_log: Option<SlowQueryLog>,
+    kill_watchdog: Option<KillWatchdog>,
+    // rejects statements that write, see `set_read_only`
+    read_only: bool,
 }
 
 impl Connection {
     pub fn connect(conn_string: &str) -> Result<Connection, Error> {
+        Connection::connect_with_options(conn_string, &ConnectOptions::new())
+    }
+
+    /// Connect with custom crypt, auth or compression plugins
+    pub fn connect_with_options(
+        conn_string: &str,
+        plugins: &ConnectOptions,
+    ) -> Result<Connection, Error> {
         let (conn_params, conn_options) = ConnParams::from_url(conn_string)?;
-        let mut wp = WireProtocol::new(&conn_params, &conn_options)?;
-        let (client_public, client_secret) = srp::get_client_seed();
+        let mut wp = WireProtocol::new(&conn_params, &conn_options, plugins)?;
         wp.op_connect(
             &conn_params.db_name,
             &conn_params.username,
             &conn_params.password,
             &conn_options,
-            &client_public,
         )?;
         wp.parse_connect_response(
             &conn_params.username,
             &conn_params.password,
             &conn_options,
-            &client_public,
-            &client_secret,
         )?;
 
         wp.op_attach(
@@ -73,32 +96,45 @@ impl Connection {
 
         wp.op_transaction(true)?;
         let (trans_handle, _, _) = wp.op_response()?;
+        wp.attached();
 
-        Ok(Connection {
+        let read_only = conn_options["read_only"] == "true";
+        let conn = Connection {
             wp: RefCell::new(wp),
             trans_handle,
             conn_params,
             conn_options,
-        })
+            contention_stats: RefCell::new(ContentionStats::default()),
+            default_tx_options: None,
+            slow_query_log: None,
+            kill_watchdog: None,
+            read_only,
+        };
+        conn.run_init_script()?;
+        Ok(conn)
     }
 
     pub fn create_database(conn_string: &str) -> Result<Connection, Error> {
+        Connection::create_database_with_options(conn_string, &ConnectOptions::new())
+    }
+
+    /// Create a database with custom crypt, auth or compression plugins
+    pub fn create_database_with_options(
+        conn_string: &str,
+        plugins: &ConnectOptions,
+    ) -> Result<Connection, Error> {
         let (conn_params, conn_options) = ConnParams::from_url(conn_string)?;
-        let mut wp = WireProtocol::new(&conn_params, &conn_options)?;
-        let (client_public, client_secret) = srp::get_client_seed();
+        let mut wp = WireProtocol::new(&conn_params, &conn_options, plugins)?;
         wp.op_connect(
             &conn_params.db_name,
             &conn_params.username,
             &conn_params.password,
             &conn_options,
-            &client_public,
         )?;
         wp.parse_connect_response(
             &conn_params.username,
             &conn_params.password,
             &conn_options,
-            &client_public,
-            &client_secret,
         )?;
 
         let page_size: u32 = conn_options["page_size"].parse().unwrap();
@@ -115,18 +151,35 @@ impl Connection {
 
         wp.op_transaction(true)?;
         let (trans_handle, _, _) = wp.op_response()?;
+        wp.attached();
 
+        let read_only = conn_options["read_only"] == "true";
         Ok(Connection {
             wp: RefCell::new(wp),
             trans_handle,
             conn_params,
             conn_options,
+            contention_stats: RefCell::new(ContentionStats::default()),
+            default_tx_options: None,
+            slow_query_log: None,
+            kill_watchdog: None,
+            read_only,
         })
     }
 
-    pub(crate) fn _execute_batch(&mut self, query: &str, trans_handle: i32) -> Result<(), Error> {
+    pub(crate) fn _execute_batch(&self, query: &str, trans_handle: i32) -> Result<(), Error> {
+        if self.read_only {
+            let stmt = self._prepare(query, trans_handle, false)?;
+            statement::check_read_only(true, stmt.kind())?;
+        }
+        self._execute_committed(query, trans_handle)
+    }
+
+    /// Execute and commit retaining, whatever `read_only` says
+    fn _execute_committed(&self, query: &str, trans_handle: i32) -> Result<(), Error> {
+        let (query, _) = self.translate(query);
         let mut wp = self.wp.borrow_mut();
-        wp.op_exec_immediate(trans_handle, query)?;
+        wp.op_exec_immediate(trans_handle, &query)?;
         wp.op_response()?;
 
         // commit automatically
@@ -136,16 +189,52 @@ impl Connection {
         Ok(())
     }
 
+    /// Execute without commit (savepoint handling etc.)
+    pub(crate) fn _execute_immediate(&self, query: &str, trans_handle: i32) -> Result<(), Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_exec_immediate(trans_handle, query)?;
+        wp.op_response()?;
+        Ok(())
+    }
+
     pub fn execute_batch(&mut self, query: &str) -> Result<(), Error> {
-        self._execute_batch(query, self.trans_handle)
+        let start = std::time::Instant::now();
+        self._execute_batch(query, self.trans_handle)?;
+        if let Some(log) = &self.slow_query_log {
+            log.record(query, &[], start.elapsed());
+        }
+        Ok(())
+    }
+
+    /// Run a script of several statements ending with `;` (`SET TERM`
+    /// changes the terminator around PSQL bodies), each committed as it
+    /// runs. Stops at the first failing statement.
+    pub fn execute_script(&mut self, script: &str) -> Result<(), Error> {
+        for statement in script::split_script(script)? {
+            self.execute_batch(statement)?;
+        }
+        Ok(())
+    }
+
+    /// Run the `ConnectOptions::init_script`, if any. Session settings are
+    /// allowed on read-only connections too.
+    fn run_init_script(&self) -> Result<(), Error> {
+        let Some(init_script) = self.wp.borrow().init_script() else {
+            return Ok(());
+        };
+        for statement in script::split_script(&init_script)? {
+            self._execute_committed(statement, self.trans_handle)?;
+        }
+        Ok(())
     }
 
     pub(crate) fn _execute<P: Params>(
-        &mut self,
+        &self,
         query: &str,
         params: P,
         trans_handle: i32,
     ) -> Result<(), Error> {
+        let (query, param_order) = self.translate(query);
         let mut stmt = {
             let mut wp = self.wp.borrow_mut();
             wp.op_allocate_statement()?;
@@ -158,7 +247,7 @@ impl Connection {
                 stmt_handle
             };
 
-            wp.op_prepare_statement(stmt_handle, trans_handle, query)?;
+            wp.op_prepare_statement(stmt_handle, trans_handle, &query)?;
             if (wp.accept_type & PTYPE_MASK) == PTYPE_LAZY_SEND && wp.lazy_response_count > 0 {
                 wp.lazy_response_count -= 1;
                 let (h, _, _) = wp.op_response()?;
@@ -167,7 +256,8 @@ impl Connection {
             let (_, buf, _) = wp.op_response()?;
             let (stmt_type, xsqlda) = wp.parse_xsqlda(&buf, stmt_handle)?;
 
-            Statement::new(self, trans_handle, stmt_handle, stmt_type, xsqlda, true)
+            Statement::new(self, &query, trans_handle, stmt_handle, stmt_type, xsqlda, true)
+                .with_param_order(param_order)
         };
 
         stmt.execute(params)?;
@@ -179,6 +269,54 @@ impl Connection {
         self._execute(query, params, self.trans_handle)
     }
 
+    /// Prepare once and execute for each parameter set in a single transaction.
+    /// Returns the total number of affected rows.
+    pub fn execute_many<P, I>(&mut self, query: &str, params: I) -> Result<usize, Error>
+    where
+        P: Params,
+        I: IntoIterator<Item = P>,
+    {
+        self.execute_many_chunked(query, params, 0)
+    }
+
+    /// Like `execute_many`, but commits every `commit_every` executions (0 = only at the end).
+    /// On error the executions since the last commit are rolled back.
+    pub fn execute_many_chunked<P, I>(
+        &mut self,
+        query: &str,
+        params: I,
+        commit_every: usize,
+    ) -> Result<usize, Error>
+    where
+        P: Params,
+        I: IntoIterator<Item = P>,
+    {
+        let trans_handle = self._begin_trans()?;
+        let result = (|| {
+            let mut stmt = self._prepare(query, trans_handle, false)?;
+            let mut total = 0;
+            for (i, p) in params.into_iter().enumerate() {
+                stmt.execute(p)?;
+                total += stmt.rowcount();
+                if commit_every > 0 && (i + 1) % commit_every == 0 {
+                    stmt.commit_retaining()?;
+                }
+            }
+            Ok(total)
+        })();
+        match result {
+            Ok(total) => {
+                self._commit_final(trans_handle)?;
+                Ok(total)
+            }
+            Err(e) => {
+                // the execution error is the one worth reporting
+                let _ = self._rollback_final(trans_handle);
+                Err(e)
+            }
+        }
+    }
+
     pub(crate) fn _commit(&self, trans_handle: i32) -> Result<(), Error> {
         let mut wp = self.wp.borrow_mut();
         wp.op_commit_retaining(trans_handle)?;
@@ -194,24 +332,169 @@ impl Connection {
         Ok(())
     }
 
+    /// Prepare phase of a two-phase commit
+    pub(crate) fn _prepare_2pc(&self, trans_handle: i32, message: &[u8]) -> Result<(), Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_prepare2(trans_handle, message)?;
+        wp.op_response()?;
+        Ok(())
+    }
+
     pub fn commit(&self) -> Result<(), Error> {
         self._commit(self.trans_handle)
     }
 
-    pub(crate) fn _begin_trans(&mut self) -> Result<i32, Error> {
+    pub(crate) fn _begin_trans(&self) -> Result<i32, Error> {
+        if let Some(options) = &self.default_tx_options {
+            return self._begin_trans_with_options(options);
+        }
         let mut wp = self.wp.borrow_mut();
         wp.op_transaction(false)?;
         let (trans_handle, _, _) = wp.op_response()?;
         Ok(trans_handle)
     }
 
-    pub(crate) fn _begin_trans_with_options(&mut self, options: &super::transaction::TransactionOptions) -> Result<i32, Error> {
-        let mut wp = self.wp.borrow_mut();
-        wp.op_transaction_with_options(options)?;
-        let (trans_handle, _, _) = wp.op_response()?;
+    pub(crate) fn _begin_trans_with_options(&self, options: &TransactionOptions) -> Result<i32, Error> {
+        let trans_handle = {
+            let mut wp = self.wp.borrow_mut();
+            wp.op_transaction_with_options(options)?;
+            let (trans_handle, _, _) = wp.op_response()?;
+            trans_handle
+        };
+        if let Err(e) = self._set_tag(options, trans_handle) {
+            self._rollback_final(trans_handle)?;
+            return Err(e);
+        }
         Ok(trans_handle)
     }
 
+    /// Record the tag of `options` in the transaction, if any
+    fn _set_tag(&self, options: &TransactionOptions, trans_handle: i32) -> Result<(), Error> {
+        match &options.tag {
+            Some(tag) => self._execute(SET_TAG_SQL, (tag.as_str(),), trans_handle),
+            None => Ok(()),
+        }
+    }
+
+    /// Options for `transaction()` and the transactions the connection starts
+    /// itself (`execute_many`, `truncate_table`, ...); its own autocommit
+    /// transaction is committed and started again with them, so statements
+    /// run through the connection see them too.
+    pub fn set_default_transaction_options(&mut self, options: TransactionOptions) -> Result<(), Error> {
+        self._commit_final(self.trans_handle)?;
+        let trans_handle = {
+            let mut wp = self.wp.borrow_mut();
+            wp.op_transaction_autocommit_with_options(&options)?;
+            let (trans_handle, _, _) = wp.op_response()?;
+            trans_handle
+        };
+        self.trans_handle = trans_handle;
+        self._set_tag(&options, trans_handle)?;
+        self.default_tx_options = Some(options);
+        Ok(())
+    }
+
+    pub fn default_transaction_options(&self) -> Option<&TransactionOptions> {
+        self.default_tx_options.as_ref()
+    }
+
+    /// Log statements slower than the threshold of `log`, `None` turns the log off
+    pub fn set_slow_query_log(&mut self, log: Option<SlowQueryLog>) {
+        self.slow_query_log = log;
+    }
+
+    /// Get the slow query log settings
+    pub fn slow_query_log(&self) -> Option<&SlowQueryLog> {
+        self.slow_query_log.as_ref()
+    }
+
+    /// Kill statements running longer than `timeout` through a control
+    /// connection to `control_url`, for servers without statement timeouts
+    /// (before Firebird 4); a killed statement fails with `Error::Timeout`
+    pub fn set_kill_timeout(&mut self, timeout: Duration, control_url: &str) -> Result<(), Error> {
+        let attachment_id = self.attachment_id()?;
+        self.kill_watchdog = Some(KillWatchdog::for_attachment(timeout, control_url, attachment_id));
+        Ok(())
+    }
+
+    /// Stop killing long running statements
+    pub fn clear_kill_timeout(&mut self) {
+        self.kill_watchdog = None;
+    }
+
+    pub(crate) fn kill_watchdog(&self) -> Option<&KillWatchdog> {
+        self.kill_watchdog.as_ref()
+    }
+
+    /// Execute a statement that must be over by `deadline`. The server's
+    /// statement timeout (Firebird 4), op_cancel and the socket read timeout
+    /// are combined so the call returns by the deadline; errors that have
+    /// nothing to do with it are returned as `Err`. See `DeadlineOutcome`.
+    pub fn execute_with_deadline<P: Params>(
+        &mut self,
+        query: &str,
+        params: P,
+        deadline: Instant,
+    ) -> Result<DeadlineOutcome, Error> {
+        let Some(deadline) = Deadline::new(deadline, Instant::now()) else {
+            return Ok(DeadlineOutcome::Expired);
+        };
+        self.wp.borrow_mut().set_deadline(Some(deadline));
+        let result = self._execute(query, params, self.trans_handle);
+        self.wp.borrow_mut().set_deadline(None);
+        deadline.outcome(result)
+    }
+
+    /// Set context variable `name` of `namespace` (`USER_SESSION` or
+    /// `USER_TRANSACTION`, listed in MON$CONTEXT_VARIABLES) to `value`,
+    /// stored as text; `Param::Null` removes it. Triggers and views read it
+    /// with RDB$GET_CONTEXT, e.g. to filter rows by tenant.
+    pub fn set_context<V: ToSqlParam>(&self, namespace: &str, name: &str, value: V) -> Result<(), Error> {
+        self._execute(
+            "SELECT RDB$SET_CONTEXT(?, ?, ?) FROM RDB$DATABASE",
+            (namespace, name, value),
+            self.trans_handle,
+        )
+    }
+
+    /// Context variable `name` of `namespace` parsed as `T`, `None` if unset
+    pub fn get_context<T: FromStr>(&self, namespace: &str, name: &str) -> Result<Option<T>, Error> {
+        let mut stmt = self.prepare("SELECT RDB$GET_CONTEXT(?, ?) FROM RDB$DATABASE")?;
+        let value: Option<String> = stmt.query_row((namespace, name), |row| row.get(0))?;
+        value
+            .map(|v| {
+                v.parse().map_err(|_| {
+                    Error::ValueError(ValueError::new(&format!(
+                        "Context variable {} can't be parsed: {}",
+                        name, v
+                    )))
+                })
+            })
+            .transpose()
+    }
+
+    /// Set the context variable `name` of the session; `None` removes it
+    #[deprecated(note = "use `set_context(\"USER_SESSION\", name, value)`")]
+    pub fn set_session_context(&self, name: &str, value: Option<&str>) -> Result<(), Error> {
+        self.set_context("USER_SESSION", name, value.map_or(Param::Null, Param::from))
+    }
+
+    /// Context variable `name` of the session, `None` if unset
+    #[deprecated(note = "use `get_context::<String>(\"USER_SESSION\", name)`")]
+    pub fn session_context(&self, name: &str) -> Result<Option<String>, Error> {
+        self.get_context("USER_SESSION", name)
+    }
+
+    /// Clear the session state (Firebird 4+ `ALTER SESSION RESET`): context
+    /// variables, session settings and global temporary tables, as if the
+    /// connection were new. `PoolOptions::reset_session` runs it when a
+    /// connection goes back to the pool. The `ConnectOptions::init_script`
+    /// runs again afterwards.
+    pub fn reset_session(&self) -> Result<(), Error> {
+        self._execute_immediate("ALTER SESSION RESET", self.trans_handle)?;
+        self.run_init_script()
+    }
+
     pub(crate) fn _rollback(&mut self, trans_handle: i32) -> Result<(), Error> {
         let mut wp = self.wp.borrow_mut();
         wp.op_rollback_retaining(trans_handle)?;
@@ -220,7 +503,7 @@ impl Connection {
     }
 
     /// Rollback and finalize the transaction (for Transaction struct)
-    pub(crate) fn _rollback_final(&mut self, trans_handle: i32) -> Result<(), Error> {
+    pub(crate) fn _rollback_final(&self, trans_handle: i32) -> Result<(), Error> {
         let mut wp = self.wp.borrow_mut();
         wp.op_rollback(trans_handle)?;
         wp.op_response()?;
@@ -231,7 +514,8 @@ impl Connection {
         self._rollback(self.trans_handle)
     }
 
-    pub fn _prepare(&mut self, query: &str, trans_handle: i32, autocommit: bool) -> Result<Statement<'_>, Error> {
+    pub fn _prepare(&self, query: &str, trans_handle: i32, autocommit: bool) -> Result<Statement<'_>, Error> {
+        let (query, param_order) = self.translate(query);
         let mut wp = self.wp.borrow_mut();
         wp.op_allocate_statement()?;
 
@@ -243,7 +527,7 @@ impl Connection {
             stmt_handle
         };
 
-        wp.op_prepare_statement(stmt_handle, trans_handle, query)?;
+        wp.op_prepare_statement(stmt_handle, trans_handle, &query)?;
         if (wp.accept_type & PTYPE_MASK) == PTYPE_LAZY_SEND && wp.lazy_response_count > 0 {
             wp.lazy_response_count -= 1;
             let (h, _, _) = wp.op_response()?;
@@ -254,34 +538,165 @@ impl Connection {
 
         Ok(Statement::new(
             self,
+            &query,
             trans_handle,
             stmt_handle,
             stmt_type,
             xsqlda,
             autocommit,
-        ))
+        )
+        .with_param_order(param_order))
     }
 
-    pub fn prepare(&mut self, query: &str) -> Result<Statement<'_>, Error> {
+    pub fn prepare(&self, query: &str) -> Result<Statement<'_>, Error> {
         self._prepare(query, self.trans_handle, true)
     }
 
     /// Prepare a statement without autocommit (for use in transactions)
-    pub fn prepare_no_autocommit(&mut self, query: &str) -> Result<Statement<'_>, Error> {
+    pub fn prepare_no_autocommit(&self, query: &str) -> Result<Statement<'_>, Error> {
         self._prepare(query, self.trans_handle, false)
     }
 
-    pub fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
+    /// Read a blob left on the server by `BlobOptions::streamed` segment by
+    /// segment, in the transaction its row was fetched in
+    pub fn blob_reader(&self, blob: &BlobId) -> Result<BlobReader<'_>, Error> {
+        BlobReader::open(self, blob)
+    }
+
+    /// Create a blob written segment by segment, to bind as a parameter
+    /// once `BlobWriter::finish` returned its id
+    pub fn blob_writer(&self) -> Result<BlobWriter<'_>, Error> {
+        BlobWriter::create(self, self.trans_handle)
+    }
+
+    pub fn transaction(&self) -> Result<Transaction<'_>, Error> {
         Transaction::new(self)
     }
 
     /// Start a transaction with custom options (isolation level, lock wait, etc.)
-    pub fn transaction_with_options(&mut self, options: super::transaction::TransactionOptions) -> Result<Transaction<'_>, Error> {
+    pub fn transaction_with_options(&self, options: TransactionOptions) -> Result<Transaction<'_>, Error> {
         Transaction::with_options(self, options)
     }
 
+    /// Start a transaction known by `name`, alongside any others running on
+    /// this connection, e.g. a read-only reporting transaction next to the
+    /// one writing. Each has its own handle; statements run in the
+    /// transaction that prepared them, or in the one given to
+    /// `Statement::query_in`. The name is recorded as the tag of the
+    /// transaction unless `options` has one (see `TransactionOptions::tag`).
+    pub fn begin_named_transaction(
+        &self,
+        name: &str,
+        options: TransactionOptions,
+    ) -> Result<Transaction<'_>, Error> {
+        Transaction::named(self, name, options)
+    }
+
+    /// Run `f` in a transaction and commit it. On contention (deadlock,
+    /// update conflict, lock timeout) the transaction is rolled back and run
+    /// again as `policy` allows; other errors are returned at once.
+    pub fn retry_transaction<T, F>(&self, policy: &RetryPolicy, mut f: F) -> Result<T, Error>
+    where
+        F: FnMut(&mut Transaction<'_>) -> Result<T, Error>,
+    {
+        retry::retry_on(policy, &[RetryClass::Transaction], || {
+            let mut tx = self.transaction()?;
+            let value = f(&mut tx)?;
+            tx.commit()?;
+            Ok(value)
+        })
+    }
+
+    /// Optimistic locking update.
+    /// Sets `values` and bumps `version_column` where `key_column = key` and
+    /// `version_column = version`. Returns the new version, or `Error::StaleRow`
+    /// if no row matched.
+    pub fn update_versioned<K: Into<Param>>(
+        &mut self,
+        table: &str,
+        key_column: &str,
+        key: K,
+        version_column: &str,
+        version: i64,
+        values: &[(&str, Param)],
+    ) -> Result<i64, Error> {
+        let columns: Vec<&str> = values.iter().map(|(c, _)| *c).collect();
+        let query = versioned::update_versioned_sql(table, key_column, version_column, &columns);
+        let mut stmt = self.prepare(&query)?;
+        versioned::execute_versioned(&mut stmt, key.into(), version, values)
+    }
+
     // methods for Statement
 
+    /// Record a contention error, setting the time spent in the failed execute
+    pub(crate) fn record_contention(&self, err: &mut Error, waited: std::time::Duration) {
+        if let Error::FirebirdError(e) = err
+            && let Some(contention) = e.contention()
+        {
+            e.waited = Some(waited);
+            self.contention_stats.borrow_mut().record(contention, waited);
+        }
+    }
+
+    /// Number of times a statement is prepared again when the server reports it outdated
+    /// `max_rows` URL parameter, `None` for no limit
+    pub(crate) fn max_rows(&self) -> Option<usize> {
+        limit_option(&self.conn_options["max_rows"])
+    }
+
+    /// `max_params` URL parameter, `None` for no limit
+    pub(crate) fn max_params(&self) -> Option<usize> {
+        limit_option(&self.conn_options["max_params"])
+    }
+
+    /// Reject statements other than SELECT with an error before they run,
+    /// e.g. on a connection to a replica (`read_only` URL parameter)
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Back to the `read_only` URL parameter, for a connection returned to
+    /// its pool
+    pub(crate) fn reset_read_only(&mut self) {
+        self.read_only = self.conn_options["read_only"] == "true";
+    }
+
+    /// Whether `Row::get` fails instead of truncating or rounding a value
+    /// (`strict_types` URL parameter)
+    pub(crate) fn strict_types(&self) -> bool {
+        self.conn_options["strict_types"] == "true"
+    }
+
+    /// `query` translated to Firebird syntax as the `compat` URL parameter
+    /// asks, with the order to bind its parameters in when they moved
+    fn translate<'q>(&self, query: &'q str) -> (Cow<'q, str>, Option<Vec<usize>>) {
+        #[cfg(feature = "compat")]
+        if let Some(t) = compat::for_option(&self.conn_options["compat"], query) {
+            let order = t.reorders().then_some(t.param_order);
+            return (Cow::Owned(t.sql), order);
+        }
+        (Cow::Borrowed(query), None)
+    }
+
+    pub(crate) fn reprepare_retries(&self) -> u32 {
+        self.conn_options["reprepare_retries"].parse().unwrap_or(1)
+    }
+
+    /// Characters written for true/false into CHAR(1) columns when the
+    /// `legacy_bool` option is enabled ("true"/"TF", "YN" or "10")
+    pub(crate) fn legacy_bool(&self) -> Option<(char, char)> {
+        match self.conn_options["legacy_bool"].to_ascii_uppercase().as_str() {
+            "TRUE" | "TF" => Some(('T', 'F')),
+            "YN" => Some(('Y', 'N')),
+            "10" => Some(('1', '0')),
+            _ => None,
+        }
+    }
+
     pub(crate) fn _execute_statement(
         &self,
         trans_handle: i32,
@@ -295,6 +710,114 @@ impl Connection {
         Ok(wp.rowcount(stmt_handle, stmt_type)?)
     }
 
+    /// Protocol, authentication, wire encryption and compression negotiated at connect
+    pub fn handshake_info(&self) -> HandshakeInfo {
+        self.wp.borrow().handshake_info()
+    }
+
+    /// Round trip to the server (op_ping). Fails with `Error::ConnectionLost`
+    /// if the server is gone, or `Error::Timeout` past the `read_timeout`
+    /// URL parameter.
+    pub fn ping(&self) -> Result<(), Error> {
+        self.wp.borrow_mut().op_ping()
+    }
+
+    /// Whether the connection was given up after a timeout or a cancel
+    /// that left it out of step with the server
+    pub(crate) fn is_desynced(&self) -> bool {
+        self.wp.borrow().is_desynced()
+    }
+
+    /// Socket counters (round trips, writes, bytes) since connect or the last reset
+    pub fn wire_stats(&self) -> WireStats {
+        self.wp.borrow().wire_stats()
+    }
+
+    pub fn reset_wire_stats(&self) {
+        self.wp.borrow_mut().reset_wire_stats()
+    }
+
+    /// Statement, transaction and blob handles held on the server, and the
+    /// most held at once
+    pub fn resource_stats(&self) -> ResourceStats {
+        self.wp.borrow().resource_stats()
+    }
+
+    /// Warn (log target `firebirust::leaks`) when handles go over the limits
+    /// of `detection` or a transaction is dropped unfinished; `None` turns it off
+    pub fn set_leak_detection(&self, detection: Option<LeakDetection>) {
+        self.wp.borrow_mut().set_leak_detection(detection)
+    }
+
+    pub fn leak_detection(&self) -> Option<LeakDetection> {
+        self.wp.borrow().leak_detection()
+    }
+
+    /// Whether execute, row count and the first fetch share one round trip
+    pub(crate) fn pipeline(&self) -> bool {
+        self.conn_options["pipeline"] == "true"
+    }
+
+    /// Rows requested per op_fetch (`fetch_size` URL parameter)
+    pub(crate) fn fetch_size(&self) -> u32 {
+        self.conn_options["fetch_size"].parse().unwrap_or(400)
+    }
+
+    /// `_execute_statement` that also sends the first fetch (when `fetch` is given)
+    /// and reads all responses after a single flush.
+    pub(crate) fn _execute_statement_pipelined(
+        &self,
+        trans_handle: i32,
+        stmt_handle: i32,
+        stmt_type: u32,
+        params: &[(Vec<u8>, Vec<u8>, bool)],
+        fetch: Option<(&Vec<u8>, &[XSQLVar])>,
+    ) -> Result<(usize, Option<FetchBatch>), Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.begin_deferred();
+        let queued = (|| {
+            wp.op_execute(stmt_handle, trans_handle, params)?;
+            wp.op_info_sql(stmt_handle, &[ISC_INFO_SQL_RECORDS])?;
+            if let Some((blr, _)) = fetch {
+                wp.op_fetch(stmt_handle, blr)?;
+            }
+            Ok::<(), Error>(())
+        })();
+        if let Err(e) = queued {
+            // a partly packed op_execute must not reach the server
+            wp.discard_deferred();
+            return Err(e);
+        }
+        wp.flush_deferred()?;
+
+        // every queued operation gets a response: read them all before reporting an error
+        let executed = wp.op_response();
+        let info = wp.op_response();
+        let fetched = fetch.map(|(_, xsqlda)| wp.op_fetch_response(xsqlda));
+        executed?;
+        let (_, _, buf) = info?;
+        let rowcount = WireProtocol::parse_rowcount(&buf, stmt_type);
+        Ok((rowcount, fetched.transpose()?))
+    }
+
+    /// Prepare the query again on the same statement handle (metadata may have changed)
+    pub(crate) fn _reprepare(
+        &self,
+        query: &str,
+        trans_handle: i32,
+        stmt_handle: i32,
+    ) -> Result<(u32, Vec<XSQLVar>), Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_prepare_statement(stmt_handle, trans_handle, query)?;
+        let (_, _, buf) = wp.op_response()?;
+        wp.parse_xsqlda(&buf, stmt_handle)
+    }
+
+    pub(crate) fn _describe_input(&self, stmt_handle: i32) -> Result<Vec<XSQLVar>, Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.describe_input(stmt_handle)
+    }
+
     pub(crate) fn _fetch(
         &self,
         stmt_handle: i32,
@@ -306,13 +829,83 @@ impl Connection {
         wp.op_fetch_response(xsqlda)
     }
 
+    /// Next `count` rows of an open cursor, and whether more may follow
+    pub(crate) fn _fetch_rows(
+        &self,
+        stmt_handle: i32,
+        blr: &[u8],
+        xsqlda: &[XSQLVar],
+        count: u32,
+    ) -> Result<(Vec<Vec<CellValue>>, bool), Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_fetch_rows(stmt_handle, blr, count)?;
+        wp.op_fetch_response(xsqlda)
+    }
+
+    pub(crate) fn _set_cursor(&self, stmt_handle: i32, name: &str) -> Result<(), Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_set_cursor(stmt_handle, name)?;
+        wp.op_response()?;
+        Ok(())
+    }
+
     pub(crate) fn _get_blob_segments(
         &self,
         blob_id: &Vec<u8>,
         trans_handle: i32,
+        bpb: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.get_blob_segments(blob_id, trans_handle, bpb)
+    }
+
+    pub(crate) fn _open_blob(&self, blob_id: &[u8], trans_handle: i32, bpb: &[u8]) -> Result<i32, Error> {
+        self.wp.borrow_mut().open_blob_stream(blob_id, trans_handle, bpb)
+    }
+
+    pub(crate) fn _read_blob(&self, blob_handle: i32, data: &mut Vec<u8>) -> Result<bool, Error> {
+        self.wp.borrow_mut().read_blob_stream(blob_handle, data)
+    }
+
+    pub(crate) fn _close_blob(&self, blob_handle: i32) -> Result<(), Error> {
+        self.wp.borrow_mut().close_blob_stream(blob_handle)
+    }
+
+    pub(crate) fn _create_blob(&self, trans_handle: i32) -> Result<(i32, Vec<u8>), Error> {
+        self.wp.borrow_mut().create_blob_stream(trans_handle)
+    }
+
+    pub(crate) fn _write_blob(&self, blob_handle: i32, data: &[u8]) -> Result<(), Error> {
+        self.wp.borrow_mut().write_blob_stream(blob_handle, data)
+    }
+
+    pub(crate) fn _cancel_blob(&self, blob_handle: i32) -> Result<(), Error> {
+        self.wp.borrow_mut().cancel_blob_stream(blob_handle)
+    }
+
+    pub(crate) fn _get_slice(
+        &self,
+        array_id: &[u8],
+        trans_handle: i32,
+        desc: &ArrayDesc,
+    ) -> Result<Array, Error> {
+        let element = desc.element()?;
+        let elements = {
+            let mut wp = self.wp.borrow_mut();
+            wp.get_slice(array_id, trans_handle, &desc.sdl(), desc.slice_length()?, &element)?
+        };
+        desc.read(elements)
+    }
+
+    pub(crate) fn _put_slice(
+        &self,
+        array: &Array,
+        trans_handle: i32,
+        desc: &ArrayDesc,
     ) -> Result<Vec<u8>, Error> {
+        let data = desc.encode(array)?;
         let mut wp = self.wp.borrow_mut();
-        wp.get_blob_segments(blob_id, trans_handle)
+        wp.put_slice(trans_handle, &desc.sdl(), desc.slice_length()?, &data)
     }
 
     pub(crate) fn _free_statement(&self, stmt_handle: i32, drop_type: i32) -> () {
@@ -328,6 +921,7 @@ impl Connection {
     // methods for Transaction
     pub(crate) fn drop_transaction(&self, trans_handle: i32) -> () {
         let mut wp = self.wp.borrow_mut();
+        wp.implicit_rollback();
         wp.op_rollback(trans_handle).unwrap();
         wp.op_response().unwrap();
     }
@@ -354,6 +948,289 @@ impl Connection {
         wp.op_cancel_events(event_id)
     }
 
+    /// Get lock timeout / deadlock / update conflict counters of this connection
+    pub fn contention_stats(&self) -> ContentionStats {
+        self.contention_stats.borrow().clone()
+    }
+
+    /// Reset the contention counters
+    pub fn reset_contention_stats(&self) {
+        *self.contention_stats.borrow_mut() = ContentionStats::default();
+    }
+
+    /// GUID, creation date, ODS version and last nbackup of the database
+    /// (Firebird 4.0+)
+    pub fn database_identity(&self) -> Result<DatabaseIdentity, Error> {
+        let mut stmt = self.prepare(db_identity::DATABASE_IDENTITY_SQL)?;
+        stmt.query_row((), DatabaseIdentity::from_row)
+    }
+
+    // ===== Interop (advanced) =====
+
+    /// Id the server gave this attachment, as `MON$ATTACHMENT_ID` and
+    /// `CURRENT_CONNECTION` show it. Advanced: see
+    /// "Interop with fbclient Code" in the README.
+    pub fn attachment_id(&self) -> Result<i64, Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_info_database(&[ISC_INFO_ATTACHMENT_ID, ISC_INFO_END])?;
+        let (_, _, buf) = wp.op_response()?;
+        interop::parse_info_id(&buf, ISC_INFO_ATTACHMENT_ID)
+            .ok_or_else(|| Error::ValueError(ValueError::new("No attachment id in database info")))
+    }
+
+    /// Id of the connection's own (autocommit) transaction, as
+    /// `MON$TRANSACTION_ID` shows it; see `Transaction::transaction_id`
+    pub fn transaction_id(&self) -> Result<i64, Error> {
+        self._transaction_id(self.trans_handle)
+    }
+
+    pub(crate) fn _transaction_id(&self, trans_handle: i32) -> Result<i64, Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_info_transaction(trans_handle, &[ISC_INFO_TRA_ID as u8, ISC_INFO_END])?;
+        let (_, _, buf) = wp.op_response()?;
+        interop::parse_info_id(&buf, ISC_INFO_TRA_ID as u8)
+            .ok_or_else(|| Error::ValueError(ValueError::new("No transaction id in transaction info")))
+    }
+
+    /// Database as `host:path`, as given in the connection URL
+    pub(crate) fn db_path(&self) -> String {
+        format!("{}:{}", self.conn_params.host, self.conn_params.db_name)
+    }
+
+    /// Wire protocol handle of the attachment. Advanced: it is only
+    /// meaningful on this connection and is NOT an fbclient `isc_db_handle`;
+    /// never pass it to fbclient.
+    pub fn raw_handle(&self) -> i32 {
+        self.wp.borrow().db_handle
+    }
+
+    // ===== Limbo Transactions =====
+
+    /// List the ids of transactions in limbo (prepared but neither committed nor rolled back)
+    pub fn limbo_transactions(&self) -> Result<Vec<i64>, Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_info_database(&[ISC_INFO_LIMBO, ISC_INFO_END])?;
+        let (_, _, buf) = wp.op_response()?;
+        Ok(limbo::parse_limbo_info(&buf))
+    }
+
+    /// Commit or roll back a transaction in limbo by id
+    pub fn recover_limbo(&mut self, transaction_id: i64, action: LimboAction) -> Result<(), Error> {
+        let mut wp = self.wp.borrow_mut();
+        wp.op_reconnect(transaction_id)?;
+        let (trans_handle, _, _) = wp.op_response()?;
+        match action {
+            LimboAction::Commit => wp.op_commit(trans_handle)?,
+            LimboAction::Rollback => wp.op_rollback(trans_handle)?,
+        }
+        wp.op_response()?;
+        Ok(())
+    }
+
+    // ===== Schema Helpers =====
+
+    /// Run a DDL statement in its own transaction and commit it.
+    /// Fails with `Error::ObjectInUse` if the object is still in use.
+    pub fn execute_ddl(&mut self, sql: &str) -> Result<(), Error> {
+        let trans_handle = self._begin_trans()?;
+        match self._execute_immediate(sql, trans_handle) {
+            Ok(()) => self._commit_final(trans_handle).map_err(ddl::ddl_error),
+            Err(e) => {
+                self._rollback_final(trans_handle)?;
+                Err(ddl::ddl_error(e))
+            }
+        }
+    }
+
+    /// Whether a table (not a view) exists. Unquoted names are matched in
+    /// upper case, `"quoted"` names as written
+    pub fn table_exists(&self, table: &str) -> Result<bool, Error> {
+        let mut stmt = self.prepare(
+            "SELECT COUNT(*) FROM RDB$RELATIONS WHERE RDB$RELATION_NAME = ? AND RDB$VIEW_BLR IS NULL",
+        )?;
+        let name = ddl::object_name(table);
+        let count: i64 = stmt.query_row((name.as_str(),), |row| row.get(0))?;
+        Ok(count > 0)
+    }
+
+    /// Drop a table if it exists; returns whether it was dropped
+    pub fn drop_table_if_exists(&mut self, table: &str) -> Result<bool, Error> {
+        if !self.table_exists(table)? {
+            return Ok(false);
+        }
+        self.execute_ddl(&format!("DROP TABLE {}", ddl::quote(&ddl::object_name(table))))?;
+        Ok(true)
+    }
+
+    /// Remove all rows of a table (Firebird has no TRUNCATE), see `TruncateMode`
+    pub fn truncate_table(&mut self, table: &str, mode: TruncateMode) -> Result<(), Error> {
+        let name = ddl::object_name(table);
+        match mode {
+            TruncateMode::DeleteAll => {
+                let query = format!("DELETE FROM {} ROWS {}", ddl::quote(&name), ddl::DELETE_CHUNK);
+                let trans_handle = self._begin_trans()?;
+                let result = (|| {
+                    let mut stmt = self._prepare(&query, trans_handle, false)?;
+                    loop {
+                        stmt.execute(())?;
+                        if stmt.rowcount() == 0 {
+                            return Ok(());
+                        }
+                        stmt.commit_retaining()?;
+                    }
+                })();
+                match result {
+                    Ok(()) => self._commit_final(trans_handle)?,
+                    Err(e) => {
+                        // the DELETE error is the one worth reporting
+                        let _ = self._rollback_final(trans_handle);
+                        return Err(e);
+                    }
+                }
+
+                let mut stmt = self.prepare(
+                    "SELECT RDB$FIELD_NAME FROM RDB$RELATION_FIELDS \
+                     WHERE RDB$RELATION_NAME = ? AND RDB$IDENTITY_TYPE IS NOT NULL",
+                )?;
+                let columns = stmt
+                    .query_map((name.as_str(),), |row| row.get::<String>(0))?
+                    .map(|c| c.map(|c| c.trim_end().to_string()))
+                    .collect::<Result<Vec<_>, _>>()?;
+                drop(stmt);
+                for sql in ddl::restart_identity_sql(&name, &columns) {
+                    self.execute_ddl(&sql)?;
+                }
+            }
+            TruncateMode::RecreateFromDdl => {
+                let count = |conn: &Self, sql: &str| -> Result<i64, Error> {
+                    conn.prepare(sql)?.query_row((name.as_str(),), |row| row.get(0))
+                };
+                let mut dependents = Vec::new();
+                for (kind, sql) in ddl::RECREATE_DEPENDENTS {
+                    if count(self, sql)? > 0 {
+                        dependents.push(*kind);
+                    }
+                }
+                let has_partial: i64 = self
+                    .prepare(ddl::HAS_PARTIAL_INDEXES_SQL)?
+                    .query_row((), |row| row.get(0))?;
+                if has_partial > 0 && count(self, ddl::PARTIAL_INDEXES_SQL)? > 0 {
+                    dependents.push("partial indexes");
+                }
+                if !dependents.is_empty() {
+                    return Err(ddl::recreate_refused(&name, &dependents));
+                }
+
+                let mut statements = crate::ddl_extractor::extract_table_ddl(self, &name)?;
+                statements[0] = ddl::recreate_table_sql(&statements[0]);
+                // one transaction, so a failed recreate leaves the table as it was
+                let trans_handle = self._begin_trans()?;
+                let result = statements
+                    .iter()
+                    .try_for_each(|sql| self._execute_immediate(sql, trans_handle));
+                match result {
+                    Ok(()) => self._commit_final(trans_handle).map_err(ddl::ddl_error)?,
+                    Err(e) => {
+                        // the DDL error is the one worth reporting
+                        let _ = self._rollback_final(trans_handle);
+                        return Err(ddl::ddl_error(e));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // ===== Sequences =====
+
+    /// Add `increment` to a sequence (generator) and return the new value,
+    /// like `GEN_ID`. Unquoted names are matched in upper case.
+    pub fn gen_id(&self, sequence: &str, increment: i64) -> Result<i64, Error> {
+        let mut stmt = self.prepare(&ddl::gen_id_sql(sequence, increment))?;
+        stmt.query_row((), |row| row.get(0))
+    }
+
+    /// Next value of a sequence, like `NEXT VALUE FOR`
+    pub fn next_value(&self, sequence: &str) -> Result<i64, Error> {
+        self.gen_id(sequence, 1)
+    }
+
+    /// Current value of a sequence, without changing it
+    pub fn sequence_current(&self, sequence: &str) -> Result<i64, Error> {
+        self.gen_id(sequence, 0)
+    }
+
+    /// Whether a sequence (generator) exists
+    pub fn sequence_exists(&self, sequence: &str) -> Result<bool, Error> {
+        let mut stmt = self.prepare("SELECT COUNT(*) FROM RDB$GENERATORS WHERE RDB$GENERATOR_NAME = ?")?;
+        let name = ddl::object_name(sequence);
+        let count: i64 = stmt.query_row((name.as_str(),), |row| row.get(0))?;
+        Ok(count > 0)
+    }
+
+    /// Create a sequence; the first `next_value` returns `start` (1 if
+    /// `None`) on every server version
+    pub fn create_sequence(&mut self, sequence: &str, start: Option<i64>) -> Result<(), Error> {
+        let protocol_version = self.handshake_info().protocol_version;
+        self.execute_ddl(&ddl::create_sequence_sql(sequence, start, protocol_version))
+    }
+
+    /// Create a sequence unless it exists; returns whether it was created
+    pub fn create_sequence_if_not_exists(
+        &mut self,
+        sequence: &str,
+        start: Option<i64>,
+    ) -> Result<bool, Error> {
+        if self.sequence_exists(sequence)? {
+            return Ok(false);
+        }
+        self.create_sequence(sequence, start)?;
+        Ok(true)
+    }
+
+    /// Drop a sequence if it exists; returns whether it was dropped
+    pub fn drop_sequence_if_exists(&mut self, sequence: &str) -> Result<bool, Error> {
+        if !self.sequence_exists(sequence)? {
+            return Ok(false);
+        }
+        self.execute_ddl(&format!("DROP SEQUENCE {}", ddl::quote(&ddl::object_name(sequence))))?;
+        Ok(true)
+    }
+
+    // ===== Replication =====
+
+    /// Start replicating the tables of the publication (Firebird 4.0+)
+    pub fn enable_publication(&mut self) -> Result<(), Error> {
+        self.execute_ddl("ALTER DATABASE ENABLE PUBLICATION")
+    }
+
+    pub fn disable_publication(&mut self) -> Result<(), Error> {
+        self.execute_ddl("ALTER DATABASE DISABLE PUBLICATION")
+    }
+
+    /// Add tables to the publication; an empty list adds all tables,
+    /// including those created later
+    pub fn include_in_publication(&mut self, tables: &[&str]) -> Result<(), Error> {
+        self.execute_ddl(&replication::publication_tables_sql(true, tables))
+    }
+
+    /// Remove tables from the publication; an empty list removes all
+    pub fn exclude_from_publication(&mut self, tables: &[&str]) -> Result<(), Error> {
+        self.execute_ddl(&replication::publication_tables_sql(false, tables))
+    }
+
+    /// Publications of the database (RDB$PUBLICATIONS)
+    pub fn publications(&self) -> Result<Vec<Publication>, Error> {
+        let mut stmt = self.prepare(replication::PUBLICATIONS_SQL)?;
+        stmt.query_map((), Publication::from_row)?.collect()
+    }
+
+    /// Tables of the publications (RDB$PUBLICATION_TABLES)
+    pub fn publication_tables(&self) -> Result<Vec<PublicationTable>, Error> {
+        let mut stmt = self.prepare(replication::PUBLICATION_TABLES_SQL)?;
+        stmt.query_map((), PublicationTable::from_row)?.collect()
+    }
+
     /// Extract complete database schema DDL
     /// Similar to `isql -x` command
     pub fn extract_ddl(&mut self) -> Result<String, Error> {
@@ -22,13 +22,18 @@
 
 #![allow(dead_code)]
 
-use std::cell::RefCell;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
+use async_lock::{Mutex, MutexGuard};
+
 use super::cellvalue::CellValue;
 use super::conn_params::ConnParams;
-use super::error::Error;
+use super::error::{Error, limit_option};
 use super::params::Params;
+use super::resources::{LeakDetection, ResourceStats};
+use super::retry::{self, RetryClass, RetryPolicy};
+use super::statement;
 use super::statement_async::StatementAsync;
 use super::transaction_async::*;
 use super::wireprotocol_async::*;
@@ -36,7 +41,8 @@ use super::xsqlvar::XSQLVar;
 use super::*;
 
 pub struct ConnectionAsync {
-    wp: RefCell<WireProtocolAsync>,
+    // an async lock, as requests hold it across awaits
+    wp: Mutex<WireProtocolAsync>,
     trans_handle: i32, // transaction for operating from connection methods
     conn_params: ConnParams,
     conn_options: HashMap<String, String>,
@@ -44,23 +50,27 @@ pub struct ConnectionAsync {
 
 impl ConnectionAsync {
     pub async fn connect(conn_string: &str) -> Result<ConnectionAsync, Error> {
+        ConnectionAsync::connect_with_options(conn_string, &ConnectOptions::new()).await
+    }
+
+    /// Connect with custom crypt, auth or compression plugins
+    pub async fn connect_with_options(
+        conn_string: &str,
+        plugins: &ConnectOptions,
+    ) -> Result<ConnectionAsync, Error> {
         let (conn_params, conn_options) = ConnParams::from_url(conn_string)?;
-        let mut wp = WireProtocolAsync::new(&conn_params, &conn_options).await?;
-        let (client_public, client_secret) = srp::get_client_seed();
+        let mut wp = WireProtocolAsync::new(&conn_params, &conn_options, plugins).await?;
         wp.op_connect(
             &conn_params.db_name,
             &conn_params.username,
             &conn_params.password,
             &conn_options,
-            &client_public,
         )
         .await?;
         wp.parse_connect_response(
             &conn_params.username,
             &conn_params.password,
             &conn_options,
-            &client_public,
-            &client_secret,
         )
         .await?;
 
@@ -76,33 +86,40 @@ impl ConnectionAsync {
 
         wp.op_transaction(true).await?;
         let (trans_handle, _, _) = wp.op_response().await?;
+        wp.attached();
 
-        Ok(ConnectionAsync {
-            wp: RefCell::new(wp),
+        let mut conn = ConnectionAsync {
+            wp: Mutex::new(wp),
             trans_handle,
             conn_params,
             conn_options,
-        })
+        };
+        conn.run_init_script().await?;
+        Ok(conn)
     }
 
     pub async fn create_database(conn_string: &str) -> Result<ConnectionAsync, Error> {
+        ConnectionAsync::create_database_with_options(conn_string, &ConnectOptions::new()).await
+    }
+
+    /// Create a database with custom crypt, auth or compression plugins
+    pub async fn create_database_with_options(
+        conn_string: &str,
+        plugins: &ConnectOptions,
+    ) -> Result<ConnectionAsync, Error> {
         let (conn_params, conn_options) = ConnParams::from_url(conn_string)?;
-        let mut wp = WireProtocolAsync::new(&conn_params, &conn_options).await?;
-        let (client_public, client_secret) = srp::get_client_seed();
+        let mut wp = WireProtocolAsync::new(&conn_params, &conn_options, plugins).await?;
         wp.op_connect(
             &conn_params.db_name,
             &conn_params.username,
             &conn_params.password,
             &conn_options,
-            &client_public,
         )
         .await?;
         wp.parse_connect_response(
             &conn_params.username,
             &conn_params.password,
             &conn_options,
-            &client_public,
-            &client_secret,
         )
         .await?;
 
@@ -121,22 +138,66 @@ impl ConnectionAsync {
 
         wp.op_transaction(true).await?;
         let (trans_handle, _, _) = wp.op_response().await?;
+        wp.attached();
 
         Ok(ConnectionAsync {
-            wp: RefCell::new(wp),
+            wp: Mutex::new(wp),
             trans_handle,
             conn_params,
             conn_options,
         })
     }
 
+    /// `max_rows` URL parameter, `None` for no limit
+    pub(crate) fn max_rows(&self) -> Option<usize> {
+        limit_option(&self.conn_options["max_rows"])
+    }
+
+    /// `max_params` URL parameter, `None` for no limit
+    pub(crate) fn max_params(&self) -> Option<usize> {
+        limit_option(&self.conn_options["max_params"])
+    }
+
+    /// `query` translated to Firebird syntax as the `compat` URL parameter
+    /// asks, with the order to bind its parameters in when they moved
+    fn translate<'q>(&self, query: &'q str) -> (Cow<'q, str>, Option<Vec<usize>>) {
+        #[cfg(feature = "compat")]
+        if let Some(t) = compat::for_option(&self.conn_options["compat"], query) {
+            let order = t.reorders().then_some(t.param_order);
+            return (Cow::Owned(t.sql), order);
+        }
+        (Cow::Borrowed(query), None)
+    }
+
+    /// Whether statements other than SELECT are rejected (`read_only` URL
+    /// parameter), see `Connection::set_read_only`
+    pub fn is_read_only(&self) -> bool {
+        self.conn_options["read_only"] == "true"
+    }
+
+    /// Whether `Row::get` fails instead of truncating or rounding a value
+    /// (`strict_types` URL parameter)
+    pub(crate) fn strict_types(&self) -> bool {
+        self.conn_options["strict_types"] == "true"
+    }
+
     pub(crate) async fn _execute_batch(
         &mut self,
         query: &str,
         trans_handle: i32,
     ) -> Result<(), Error> {
-        let mut wp = self.wp.borrow_mut();
-        wp.op_exec_immediate(trans_handle, query).await?;
+        if self.is_read_only() {
+            let stmt = self._prepare(query, trans_handle).await?;
+            statement::check_read_only(true, stmt.kind())?;
+        }
+        self._execute_committed(query, trans_handle).await
+    }
+
+    /// Execute and commit retaining, whatever `read_only` says
+    async fn _execute_committed(&mut self, query: &str, trans_handle: i32) -> Result<(), Error> {
+        let (query, _) = self.translate(query);
+        let mut wp = self.wp.lock().await;
+        wp.op_exec_immediate(trans_handle, &query).await?;
         wp.op_response().await?;
 
         // commit automatically
@@ -146,18 +207,67 @@ impl ConnectionAsync {
         Ok(())
     }
 
+    /// Statement, transaction and blob handles held on the server, see
+    /// `Connection::resource_stats`
+    pub fn resource_stats(&self) -> ResourceStats {
+        self.wp_now().resource_stats()
+    }
+
+    /// Warn when handles go over the limits of `detection`, see
+    /// `Connection::set_leak_detection`
+    pub fn set_leak_detection(&self, detection: Option<LeakDetection>) {
+        self.wp_now().set_leak_detection(detection)
+    }
+
+    /// Protocol, authentication and wire encryption negotiated at connect
+    pub fn handshake_info(&self) -> HandshakeInfo {
+        self.wp_now().handshake_info()
+    }
+
+    /// Round trip to the server (op_ping), see `Connection::ping`
+    pub async fn ping(&self) -> Result<(), Error> {
+        self.wp.lock().await.op_ping().await
+    }
+
+    /// GUID, creation date, ODS version and last nbackup of the database,
+    /// see `Connection::database_identity`
+    pub async fn database_identity(&mut self) -> Result<DatabaseIdentity, Error> {
+        let mut stmt = self.prepare(db_identity::DATABASE_IDENTITY_SQL).await?;
+        stmt.query_row((), DatabaseIdentity::from_row).await
+    }
+
     pub async fn execute_batch(&mut self, query: &str) -> Result<(), Error> {
         self._execute_batch(query, self.trans_handle).await
     }
 
+    /// Run a script of several statements, see `Connection::execute_script`
+    pub async fn execute_script(&mut self, script: &str) -> Result<(), Error> {
+        for statement in script::split_script(script)? {
+            self.execute_batch(statement).await?;
+        }
+        Ok(())
+    }
+
+    /// Run the `ConnectOptions::init_script`, if any
+    async fn run_init_script(&mut self) -> Result<(), Error> {
+        let Some(init_script) = self.wp.lock().await.init_script() else {
+            return Ok(());
+        };
+        for statement in script::split_script(&init_script)? {
+            self._execute_committed(statement, self.trans_handle).await?;
+        }
+        Ok(())
+    }
+
     pub(crate) async fn _execute<P: Params>(
         &mut self,
         query: &str,
         params: P,
         trans_handle: i32,
     ) -> Result<(), Error> {
+        let (query, param_order) = self.translate(query);
         let mut stmt = {
-            let mut wp = self.wp.borrow_mut();
+            let mut wp = self.wp.lock().await;
             wp.op_allocate_statement().await?;
 
             let mut stmt_handle = if (wp.accept_type & PTYPE_MASK) == PTYPE_LAZY_SEND {
@@ -168,7 +278,7 @@ impl ConnectionAsync {
                 stmt_handle
             };
 
-            wp.op_prepare_statement(stmt_handle, trans_handle, query)
+            wp.op_prepare_statement(stmt_handle, trans_handle, &query)
                 .await?;
             if (wp.accept_type & PTYPE_MASK) == PTYPE_LAZY_SEND && wp.lazy_response_count > 0 {
                 wp.lazy_response_count -= 1;
@@ -178,7 +288,16 @@ impl ConnectionAsync {
             let (_, buf, _) = wp.op_response().await?;
             let (stmt_type, xsqlda) = wp.parse_xsqlda(&buf, stmt_handle).await?;
 
-            StatementAsync::new(self, trans_handle, stmt_handle, stmt_type, xsqlda, true)
+            StatementAsync::new(
+                self,
+                &query,
+                trans_handle,
+                stmt_handle,
+                stmt_type,
+                xsqlda,
+                true,
+            )
+            .with_param_order(param_order)
         };
 
         stmt.execute(params).await?;
@@ -191,7 +310,7 @@ impl ConnectionAsync {
     }
 
     pub(crate) async fn _commit(&self, trans_handle: i32) -> Result<(), Error> {
-        let mut wp = self.wp.borrow_mut();
+        let mut wp = self.wp.lock().await;
         wp.op_commit_retaining(trans_handle).await?;
         wp.op_response().await?;
         Ok(())
@@ -202,14 +321,14 @@ impl ConnectionAsync {
     }
 
     pub(crate) async fn _begin_trans(&mut self) -> Result<i32, Error> {
-        let mut wp = self.wp.borrow_mut();
+        let mut wp = self.wp.lock().await;
         wp.op_transaction(false).await?;
         let (trans_handle, _, _) = wp.op_response().await?;
         Ok(trans_handle)
     }
 
     pub(crate) async fn _rollback(&mut self, trans_handle: i32) -> Result<(), Error> {
-        let mut wp = self.wp.borrow_mut();
+        let mut wp = self.wp.lock().await;
         wp.op_rollback_retaining(trans_handle).await?;
         wp.op_response().await?;
         Ok(())
@@ -220,11 +339,12 @@ impl ConnectionAsync {
     }
 
     pub async fn _prepare(
-        &mut self,
+        &self,
         query: &str,
         trans_handle: i32,
     ) -> Result<StatementAsync<'_>, Error> {
-        let mut wp = self.wp.borrow_mut();
+        let (query, param_order) = self.translate(query);
+        let mut wp = self.wp.lock().await;
         wp.op_allocate_statement().await?;
 
         let mut stmt_handle = if (wp.accept_type & PTYPE_MASK) == PTYPE_LAZY_SEND {
@@ -235,7 +355,7 @@ impl ConnectionAsync {
             stmt_handle
         };
 
-        wp.op_prepare_statement(stmt_handle, trans_handle, query)
+        wp.op_prepare_statement(stmt_handle, trans_handle, &query)
             .await?;
         if (wp.accept_type & PTYPE_MASK) == PTYPE_LAZY_SEND && wp.lazy_response_count > 0 {
             wp.lazy_response_count -= 1;
@@ -247,12 +367,14 @@ impl ConnectionAsync {
 
         Ok(StatementAsync::new(
             self,
+            &query,
             trans_handle,
             stmt_handle,
             stmt_type,
             xsqlda,
             true, // autocommit is true
-        ))
+        )
+        .with_param_order(param_order))
     }
 
     pub async fn prepare(&mut self, query: &str) -> Result<StatementAsync<'_>, Error> {
@@ -263,6 +385,22 @@ impl ConnectionAsync {
         TransactionAsync::new(self).await
     }
 
+    /// Run `f` in a transaction and commit it, running it again on
+    /// contention as `policy` allows (see `Connection::retry_transaction`)
+    pub async fn retry_transaction<T, F>(&mut self, policy: &RetryPolicy, mut f: F) -> Result<T, Error>
+    where
+        F: AsyncFnMut(&mut TransactionAsync<'_>) -> Result<T, Error>,
+    {
+        let runtime = self.wp.lock().await.runtime();
+        retry::retry_async_on(&runtime, policy, &[RetryClass::Transaction], async || {
+            let mut tx = self.transaction().await?;
+            let value = f(&mut tx).await?;
+            tx.commit().await?;
+            Ok(value)
+        })
+        .await
+    }
+
     // methods for Statement
 
     pub(crate) async fn _execute_statement(
@@ -272,46 +410,111 @@ impl ConnectionAsync {
         stmt_type: u32,
         params: &[(Vec<u8>, Vec<u8>, bool)],
     ) -> Result<usize, Error> {
-        let mut wp = self.wp.borrow_mut();
+        let mut wp = self.wp.lock().await;
         wp.op_execute(stmt_handle, trans_handle, params).await?;
         wp.op_response().await?;
         Ok(wp.rowcount(stmt_handle, stmt_type).await?)
     }
 
+    /// Prepare the query again on the same statement handle (metadata may have changed)
+    pub(crate) async fn _reprepare(
+        &self,
+        query: &str,
+        trans_handle: i32,
+        stmt_handle: i32,
+    ) -> Result<(u32, Vec<XSQLVar>), Error> {
+        let mut wp = self.wp.lock().await;
+        wp.op_prepare_statement(stmt_handle, trans_handle, query)
+            .await?;
+        let (_, _, buf) = wp.op_response().await?;
+        wp.parse_xsqlda(&buf, stmt_handle).await
+    }
+
+    pub(crate) async fn _describe_input(&self, stmt_handle: i32) -> Result<Vec<XSQLVar>, Error> {
+        let mut wp = self.wp.lock().await;
+        wp.describe_input(stmt_handle).await
+    }
+
     pub(crate) async fn _fetch(
         &self,
         stmt_handle: i32,
         blr: &Vec<u8>,
         xsqlda: &[XSQLVar],
     ) -> Result<(Vec<Vec<CellValue>>, bool), Error> {
-        let mut wp = self.wp.borrow_mut();
+        let mut wp = self.wp.lock().await;
         wp.op_fetch(stmt_handle, &blr).await?;
         wp.op_fetch_response(xsqlda).await
     }
 
+    /// Next row of an open cursor, fetched alone
+    pub(crate) async fn _fetch_one(
+        &self,
+        stmt_handle: i32,
+        blr: &[u8],
+        xsqlda: &[XSQLVar],
+    ) -> Result<Option<Vec<CellValue>>, Error> {
+        let mut wp = self.wp.lock().await;
+        wp.op_fetch_rows(stmt_handle, blr, 1).await?;
+        let (rows, _) = wp.op_fetch_response(xsqlda).await?;
+        Ok(rows.into_iter().next())
+    }
+
+    pub(crate) async fn _set_cursor(&self, stmt_handle: i32, name: &str) -> Result<(), Error> {
+        let mut wp = self.wp.lock().await;
+        wp.op_set_cursor(stmt_handle, name).await?;
+        wp.op_response().await?;
+        Ok(())
+    }
+
     pub(crate) async fn _get_blob_segments(
         &self,
         blob_id: &Vec<u8>,
         trans_handle: i32,
+        bpb: &[u8],
     ) -> Result<Vec<u8>, Error> {
-        let mut wp = self.wp.borrow_mut();
-        wp.get_blob_segments(blob_id, trans_handle).await
+        let mut wp = self.wp.lock().await;
+        wp.get_blob_segments(blob_id, trans_handle, bpb).await
     }
 
     pub(crate) async fn _free_statement(&self, stmt_handle: i32, drop_type: i32) -> () {
-        let mut wp = self.wp.borrow_mut();
-        wp.op_free_statement(stmt_handle, drop_type).await.unwrap();
+        // also runs from Drop, where a lost connection must not panic
+        let mut wp = self.wp.lock().await;
+        if wp.op_free_statement(stmt_handle, drop_type).await.is_err() {
+            return;
+        }
         if (wp.accept_type & PTYPE_MASK) == PTYPE_LAZY_SEND {
             wp.lazy_response_count += 1;
         } else {
-            wp.op_response().await.unwrap();
+            let _ = wp.op_response().await;
         }
     }
 
+    /// Run cleanup from `Drop` on the connection's runtime. Skipped while
+    /// another future is using the connection: blocking on the lock here
+    /// would never let that future finish.
+    pub(crate) fn block_on(&self, future: LocalBoxFuture<'_, ()>) {
+        let Some(wp) = self.wp.try_lock() else {
+            log::warn!(target: "firebirust::connection", "connection busy, cleanup on drop skipped");
+            return;
+        };
+        let runtime = wp.runtime();
+        drop(wp);
+        runtime.block_on(future);
+    }
+
+    /// The wire protocol for a quick synchronous call. Panics while another
+    /// future is in the middle of a request on this connection.
+    fn wp_now(&self) -> MutexGuard<'_, WireProtocolAsync> {
+        self.wp
+            .try_lock()
+            .expect("ConnectionAsync is in use by another future")
+    }
+
     // methods for Transaction
     pub(crate) async fn drop_transaction(&self, trans_handle: i32) -> () {
-        let mut wp = self.wp.borrow_mut();
-        wp.op_rollback(trans_handle).await.unwrap();
-        wp.op_response().await.unwrap();
+        let mut wp = self.wp.lock().await;
+        if wp.op_rollback(trans_handle).await.is_ok() {
+            let _ = wp.op_response().await;
+        }
     }
 }
@@ -20,222 +20,250 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-fn quaterround_u32(state: &mut [u32; 16], i: usize, j: usize, k: usize, l: usize) {
-    let mut a = state[i];
-    let mut b = state[j];
-    let mut c = state[k];
-    let mut d = state[l];
-    a = a.wrapping_add(b);
-    d ^= a;
-    d = d.rotate_left(16);
-    c = c.wrapping_add(d);
-    b ^= c;
-    b = b.rotate_left(12);
-    a = a.wrapping_add(b);
-    d ^= a;
-    d = d.rotate_left(8);
-    c = c.wrapping_add(d);
-    b ^= c;
-    b = b.rotate_left(7);
-    state[i] = a;
-    state[j] = b;
-    state[k] = c;
-    state[l] = d;
+//! Wire encryption
+//!
+//! After authentication the server and client agree on a crypt plugin and
+//! every byte on the wire goes through a stream cipher keyed by the SRP
+//! session key. ChaCha (IETF, 96 bit nonce), ChaCha64 (64 bit nonce and
+//! counter) and Arc4 are built in; the ChaCha variants run on the RustCrypto
+//! `chacha20` crate.
+//!
+//! A plugin is a `CryptPlugin` that builds one `CryptTranslator` per
+//! direction.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{CryptPlugin, CryptTranslator, Error};
+//!
+//! struct Xor(u8);
+//!
+//! impl CryptTranslator for Xor {
+//!     fn translate(&mut self, data: &[u8]) -> Vec<u8> {
+//!         data.iter().map(|b| b ^ self.0).collect()
+//!     }
+//! }
+//!
+//! struct XorPlugin;
+//!
+//! impl CryptPlugin for XorPlugin {
+//!     fn name(&self) -> &str {
+//!         "Xor"
+//!     }
+//!
+//!     fn translator(&self, key: &[u8], _data: &[u8]) -> Result<Box<dyn CryptTranslator>, Error> {
+//!         Ok(Box::new(Xor(key[0])))
+//!     }
+//! }
+//! ```
+
+use super::error::{Error, ValueError};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use chacha20::{ChaCha20, ChaCha20Legacy};
+use digest::Digest;
+use sha2::Sha256;
+
+/// Stream cipher for one direction of the wire
+pub trait CryptTranslator: Send {
+    fn translate(&mut self, data: &[u8]) -> Vec<u8>;
 }
 
-pub(crate) trait CryptTranslator {
-    fn translate(&mut self, plain: &[u8]) -> Vec<u8>;
+/// Wire crypt plugin, matched by the name the server announces
+pub trait CryptPlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Build a translator from the session key and the plugin specific
+    /// data sent by the server (the nonce for ChaCha)
+    fn translator(&self, key: &[u8], data: &[u8]) -> Result<Box<dyn CryptTranslator>, Error>;
+}
+
+enum ChaChaCipher {
+    Ietf(ChaCha20),
+    Legacy(ChaCha20Legacy),
 }
 
-#[derive(Debug)]
 pub(crate) struct ChaCha {
-    key: Vec<u32>,
-    nonce: Vec<u32>,
-    counter: u64,
-    block: [u8; 64],
-    block_pos: usize,
+    cipher: ChaChaCipher,
 }
 
 impl ChaCha {
-    pub fn new(key: &[u8], nonce: &[u8]) -> ChaCha {
+    /// 32 bytes key, 12 bytes nonce (IETF) or 8 bytes nonce (64 bit counter)
+    pub fn new(key: &[u8], nonce: &[u8]) -> Result<ChaCha, Error> {
         if key.len() != 32 {
-            panic!("ChaCha key is 32 bytes length");
-        }
-        if nonce.len() != 8 && nonce.len() != 12 {
-            panic!("ChaCha nonce is 8 bytes or 12 bytes length");
-        }
-
-        let key = key
-            .chunks_exact(4)
-            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-            .collect::<Vec<u32>>();
-
-        let nonce = nonce
-            .chunks_exact(4)
-            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-            .collect::<Vec<u32>>();
-
-        if key.len() != 8 {
-            panic!("Invalid key length.");
-        }
-        if nonce.len() != 2 && nonce.len() != 3 {
-            panic!("Invalid nonce length.");
+            return Err(ValueError::new("ChaCha key is 32 bytes length").into());
         }
-
-        let counter = 0u64;
-
-        let mut chacha = ChaCha {
-            key,
-            nonce,
-            counter,
-            block: [0; 64],
-            block_pos: 0,
+        let cipher = match nonce.len() {
+            12 => ChaChaCipher::Ietf(ChaCha20::new(key.into(), nonce.into())),
+            8 => ChaChaCipher::Legacy(ChaCha20Legacy::new(key.into(), nonce.into())),
+            _ => {
+                return Err(ValueError::new("ChaCha nonce is 8 bytes or 12 bytes length").into());
+            }
         };
-        chacha.set_chacha20_round_block();
-        chacha
-    }
-
-    fn to_state(&self) -> [u32; 16] {
-        let mut state = [0u32; 16];
-        state[0] = 0x61707865;
-        state[1] = 0x3320646e;
-        state[2] = 0x79622d32;
-        state[3] = 0x6b206574;
-        (0..self.key.len()).into_iter().for_each(|i| {
-            state[4 + i] = self.key[i];
-        });
-
-        if self.nonce.len() == 3 {
-            state[12] = self.counter as u32;
-            state[13] = self.nonce[0];
-            state[14] = self.nonce[1];
-            state[15] = self.nonce[2];
-        } else {
-            state[12] = self.counter as u32;
-            state[13] = (self.counter >> 32) as u32;
-            state[14] = self.nonce[0];
-            state[15] = self.nonce[1];
-        }
-        state
-    }
-
-    fn set_chacha20_round_block(&mut self) {
-        let state = self.to_state();
-
-        let mut x = [0u32; 16];
-        x.copy_from_slice(&state);
-        for _ in 0..10 {
-            quaterround_u32(&mut x, 0, 4, 8, 12);
-            quaterround_u32(&mut x, 1, 5, 9, 13);
-            quaterround_u32(&mut x, 2, 6, 10, 14);
-            quaterround_u32(&mut x, 3, 7, 11, 15);
-
-            quaterround_u32(&mut x, 0, 5, 10, 15);
-            quaterround_u32(&mut x, 1, 6, 11, 12);
-            quaterround_u32(&mut x, 2, 7, 8, 13);
-            quaterround_u32(&mut x, 3, 4, 9, 14);
-        }
-        for i in 0..16 {
-            x[i] = x[i].wrapping_add(state[i]);
-        }
-        let key_stream = x
-            .iter()
-            .flat_map(|state| state.to_le_bytes())
-            .collect::<Vec<u8>>();
-
-        self.block.copy_from_slice(&key_stream);
-        self.block_pos = 0;
+        Ok(ChaCha { cipher })
     }
 }
 
 impl CryptTranslator for ChaCha {
-    fn translate(&mut self, plain: &[u8]) -> Vec<u8> {
-        let mut enc = vec![0u8; plain.len()];
-
-        for i in 0..plain.len() {
-            enc[i] = plain[i] ^ self.block[self.block_pos];
-            self.block_pos += 1;
-            if self.block.len() == self.block_pos {
-                self.counter += 1;
-                self.set_chacha20_round_block()
-            }
+    fn translate(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut buf = data.to_vec();
+        match &mut self.cipher {
+            ChaChaCipher::Ietf(c) => c.apply_keystream(&mut buf),
+            ChaChaCipher::Legacy(c) => c.apply_keystream(&mut buf),
         }
-        enc
+        buf
     }
 }
 
-#[derive(Debug)]
+/// RC4 with a variable length key (the RustCrypto `rc4` crate fixes the key
+/// size at compile time)
 pub(crate) struct Arc4 {
-    state: Vec<u8>,
-    x: usize,
-    y: usize,
+    state: [u8; 256],
+    x: u8,
+    y: u8,
 }
 
 impl Arc4 {
     pub fn new(key: &[u8]) -> Arc4 {
-        let mut state: Vec<u8> = Vec::new();
-        for i in 0..256 {
-            state.push(i as u8);
+        let mut state = [0u8; 256];
+        for (i, s) in state.iter_mut().enumerate() {
+            *s = i as u8;
         }
-        assert_eq!(state.len(), 256);
-
-        let mut index1: usize = 0;
-        let mut index2: usize = 0;
-
+        let mut j: u8 = 0;
         for i in 0..256 {
-            index2 = (key[index1] as usize + state[i] as usize + index2) % 256;
-            let tmp: u8 = state[i];
-            state[i] = state[index2];
-            state[index2] = tmp;
-            index1 = (index1 + 1) % key.len();
+            j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+            state.swap(i, j as usize);
         }
-
         Arc4 { state, x: 0, y: 0 }
     }
 }
 
 impl CryptTranslator for Arc4 {
-    fn translate(&mut self, plain: &[u8]) -> Vec<u8> {
-        let mut enc: Vec<u8> = Vec::new();
-        for i in 0..plain.len() {
-            self.x = (self.x + 1) % 256;
-            self.y = (self.y + self.state[self.x] as usize) % 256;
-
-            let tmp = self.state[self.x];
-            self.state[self.x] = self.state[self.y];
-            self.state[self.y] = tmp;
-
-            let xor_index: usize =
-                (self.state[self.x] as usize + self.state[self.y] as usize) % 256;
-            enc.push(plain[i] ^ self.state[xor_index]);
-        }
+    fn translate(&mut self, data: &[u8]) -> Vec<u8> {
+        data.iter()
+            .map(|b| {
+                self.x = self.x.wrapping_add(1);
+                self.y = self.y.wrapping_add(self.state[self.x as usize]);
+                self.state.swap(self.x as usize, self.y as usize);
+                let k = self.state[self.x as usize].wrapping_add(self.state[self.y as usize]);
+                b ^ self.state[k as usize]
+            })
+            .collect()
+    }
+}
+
+struct ChaChaPlugin(&'static str);
 
-        enc
+impl CryptPlugin for ChaChaPlugin {
+    fn name(&self) -> &str {
+        self.0
+    }
+
+    fn translator(&self, key: &[u8], data: &[u8]) -> Result<Box<dyn CryptTranslator>, Error> {
+        let key = Sha256::digest(key);
+        Ok(Box::new(ChaCha::new(&key, data)?))
     }
 }
 
-#[test]
-fn test_arc4() {
-    let mut a1 = Arc4::new(b"a key");
-    let enc = a1.translate(b"plain text");
-    let correct: Vec<u8> = vec![0x4b, 0x4b, 0xdc, 0x65, 0x02, 0xb3, 0x08, 0x17, 0x48, 0x82];
-    assert_eq!(&enc, &correct);
+struct Arc4Plugin;
 
-    let mut a2 = Arc4::new(b"a key");
-    let plain = a2.translate(&enc);
-    assert_eq!(&plain, b"plain text");
+impl CryptPlugin for Arc4Plugin {
+    fn name(&self) -> &str {
+        "Arc4"
+    }
+
+    fn translator(&self, key: &[u8], _data: &[u8]) -> Result<Box<dyn CryptTranslator>, Error> {
+        Ok(Box::new(Arc4::new(key)))
+    }
+}
+
+static BUILTIN_PLUGINS: [&dyn CryptPlugin; 3] = [
+    &ChaChaPlugin("ChaCha64"),
+    &ChaChaPlugin("ChaCha"),
+    &Arc4Plugin,
+];
+
+/// Built in plugin by wire name
+pub(crate) fn builtin_plugin(name: &[u8]) -> Option<&'static dyn CryptPlugin> {
+    BUILTIN_PLUGINS
+        .iter()
+        .copied()
+        .find(|p| p.name().as_bytes() == name)
 }
 
-#[test]
-fn test_chacha() {
-    use hex;
-    let key =
-        hex::decode("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f").unwrap();
-    let nonce = hex::decode("000000000000000000000000").unwrap();
-    let mut a1 = ChaCha::new(&key, &nonce);
-    let enc = a1.translate(b"plain text");
-
-    let mut a2 = ChaCha::new(&key, &nonce);
-    let plain = a2.translate(&enc);
-    assert_eq!(&plain, b"plain text");
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arc4() {
+        let mut a1 = Arc4::new(b"a key");
+        let enc = a1.translate(b"plain text");
+        let correct: Vec<u8> = vec![0x4b, 0x4b, 0xdc, 0x65, 0x02, 0xb3, 0x08, 0x17, 0x48, 0x82];
+        assert_eq!(&enc, &correct);
+
+        let mut a2 = Arc4::new(b"a key");
+        let plain = a2.translate(&enc);
+        assert_eq!(&plain, b"plain text");
+    }
+
+    #[test]
+    fn test_chacha() {
+        let key = hex::decode("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f")
+            .unwrap();
+        let nonce = hex::decode("000000000000000000000000").unwrap();
+        let mut a1 = ChaCha::new(&key, &nonce).unwrap();
+        let enc = a1.translate(b"plain text");
+
+        let mut a2 = ChaCha::new(&key, &nonce).unwrap();
+        let plain = a2.translate(&enc);
+        assert_eq!(&plain, b"plain text");
+
+        assert!(ChaCha::new(&key[..16], &nonce).is_err());
+        assert!(ChaCha::new(&key, &nonce[..4]).is_err());
+    }
+
+    #[test]
+    fn test_chacha_keystream() {
+        // Output of the previous in-tree implementation, across block
+        // boundaries and split calls
+        let key: Vec<u8> = (0u8..32).collect();
+        let data: Vec<u8> = (0u8..150).collect();
+
+        let mut a = ChaCha::new(&key, &[0, 0, 0, 0, 0, 0, 0, 0x4a, 0, 0, 0, 0]).unwrap();
+        let mut enc = a.translate(&data[..10]);
+        enc.extend(a.translate(&data[10..]));
+        assert_eq!(
+            hex::encode(&enc),
+            "af041c43bfa5334e893b908b661900a0c249b03979de5daeee4f86a8f3ffc0b0\
+             a35afa5f862e7d8608a8892d83239d7371930be139c942ff2f486c36a0a12074\
+             620e13b0045e9fa667976d24f42e53a2dc424dd1697950b5261690b7c2ae9127\
+             ea5a68c016056cf5dd101ea68146fd5b093dc833b24b42ba925856378c003fc8\
+             e927f61cbbe689c6aa4374a360c04af1b645a6fee42c"
+        );
+
+        let mut b = ChaCha::new(&key, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert_eq!(
+            hex::encode(b.translate(&data)),
+            "8ceb5a3cc38d6c31c3a6f0ae99e0dcecb0c67b4535666fdadef3a6c82c8e713b\
+             ecb2dd84ab60b6afade68ac1e888f3ba425b212bc0722071e943cbed858d88f4\
+             afa7e7bbe1c9eedb58f6a29dc6a16911c5dd360248187e4b438c6eb930b5fbda\
+             57716dee0e0a30e81570ed65b61480dd3ef1e80c1f6b8175bffd40076d1aae92\
+             af575854b6efc6dc4f45768de2c3f8cb0d7331055e4b"
+        );
+    }
+
+    #[test]
+    fn test_builtin_plugin() {
+        let session_key = b"session key";
+        let nonce = [7u8; 12];
+        let plugin = builtin_plugin(b"ChaCha").unwrap();
+        let mut enc = plugin.translator(session_key, &nonce).unwrap();
+        let key = Sha256::digest(session_key);
+        let mut dec = ChaCha::new(&key, &nonce).unwrap();
+        assert_eq!(dec.translate(&enc.translate(b"plain text")), b"plain text");
+
+        assert_eq!(builtin_plugin(b"Arc4").unwrap().name(), "Arc4");
+        assert_eq!(builtin_plugin(b"ChaCha64").unwrap().name(), "ChaCha64");
+        assert!(builtin_plugin(b"Unknown").is_none());
+    }
 }
@@ -0,0 +1,160 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Identity of a database for inventories and agents
+//!
+//! `DatabaseIdentity` tells databases apart whatever path or alias they are
+//! reached by: the GUID is generated when the database is created, and a
+//! gbak restore creates a new database with a new one. It also carries the
+//! creation date, the ODS version and the last nbackup from
+//! RDB$BACKUP_HISTORY.
+//!
+//! Needs Firebird 4.0 or later (`DB_GUID` context variable).
+//!
+//! # Example
+//!
+//! ```ignore
+//! let id = conn.database_identity()?;
+//! println!("{} ODS {} created {}", id.guid, id.ods_version(), id.created);
+//! if let Some(backup) = &id.last_backup {
+//!     println!("last level {} backup at {}", backup.level, backup.timestamp);
+//! }
+//! ```
+
+use super::error::Error;
+use super::row::{FromRow, Row};
+
+pub(crate) const DATABASE_IDENTITY_SQL: &str = "SELECT RDB$GET_CONTEXT('SYSTEM', 'DB_GUID'),
+    m.MON$DATABASE_NAME, m.MON$CREATION_DATE, m.MON$ODS_MAJOR, m.MON$ODS_MINOR,
+    b.RDB$TIMESTAMP, b.RDB$BACKUP_LEVEL, b.RDB$GUID, b.RDB$SCN, b.RDB$FILE_NAME
+    FROM MON$DATABASE m
+    LEFT JOIN (SELECT FIRST 1 RDB$TIMESTAMP, RDB$BACKUP_LEVEL, RDB$GUID, RDB$SCN, RDB$FILE_NAME
+        FROM RDB$BACKUP_HISTORY
+        ORDER BY RDB$TIMESTAMP DESC, RDB$BACKUP_ID DESC) b ON TRUE";
+
+/// Identity of the connected database
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseIdentity {
+    /// GUID of the database, e.g. "{0B4F2E7C-...}"
+    pub guid: String,
+    /// File name as the server sees it
+    pub file_name: String,
+    pub created: chrono::DateTime<chrono_tz::Tz>,
+    pub ods_major: u16,
+    pub ods_minor: u16,
+    /// Most recent nbackup, `None` if there was none
+    pub last_backup: Option<BackupInfo>,
+}
+
+/// An nbackup recorded in RDB$BACKUP_HISTORY
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupInfo {
+    pub timestamp: chrono::DateTime<chrono_tz::Tz>,
+    /// 0 for a full backup, n for an increment over level n - 1
+    pub level: i32,
+    /// GUID of the backup, referenced by the next level
+    pub guid: String,
+    /// Page SCN the backup was taken at
+    pub scn: i32,
+    pub file_name: String,
+}
+
+impl DatabaseIdentity {
+    /// ODS version as "major.minor", e.g. "13.1"
+    pub fn ods_version(&self) -> String {
+        format!("{}.{}", self.ods_major, self.ods_minor)
+    }
+}
+
+impl FromRow for DatabaseIdentity {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        let last_backup = match row.get_nullable(5)? {
+            Some(timestamp) => Some(BackupInfo {
+                timestamp,
+                level: row.get(6)?,
+                guid: row.get::<String>(7)?.trim_end().to_string(),
+                scn: row.get(8)?,
+                file_name: row.get::<String>(9)?.trim_end().to_string(),
+            }),
+            None => None,
+        };
+        Ok(DatabaseIdentity {
+            guid: row.get(0)?,
+            file_name: row.get::<String>(1)?.trim_end().to_string(),
+            created: row.get(2)?,
+            ods_major: row.get::<i16>(3)? as u16,
+            ods_minor: row.get::<i16>(4)? as u16,
+            last_backup,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellvalue::CellValue;
+    use chrono::TimeZone;
+    use std::sync::Arc;
+
+    fn row(backup: bool) -> Row {
+        let ts = chrono_tz::UTC
+            .with_ymd_and_hms(2024, 5, 1, 12, 0, 0)
+            .unwrap();
+        let guid = "{0B4F2E7C-1D2A-4C5B-9E8F-112233445566}".to_string();
+        let mut cells = vec![
+            CellValue::Varying(guid.clone()),
+            CellValue::Text("/data/employee.fdb   ".to_string()),
+            CellValue::TimeStampTz(ts),
+            CellValue::Short(13),
+            CellValue::Short(1),
+        ];
+        if backup {
+            cells.extend([
+                CellValue::TimeStampTz(ts),
+                CellValue::Long(1),
+                CellValue::Varying(guid),
+                CellValue::Long(1234),
+                CellValue::Varying("/backup/employee.nbk".to_string()),
+            ]);
+        } else {
+            cells.extend(std::iter::repeat_n(CellValue::Null, 5));
+        }
+        Row::new(cells, Arc::from(Vec::new()), false, 1)
+    }
+
+    #[test]
+    fn test_from_row() {
+        let id = DatabaseIdentity::from_row(&row(false)).unwrap();
+        assert_eq!(id.file_name, "/data/employee.fdb");
+        assert_eq!(id.ods_version(), "13.1");
+        assert!(id.last_backup.is_none());
+
+        let backup = DatabaseIdentity::from_row(&row(true))
+            .unwrap()
+            .last_backup
+            .unwrap();
+        assert_eq!(backup.level, 1);
+        assert_eq!(backup.scn, 1234);
+        assert_eq!(backup.file_name, "/backup/employee.nbk");
+    }
+}
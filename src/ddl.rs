@@ -0,0 +1,281 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Schema helpers for setup and teardown code
+//!
+//! `execute_ddl` runs a statement in its own transaction and commits it at
+//! once, so the change is visible to the following statements. A table still
+//! used by another statement or attachment is reported as
+//! `Error::ObjectInUse` instead of an opaque metadata update error.
+//!
+//! # Example
+//!
+//! ```ignore
+//! conn.drop_table_if_exists("foo")?;
+//! conn.execute_ddl("CREATE TABLE foo (a INTEGER)")?;
+//! assert!(conn.table_exists("foo")?);
+//!
+//! // Firebird has no TRUNCATE
+//! conn.truncate_table("foo", TruncateMode::DeleteAll)?;
+//!
+//! conn.create_sequence("seq_foo", Some(100))?;
+//! assert_eq!(conn.next_value("seq_foo")?, 100);
+//! assert_eq!(conn.gen_id("seq_foo", 10)?, 110);
+//! assert_eq!(conn.sequence_current("seq_foo")?, 110);
+//! ```
+
+use super::error::{Error, ValueError};
+
+/// Rows deleted per transaction by `TruncateMode::DeleteAll`
+pub(crate) const DELETE_CHUNK: usize = 10_000;
+
+/// How `Connection::truncate_table` clears a table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncateMode {
+    /// Delete the rows in chunks, committing each chunk, then restart the
+    /// identity columns. Works on tables referenced by other objects.
+    DeleteAll,
+    /// Recreate the table from its extracted DDL in one transaction, which
+    /// also resets its identity generators. Faster for large tables, but
+    /// only columns, primary key, unique constraints and plain indexes are
+    /// recreated: tables with triggers, checks, foreign keys (either way),
+    /// grants, or expression, partial or inactive indexes are refused with
+    /// `Error::ValueError` and should use `DeleteAll`. Fails with
+    /// `Error::ObjectInUse` while the table is in use.
+    RecreateFromDdl,
+}
+
+/// `ALTER TABLE` statements restarting the identity columns of a table
+pub(crate) fn restart_identity_sql(table: &str, columns: &[String]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|c| {
+            format!(
+                "ALTER TABLE {} ALTER COLUMN {} RESTART",
+                quote(table),
+                quote(c)
+            )
+        })
+        .collect()
+}
+
+/// What `TruncateMode::RecreateFromDdl` can't recreate, each with a query
+/// counting such objects for one table
+pub(crate) const RECREATE_DEPENDENTS: &[(&str, &str)] = &[
+    (
+        "triggers",
+        "SELECT COUNT(*) FROM RDB$TRIGGERS \
+         WHERE RDB$RELATION_NAME = ? AND COALESCE(RDB$SYSTEM_FLAG, 0) = 0",
+    ),
+    (
+        "check or foreign key constraints",
+        "SELECT COUNT(*) FROM RDB$RELATION_CONSTRAINTS \
+         WHERE RDB$RELATION_NAME = ? AND RDB$CONSTRAINT_TYPE IN ('CHECK', 'FOREIGN KEY')",
+    ),
+    (
+        "foreign keys referencing it",
+        "SELECT COUNT(*) FROM RDB$REF_CONSTRAINTS rc \
+         JOIN RDB$RELATION_CONSTRAINTS uq ON uq.RDB$CONSTRAINT_NAME = rc.RDB$CONST_NAME_UQ \
+         WHERE uq.RDB$RELATION_NAME = ?",
+    ),
+    (
+        "grants",
+        "SELECT COUNT(*) FROM RDB$USER_PRIVILEGES p \
+         JOIN RDB$RELATIONS r ON r.RDB$RELATION_NAME = p.RDB$RELATION_NAME \
+         WHERE p.RDB$RELATION_NAME = ? AND p.RDB$USER <> r.RDB$OWNER_NAME",
+    ),
+    (
+        "expression or inactive indexes",
+        "SELECT COUNT(*) FROM RDB$INDICES \
+         WHERE RDB$RELATION_NAME = ? AND (RDB$EXPRESSION_BLR IS NOT NULL OR RDB$INDEX_INACTIVE = 1)",
+    ),
+];
+
+/// Partial indexes only exist where `RDB$INDICES` has a condition (Firebird 5)
+pub(crate) const HAS_PARTIAL_INDEXES_SQL: &str = "SELECT COUNT(*) FROM RDB$RELATION_FIELDS \
+     WHERE RDB$RELATION_NAME = 'RDB$INDICES' AND RDB$FIELD_NAME = 'RDB$CONDITION_SOURCE'";
+
+pub(crate) const PARTIAL_INDEXES_SQL: &str = "SELECT COUNT(*) FROM RDB$INDICES \
+     WHERE RDB$RELATION_NAME = ? AND RDB$CONDITION_SOURCE IS NOT NULL";
+
+/// Error refusing `TruncateMode::RecreateFromDdl` for a table with
+/// dependents it would lose
+pub(crate) fn recreate_refused(table: &str, dependents: &[&str]) -> Error {
+    Error::ValueError(ValueError::new(&format!(
+        "Table {} has {} which recreating it would lose; use TruncateMode::DeleteAll",
+        table,
+        dependents.join(", ")
+    )))
+}
+
+/// Turn an extracted `CREATE [GLOBAL TEMPORARY] TABLE` into `RECREATE ..`,
+/// so dropping and creating happen in the one statement
+pub(crate) fn recreate_table_sql(create: &str) -> String {
+    match create.strip_prefix("CREATE ") {
+        Some(rest) => format!("RECREATE {}", rest),
+        None => create.to_string(),
+    }
+}
+
+/// `SELECT GEN_ID(..)` adding `increment` to a sequence and returning the
+/// new value; an increment of 0 reads the current value
+pub(crate) fn gen_id_sql(sequence: &str, increment: i64) -> String {
+    format!(
+        "SELECT GEN_ID({}, {}) FROM RDB$DATABASE",
+        quote(&object_name(sequence)),
+        increment
+    )
+}
+
+/// `CREATE SEQUENCE` whose first `NEXT VALUE FOR` returns `start` when
+/// given. Firebird 4.0 (protocol 16) made `START WITH` the first value; on
+/// older servers it is the value before the first, so one less is sent.
+pub(crate) fn create_sequence_sql(
+    sequence: &str,
+    start: Option<i64>,
+    protocol_version: i32,
+) -> String {
+    let name = quote(&object_name(sequence));
+    match start {
+        Some(start) if protocol_version < 16 => {
+            format!(
+                "CREATE SEQUENCE {} START WITH {}",
+                name,
+                start.saturating_sub(1)
+            )
+        }
+        Some(start) => format!("CREATE SEQUENCE {} START WITH {}", name, start),
+        None => format!("CREATE SEQUENCE {}", name),
+    }
+}
+
+/// Name as stored in the system tables: unquoted identifiers are upper
+/// case, quoted ones keep their case and lose the quotes
+pub(crate) fn object_name(name: &str) -> String {
+    let name = name.trim();
+    match name.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\"\"", "\""),
+        None => name.to_uppercase(),
+    }
+}
+
+/// Quoted identifier for an object name as stored in the system tables
+pub(crate) fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Turn "object in use" errors of a DDL statement into `Error::ObjectInUse`
+pub(crate) fn ddl_error(err: Error) -> Error {
+    match err {
+        Error::FirebirdError(e) if e.is_object_in_use() => Error::ObjectInUse(e),
+        err => err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{FirebirdError, ISC_NO_META_UPDATE, ISC_OBJ_IN_USE};
+
+    #[test]
+    fn test_object_name() {
+        assert_eq!(object_name("foo"), "FOO");
+        assert_eq!(object_name(" Foo_1 "), "FOO_1");
+        assert_eq!(object_name("\"Foo\""), "Foo");
+        assert_eq!(object_name("\"a\"\"b\""), "a\"b");
+        assert_eq!(quote(&object_name("\"a\"\"b\"")), "\"a\"\"b\"");
+        assert_eq!(quote("FOO"), "\"FOO\"");
+    }
+
+    #[test]
+    fn test_restart_identity_sql() {
+        assert_eq!(
+            restart_identity_sql("FOO", &["ID".to_string()]),
+            vec!["ALTER TABLE \"FOO\" ALTER COLUMN \"ID\" RESTART"]
+        );
+        assert!(restart_identity_sql("FOO", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_recreate_table_sql() {
+        assert_eq!(
+            recreate_table_sql("CREATE TABLE \"FOO\" (\n    ID INTEGER\n)"),
+            "RECREATE TABLE \"FOO\" (\n    ID INTEGER\n)"
+        );
+        assert_eq!(
+            recreate_table_sql(
+                "CREATE GLOBAL TEMPORARY TABLE \"T\" (X INTEGER) ON COMMIT DELETE ROWS"
+            ),
+            "RECREATE GLOBAL TEMPORARY TABLE \"T\" (X INTEGER) ON COMMIT DELETE ROWS"
+        );
+        match recreate_refused("FOO", &["triggers", "grants"]) {
+            Error::ValueError(e) => assert!(e.message.contains("has triggers, grants")),
+            e => panic!("unexpected {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_sequence_sql() {
+        assert_eq!(
+            gen_id_sql("seq_foo", 1),
+            "SELECT GEN_ID(\"SEQ_FOO\", 1) FROM RDB$DATABASE"
+        );
+        assert_eq!(
+            gen_id_sql("\"Seq\"", -5),
+            "SELECT GEN_ID(\"Seq\", -5) FROM RDB$DATABASE"
+        );
+        assert_eq!(
+            create_sequence_sql("seq_foo", None, 17),
+            "CREATE SEQUENCE \"SEQ_FOO\""
+        );
+        assert_eq!(
+            create_sequence_sql("seq_foo", None, 15),
+            "CREATE SEQUENCE \"SEQ_FOO\""
+        );
+        // Firebird 4+ returns START WITH first, Firebird 3 the value after it
+        assert_eq!(
+            create_sequence_sql("seq_foo", Some(100), 16),
+            "CREATE SEQUENCE \"SEQ_FOO\" START WITH 100"
+        );
+        assert_eq!(
+            create_sequence_sql("seq_foo", Some(100), 15),
+            "CREATE SEQUENCE \"SEQ_FOO\" START WITH 99"
+        );
+    }
+
+    #[test]
+    fn test_ddl_error() {
+        let e = FirebirdError::with_gds_codes(
+            "unsuccessful metadata update\nobject TABLE \"FOO\" is in use",
+            -607,
+            vec![ISC_NO_META_UPDATE, ISC_OBJ_IN_USE],
+        );
+        assert!(matches!(ddl_error(e.into()), Error::ObjectInUse(_)));
+        let e = FirebirdError::with_gds_codes(
+            "unsuccessful metadata update",
+            -607,
+            vec![ISC_NO_META_UPDATE],
+        );
+        assert!(matches!(ddl_error(e.into()), Error::FirebirdError(_)));
+    }
+}
@@ -4,6 +4,9 @@
 //! Implementation follows the order and logic from Firebird source:
 //! src/isql/extract.epp
 
+use crate::error::ValueError;
+use crate::param::ToSqlParam;
+use crate::sqltype;
 use crate::{Connection, Error};
 
 /// Extracts complete DDL schema from the database (like isql -x)
@@ -220,7 +223,7 @@ fn list_domains(conn: &mut Connection, output: &mut String) -> Result<(), Error>
             // Character set
             if let Some(cs) = csid {
                 if cs > 0 {
-                    let csname = get_charset_name(cs);
+                    let csname = sqltype::charset_name(cs as u8).unwrap_or("");
                     if !csname.is_empty() && (ft == 14 || ft == 37 || ft == 261) {
                         output.push_str(&format!(" CHARACTER SET {}", csname));
                     }
@@ -284,113 +287,150 @@ fn list_all_tables(conn: &mut Connection, output: &mut String) -> Result<(), Err
     
     for (table_name, owner_name, rel_type) in tables {
         output.push_str(&format!("\n/* Table: {}, Owner: {} */\n", table_name, owner_name));
+        output.push_str(&create_table_ddl(conn, &table_name, rel_type)?);
+        output.push_str(";\n");
+    }
+    
+    Ok(())
+}
+
+/// CREATE TABLE statement of a table with its columns, primary key and
+/// unique constraints, without the terminating semicolon
+fn create_table_ddl(conn: &mut Connection, table_name: &str, rel_type: Option<i16>) -> Result<String, Error> {
+    let mut ddl = String::new();
+
+    // Global temporary table or regular table
+    if matches!(rel_type, Some(4) | Some(5)) {
+        ddl.push_str(&format!("CREATE GLOBAL TEMPORARY TABLE {} (\n", quote_identifier(table_name)));
+    } else {
+        ddl.push_str(&format!("CREATE TABLE {} (\n", quote_identifier(table_name)));
+    }
+    
+    // Get columns
+    let col_sql = r#"
+        SELECT rf.RDB$FIELD_NAME, f.RDB$FIELD_TYPE, f.RDB$FIELD_SUB_TYPE, f.RDB$FIELD_LENGTH,
+               f.RDB$FIELD_PRECISION, f.RDB$FIELD_SCALE, f.RDB$CHARACTER_LENGTH,
+               f.RDB$CHARACTER_SET_ID, rf.RDB$DEFAULT_SOURCE, rf.RDB$NULL_FLAG,
+               f.RDB$COMPUTED_SOURCE, rf.RDB$FIELD_SOURCE, rf.RDB$COLLATION_ID,
+               rf.RDB$GENERATOR_NAME, rf.RDB$IDENTITY_TYPE
+        FROM RDB$RELATION_FIELDS rf
+        JOIN RDB$FIELDS f ON rf.RDB$FIELD_SOURCE = f.RDB$FIELD_NAME
+        WHERE rf.RDB$RELATION_NAME = ?
+        ORDER BY rf.RDB$FIELD_POSITION
+    "#;
+    
+    let mut stmt = conn.prepare(col_sql)?;
+    let cols = stmt.query((table_name,))?;
+    
+    let mut columns = Vec::new();
+    for c in cols {
+        columns.push((
+            c.get::<String>(0).unwrap_or_default().trim().to_string(),
+            c.get::<i16>(1).unwrap_or(0),
+            c.get::<i16>(2).unwrap_or(0),
+            c.get::<i16>(3).unwrap_or(0),
+            c.get::<i16>(4).unwrap_or(0),
+            c.get::<i16>(5).unwrap_or(0),
+            c.get::<i16>(6).unwrap_or(0),
+            c.get::<Option<String>>(8).ok().flatten(),
+            c.get::<Option<i16>>(9).ok().flatten(),
+            c.get::<Option<String>>(10).ok().flatten(),
+            c.get::<String>(11).unwrap_or_default().trim().to_string(),
+            c.get::<Option<i16>>(12).ok().flatten(),
+            c.get::<Option<String>>(13).ok().flatten(),
+            c.get::<Option<i16>>(14).ok().flatten(),
+        ));
+    }
+    drop(stmt);
+    
+    let mut col_defs = Vec::new();
+    for col in columns {
+        let (cname, ft, st, len, prec, scale, clen, def, nullf, comp, fsource, _coll_id, gen_name, ident_type) = col;
         
-        // Global temporary table or regular table
-        if rel_type == Some(4) {
-            output.push_str(&format!("CREATE GLOBAL TEMPORARY TABLE {} (\n", quote_identifier(&table_name)));
-        } else if rel_type == Some(5) {
-            output.push_str(&format!("CREATE GLOBAL TEMPORARY TABLE {} (\n", quote_identifier(&table_name)));
+        let mut col_def = format!("        {}", quote_identifier(&cname));
+        
+        // Check if it's a domain (not a system domain)
+        if !fsource.starts_with("RDB$") && !fsource.is_empty() {
+            col_def.push_str(&format!(" {}", quote_identifier(&fsource)));
         } else {
-            output.push_str(&format!("CREATE TABLE {} (\n", quote_identifier(&table_name)));
+            // Format base type
+            let type_str = format_data_type(ft, st, len, prec, scale, clen, None, None);
+            col_def.push_str(&format!(" {}", type_str));
         }
         
-        // Get columns
-        let col_sql = r#"
-            SELECT rf.RDB$FIELD_NAME, f.RDB$FIELD_TYPE, f.RDB$FIELD_SUB_TYPE, f.RDB$FIELD_LENGTH,
-                   f.RDB$FIELD_PRECISION, f.RDB$FIELD_SCALE, f.RDB$CHARACTER_LENGTH,
-                   f.RDB$CHARACTER_SET_ID, rf.RDB$DEFAULT_SOURCE, rf.RDB$NULL_FLAG,
-                   f.RDB$COMPUTED_SOURCE, rf.RDB$FIELD_SOURCE, rf.RDB$COLLATION_ID,
-                   rf.RDB$GENERATOR_NAME, rf.RDB$IDENTITY_TYPE
-            FROM RDB$RELATION_FIELDS rf
-            JOIN RDB$FIELDS f ON rf.RDB$FIELD_SOURCE = f.RDB$FIELD_NAME
-            WHERE rf.RDB$RELATION_NAME = ?
-            ORDER BY rf.RDB$FIELD_POSITION
-        "#;
-        
-        let mut stmt = conn.prepare(col_sql)?;
-        let cols = stmt.query((table_name.as_str(),))?;
-        
-        let mut columns = Vec::new();
-        for c in cols {
-            columns.push((
-                c.get::<String>(0).unwrap_or_default().trim().to_string(),
-                c.get::<i16>(1).unwrap_or(0),
-                c.get::<i16>(2).unwrap_or(0),
-                c.get::<i16>(3).unwrap_or(0),
-                c.get::<i16>(4).unwrap_or(0),
-                c.get::<i16>(5).unwrap_or(0),
-                c.get::<i16>(6).unwrap_or(0),
-                c.get::<Option<String>>(8).ok().flatten(),
-                c.get::<Option<i16>>(9).ok().flatten(),
-                c.get::<Option<String>>(10).ok().flatten(),
-                c.get::<String>(11).unwrap_or_default().trim().to_string(),
-                c.get::<Option<i16>>(12).ok().flatten(),
-                c.get::<Option<String>>(13).ok().flatten(),
-                c.get::<Option<i16>>(14).ok().flatten(),
-            ));
+        // Computed by
+        if let Some(ref c) = comp {
+            let trimmed = c.trim();
+            if !trimmed.is_empty() {
+                col_def.push_str(&format!(" COMPUTED BY {}", trimmed));
+            }
         }
-        drop(stmt);
         
-        let mut col_defs = Vec::new();
-        for col in columns {
-            let (cname, ft, st, len, prec, scale, clen, def, nullf, comp, fsource, _coll_id, gen_name, ident_type) = col;
-            
-            let mut col_def = format!("        {}", quote_identifier(&cname));
-            
-            // Check if it's a domain (not a system domain)
-            if !fsource.starts_with("RDB$") && !fsource.is_empty() {
-                col_def.push_str(&format!(" {}", quote_identifier(&fsource)));
-            } else {
-                // Format base type
-                let type_str = format_data_type(ft, st, len, prec, scale, clen, None, None);
-                col_def.push_str(&format!(" {}", type_str));
+        // Default
+        if let Some(ref d) = def {
+            let trimmed = d.trim();
+            if !trimmed.is_empty() {
+                col_def.push_str(&format!(" {}", trimmed));
             }
-            
-            // Computed by
-            if let Some(ref c) = comp {
-                let trimmed = c.trim();
-                if !trimmed.is_empty() {
-                    col_def.push_str(&format!(" COMPUTED BY {}", trimmed));
-                }
-            }
-            
-            // Default
-            if let Some(ref d) = def {
-                let trimmed = d.trim();
-                if !trimmed.is_empty() {
-                    col_def.push_str(&format!(" {}", trimmed));
-                }
-            }
-            
-            // GENERATED ALWAYS/BY DEFAULT AS IDENTITY
-            if let Some(ref _gen) = gen_name {
-                if let Some(ident) = ident_type {
-                    let ident_str = match ident {
-                        1 => "BY DEFAULT",
-                        2 => "ALWAYS",
-                        _ => "BY DEFAULT",
-                    };
-                    col_def.push_str(&format!(" GENERATED {} AS IDENTITY", ident_str));
-                }
-            }
-            
-            // NOT NULL
-            if nullf == Some(1) {
-                col_def.push_str(" NOT NULL");
-            }
-            
-            col_defs.push(col_def);
         }
         
-        output.push_str(&col_defs.join(",\n"));
+        // GENERATED ALWAYS/BY DEFAULT AS IDENTITY
+        if let (Some(_), Some(ident)) = (&gen_name, ident_type) {
+            let ident_str = match ident {
+                1 => "BY DEFAULT",
+                2 => "ALWAYS",
+                _ => "BY DEFAULT",
+            };
+            col_def.push_str(&format!(" GENERATED {} AS IDENTITY", ident_str));
+        }
         
-        // Primary Keys and Unique constraints
-        list_table_constraints(conn, &table_name, output)?;
+        // NOT NULL
+        if nullf == Some(1) {
+            col_def.push_str(" NOT NULL");
+        }
         
-        output.push_str("\n);\n");
+        col_defs.push(col_def);
     }
     
-    Ok(())
+    ddl.push_str(&col_defs.join(",\n"));
+    
+    // Primary Keys and Unique constraints
+    list_table_constraints(conn, table_name, &mut ddl)?;
+    
+    ddl.push_str("\n)");
+    Ok(ddl)
+}
+
+/// Statements recreating one table: CREATE TABLE with primary key and unique
+/// constraints, then its other indexes. Foreign keys, checks, triggers and
+/// grants are not included.
+pub(crate) fn extract_table_ddl(conn: &mut Connection, table_name: &str) -> Result<Vec<String>, Error> {
+    let sql = r#"
+        SELECT r.RDB$RELATION_TYPE
+        FROM RDB$RELATIONS r
+        WHERE r.RDB$RELATION_NAME = ?
+          AND r.RDB$VIEW_BLR IS NULL
+    "#;
+    let mut stmt = conn.prepare(sql)?;
+    let rel_type = match stmt.query((table_name,))?.next() {
+        Some(row) => row.get::<Option<i16>>(0).ok().flatten(),
+        None => {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "Table {} does not exist",
+                table_name
+            ))));
+        }
+    };
+    drop(stmt);
+
+    let mut statements = vec![create_table_ddl(conn, table_name, rel_type)?];
+    for (iname, unique, idx_type) in query_indexes(conn, Some(table_name))?
+        .into_iter()
+        .map(|(iname, _, unique, idx_type)| (iname, unique, idx_type))
+    {
+        statements.push(create_index_ddl(conn, &iname, table_name, unique, idx_type)?);
+    }
+    Ok(statements)
 }
 
 // ============================================================================
@@ -508,7 +548,7 @@ fn list_procedure_headers(conn: &mut Connection, output: &mut String) -> Result<
                 // Add character set for string types if not default
                 if let Some(cs) = csid {
                     if cs > 0 && (ft == 14 || ft == 37) {
-                        let csname = get_charset_name(cs);
+                        let csname = sqltype::charset_name(cs as u8).unwrap_or("");
                         if !csname.is_empty() && csname != "NONE" {
                             type_str.push_str(&format!(" CHARACTER SET {}", csname));
                         }
@@ -590,8 +630,27 @@ fn list_package_headers(conn: &mut Connection, output: &mut String) -> Result<()
 // 12. INDEXES
 // ============================================================================
 fn list_indexes(conn: &mut Connection, output: &mut String) -> Result<(), Error> {
+    let idxs = query_indexes(conn, None)?;
+    
+    if !idxs.is_empty() {
+        output.push_str("\n/*  Index definitions for all user tables */\n\n");
+        
+        for (iname, tname, unique, idx_type) in idxs {
+            output.push_str(&create_index_ddl(conn, &iname, &tname, unique, idx_type)?);
+            output.push_str(";\n");
+        }
+    }
+    
+    Ok(())
+}
+
+/// (index, table, unique flag, index type)
+type IndexRow = (String, String, Option<i16>, Option<i16>);
+
+/// Indexes not backing a constraint, of all user tables or of one table
+fn query_indexes(conn: &mut Connection, table_name: Option<&str>) -> Result<Vec<IndexRow>, Error> {
     // Query from ISQL - exclude indexes that are part of constraints
-    let sql = r#"
+    let sql = format!(r#"
         SELECT i.RDB$INDEX_NAME, i.RDB$RELATION_NAME, i.RDB$UNIQUE_FLAG, i.RDB$INDEX_TYPE
         FROM RDB$INDICES i
         JOIN RDB$RELATIONS r ON i.RDB$RELATION_NAME = r.RDB$RELATION_NAME
@@ -600,13 +659,15 @@ fn list_indexes(conn: &mut Connection, output: &mut String) -> Result<(), Error>
               SELECT 1 FROM RDB$RELATION_CONSTRAINTS rc 
               WHERE rc.RDB$INDEX_NAME = i.RDB$INDEX_NAME
           )
+          {}
         ORDER BY i.RDB$RELATION_NAME, i.RDB$INDEX_NAME
-    "#;
+    "#, if table_name.is_some() { "AND i.RDB$RELATION_NAME = ?" } else { "" });
+    let params: Vec<&dyn ToSqlParam> = table_name.iter().map(|t| t as &dyn ToSqlParam).collect();
     
-    let mut stmt = conn.prepare(sql)?;
-    let rows = stmt.query(())?;
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query(params.as_slice())?;
     
-    let mut idxs: Vec<(String, String, Option<i16>, Option<i16>)> = Vec::new();
+    let mut idxs = Vec::new();
     for row in rows {
         idxs.push((
             row.get::<String>(0).unwrap_or_default().trim().to_string(),
@@ -617,43 +678,46 @@ fn list_indexes(conn: &mut Connection, output: &mut String) -> Result<(), Error>
     }
     drop(stmt);
     
-    if !idxs.is_empty() {
-        output.push_str("\n/*  Index definitions for all user tables */\n\n");
-        
-        for (iname, tname, unique, idx_type) in idxs {
-            let unique_str = if unique == Some(1) { " UNIQUE" } else { "" };
-            let desc_str = if idx_type == Some(1) { " DESCENDING" } else { "" };
-            
-            output.push_str(&format!("CREATE{}{} INDEX {} ON {}",
-                unique_str, desc_str, quote_identifier(&iname), quote_identifier(&tname)));
-            
-            // Get index segments
-            let seg_sql = r#"
-                SELECT s.RDB$FIELD_NAME 
-                FROM RDB$INDEX_SEGMENTS s
-                WHERE s.RDB$INDEX_NAME = ?
-                ORDER BY s.RDB$FIELD_POSITION
-            "#;
-            
-            let mut stmt2 = conn.prepare(seg_sql)?;
-            let segs = stmt2.query((iname.as_str(),))?;
-            
-            let mut cols = Vec::new();
-            for seg in segs {
-                let col = seg.get::<String>(0).unwrap_or_default().trim().to_string();
-                cols.push(quote_identifier(&col));
-            }
-            drop(stmt2);
-            
-            if !cols.is_empty() {
-                output.push_str(&format!(" ({})", cols.join(", ")));
-            }
-            
-            output.push_str(";\n");
-        }
+    Ok(idxs)
+}
+
+/// CREATE INDEX statement, without the terminating semicolon
+fn create_index_ddl(
+    conn: &mut Connection,
+    iname: &str,
+    tname: &str,
+    unique: Option<i16>,
+    idx_type: Option<i16>,
+) -> Result<String, Error> {
+    let unique_str = if unique == Some(1) { " UNIQUE" } else { "" };
+    let desc_str = if idx_type == Some(1) { " DESCENDING" } else { "" };
+    
+    let mut ddl = format!("CREATE{}{} INDEX {} ON {}",
+        unique_str, desc_str, quote_identifier(iname), quote_identifier(tname));
+    
+    // Get index segments
+    let seg_sql = r#"
+        SELECT s.RDB$FIELD_NAME 
+        FROM RDB$INDEX_SEGMENTS s
+        WHERE s.RDB$INDEX_NAME = ?
+        ORDER BY s.RDB$FIELD_POSITION
+    "#;
+    
+    let mut stmt = conn.prepare(seg_sql)?;
+    let segs = stmt.query((iname,))?;
+    
+    let mut cols = Vec::new();
+    for seg in segs {
+        let col = seg.get::<String>(0).unwrap_or_default().trim().to_string();
+        cols.push(quote_identifier(&col));
+    }
+    drop(stmt);
+    
+    if !cols.is_empty() {
+        ddl.push_str(&format!(" ({})", cols.join(", ")));
     }
     
-    Ok(())
+    Ok(ddl)
 }
 
 // ============================================================================
@@ -1458,68 +1522,6 @@ fn format_data_type(ft: i16, st: i16, len: i16, prec: i16, scale: i16, clen: i16
     }
 }
 
-/// Get character set name by ID
-fn get_charset_name(id: i16) -> &'static str {
-    match id {
-        0 => "NONE",
-        1 => "OCTETS",
-        2 => "ASCII",
-        3 => "UNICODE_FSS",
-        4 => "UTF8",
-        5 => "SJIS_0208",
-        6 => "EUCJ_0208",
-        9 => "DOS437",
-        10 => "DOS850",
-        11 => "DOS865",
-        12 => "DOS861",
-        13 => "DOS895",
-        14 => "BIG_5",
-        15 => "GB2312",
-        16 => "DOS857",
-        17 => "DOS863",
-        18 => "DOS860",
-        19 => "ISO8859_1",
-        20 => "ISO8859_2",
-        21 => "KSC_5601",
-        22 => "DOS862",
-        23 => "DOS864",
-        24 => "ISO8859_3",
-        25 => "ISO8859_4",
-        26 => "ISO8859_5",
-        27 => "ISO8859_6",
-        28 => "ISO8859_7",
-        29 => "ISO8859_8",
-        30 => "ISO8859_9",
-        31 => "ISO8859_13",
-        32 => "ISO8859_15",
-        34 => "KOI8R",
-        35 => "KOI8U",
-        36 => "WIN1250",
-        37 => "WIN1251",
-        38 => "WIN1252",
-        39 => "WIN1253",
-        40 => "WIN1254",
-        41 => "WIN1255",
-        42 => "WIN1256",
-        43 => "WIN1257",
-        44 => "WIN1258",
-        45 => "WIN_1258",
-        46 => "ISO8859_10",
-        47 => "ISO8859_11",
-        48 => "ISO8859_14",
-        52 => "DOS737",
-        53 => "DOS775",
-        54 => "DOS858",
-        55 => "DOS862",
-        56 => "DOS864",
-        57 => "DOS866",
-        58 => "DOS869",
-        59 => "CYRL",
-        60 => "DOS_437",
-        _ => "",
-    }
-}
-
 /// List table constraints (PK, Unique)
 fn list_table_constraints(conn: &mut Connection, table_name: &str, output: &mut String) -> Result<(), Error> {
     let sql = r#"
@@ -0,0 +1,197 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Execute with a deadline
+//!
+//! `Connection::execute_with_deadline` returns by the deadline whatever the
+//! server does. Three mechanisms are combined, the first to fire wins:
+//!
+//! - Firebird 4 and later get a statement timeout with the execute, so the
+//!   server cancels the statement itself.
+//! - When the answer is not in shortly before the deadline, the request is
+//!   cancelled with op_cancel (older servers, or a slow prepare).
+//! - A server that does not acknowledge the cancel by the deadline is given
+//!   up on; the connection is closed for further requests.
+//!
+//! Every read of the call waits at most until the deadline, the cancel and
+//! the reads after it included, so the call does not outlast it by more than
+//! a socket wakeup.
+//!
+//! The outcome tells which of them ended the call.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use std::time::{Duration, Instant};
+//! use firebirust::DeadlineOutcome;
+//!
+//! let deadline = Instant::now() + Duration::from_secs(5);
+//! match conn.execute_with_deadline("EXECUTE PROCEDURE recalc(?)", (year,), deadline)? {
+//!     DeadlineOutcome::Completed => println!("done"),
+//!     DeadlineOutcome::Abandoned => conn = Connection::connect(url)?,
+//!     outcome => println!("gave up: {:?}", outcome),
+//! }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use super::error::Error;
+
+/// Upper bound of the time left for a cancel to be acknowledged
+const MAX_GRACE: Duration = Duration::from_secs(1);
+/// Smallest socket read timeout (zero is not a valid one)
+const MIN_TIMEOUT: Duration = Duration::from_millis(1);
+
+/// How `Connection::execute_with_deadline` ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadlineOutcome {
+    /// The statement finished before the deadline
+    Completed,
+    /// The deadline had passed before anything was sent
+    Expired,
+    /// The server cancelled the statement at its statement timeout
+    /// (Firebird 4 and later)
+    StatementTimeout,
+    /// The request was cancelled with op_cancel; the connection is usable
+    Cancelled,
+    /// The server did not acknowledge the cancel in time; the connection
+    /// refuses further requests and must be replaced
+    Abandoned,
+}
+
+/// Read and statement timeouts of the requests of a call with a deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Deadline {
+    /// The call gives up on the connection by then
+    end: Instant,
+    /// A request whose answer is not in by then is cancelled
+    cancel_at: Instant,
+    /// Time left after `cancel_at` for the cancel to be acknowledged
+    grace: Duration,
+}
+
+impl Deadline {
+    /// `None` if `deadline` is not after `now`
+    pub(crate) fn new(deadline: Instant, now: Instant) -> Option<Deadline> {
+        let budget = deadline
+            .checked_duration_since(now)
+            .filter(|b| !b.is_zero())?;
+        let grace = (budget / 10).min(MAX_GRACE);
+        Some(Deadline {
+            end: deadline,
+            cancel_at: deadline - grace,
+            grace,
+        })
+    }
+
+    /// Read timeout at `now`: until `cancel_at`, then what is left of the
+    /// grace for the op_cancel sent after it; `None` once the deadline has
+    /// passed
+    pub(crate) fn read_timeout(&self, now: Instant) -> Option<Duration> {
+        let until = if now < self.cancel_at {
+            self.cancel_at
+        } else {
+            self.end
+        };
+        until
+            .checked_duration_since(now)
+            .filter(|left| !left.is_zero())
+            .map(|left| left.max(MIN_TIMEOUT))
+    }
+
+    /// Statement timeout in milliseconds of an execute sent at `now`, a grace
+    /// before the read times out so the server's own cancel arrives first
+    pub(crate) fn statement_timeout(&self, now: Instant) -> u32 {
+        let left = self
+            .cancel_at
+            .saturating_duration_since(now)
+            .saturating_sub(self.grace);
+        left.as_millis().clamp(1, u32::MAX as u128) as u32
+    }
+
+    /// Outcome of a call with this deadline that ended with `result`;
+    /// errors unrelated to the deadline are returned as they are
+    pub(crate) fn outcome(&self, result: Result<(), Error>) -> Result<DeadlineOutcome, Error> {
+        match result {
+            Ok(()) => Ok(DeadlineOutcome::Completed),
+            Err(Error::FirebirdError(e)) if e.is_statement_timeout() => {
+                Ok(DeadlineOutcome::StatementTimeout)
+            }
+            Err(Error::Timeout) => Ok(DeadlineOutcome::Cancelled),
+            Err(Error::ConnectionLost) if Instant::now() >= self.cancel_at => {
+                Ok(DeadlineOutcome::Abandoned)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::FirebirdError;
+
+    #[test]
+    fn test_deadline() {
+        let now = Instant::now();
+        assert_eq!(Deadline::new(now, now), None);
+
+        let d = Deadline::new(now + Duration::from_secs(5), now).unwrap();
+        assert_eq!(d.grace, Duration::from_millis(500));
+        assert_eq!(d.read_timeout(now), Some(Duration::from_millis(4500)));
+        assert_eq!(d.statement_timeout(now), 4000);
+        // past cancel_at only the rest of the grace is left, for op_cancel,
+        // however many requests follow
+        assert_eq!(
+            d.read_timeout(now + Duration::from_millis(4500)),
+            Some(d.grace)
+        );
+        assert_eq!(
+            d.read_timeout(now + Duration::from_millis(4800)),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(d.read_timeout(now + Duration::from_secs(5)), None);
+        assert_eq!(d.read_timeout(now + Duration::from_secs(6)), None);
+        assert_eq!(d.statement_timeout(now + Duration::from_secs(6)), 1);
+
+        let d = Deadline::new(now + Duration::from_secs(60), now).unwrap();
+        assert_eq!(d.grace, MAX_GRACE);
+        assert_eq!(d.read_timeout(now), Some(Duration::from_secs(59)));
+
+        assert_eq!(d.outcome(Ok(())).unwrap(), DeadlineOutcome::Completed);
+        assert_eq!(
+            d.outcome(Err(Error::Timeout)).unwrap(),
+            DeadlineOutcome::Cancelled
+        );
+        let e = FirebirdError::with_gds_codes("", -901, vec![335544794, 335545129]);
+        assert_eq!(
+            d.outcome(Err(Error::FirebirdError(e))).unwrap(),
+            DeadlineOutcome::StatementTimeout
+        );
+        // lost before the deadline: not ours to explain
+        assert!(matches!(
+            d.outcome(Err(Error::ConnectionLost)),
+            Err(Error::ConnectionLost)
+        ));
+    }
+}
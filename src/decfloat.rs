@@ -116,18 +116,10 @@ fn decimal128_to_sign_digits_exponent(b: &[u8]) -> Result<(i32, u128, i32), Valu
 
     let cf = (((b[0] & 0x7f) as u32) << 10) + ((b[1] as u32) << 2) + (b[2] >> 6) as u32;
     if (cf & 0x1F000) == 0x1F000 {
-        if sign == 1 {
-            return Err(ValueError::new("-NaN"));
-        } else {
-            return Err(ValueError::new("NaN"));
-        }
+        return Err(special_value(sign, "NaN"));
     }
     if (cf & 0x1F000) == 0x1E000 {
-        if sign == 1 {
-            return Err(ValueError::new("-Inf"));
-        } else {
-            return Err(ValueError::new("Inf"));
-        }
+        return Err(special_value(sign, "Infinity"));
     }
 
     let mut exponent: i32;
@@ -143,19 +135,19 @@ fn decimal128_to_sign_digits_exponent(b: &[u8]) -> Result<(i32, u128, i32), Valu
         prefix = ((cf >> 12) & 0x07) as i64;
     } else if (cf & 0x1e000) == 0x18000 {
         exponent = (0x0000 + (cf & 0x00fff)) as i32;
-        prefix = (8 + (cf >> 12) & 0x01) as i64;
+        prefix = (8 + ((cf >> 12) & 0x01)) as i64;
     } else if (cf & 0x1e000) == 0x1a000 {
         exponent = (0x1000 + (cf & 0x00fff)) as i32;
-        prefix = (8 + (cf >> 12) & 0x01) as i64;
+        prefix = (8 + ((cf >> 12) & 0x01)) as i64;
     } else if (cf & 0x1e000) == 0x1c000 {
         exponent = (0x2000 + (cf & 0x00fff)) as i32;
-        prefix = (8 + (cf >> 12) & 0x01) as i64;
+        prefix = (8 + ((cf >> 12) & 0x01)) as i64;
     } else {
         return Err(ValueError::new("decimal128 value error"));
     }
     exponent -= 6176;
 
-    let dpd_bits = utils::bytes_to_uint128(&b);
+    let dpd_bits = utils::bytes_to_buint128(b);
     let mask: u128 = 0x3fffffffffffffffffffffffffff;
     let digits = calc_significand(prefix, dpd_bits & mask, 110)?;
 
@@ -167,11 +159,7 @@ pub fn decimal_fixed_to_decimal(
     _scale: i32,
 ) -> Result<rust_decimal::Decimal, ValueError> {
     let (sign, digits, _) = decimal128_to_sign_digits_exponent(b)?;
-    let mut num = digits as i64;
-    if sign != 0 {
-        num *= -1;
-    }
-    Ok(rust_decimal::Decimal::new(num, 0))
+    to_decimal(sign, digits, 0)
 }
 
 pub fn decimal64_to_decimal(b: &[u8]) -> Result<rust_decimal::Decimal, ValueError> {
@@ -180,22 +168,14 @@ pub fn decimal64_to_decimal(b: &[u8]) -> Result<rust_decimal::Decimal, ValueErro
 
     let cf = ((b[0] as u32) >> 2) & 0x1f;
 
-    let dpd_bits = utils::bytes_to_uint128(&b);
-    let mask: u128 = 0x3fffffffffffffffffffffffffff;
+    let dpd_bits = utils::bytes_to_buint64(b) as u128;
+    let mask: u128 = 0x3ffffffffffff;
 
     if cf == 0x1f {
-        if sign == 1 {
-            return Err(ValueError::new("-NaN"));
-        } else {
-            return Err(ValueError::new("NaN"));
-        }
+        return Err(special_value(sign, "NaN"));
     }
     if cf == 0x1e {
-        if sign == 1 {
-            return Err(ValueError::new("-Inf"));
-        } else {
-            return Err(ValueError::new("Inf"));
-        }
+        return Err(special_value(sign, "Infinity"));
     }
 
     let mut exponent: i32 = (((b[0] as i32) & 3) << 6) + (((b[1] as i32) >> 2) & 0x3f);
@@ -211,13 +191,13 @@ pub fn decimal64_to_decimal(b: &[u8]) -> Result<rust_decimal::Decimal, ValueErro
         prefix = (cf & 0x07) as i64;
     } else if (cf & 0x1e) == 0x18 {
         exponent = 0x000 + exponent;
-        prefix = (8 + cf & 1) as i64;
+        prefix = (8 + (cf & 1)) as i64;
     } else if (cf & 0x1e) == 0x1a {
         exponent = 0x100 + exponent;
-        prefix = (8 + cf & 1) as i64;
+        prefix = (8 + (cf & 1)) as i64;
     } else if (cf & 0x1e) == 0x1c {
         exponent = 0x200 + exponent;
-        prefix = (8 + cf & 1) as i64;
+        prefix = (8 + (cf & 1)) as i64;
     } else {
         return Err(ValueError::new("decimal64 value error"));
     }
@@ -225,19 +205,298 @@ pub fn decimal64_to_decimal(b: &[u8]) -> Result<rust_decimal::Decimal, ValueErro
     let digits = calc_significand(prefix, dpd_bits & mask, 50)?;
     exponent -= 398;
 
-    let mut num = digits as i64;
-    if sign != 0 {
-        num *= -1;
-    }
-    Ok(rust_decimal::Decimal::new(num, exponent as u32))
+    to_decimal(sign, digits, exponent)
 }
 
 pub fn decimal128_to_decimal(b: &[u8]) -> Result<rust_decimal::Decimal, ValueError> {
-    // https://en.wikipedia.org/wiki/Decimal64_floating-point_format
+    // https://en.wikipedia.org/wiki/Decimal128_floating-point_format
     let (sign, digits, exponent) = decimal128_to_sign_digits_exponent(b)?;
-    let mut num = digits as i64;
-    if sign != 0 {
-        num *= -1;
+    to_decimal(sign, digits, exponent)
+}
+
+fn special_value(sign: i32, name: &str) -> ValueError {
+    let sign = if sign == 1 { "-" } else { "" };
+    ValueError::new(&format!("DECFLOAT {}{} has no Decimal value", sign, name))
+}
+
+/// Largest coefficient of a `Decimal` (96 bits)
+const MAX_MANTISSA: u128 = (1 << 96) - 1;
+
+/// `digits` x 10^`exponent` as a `Decimal`. Digits past the 28 decimal
+/// places (or 96 bits) a `Decimal` holds are rounded half to even.
+fn to_decimal(sign: i32, digits: u128, exponent: i32) -> Result<rust_decimal::Decimal, ValueError> {
+    let out_of_range = || ValueError::new("DECFLOAT value is out of the range of Decimal");
+    let mut digits = digits;
+    let mut scale = -exponent;
+    if digits == 0 {
+        scale = scale.clamp(0, 28);
+    }
+    while scale < 0 {
+        digits = digits
+            .checked_mul(10)
+            .filter(|d| *d <= MAX_MANTISSA)
+            .ok_or_else(out_of_range)?;
+        scale += 1;
+    }
+    let mut dropped = (scale - 28).max(0);
+    while dropped < scale && round_digits(digits, dropped as u32) > MAX_MANTISSA {
+        dropped += 1;
+    }
+    digits = round_digits(digits, dropped as u32);
+    if digits > MAX_MANTISSA {
+        return Err(out_of_range());
+    }
+    let num = if sign != 0 {
+        -(digits as i128)
+    } else {
+        digits as i128
+    };
+    Ok(rust_decimal::Decimal::from_i128_with_scale(
+        num,
+        (scale - dropped) as u32,
+    ))
+}
+
+/// `digits` without its last `n` digits, rounded half to even
+fn round_digits(digits: u128, n: u32) -> u128 {
+    let Some(p) = 10u128.checked_pow(n) else {
+        return 0;
+    };
+    let (q, r) = (digits / p, digits % p);
+    if r * 2 > p || (r * 2 == p && q % 2 == 1) {
+        q + 1
+    } else {
+        q
+    }
+}
+
+fn int_to_dpd(n: u16) -> u16 {
+    // Convert int (0-999) to DPD encoded value, the inverse of dpd_to_int
+    let (d2, d1, d0) = (n / 100, n / 10 % 10, n % 10);
+    let low = d0 & 1;
+    match (d2 > 7, d1 > 7, d0 > 7) {
+        (false, false, false) => d2 << 7 | d1 << 4 | d0,
+        (false, false, true) => d2 << 7 | d1 << 4 | 0b1000 | low,
+        (false, true, false) => d2 << 7 | (d0 >> 1) << 5 | (d1 & 1) << 4 | 0b1010 | low,
+        (true, false, false) => (d0 >> 1) << 8 | (d2 & 1) << 7 | d1 << 4 | 0b1100 | low,
+        (true, true, false) => (d0 >> 1) << 8 | (d2 & 1) << 7 | (d1 & 1) << 4 | 0b1110 | low,
+        (true, false, true) => {
+            (d1 >> 1) << 8 | (d2 & 1) << 7 | 0b0100000 | (d1 & 1) << 4 | 0b1110 | low
+        }
+        (false, true, true) => d2 << 7 | 0b1000000 | (d1 & 1) << 4 | 0b1110 | low,
+        (true, true, true) => (d2 & 1) << 7 | 0b1100000 | (d1 & 1) << 4 | 0b1110 | low,
+    }
+}
+
+fn encode_decfloat(
+    sign: bool,
+    coefficient: u128,
+    exponent: i32,
+    declets: u32,
+    exponent_bits: u32,
+    bias: i32,
+) -> u128 {
+    // sign, 5 bit combination field, exponent continuation and the
+    // coefficient's trailing digits as declets
+    // https://en.wikipedia.org/wiki/Decimal128_floating-point_format
+    let mut c = coefficient;
+    let mut bits: u128 = 0;
+    for i in 0..declets {
+        bits |= (int_to_dpd((c % 1000) as u16) as u128) << (10 * i);
+        c /= 1000;
+    }
+    let leading = c as u32;
+    let e = (exponent + bias) as u32;
+    let top = e >> exponent_bits;
+    let combination = if leading < 8 {
+        top << 3 | leading
+    } else {
+        0b11000 | top << 1 | (leading & 1)
+    };
+    let shift = 10 * declets;
+    bits |= ((e & ((1 << exponent_bits) - 1)) as u128) << shift;
+    bits |= (combination as u128) << (shift + exponent_bits);
+    if sign {
+        bits |= 1 << (shift + exponent_bits + 5);
+    }
+    bits
+}
+
+/// `d` as a big endian decimal64 (DECFLOAT(16)), `None` when it has more
+/// than 16 significant digits
+pub fn decimal_to_decimal64(d: &rust_decimal::Decimal) -> Option<[u8; 8]> {
+    let d = if d.mantissa().unsigned_abs() >= 10u128.pow(16) {
+        d.normalize()
+    } else {
+        *d
+    };
+    let coefficient = d.mantissa().unsigned_abs();
+    if coefficient >= 10u128.pow(16) {
+        return None;
+    }
+    let bits = encode_decfloat(
+        d.is_sign_negative(),
+        coefficient,
+        -(d.scale() as i32),
+        5,
+        8,
+        398,
+    );
+    Some((bits as u64).to_be_bytes())
+}
+
+/// `d` as a big endian decimal128 (DECFLOAT(34))
+pub fn decimal_to_decimal128(d: &rust_decimal::Decimal) -> [u8; 16] {
+    let coefficient = d.mantissa().unsigned_abs();
+    let bits = encode_decfloat(
+        d.is_sign_negative(),
+        coefficient,
+        -(d.scale() as i32),
+        11,
+        12,
+        6176,
+    );
+    bits.to_be_bytes()
+}
+
+/// A `Decimal` bound as DECFLOAT (Firebird 4.0+) rather than as text:
+/// DECFLOAT(16) when its digits fit, DECFLOAT(34) otherwise
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecFloat(pub rust_decimal::Decimal);
+
+impl From<rust_decimal::Decimal> for DecFloat {
+    fn from(d: rust_decimal::Decimal) -> DecFloat {
+        DecFloat(d)
+    }
+}
+
+impl From<DecFloat> for rust_decimal::Decimal {
+    fn from(d: DecFloat) -> rust_decimal::Decimal {
+        d.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal64_big_endian() {
+        // 123E+0: combination field 0x08, exponent continuation 0x8e
+        let b = [0x22, 0x38, 0, 0, 0, 0, 0, 0xa3];
+        assert_eq!(
+            decimal64_to_decimal(&b).unwrap(),
+            rust_decimal::Decimal::new(123, 0)
+        );
+
+        let b = [0xa2, 0x38, 0, 0, 0, 0, 0, 0xa3];
+        assert_eq!(
+            decimal64_to_decimal(&b).unwrap(),
+            rust_decimal::Decimal::new(-123, 0)
+        );
+    }
+
+    #[test]
+    fn test_decimal128_big_endian() {
+        let mut b = [0u8; 16];
+        b[0] = 0x22;
+        b[1] = 0x08;
+        b[15] = 0xa3;
+        assert_eq!(
+            decimal128_to_decimal(&b).unwrap(),
+            rust_decimal::Decimal::new(123, 0)
+        );
+        assert_eq!(decimal128_to_sign_digits_exponent(&b).unwrap(), (0, 123, 0));
+    }
+
+    #[test]
+    fn test_dpd_round_trip() {
+        for n in 0..1000 {
+            assert_eq!(dpd_to_int(int_to_dpd(n)).unwrap(), n);
+        }
+    }
+
+    #[test]
+    fn test_decfloat_round_trip() {
+        let values = [
+            "0",
+            "123",
+            "-123",
+            "1.23",
+            "-0.0001",
+            "9876543210.123456",
+            "8000000000000000",
+            "79228162514264337593543950335",
+            "-0.0000000000000000000000000001",
+            "1000000",
+        ];
+        for v in values {
+            let d: rust_decimal::Decimal = v.parse().unwrap();
+            let b = decimal_to_decimal128(&d);
+            assert_eq!(decimal128_to_decimal(&b).unwrap(), d, "{}", v);
+            if let Some(b) = decimal_to_decimal64(&d) {
+                assert_eq!(decimal64_to_decimal(&b).unwrap(), d, "{}", v);
+            }
+        }
+        let d: rust_decimal::Decimal = "9876543210.123456".parse().unwrap();
+        assert!(decimal_to_decimal64(&d).is_some());
+        // 17 digits need DECFLOAT(34), trailing zeros don't count
+        let d: rust_decimal::Decimal = "98765432101.234567".parse().unwrap();
+        assert!(decimal_to_decimal64(&d).is_none());
+        let d: rust_decimal::Decimal = "1.00000000000000000000".parse().unwrap();
+        assert_eq!(
+            decimal64_to_decimal(&decimal_to_decimal64(&d).unwrap()).unwrap(),
+            d
+        );
+    }
+
+    #[test]
+    fn test_to_decimal() {
+        let dec = |s: &str| s.parse::<rust_decimal::Decimal>().unwrap();
+        assert_eq!(to_decimal(0, 123, -2).unwrap(), dec("1.23"));
+        assert_eq!(to_decimal(1, 123, 3).unwrap(), dec("-123000"));
+        assert_eq!(to_decimal(0, 0, 6000).unwrap(), dec("0"));
+        assert_eq!(to_decimal(0, 0, -6000).unwrap(), dec("0"));
+        // 34 digits are rounded half to even to 28 decimal places
+        let digits = 1234567890123456789012345678905000u128;
+        assert_eq!(
+            to_decimal(0, digits, -34).unwrap(),
+            dec("0.1234567890123456789012345679")
+        );
+        assert_eq!(
+            to_decimal(0, 25, -30).unwrap(),
+            dec("0.0000000000000000000000000000")
+        );
+        assert_eq!(
+            to_decimal(0, 35, -30).unwrap(),
+            dec("0.0000000000000000000000000000")
+        );
+        assert_eq!(
+            to_decimal(0, 151, -30).unwrap(),
+            dec("0.0000000000000000000000000002")
+        );
+        // past 96 bits an integer part is rounded too
+        assert_eq!(
+            to_decimal(0, 7922816251426433759354395033549, -2).unwrap(),
+            dec("79228162514264337593543950335")
+        );
+        assert!(to_decimal(0, 1, 29).is_err());
+        assert!(to_decimal(0, 7922816251426433759354395033600, -2).is_err());
+    }
+
+    #[test]
+    fn test_decfloat_special_values() {
+        let mut b = [0u8; 16];
+        b[0] = 0x7c;
+        match decimal128_to_decimal(&b) {
+            Err(e) => assert_eq!(e.message, "DECFLOAT NaN has no Decimal value"),
+            Ok(_) => panic!("NaN is not a Decimal"),
+        }
+        b[0] = 0xf8;
+        match decimal128_to_decimal(&b) {
+            Err(e) => assert_eq!(e.message, "DECFLOAT -Infinity has no Decimal value"),
+            Ok(_) => panic!("-Infinity is not a Decimal"),
+        }
+        assert!(decimal64_to_decimal(&[0x78, 0, 0, 0, 0, 0, 0, 0]).is_err());
     }
-    Ok(rust_decimal::Decimal::new(num, exponent as u32))
 }
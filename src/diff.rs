@@ -0,0 +1,368 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Row by row comparison of a table in two databases
+//!
+//! `diff_tables` reads the table from both connections in key order and
+//! merges the two streams, reporting each key that only one side has or
+//! whose row differs. Rows are fetched in batches of `fetch_size` and
+//! compared by their canonical digest (see `Row::digest`), so memory does
+//! not grow with the size of the table.
+//!
+//! The merge needs both servers to sort the keys the way Rust compares
+//! them: numbers, dates and times always do, text keys only with a binary
+//! collation (UTF8, OCTETS, NONE). Keys out of order stop the comparison
+//! with an error rather than report wrong differences.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{Diff, diff_tables};
+//!
+//! let report = diff_tables(&primary, &replica, "orders", &["id"], |diff| {
+//!     match diff {
+//!         Diff::Inserted(key) => println!("only on replica: {:?}", key),
+//!         Diff::Updated(key) => println!("differs: {:?}", key),
+//!         Diff::Deleted(key) => println!("missing on replica: {:?}", key),
+//!     }
+//!     Ok(())
+//! })?;
+//! assert!(report.is_equal());
+//! ```
+
+use std::cmp::Ordering;
+
+use sha2::{Digest, Sha256};
+
+use super::cellvalue::CellValue;
+use super::checksum::{checksum_query, digest_row, name_order};
+use super::connection::Connection;
+use super::ddl::object_name;
+use super::error::{Error, ValueError};
+use super::param::Param;
+use super::statement::Cursor;
+
+/// A key that differs, with the values of the key columns
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diff {
+    /// Only in the second database
+    Inserted(Vec<Param>),
+    /// In both, with different values
+    Updated(Vec<Param>),
+    /// Only in the first database
+    Deleted(Vec<Param>),
+}
+
+/// Counts of a `diff_tables` run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiffReport {
+    pub rows_a: u64,
+    pub rows_b: u64,
+    pub inserted: u64,
+    pub updated: u64,
+    pub deleted: u64,
+}
+
+impl DiffReport {
+    /// Whether both tables hold the same rows
+    pub fn is_equal(&self) -> bool {
+        self.inserted == 0 && self.updated == 0 && self.deleted == 0
+    }
+}
+
+/// One side of the merge: the current row, its key and its digest
+struct Side<'s, 'conn> {
+    cursor: Cursor<'s, 'conn>,
+    // key column indexes, and all columns sorted by name
+    keys: Vec<usize>,
+    order: Vec<usize>,
+    current: Option<(Vec<CellValue>, [u8; 32])>,
+    rows: u64,
+}
+
+impl Side<'_, '_> {
+    fn advance(&mut self) -> Result<(), Error> {
+        let Some(row) = self.cursor.next_row()? else {
+            self.current = None;
+            return Ok(());
+        };
+        let key: Vec<CellValue> = self
+            .keys
+            .iter()
+            .map(|&idx| row.cell(idx).cloned().unwrap_or(CellValue::Null))
+            .collect();
+        if let Some((prev, _)) = &self.current
+            && compare_keys(prev, &key)? != Ordering::Less
+        {
+            return Err(Error::ValueError(ValueError::new(
+                "Keys are not unique or not sorted as compared; use a binary collation for text keys",
+            )));
+        }
+        let mut hasher = Sha256::new();
+        digest_row(&row, &self.order, &mut hasher);
+        self.current = Some((key, hasher.finalize().into()));
+        self.rows += 1;
+        Ok(())
+    }
+}
+
+/// Compare `table` in the databases of `conn_a` and `conn_b` by the unique
+/// `keys`, calling `on_diff` for each key that differs, in key order
+pub fn diff_tables<F>(
+    conn_a: &Connection,
+    conn_b: &Connection,
+    table: &str,
+    keys: &[&str],
+    mut on_diff: F,
+) -> Result<DiffReport, Error>
+where
+    F: FnMut(Diff) -> Result<(), Error>,
+{
+    if keys.is_empty() {
+        return Err(Error::ValueError(ValueError::new(
+            "diff_tables needs key columns",
+        )));
+    }
+    let sql = checksum_query(table, keys);
+    let mut stmt_a = conn_a.prepare(&sql)?;
+    let mut stmt_b = conn_b.prepare(&sql)?;
+    let mut a = open_side(stmt_a.open_cursor(())?, keys, conn_a.fetch_size())?;
+    let mut b = open_side(stmt_b.open_cursor(())?, keys, conn_b.fetch_size())?;
+    a.advance()?;
+    b.advance()?;
+
+    let mut report = DiffReport::default();
+    loop {
+        let diff = match (&a.current, &b.current) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((key_a, _)), Some((key_b, _))) => compare_keys(key_a, key_b)?,
+        };
+        match diff {
+            Ordering::Less => {
+                report.deleted += 1;
+                on_diff(Diff::Deleted(key_params(&a)))?;
+                a.advance()?;
+            }
+            Ordering::Greater => {
+                report.inserted += 1;
+                on_diff(Diff::Inserted(key_params(&b)))?;
+                b.advance()?;
+            }
+            Ordering::Equal => {
+                if a.current.as_ref().map(|c| c.1) != b.current.as_ref().map(|c| c.1) {
+                    report.updated += 1;
+                    on_diff(Diff::Updated(key_params(&a)))?;
+                }
+                a.advance()?;
+                b.advance()?;
+            }
+        }
+    }
+    report.rows_a = a.rows;
+    report.rows_b = b.rows;
+    Ok(report)
+}
+
+fn open_side<'s, 'conn>(
+    mut cursor: Cursor<'s, 'conn>,
+    keys: &[&str],
+    fetch_size: u32,
+) -> Result<Side<'s, 'conn>, Error> {
+    cursor.set_fetch_size(fetch_size);
+    let columns = cursor.columns();
+    let keys = keys
+        .iter()
+        .map(|key| {
+            let key = object_name(key);
+            columns.iter().position(|c| c.name == key).ok_or_else(|| {
+                Error::ValueError(ValueError::new(&format!("Key column {} not found", key)))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let order = name_order(columns);
+    Ok(Side {
+        cursor,
+        keys,
+        order,
+        current: None,
+        rows: 0,
+    })
+}
+
+fn key_params(side: &Side<'_, '_>) -> Vec<Param> {
+    side.current
+        .as_ref()
+        .map(|(key, _)| key.iter().map(cell_param).collect())
+        .unwrap_or_default()
+}
+
+/// Parameter holding the value of a key cell
+fn cell_param(cell: &CellValue) -> Param {
+    match cell.clone() {
+        CellValue::Null => Param::Null,
+        CellValue::Text(v) | CellValue::Varying(v) => Param::Text(v),
+        CellValue::Short(v) => Param::Short(v),
+        CellValue::Long(v) => Param::Long(v),
+        CellValue::Int64(v) => Param::Int64(v),
+        CellValue::Int128(v) => Param::Int128(v),
+        CellValue::Float(v) => Param::Float(v),
+        CellValue::Double(v) => Param::Double(v),
+        CellValue::Time(v) => Param::Time(v),
+        CellValue::Date(v) => Param::Date(v),
+        CellValue::TimeStamp(v) => Param::TimeStamp(v),
+        CellValue::TimeStampTz(v) => Param::TimeStampTZ(v),
+        CellValue::TimeTz((t, tz)) => Param::TimeTz(t, tz),
+        CellValue::BlobBinary(v) | CellValue::Octets(v) => Param::Blob(v),
+        CellValue::BlobText(v) => Param::Text(String::from_utf8_lossy(&v).into_owned()),
+        CellValue::Decimal(v) => Param::Decimal(v),
+        CellValue::Boolean(v) => Param::Boolean(v),
+        // ARRAY and BLOB columns can't be indexed, so they are never keys
+        CellValue::Array(_) | CellValue::BlobId(_) => Param::Null,
+    }
+}
+
+/// Order of two keys as the server sorts them ascending
+fn compare_keys(a: &[CellValue], b: &[CellValue]) -> Result<Ordering, Error> {
+    for (x, y) in a.iter().zip(b) {
+        match compare_cells(x, y)? {
+            Ordering::Equal => continue,
+            ord => return Ok(ord),
+        }
+    }
+    Ok(Ordering::Equal)
+}
+
+fn compare_cells(a: &CellValue, b: &CellValue) -> Result<Ordering, Error> {
+    use CellValue::*;
+    let ord = match (a, b) {
+        // NULLs sort first in ascending order
+        (Null, Null) => Some(Ordering::Equal),
+        (Null, _) => Some(Ordering::Less),
+        (_, Null) => Some(Ordering::Greater),
+        (Text(x) | Varying(x), Text(y) | Varying(y)) => Some(x.trim_end().cmp(y.trim_end())),
+        (Octets(x), Octets(y)) => Some(x.cmp(y)),
+        (Date(x), Date(y)) => Some(x.cmp(y)),
+        (Time(x), Time(y)) => Some(x.cmp(y)),
+        (TimeStamp(x), TimeStamp(y)) => Some(x.cmp(y)),
+        (TimeStampTz(x), TimeStampTz(y)) => Some(x.naive_utc().cmp(&y.naive_utc())),
+        (Boolean(x), Boolean(y)) => Some(x.cmp(y)),
+        (Float(x), Float(y)) => x.partial_cmp(y),
+        (Double(x), Double(y)) => x.partial_cmp(y),
+        _ => match (integer(a), integer(b)) {
+            (Some(x), Some(y)) => Some(x.cmp(&y)),
+            _ => match (a, b) {
+                (Decimal(x), Decimal(y)) => Some(x.cmp(y)),
+                (Decimal(x), _) => integer(b).and_then(|y| {
+                    rust_decimal::Decimal::try_from_i128_with_scale(y, 0)
+                        .ok()
+                        .map(|y| x.cmp(&y))
+                }),
+                (_, Decimal(y)) => integer(a).and_then(|x| {
+                    rust_decimal::Decimal::try_from_i128_with_scale(x, 0)
+                        .ok()
+                        .map(|x| x.cmp(y))
+                }),
+                _ => None,
+            },
+        },
+    };
+    ord.ok_or_else(|| Error::ValueError(ValueError::new("Key values can't be compared")))
+}
+
+fn integer(cell: &CellValue) -> Option<i128> {
+    match cell {
+        CellValue::Short(v) => Some(*v as i128),
+        CellValue::Long(v) => Some(*v as i128),
+        CellValue::Int64(v) => Some(*v as i128),
+        CellValue::Int128(v) => Some(*v),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_compare_keys() {
+        let key =
+            |id: i64, name: &str| vec![CellValue::Int64(id), CellValue::Varying(name.to_string())];
+        assert_eq!(
+            compare_keys(&key(1, "b"), &key(2, "a")).unwrap(),
+            Ordering::Less
+        );
+        assert_eq!(
+            compare_keys(&key(2, "b"), &key(2, "a")).unwrap(),
+            Ordering::Greater
+        );
+        assert_eq!(
+            compare_keys(
+                &[CellValue::Text("ab  ".to_string())],
+                &[CellValue::Varying("ab".to_string())]
+            )
+            .unwrap(),
+            Ordering::Equal
+        );
+        // the same key read as INTEGER on one side and NUMERIC on the other
+        assert_eq!(
+            compare_keys(
+                &[CellValue::Long(5)],
+                &[CellValue::Decimal(Decimal::new(500, 2))]
+            )
+            .unwrap(),
+            Ordering::Equal
+        );
+        assert_eq!(
+            compare_keys(&[CellValue::Null], &[CellValue::Short(0)]).unwrap(),
+            Ordering::Less
+        );
+        assert!(
+            compare_keys(
+                &[CellValue::Long(5)],
+                &[CellValue::Varying("5".to_string())]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cell_param() {
+        assert_eq!(cell_param(&CellValue::Int64(7)), Param::Int64(7));
+        assert_eq!(
+            cell_param(&CellValue::Text("x".to_string())),
+            Param::Text("x".to_string())
+        );
+        assert_eq!(cell_param(&CellValue::Null), Param::Null);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut report = DiffReport::default();
+        assert!(report.is_equal());
+        report.updated = 1;
+        assert!(!report.is_equal());
+    }
+}
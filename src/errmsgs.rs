@@ -1494,5 +1494,5 @@ pub fn error_message_by_id(id: u32) -> &'static str {
          337182759=> r#"parameter "@1" is incompatible with action "@2"\n"#,
          337182760=> r#"mandatory switch "@1" is missing\n"#,
     };
-    map[&id]
+    map.get(&id).copied().unwrap_or("")
 }
@@ -20,10 +20,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use std::time::Duration;
 use std::{error, fmt, io};
 use url::ParseError;
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     IoError(io::Error),
     FirebirdError(FirebirdError),
@@ -32,6 +34,23 @@ pub enum Error {
     UrlError(UrlError),
     PoolTimeout,
     PoolError(String),
+    /// Optimistic locking: the row was changed or deleted since it was read
+    StaleRow,
+    /// No response within the `read_timeout` URL parameter; the request
+    /// was cancelled and the connection is still usable
+    Timeout,
+    /// The server closed or reset the connection, or did not answer the
+    /// cancellation of a timed out request
+    ConnectionLost,
+    /// DDL on an object still used by a statement or another attachment
+    ObjectInUse(FirebirdError),
+    /// `Row::get` could not convert a column to the requested type
+    Conversion(ConversionError),
+    /// (limit, configured maximum): a `max_*` URL parameter stopped the
+    /// request before it ran out of memory or broke the protocol
+    LimitExceeded(Limit, usize),
+    /// A `MultiTransaction` commit failed after databases had prepared
+    TwoPhaseCommit(TwoPhaseCommitError),
 }
 
 impl From<UrlError> for Error {
@@ -52,16 +71,169 @@ impl From<FirebirdError> for Error {
     }
 }
 
+// gds codes
+pub(crate) const ISC_OBSOLETE_METADATA: u32 = 335544356;
+pub(crate) const ISC_UNPREPARED_STMT: u32 = 335544711;
+pub(crate) const ISC_DEADLOCK: u32 = 335544336;
+pub(crate) const ISC_LOCK_CONFLICT: u32 = 335544345;
+pub(crate) const ISC_UPDATE_CONFLICT: u32 = 335544451;
+pub(crate) const ISC_LOCK_TIMEOUT: u32 = 335544510;
+pub(crate) const ISC_NO_META_UPDATE: u32 = 335544351;
+pub(crate) const ISC_OBJ_IN_USE: u32 = 335544453;
+pub(crate) const ISC_CANCELLED: u32 = 335544794;
+pub(crate) const ISC_CFG_STMT_TIMEOUT: u32 = 335545127;
+pub(crate) const ISC_ATT_STMT_TIMEOUT: u32 = 335545128;
+pub(crate) const ISC_REQ_STMT_TIMEOUT: u32 = 335545129;
+
+/// Guard set by the `max_rows`, `max_blob_size` and `max_params` URL parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// Rows fetched by one query
+    Rows,
+    /// Bytes of a blob read into a row
+    BlobSize,
+    /// Parameters bound to one statement
+    Params,
+}
+
+/// `max_*` URL parameter value, `None` for 0 (no limit)
+pub(crate) fn limit_option(value: &str) -> Option<usize> {
+    value.parse().ok().filter(|n| *n > 0)
+}
+
+/// Kind of data contention reported by the server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contention {
+    /// Lock wait timed out (`LockWait::Timeout`)
+    LockTimeout,
+    /// Deadlock between transactions
+    Deadlock,
+    /// Concurrent update of the same record (update conflict)
+    UpdateConflict,
+    /// Lock conflict on a no wait transaction (`LockWait::NoWait`)
+    LockConflict,
+}
+
+/// Contention counters of a connection
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContentionStats {
+    pub lock_timeouts: u64,
+    pub deadlocks: u64,
+    pub update_conflicts: u64,
+    pub lock_conflicts: u64,
+    /// Total time spent in statements that failed with contention
+    pub total_wait: Duration,
+}
+
+impl ContentionStats {
+    pub(crate) fn record(&mut self, contention: Contention, waited: Duration) {
+        match contention {
+            Contention::LockTimeout => self.lock_timeouts += 1,
+            Contention::Deadlock => self.deadlocks += 1,
+            Contention::UpdateConflict => self.update_conflicts += 1,
+            Contention::LockConflict => self.lock_conflicts += 1,
+        }
+        self.total_wait += waited;
+    }
+}
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct FirebirdError {
     pub message: String,
     pub sql_code: i32,
+    /// gds codes from the status vector (sorted)
+    pub gds_codes: Vec<u32>,
+    /// Time spent in the failed execute (set for contention errors)
+    pub waited: Option<Duration>,
 }
 
 impl FirebirdError {
     pub fn new(message: &str, sql_code: i32) -> FirebirdError {
         let message = message.to_string();
-        FirebirdError { message, sql_code }
+        FirebirdError {
+            message,
+            sql_code,
+            gds_codes: Vec::new(),
+            waited: None,
+        }
+    }
+
+    pub fn with_gds_codes(message: &str, sql_code: i32, mut gds_codes: Vec<u32>) -> FirebirdError {
+        gds_codes.sort();
+        FirebirdError {
+            message: message.to_string(),
+            sql_code,
+            gds_codes,
+            waited: None,
+        }
+    }
+
+    /// True if the status vector contains the gds code
+    pub fn has_gds_code(&self, gds_code: u32) -> bool {
+        self.gds_codes.contains(&gds_code)
+    }
+
+    /// True if the prepared statement is outdated (e.g. after DDL) and must be prepared again
+    pub fn is_stale_statement(&self) -> bool {
+        self.has_gds_code(ISC_OBSOLETE_METADATA) || self.has_gds_code(ISC_UNPREPARED_STMT)
+    }
+
+    /// True if the request was cancelled (op_cancel, or a kill through MON$STATEMENTS)
+    pub fn is_cancelled(&self) -> bool {
+        self.has_gds_code(ISC_CANCELLED)
+    }
+
+    /// True if the server cancelled the statement at its statement timeout
+    /// (config, attachment or statement level, Firebird 4 and later)
+    pub fn is_statement_timeout(&self) -> bool {
+        self.has_gds_code(ISC_CFG_STMT_TIMEOUT)
+            || self.has_gds_code(ISC_ATT_STMT_TIMEOUT)
+            || self.has_gds_code(ISC_REQ_STMT_TIMEOUT)
+    }
+
+    /// True if DDL failed because the object is in use
+    pub fn is_object_in_use(&self) -> bool {
+        self.has_gds_code(ISC_OBJ_IN_USE)
+    }
+
+    /// Classify lock/update contention, None for other errors.
+    /// A lock timeout is reported together with the deadlock code, so it is checked first.
+    pub fn contention(&self) -> Option<Contention> {
+        if self.has_gds_code(ISC_LOCK_TIMEOUT) {
+            Some(Contention::LockTimeout)
+        } else if self.has_gds_code(ISC_UPDATE_CONFLICT) {
+            Some(Contention::UpdateConflict)
+        } else if self.has_gds_code(ISC_LOCK_CONFLICT) {
+            Some(Contention::LockConflict)
+        } else if self.has_gds_code(ISC_DEADLOCK) {
+            Some(Contention::Deadlock)
+        } else {
+            None
+        }
+    }
+}
+
+impl Error {
+    /// Error for a failed socket read or write: an expired read timeout is
+    /// `Timeout`, a closed or reset connection `ConnectionLost`
+    pub(crate) fn from_socket(err: io::Error) -> Error {
+        match err.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Error::Timeout,
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::WriteZero => Error::ConnectionLost,
+            _ => Error::IoError(err),
+        }
+    }
+
+    /// Classify lock/update contention ("data contention" rather than a bug)
+    pub fn contention(&self) -> Option<Contention> {
+        match self {
+            Error::FirebirdError(e) => e.contention(),
+            _ => None,
+        }
     }
 }
 
@@ -84,6 +256,61 @@ impl ValueError {
     }
 }
 
+/// Failed two-phase commit of a `MultiTransaction`
+#[derive(Debug)]
+pub struct TwoPhaseCommitError {
+    /// The prepare or commit error that stopped the commit
+    pub cause: Box<Error>,
+    /// Prepared transactions the commit did not finish. Those still listed
+    /// by `Connection::limbo_transactions` must be resolved with
+    /// `Connection::recover_limbo`.
+    pub prepared: Vec<PreparedTransaction>,
+}
+
+/// A prepared transaction of one database in a two-phase commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedTransaction {
+    /// Name the database was registered under in the `MultiDb`
+    pub database: String,
+    /// Database as `host:path`
+    pub path: String,
+    /// Transaction id, as `Connection::limbo_transactions` lists it
+    pub transaction_id: i64,
+}
+
+/// Failed conversion of a column value, with where it happened
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ConversionError {
+    /// Column name (alias if available)
+    pub column: String,
+    /// Column index, from 0
+    pub index: usize,
+    /// Declared SQL type, e.g. `VARCHAR CHARACTER SET UTF8`
+    pub sql_type: String,
+    /// Requested Rust type, e.g. `i32`
+    pub rust_type: &'static str,
+    /// Row number in the result set, from 1
+    pub row: usize,
+    /// What the conversion complained about
+    pub message: String,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "row {}, column {} `{}' {}: can't read as {}: {}",
+            self.row, self.index, self.column, self.sql_type, self.rust_type, self.message
+        )
+    }
+}
+
+impl From<ConversionError> for Error {
+    fn from(x: ConversionError) -> Error {
+        Error::Conversion(x)
+    }
+}
+
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct ParamError {
     pub message: String,
@@ -106,6 +333,8 @@ pub enum UrlError {
     /// (feature_name, value)
     InvalidValue(String, String),
     UnknownParameter(String),
+    /// Service name given as the port, not in the services file
+    UnknownService(String),
     BadUrl,
 }
 
@@ -133,6 +362,7 @@ impl fmt::Display for UrlError {
             UrlError::UnknownParameter(ref parameter) => {
                 write!(f, "Unknown URL parameter `{}'", parameter)
             }
+            UrlError::UnknownService(ref name) => write!(f, "Unknown service `{}'", name),
             UrlError::BadUrl => write!(f, "Invalid or incomplete connection URL"),
         }
     }
@@ -149,3 +379,41 @@ impl From<ParseError> for UrlError {
         UrlError::ParseError(x)
     }
 }
+
+#[test]
+fn test_stale_statement() {
+    let e = FirebirdError::with_gds_codes("metadata is obsolete", 0, vec![335544569, ISC_OBSOLETE_METADATA]);
+    assert_eq!(e.gds_codes, vec![ISC_OBSOLETE_METADATA, 335544569]);
+    assert!(e.is_stale_statement());
+    assert!(!FirebirdError::new("error", -104).is_stale_statement());
+}
+
+#[test]
+fn test_contention() {
+    let e = FirebirdError::with_gds_codes("", -901, vec![ISC_DEADLOCK, ISC_LOCK_TIMEOUT]);
+    assert_eq!(e.contention(), Some(Contention::LockTimeout));
+    let e = FirebirdError::with_gds_codes("", -913, vec![ISC_DEADLOCK]);
+    assert_eq!(e.contention(), Some(Contention::Deadlock));
+    let e = FirebirdError::with_gds_codes("", -913, vec![ISC_DEADLOCK, ISC_UPDATE_CONFLICT]);
+    assert_eq!(Error::FirebirdError(e).contention(), Some(Contention::UpdateConflict));
+    assert_eq!(FirebirdError::new("error", -104).contention(), None);
+}
+
+#[test]
+fn test_limit_option() {
+    assert_eq!(limit_option("1000"), Some(1000));
+    assert_eq!(limit_option("0"), None);
+    assert_eq!(limit_option(""), None);
+}
+
+#[test]
+fn test_from_socket() {
+    let e = Error::from_socket(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+    assert!(matches!(e, Error::ConnectionLost));
+    let e = Error::from_socket(io::Error::new(io::ErrorKind::UnexpectedEof, "closed"));
+    assert!(matches!(e, Error::ConnectionLost));
+    let e = Error::from_socket(io::Error::new(io::ErrorKind::WouldBlock, "timed out"));
+    assert!(matches!(e, Error::Timeout));
+    let e = Error::from_socket(io::Error::new(io::ErrorKind::PermissionDenied, "denied"));
+    assert!(matches!(e, Error::IoError(_)));
+}
@@ -0,0 +1,383 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! PSQL `EXECUTE BLOCK` statements
+//!
+//! `ExecuteBlockBuilder` assembles `EXECUTE BLOCK (...) RETURNS (...) AS
+//! ... BEGIN ... END` from named inputs, outputs and local variables. The
+//! declared type of an input is taken from its Rust value (`i32` is an
+//! `INTEGER`, `&str` a `VARCHAR` as long as the text, a `Decimal` a
+//! `DECIMAL` of its scale), and the values are bound as parameters, so
+//! nothing but names and the body is pasted into the SQL.
+//!
+//! A block with outputs returns rows only where its body runs `SUSPEND`;
+//! if the body has none, one is appended after it so the outputs come back
+//! as a single row.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::ExecuteBlockBuilder;
+//!
+//! let block = ExecuteBlockBuilder::new()
+//!     .input("customer_id", 42)
+//!     .input("country", "Brazil")
+//!     .output("total", "NUMERIC(18, 2)")
+//!     .body(
+//!         "update customer set country = :country where id = :customer_id;
+//!          select sum(amount) from orders where customer_id = :customer_id into :total;",
+//!     );
+//! let row = block.query(&conn)?.next().unwrap();
+//! let total: rust_decimal::Decimal = row.get(0)?;
+//! ```
+
+use super::connection::Connection;
+use super::ddl::{object_name, quote};
+use super::error::{Error, ValueError};
+use super::param::{Param, ToSqlParam};
+use super::row::Rows;
+
+// the longest VARCHAR in bytes, and characters of it in UTF8
+const MAX_VARCHAR_CHARS: usize = 32765 / 4;
+
+#[derive(Debug, Clone)]
+struct Input {
+    name: String,
+    sql_type: Option<String>,
+    value: Param,
+}
+
+/// Builder of an `EXECUTE BLOCK` statement with typed inputs and outputs
+#[derive(Debug, Clone, Default)]
+pub struct ExecuteBlockBuilder {
+    inputs: Vec<Input>,
+    // (name, type)
+    outputs: Vec<(String, String)>,
+    variables: Vec<(String, String)>,
+    body: String,
+}
+
+impl ExecuteBlockBuilder {
+    pub fn new() -> ExecuteBlockBuilder {
+        ExecuteBlockBuilder::default()
+    }
+
+    /// Input parameter declared with the type of `value`
+    pub fn input(mut self, name: &str, value: impl Into<Param>) -> Self {
+        self.inputs.push(Input {
+            name: name.to_string(),
+            sql_type: None,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Input parameter with an explicit type, e.g. `"VARCHAR(100)"` or a
+    /// domain; needed for a `Param::Null` value
+    pub fn input_typed(mut self, name: &str, sql_type: &str, value: impl Into<Param>) -> Self {
+        self.inputs.push(Input {
+            name: name.to_string(),
+            sql_type: Some(sql_type.to_string()),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Output parameter, a column of the rows the block returns
+    pub fn output(mut self, name: &str, sql_type: &str) -> Self {
+        self.outputs.push((name.to_string(), sql_type.to_string()));
+        self
+    }
+
+    /// Local variable, `DECLARE VARIABLE name type`
+    pub fn declare(mut self, name: &str, sql_type: &str) -> Self {
+        self.variables
+            .push((name.to_string(), sql_type.to_string()));
+        self
+    }
+
+    /// PSQL statements between `BEGIN` and `END`
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = body.to_string();
+        self
+    }
+
+    /// The statement and the values of its inputs, in order
+    pub fn build(&self) -> Result<(String, Vec<Param>), Error> {
+        let mut names: Vec<String> = Vec::new();
+        let mut declare = |name: &str| -> Result<String, Error> {
+            let name = object_name(name);
+            if name.is_empty() {
+                return Err(Error::ValueError(ValueError::new(
+                    "Empty EXECUTE BLOCK parameter name",
+                )));
+            }
+            if names.contains(&name) {
+                return Err(Error::ValueError(ValueError::new(&format!(
+                    "{} is declared twice in EXECUTE BLOCK",
+                    name
+                ))));
+            }
+            names.push(name.clone());
+            Ok(quote(&name))
+        };
+
+        let mut sql = String::from("EXECUTE BLOCK");
+        let mut params = Vec::new();
+        if !self.inputs.is_empty() {
+            let mut decls = Vec::new();
+            for input in &self.inputs {
+                let sql_type = match &input.sql_type {
+                    Some(t) => t.clone(),
+                    None => declared_type(&input.value).ok_or_else(|| {
+                        Error::ValueError(ValueError::new(&format!(
+                            "NULL input {} needs an explicit type",
+                            input.name
+                        )))
+                    })?,
+                };
+                decls.push(format!("{} {} = ?", declare(&input.name)?, sql_type));
+                params.push(input.value.clone());
+            }
+            sql.push_str(&format!(" ({})", decls.join(", ")));
+        }
+        if !self.outputs.is_empty() {
+            let mut decls = Vec::new();
+            for (name, sql_type) in &self.outputs {
+                decls.push(format!("{} {}", declare(name)?, sql_type));
+            }
+            sql.push_str(&format!(" RETURNS ({})", decls.join(", ")));
+        }
+        sql.push_str(" AS");
+        for (name, sql_type) in &self.variables {
+            sql.push_str(&format!(
+                " DECLARE VARIABLE {} {};",
+                declare(name)?,
+                sql_type
+            ));
+        }
+
+        let body = self.body.trim();
+        sql.push_str(" BEGIN ");
+        sql.push_str(body);
+        if !self.outputs.is_empty() && !has_suspend(body) {
+            if !body.is_empty() && !body.ends_with(';') {
+                sql.push(';');
+            }
+            sql.push_str(" SUSPEND;");
+        }
+        sql.push_str(" END");
+        Ok((sql, params))
+    }
+
+    /// Run the block, returning the rows of its outputs (none without outputs)
+    pub fn query(&self, conn: &Connection) -> Result<Rows, Error> {
+        let (sql, params) = self.build()?;
+        let params: Vec<&dyn ToSqlParam> = params.iter().map(|p| p as &dyn ToSqlParam).collect();
+        let mut stmt = conn.prepare(&sql)?;
+        stmt.query(&params[..])
+    }
+}
+
+/// Type an input of value `value` is declared as, `None` for NULL
+fn declared_type(value: &Param) -> Option<String> {
+    Some(match value {
+        Param::Null => return None,
+        Param::Text(s) => {
+            let chars = s.chars().count();
+            if chars > MAX_VARCHAR_CHARS {
+                "BLOB SUB_TYPE TEXT".to_string()
+            } else {
+                format!("VARCHAR({})", chars.max(1))
+            }
+        }
+        Param::Short(_) => "SMALLINT".to_string(),
+        Param::Long(_) => "INTEGER".to_string(),
+        Param::Int64(_) => "BIGINT".to_string(),
+        Param::Int128(_) => "INT128".to_string(),
+        Param::Time(_) => "TIME".to_string(),
+        Param::Date(_) => "DATE".to_string(),
+        Param::TimeStamp(_) => "TIMESTAMP".to_string(),
+        Param::Float(_) => "FLOAT".to_string(),
+        Param::Double(_) => "DOUBLE PRECISION".to_string(),
+        Param::Blob(_) => "BLOB SUB_TYPE BINARY".to_string(),
+        Param::TimeStampTZ(_) => "TIMESTAMP WITH TIME ZONE".to_string(),
+        Param::TimeTz(..) => "TIME WITH TIME ZONE".to_string(),
+        Param::Decimal(d) => {
+            let scale = d.scale();
+            let fits_int64 = i64::try_from(d.mantissa()).is_ok();
+            let precision = if fits_int64 && scale <= 18 { 18 } else { 38 };
+            format!("DECIMAL({}, {})", precision, scale)
+        }
+        Param::Boolean(_) => "BOOLEAN".to_string(),
+    })
+}
+
+/// Whether PSQL text runs SUSPEND, outside comments and string literals
+fn has_suspend(body: &str) -> bool {
+    let mut words = String::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                for d in chars.by_ref() {
+                    if d == c {
+                        break;
+                    }
+                }
+                words.push(' ');
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for d in chars.by_ref() {
+                    if d == '\n' {
+                        break;
+                    }
+                }
+                words.push(' ');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for d in chars.by_ref() {
+                    if prev == '*' && d == '/' {
+                        break;
+                    }
+                    prev = d;
+                }
+                words.push(' ');
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '$' => words.push(c),
+            _ => words.push(' '),
+        }
+    }
+    words
+        .split_whitespace()
+        .any(|w| w.eq_ignore_ascii_case("suspend"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_build() {
+        let (sql, params) = ExecuteBlockBuilder::new()
+            .input("id", 42)
+            .input("name", "Ann")
+            .input("amount", Decimal::new(12345, 2))
+            .input_typed("note", "VARCHAR(100)", Param::Null)
+            .output("total", "NUMERIC(18, 2)")
+            .declare("n", "INTEGER")
+            .body("select count(*) from t where id = :id into :n;\ntotal = :amount * :n")
+            .build()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "EXECUTE BLOCK (\"ID\" INTEGER = ?, \"NAME\" VARCHAR(3) = ?, \
+             \"AMOUNT\" DECIMAL(18, 2) = ?, \"NOTE\" VARCHAR(100) = ?) \
+             RETURNS (\"TOTAL\" NUMERIC(18, 2)) AS DECLARE VARIABLE \"N\" INTEGER; \
+             BEGIN select count(*) from t where id = :id into :n;\ntotal = :amount * :n; SUSPEND; END"
+        );
+        assert_eq!(
+            params,
+            vec![
+                Param::Long(42),
+                Param::Text("Ann".to_string()),
+                Param::Decimal(Decimal::new(12345, 2)),
+                Param::Null
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_no_outputs() {
+        let (sql, params) = ExecuteBlockBuilder::new()
+            .body("delete from t;")
+            .build()
+            .unwrap();
+        assert_eq!(sql, "EXECUTE BLOCK AS BEGIN delete from t; END");
+        assert!(params.is_empty());
+
+        // an explicit SUSPEND is kept as the only one
+        let (sql, _) = ExecuteBlockBuilder::new()
+            .output("i", "INTEGER")
+            .body("i = 0; while (i < 3) do begin i = i + 1; suspend; end")
+            .build()
+            .unwrap();
+        assert!(sql.ends_with("suspend; end END"));
+    }
+
+    #[test]
+    fn test_build_errors() {
+        assert!(
+            ExecuteBlockBuilder::new()
+                .input("a", Param::Null)
+                .build()
+                .is_err()
+        );
+        assert!(
+            ExecuteBlockBuilder::new()
+                .input("a", 1)
+                .output("A", "INTEGER")
+                .build()
+                .is_err()
+        );
+        assert!(ExecuteBlockBuilder::new().input(" ", 1).build().is_err());
+    }
+
+    #[test]
+    fn test_declared_type() {
+        assert_eq!(
+            declared_type(&Param::Text(String::new())).unwrap(),
+            "VARCHAR(1)"
+        );
+        assert_eq!(
+            declared_type(&Param::Text("ção".to_string())).unwrap(),
+            "VARCHAR(3)"
+        );
+        assert_eq!(
+            declared_type(&Param::Text("x".repeat(MAX_VARCHAR_CHARS + 1))).unwrap(),
+            "BLOB SUB_TYPE TEXT"
+        );
+        assert_eq!(declared_type(&Param::Int64(1)).unwrap(), "BIGINT");
+        assert_eq!(
+            declared_type(&Param::Decimal(Decimal::from_i128_with_scale(
+                i128::from(i64::MAX) + 1,
+                4
+            )))
+            .unwrap(),
+            "DECIMAL(38, 4)"
+        );
+        assert!(declared_type(&Param::Null).is_none());
+    }
+
+    #[test]
+    fn test_has_suspend() {
+        assert!(has_suspend("x = 1; SUSPEND;"));
+        assert!(!has_suspend(
+            "x = 'suspend'; -- suspend\n/* suspend */ y = 2;"
+        ));
+        assert!(!has_suspend("suspended = 1;"));
+    }
+}
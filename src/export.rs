@@ -0,0 +1,220 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Parallel export of a table under one snapshot
+//!
+//! `parallel_export` splits a table into ranges of an integer key and reads
+//! them concurrently, one pooled connection per partition. A lead
+//! transaction takes a snapshot and every partition starts at its number
+//! (`TransactionOptions::at_snapshot`), so the partitions together are a
+//! consistent copy of the table even while it is being written to. Rows are
+//! streamed to the sink in batches of the `fetch_size` URL parameter.
+//!
+//! The lead transaction holds one connection of the pool for the whole
+//! export: with a pool of `max_size` connections at most `max_size - 1`
+//! partitions run at a time, the others wait for a connection (up to the
+//! pool's `acquire_timeout`). Needs Firebird 4.0+.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::parallel_export;
+//!
+//! let files: Vec<Mutex<BufWriter<File>>> = (0..8).map(|i| open_part(i)).collect();
+//! let report = parallel_export(&pool, "orders", "id", 8, |partition, row| {
+//!     let mut out = files[partition].lock().unwrap();
+//!     writeln!(out, "{}", to_csv_line(row)?)?;
+//!     Ok(())
+//! })?;
+//! println!("{} rows at snapshot {}", report.rows, report.snapshot);
+//! ```
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+use super::ddl::{object_name, quote};
+use super::error::{Error, ValueError};
+use super::pool::ConnectionPool;
+use super::row::Row;
+use super::transaction::TransactionOptions;
+
+/// Result of `parallel_export`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    /// Snapshot number all partitions were read at
+    pub snapshot: i64,
+    /// Rows per partition
+    pub partition_rows: Vec<u64>,
+    /// Rows in total
+    pub rows: u64,
+}
+
+/// Export `table` in `partitions` ranges of the integer column `key`,
+/// calling `sink` with the partition index and each row, from one thread
+/// per partition. The first error (of a partition or of the sink) stops
+/// the export and is returned.
+pub fn parallel_export<F>(
+    pool: &Arc<ConnectionPool>,
+    table: &str,
+    key: &str,
+    partitions: usize,
+    sink: F,
+) -> Result<ExportReport, Error>
+where
+    F: Fn(usize, &Row) -> Result<(), Error> + Sync,
+{
+    if partitions == 0 {
+        return Err(Error::ValueError(ValueError::new(
+            "parallel_export needs at least one partition",
+        )));
+    }
+    let table = quote(&object_name(table));
+    let key = quote(&object_name(key));
+
+    let mut lead = pool.get()?;
+    let mut lead_tx = lead
+        .connection()
+        .transaction_with_options(TransactionOptions::snapshot_read_only())?;
+    let snapshot = lead_tx.snapshot_number()?;
+    let (min, max): (Option<i64>, Option<i64>) = lead_tx
+        .prepare(&format!("SELECT MIN({}), MAX({}) FROM {}", key, key, table))?
+        .query_row((), |row| Ok((row.get_nullable(0)?, row.get_nullable(1)?)))?;
+    let ranges = match (min, max) {
+        (Some(min), Some(max)) => key_ranges(min, max, partitions),
+        _ => Vec::new(),
+    };
+
+    let sql = format!("SELECT * FROM {} WHERE {} BETWEEN ? AND ?", table, key);
+    let failed = AtomicBool::new(false);
+    let results: Vec<Result<u64, Error>> = thread::scope(|scope| {
+        let workers: Vec<_> = ranges
+            .iter()
+            .enumerate()
+            .map(|(partition, &(lo, hi))| {
+                let (sql, sink, failed) = (&sql, &sink, &failed);
+                scope.spawn(move || {
+                    let result = export_range(
+                        pool,
+                        snapshot,
+                        sql,
+                        lo,
+                        hi,
+                        |row| sink(partition, row),
+                        failed,
+                    );
+                    if result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    result
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|w| {
+                w.join().unwrap_or_else(|_| {
+                    Err(Error::ValueError(ValueError::new("export worker panicked")))
+                })
+            })
+            .collect()
+    });
+    drop(lead_tx);
+
+    let mut partition_rows = vec![0; partitions];
+    for (partition, result) in results.into_iter().enumerate() {
+        partition_rows[partition] = result?;
+    }
+    Ok(ExportReport {
+        snapshot,
+        rows: partition_rows.iter().sum(),
+        partition_rows,
+    })
+}
+
+/// Read the rows with a key from `lo` to `hi` at `snapshot`
+fn export_range<F>(
+    pool: &Arc<ConnectionPool>,
+    snapshot: i64,
+    sql: &str,
+    lo: i64,
+    hi: i64,
+    mut sink: F,
+    failed: &AtomicBool,
+) -> Result<u64, Error>
+where
+    F: FnMut(&Row) -> Result<(), Error>,
+{
+    let mut guard = pool.get()?;
+    let conn = guard.connection();
+    let fetch_size = conn.fetch_size();
+    let mut tx = conn
+        .transaction_with_options(TransactionOptions::snapshot_read_only().at_snapshot(snapshot))?;
+    let mut stmt = tx.prepare(sql)?;
+    let mut cursor = stmt.open_cursor((lo, hi))?;
+    cursor.set_fetch_size(fetch_size);
+    let mut rows = 0;
+    while let Some(row) = cursor.next_row()? {
+        if failed.load(Ordering::Relaxed) {
+            break;
+        }
+        sink(&row)?;
+        rows += 1;
+    }
+    Ok(rows)
+}
+
+/// Split `min..=max` into at most `partitions` ranges of about the same width
+pub(crate) fn key_ranges(min: i64, max: i64, partitions: usize) -> Vec<(i64, i64)> {
+    let span = max as i128 - min as i128 + 1;
+    let n = partitions as i128;
+    (0..n)
+        .map(|i| {
+            let lo = min as i128 + span * i / n;
+            let hi = min as i128 + span * (i + 1) / n - 1;
+            (lo, hi)
+        })
+        .filter(|(lo, hi)| lo <= hi)
+        .map(|(lo, hi)| (lo as i64, hi as i64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_ranges() {
+        assert_eq!(
+            key_ranges(1, 100, 4),
+            vec![(1, 25), (26, 50), (51, 75), (76, 100)]
+        );
+        assert_eq!(key_ranges(1, 10, 3), vec![(1, 3), (4, 6), (7, 10)]);
+        assert_eq!(key_ranges(5, 6, 4), vec![(5, 5), (6, 6)]);
+        assert_eq!(key_ranges(7, 7, 1), vec![(7, 7)]);
+        assert_eq!(
+            key_ranges(i64::MIN, i64::MAX, 2),
+            vec![(i64::MIN, -1), (0, i64::MAX)]
+        );
+    }
+}
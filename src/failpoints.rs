@@ -0,0 +1,154 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Failure injection for resilience tests (feature `failpoints`)
+//!
+//! `Failpoints` makes a connection fail at a chosen point, the same way on
+//! every run: the socket drops after a number of packets, fetches stall,
+//! or a frame of compressed data arrives corrupted. Applications and
+//! `ConnectionPool` users can then check their retry and recovery paths
+//! without pulling cables or killing servers.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // Cargo.toml:
+//! // firebirust = { version = "0.5", features = ["failpoints"] }
+//! use firebirust::{Connection, ConnectOptions, Error, Failpoints};
+//!
+//! let options = ConnectOptions::new().failpoints(Failpoints::new().drop_after_packets(10));
+//! let conn = Connection::connect_with_options(url, &options)?;
+//! // the 11th packet finds the socket closed
+//! assert!(matches!(run_workload(&conn), Err(Error::ConnectionLost)));
+//! ```
+
+use super::error::Error;
+use std::time::Duration;
+
+/// Failures to inject into one connection; each connection made with the
+/// same `ConnectOptions` starts counting from zero
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Failpoints {
+    drop_after_packets: Option<u64>,
+    delay_fetch: Option<Duration>,
+    corrupt_compressed_frame: Option<u64>,
+    // packets sent and compressed frames received so far
+    packets: u64,
+    frames: u64,
+    dropped: bool,
+}
+
+impl Failpoints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Close the socket once `n` packets were sent, the handshake included;
+    /// later sends and reads fail with `Error::ConnectionLost`
+    pub fn drop_after_packets(mut self, n: u64) -> Self {
+        self.drop_after_packets = Some(n);
+        self
+    }
+
+    /// Wait `delay` before sending each op_fetch, to exercise fetch timeouts
+    pub fn delay_fetch(mut self, delay: Duration) -> Self {
+        self.delay_fetch = Some(delay);
+        self
+    }
+
+    /// Flip the bits of the `n`th chunk (from 1) of compressed data
+    /// received, so decompression fails. Only the synchronous `Connection`
+    /// supports wire compression.
+    pub fn corrupt_compressed_frame(mut self, n: u64) -> Self {
+        self.corrupt_compressed_frame = Some(n);
+        self
+    }
+
+    /// Count a packet about to be sent, `Err` when the connection is to
+    /// drop instead
+    pub(crate) fn on_send(&mut self) -> Result<(), Error> {
+        if self.drop_after_packets.is_some_and(|n| self.packets >= n) {
+            self.dropped = true;
+        }
+        self.check()?;
+        self.packets += 1;
+        Ok(())
+    }
+
+    /// `Err` once the connection was dropped
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        if self.dropped {
+            Err(Error::ConnectionLost)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn fetch_delay(&self) -> Option<Duration> {
+        self.delay_fetch
+    }
+
+    /// Count a received chunk of compressed data, `true` when it is to be
+    /// passed through `corrupt`
+    pub(crate) fn on_compressed_frame(&mut self) -> bool {
+        self.frames += 1;
+        self.corrupt_compressed_frame == Some(self.frames)
+    }
+}
+
+/// Flip every bit of `frame`
+pub(crate) fn corrupt(frame: &mut [u8]) {
+    frame.iter_mut().for_each(|b| *b = !*b);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_after_packets() {
+        let mut fp = Failpoints::new().drop_after_packets(2);
+        assert!(fp.on_send().is_ok());
+        assert!(fp.on_send().is_ok());
+        assert!(matches!(fp.on_send(), Err(Error::ConnectionLost)));
+        assert!(matches!(fp.check(), Err(Error::ConnectionLost)));
+
+        let mut fp = Failpoints::new();
+        for _ in 0..100 {
+            assert!(fp.on_send().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_corrupt_compressed_frame() {
+        let mut fp = Failpoints::new().corrupt_compressed_frame(2);
+        assert_eq!(
+            (0..3).map(|_| fp.on_compressed_frame()).collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+
+        let mut frame = [0x0fu8, 0xf0];
+        corrupt(&mut frame);
+        assert_eq!(frame, [0xf0, 0x0f]);
+    }
+}
@@ -0,0 +1,204 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Mapping of Rust enums to stored codes
+//!
+//! Domain enums are usually stored as a short VARCHAR code or a SMALLINT.
+//! Implement `FbEnum` (or use the `fb_enum!` macro) and the enum can be
+//! bound as a parameter and read with `Row::get`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::fb_enum;
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq)]
+//! enum Status {
+//!     Active,
+//!     Closed,
+//! }
+//!
+//! fb_enum!(Status {
+//!     Active => "A",
+//!     Closed => "C",
+//! });
+//!
+//! conn.execute("update orders set status = ? where id = ?", (Status::Closed, 1))?;
+//! let status: Status = row.get(0)?;
+//! ```
+
+use super::cellvalue::{CellValue, CellValueToVal};
+use super::error::{Error, ValueError};
+use super::param::{Param, ToSqlParam};
+
+/// Code stored in the database for an enum value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnumCode<'a> {
+    Text(&'a str),
+    Int(i64),
+}
+
+impl<'a> From<&'a str> for EnumCode<'a> {
+    fn from(v: &'a str) -> EnumCode<'a> {
+        EnumCode::Text(v)
+    }
+}
+
+impl From<i16> for EnumCode<'_> {
+    fn from(v: i16) -> Self {
+        EnumCode::Int(v as i64)
+    }
+}
+
+impl From<i32> for EnumCode<'_> {
+    fn from(v: i32) -> Self {
+        EnumCode::Int(v as i64)
+    }
+}
+
+impl From<i64> for EnumCode<'_> {
+    fn from(v: i64) -> Self {
+        EnumCode::Int(v)
+    }
+}
+
+/// Enum stored as a string or integer code
+pub trait FbEnum: Sized {
+    /// Code written to the database
+    fn to_code(&self) -> EnumCode<'static>;
+    /// Value for a code read from the database, `None` if unknown
+    fn from_code(code: EnumCode<'_>) -> Option<Self>;
+}
+
+impl<T: FbEnum> CellValueToVal<T> for CellValue {
+    fn to_val(self) -> Result<T, Error> {
+        let code = match &self {
+            // CHAR columns are blank padded
+            CellValue::Text(s) | CellValue::Varying(s) => EnumCode::Text(s.trim_end()),
+            CellValue::Short(v) => EnumCode::Int(*v as i64),
+            CellValue::Long(v) => EnumCode::Int(*v as i64),
+            CellValue::Int64(v) => EnumCode::Int(*v),
+            _ => {
+                return Err(Error::ValueError(ValueError::new(&format!(
+                    "Can't convert {:?} to {}",
+                    self,
+                    std::any::type_name::<T>()
+                ))));
+            }
+        };
+        T::from_code(code).ok_or_else(|| {
+            let shown = match code {
+                EnumCode::Text(s) => format!("'{}'", s),
+                EnumCode::Int(v) => v.to_string(),
+            };
+            Error::ValueError(ValueError::new(&format!(
+                "Unknown {} code {}",
+                std::any::type_name::<T>(),
+                shown
+            )))
+        })
+    }
+}
+
+impl<T: FbEnum> From<T> for Param {
+    fn from(v: T) -> Param {
+        match v.to_code() {
+            EnumCode::Text(s) => Param::Text(s.to_string()),
+            EnumCode::Int(v) => Param::Int64(v),
+        }
+    }
+}
+
+impl<T: FbEnum + Clone> ToSqlParam for T {
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        Param::from(self.clone()).to_value_blr_isnull()
+    }
+}
+
+/// Implement `FbEnum` for a fieldless enum from a `Variant => code` list
+#[macro_export]
+macro_rules! fb_enum {
+    ($t:ident { $($variant:ident => $code:expr),* $(,)? }) => {
+        impl $crate::FbEnum for $t {
+            fn to_code(&self) -> $crate::EnumCode<'static> {
+                match self {
+                    $(Self::$variant => $crate::EnumCode::from($code),)*
+                }
+            }
+
+            fn from_code(code: $crate::EnumCode<'_>) -> Option<Self> {
+                $(
+                    if code == $crate::EnumCode::from($code) {
+                        return Some(Self::$variant);
+                    }
+                )*
+                None
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Status {
+        Active,
+        Closed,
+    }
+
+    fb_enum!(Status {
+        Active => "A",
+        Closed => "C",
+    });
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Priority {
+        Low,
+        High,
+    }
+
+    fb_enum!(Priority {
+        Low => 1,
+        High => 9,
+    });
+
+    #[test]
+    fn test_fb_enum() {
+        let v: Status = CellValue::Text("C ".to_string()).to_val().unwrap();
+        assert_eq!(v, Status::Closed);
+        let v: Option<Status> = CellValue::Null.to_val().unwrap();
+        assert_eq!(v, None);
+        let v: Priority = CellValue::Short(9).to_val().unwrap();
+        assert_eq!(v, Priority::High);
+
+        let err: Result<Status, Error> = CellValue::Varying("X".to_string()).to_val();
+        assert!(format!("{:?}", err.unwrap_err()).contains("'X'"));
+        let err: Result<Priority, Error> = CellValue::Long(5).to_val();
+        assert!(err.is_err());
+
+        assert_eq!(Param::from(Status::Active), Param::Text("A".to_string()));
+        assert_eq!(Param::from(Priority::Low), Param::Int64(1));
+    }
+}
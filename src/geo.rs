@@ -0,0 +1,99 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Points stored as a pair of numeric columns (feature `geo`)
+//!
+//! Coordinates are read with the usual numeric conversions, so the columns
+//! may be NUMERIC/DECIMAL, DOUBLE PRECISION or integers. `x` is the longitude
+//! and `y` the latitude, following `geo_types`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::GeoPoint;
+//!
+//! let mut stmt = conn.prepare("select lon, lat from stores")?;
+//! for row in stmt.query(())? {
+//!     let p = row.get_point(0, 1)?;
+//! }
+//! let points: Vec<GeoPoint> = stmt
+//!     .query_map((), GeoPoint::from_row)?
+//!     .collect::<Result<_, _>>()?;
+//!
+//! // writing: a point is bound as two f64 parameters
+//! conn.execute("insert into stores (lon, lat) values (?, ?)", p.x_y())?;
+//! ```
+
+use super::error::Error;
+use super::row::{FromRow, Row};
+use geo_types::Point;
+
+impl Row {
+    /// Point from x (longitude) and y (latitude) columns
+    pub fn get_point(&self, x_idx: usize, y_idx: usize) -> Result<Point<f64>, Error> {
+        Ok(Point::new(self.get::<f64>(x_idx)?, self.get::<f64>(y_idx)?))
+    }
+
+    /// Point from latitude and longitude columns, in that order
+    pub fn get_lat_lon(&self, lat_idx: usize, lon_idx: usize) -> Result<Point<f64>, Error> {
+        self.get_point(lon_idx, lat_idx)
+    }
+}
+
+/// `FromRow` wrapper reading a point from the first two columns (x, y)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint(pub Point<f64>);
+
+impl FromRow for GeoPoint {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        row.get_point(0, 1).map(GeoPoint)
+    }
+}
+
+impl From<GeoPoint> for Point<f64> {
+    fn from(p: GeoPoint) -> Point<f64> {
+        p.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellvalue::CellValue;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_get_point() {
+        let row = Row::from_cells(vec![
+            CellValue::Decimal(dec!(-46.633308)),
+            CellValue::Double(-23.550520),
+        ]);
+        let p = row.get_point(0, 1).unwrap();
+        assert_eq!(p.x_y(), (-46.633308, -23.550520));
+        assert_eq!(row.get_lat_lon(1, 0).unwrap(), p);
+        assert_eq!(GeoPoint::from_row(&row).unwrap(), GeoPoint(p));
+
+        let row = Row::from_cells(vec![CellValue::Null, CellValue::Double(1.0)]);
+        assert!(row.get_point(0, 1).is_err());
+    }
+}
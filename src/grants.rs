@@ -0,0 +1,325 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Who-can-do-what report of the privileges of a database
+//!
+//! `audit_grants` reads RDB$USER_PRIVILEGES into a `GrantMatrix`: one entry
+//! per privilege held by a user, role or PSQL object on a table, view,
+//! procedure, function, package, sequence or exception, and per role
+//! membership. System objects are left out. The matrix answers what a
+//! grantee may do on an object and exports to CSV or JSON for compliance
+//! reviews.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::audit_grants;
+//!
+//! let matrix = audit_grants(&conn)?;
+//! println!("{:?}", matrix.privileges("CLERK", "ORDERS")); // ["INSERT", "SELECT"]
+//! std::fs::write("grants.csv", matrix.to_csv())?;
+//! ```
+//!
+//! Privileges held through a role are listed under the role; the role
+//! memberships (privilege "MEMBER") link users to them.
+
+use std::fmt::Write;
+
+use super::connection::Connection;
+use super::error::Error;
+use super::health::json_string;
+
+const GRANTS_SQL: &str = "SELECT p.RDB$USER, p.RDB$USER_TYPE, p.RDB$RELATION_NAME,
+    p.RDB$OBJECT_TYPE, IIF(r.RDB$VIEW_BLR IS NULL, 0, 1), p.RDB$PRIVILEGE,
+    p.RDB$FIELD_NAME, p.RDB$GRANT_OPTION, p.RDB$GRANTOR
+    FROM RDB$USER_PRIVILEGES p
+    LEFT JOIN RDB$RELATIONS r
+      ON p.RDB$OBJECT_TYPE = 0 AND r.RDB$RELATION_NAME = p.RDB$RELATION_NAME
+    WHERE p.RDB$RELATION_NAME NOT STARTING WITH 'RDB$'
+      AND p.RDB$RELATION_NAME NOT STARTING WITH 'MON$'
+      AND p.RDB$RELATION_NAME NOT STARTING WITH 'SEC$'
+      AND (r.RDB$SYSTEM_FLAG IS NULL OR r.RDB$SYSTEM_FLAG = 0)
+    ORDER BY p.RDB$USER, p.RDB$RELATION_NAME, p.RDB$PRIVILEGE, p.RDB$FIELD_NAME";
+
+const CSV_HEADER: &str =
+    "grantee,grantee_type,object,object_type,privilege,column,grant_option,grantor";
+
+/// One privilege of one grantee
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrantEntry {
+    pub grantee: String,
+    /// "USER", "ROLE", "TRIGGER", "PROCEDURE", ...
+    pub grantee_type: &'static str,
+    pub object: String,
+    /// "TABLE", "VIEW", "PROCEDURE", "ROLE", ...
+    pub object_type: &'static str,
+    /// "SELECT", "INSERT", "UPDATE", "DELETE", "REFERENCES", "EXECUTE",
+    /// "USAGE", "MEMBER", ...
+    pub privilege: &'static str,
+    /// Column of a column level UPDATE or REFERENCES
+    pub column: Option<String>,
+    /// May grant the privilege to others (or, for MEMBER, the role)
+    pub grant_option: bool,
+    pub grantor: String,
+}
+
+/// All privileges of a database, see `audit_grants`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GrantMatrix {
+    pub entries: Vec<GrantEntry>,
+}
+
+/// Read the privileges of the database
+pub fn audit_grants(conn: &Connection) -> Result<GrantMatrix, Error> {
+    let mut stmt = conn.prepare(GRANTS_SQL)?;
+    let entries = stmt
+        .query_map((), |row| {
+            let object_type: i16 = row.get_or(3, -1)?;
+            let is_view: i32 = row.get(4)?;
+            Ok(GrantEntry {
+                grantee: row.get::<String>(0)?.trim_end().to_string(),
+                grantee_type: object_type_name(row.get_or(1, -1)?),
+                object: row.get::<String>(2)?.trim_end().to_string(),
+                object_type: if object_type == 0 && is_view == 1 {
+                    "VIEW"
+                } else {
+                    object_type_name(object_type)
+                },
+                privilege: privilege_name(row.get::<String>(5)?.trim_end()),
+                column: row
+                    .get::<Option<String>>(6)?
+                    .map(|c| c.trim_end().to_string()),
+                grant_option: row.get_or::<i16>(7, 0)? != 0,
+                grantor: row.get_or(8, String::new())?.trim_end().to_string(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(GrantMatrix { entries })
+}
+
+impl GrantMatrix {
+    /// Users, roles and objects holding a privilege, sorted
+    pub fn grantees(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.entries.iter().map(|e| e.grantee.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Objects some privilege is granted on, sorted
+    pub fn objects(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.entries.iter().map(|e| e.object.as_str()).collect();
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+
+    /// Privileges `grantee` holds directly on `object`, sorted; a column
+    /// level privilege counts as the privilege
+    pub fn privileges(&self, grantee: &str, object: &str) -> Vec<&'static str> {
+        let mut privileges: Vec<&'static str> = self
+            .entries
+            .iter()
+            .filter(|e| e.grantee == grantee && e.object == object)
+            .map(|e| e.privilege)
+            .collect();
+        privileges.sort_unstable();
+        privileges.dedup();
+        privileges
+    }
+
+    /// Roles granted to `grantee`
+    pub fn roles_of(&self, grantee: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|e| e.grantee == grantee && e.privilege == "MEMBER")
+            .map(|e| e.object.as_str())
+            .collect()
+    }
+
+    /// One line per entry, with a header line (RFC 4180 quoting)
+    pub fn to_csv(&self) -> String {
+        let mut s = String::from(CSV_HEADER);
+        s.push_str("\r\n");
+        for e in &self.entries {
+            let fields = [
+                e.grantee.as_str(),
+                e.grantee_type,
+                e.object.as_str(),
+                e.object_type,
+                e.privilege,
+                e.column.as_deref().unwrap_or(""),
+                if e.grant_option { "true" } else { "false" },
+                e.grantor.as_str(),
+            ];
+            let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            s.push_str(&fields.join(","));
+            s.push_str("\r\n");
+        }
+        s
+    }
+
+    /// Array of entry objects with the CSV column names as keys
+    pub fn to_json(&self) -> String {
+        let mut s = String::from("[");
+        for (i, e) in self.entries.iter().enumerate() {
+            if i > 0 {
+                s.push(',');
+            }
+            let _ = write!(
+                s,
+                "{{\"grantee\":{},\"grantee_type\":{},\"object\":{},\"object_type\":{},\"privilege\":{},\"column\":{},\"grant_option\":{},\"grantor\":{}}}",
+                json_string(&e.grantee),
+                json_string(e.grantee_type),
+                json_string(&e.object),
+                json_string(e.object_type),
+                json_string(e.privilege),
+                e.column.as_deref().map_or("null".to_string(), json_string),
+                e.grant_option,
+                json_string(&e.grantor),
+            );
+        }
+        s.push(']');
+        s
+    }
+}
+
+/// Name of an RDB$OBJECT_TYPE / RDB$USER_TYPE code
+fn object_type_name(code: i16) -> &'static str {
+    match code {
+        0 => "TABLE",
+        1 => "VIEW",
+        2 => "TRIGGER",
+        5 => "PROCEDURE",
+        7 => "EXCEPTION",
+        8 => "USER",
+        9 => "DOMAIN",
+        11 => "CHARACTER SET",
+        13 => "ROLE",
+        14 => "SEQUENCE",
+        15 => "FUNCTION",
+        17 => "COLLATION",
+        18 => "PACKAGE",
+        _ => "OTHER",
+    }
+}
+
+/// Name of an RDB$PRIVILEGE code
+fn privilege_name(code: &str) -> &'static str {
+    match code {
+        "S" => "SELECT",
+        "I" => "INSERT",
+        "U" => "UPDATE",
+        "D" => "DELETE",
+        "R" => "REFERENCES",
+        "X" => "EXECUTE",
+        "G" => "USAGE",
+        "M" => "MEMBER",
+        "C" => "CREATE",
+        "L" => "ALTER",
+        "O" => "DROP",
+        _ => "OTHER",
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix() -> GrantMatrix {
+        let entry = |grantee: &str,
+                     grantee_type,
+                     object: &str,
+                     object_type,
+                     privilege,
+                     column: Option<&str>| {
+            GrantEntry {
+                grantee: grantee.to_string(),
+                grantee_type,
+                object: object.to_string(),
+                object_type,
+                privilege,
+                column: column.map(str::to_string),
+                grant_option: false,
+                grantor: "SYSDBA".to_string(),
+            }
+        };
+        GrantMatrix {
+            entries: vec![
+                entry("CLERK", "ROLE", "ORDERS", "TABLE", "SELECT", None),
+                entry("CLERK", "ROLE", "ORDERS", "TABLE", "UPDATE", Some("STATUS")),
+                entry(
+                    "CLERK",
+                    "ROLE",
+                    "ORDERS",
+                    "TABLE",
+                    "UPDATE",
+                    Some("NOTE, \"X\""),
+                ),
+                entry("ALICE", "USER", "CLERK", "ROLE", "MEMBER", None),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_matrix() {
+        let m = matrix();
+        assert_eq!(m.grantees(), vec!["ALICE", "CLERK"]);
+        assert_eq!(m.objects(), vec!["CLERK", "ORDERS"]);
+        assert_eq!(m.privileges("CLERK", "ORDERS"), vec!["SELECT", "UPDATE"]);
+        assert!(m.privileges("ALICE", "ORDERS").is_empty());
+        assert_eq!(m.roles_of("ALICE"), vec!["CLERK"]);
+    }
+
+    #[test]
+    fn test_export() {
+        let m = matrix();
+        let csv = m.to_csv();
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "CLERK,ROLE,ORDERS,TABLE,SELECT,,false,SYSDBA");
+        assert_eq!(
+            lines[3],
+            "CLERK,ROLE,ORDERS,TABLE,UPDATE,\"NOTE, \"\"X\"\"\",false,SYSDBA"
+        );
+
+        let json = GrantMatrix {
+            entries: m.entries[3..].to_vec(),
+        }
+        .to_json();
+        assert_eq!(
+            json,
+            r#"[{"grantee":"ALICE","grantee_type":"USER","object":"CLERK","object_type":"ROLE","privilege":"MEMBER","column":null,"grant_option":false,"grantor":"SYSDBA"}]"#
+        );
+        assert_eq!(privilege_name("X"), "EXECUTE");
+        assert_eq!(object_type_name(18), "PACKAGE");
+    }
+}
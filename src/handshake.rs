@@ -0,0 +1,122 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! What was negotiated with the server during connect
+//!
+//! # Example
+//!
+//! ```ignore
+//! let info = conn.handshake_info();
+//! println!("{}", info);
+//! assert_eq!(info.compression.as_deref(), Some("zlib"));
+//! ```
+
+use super::*;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandshakeInfo {
+    /// Wire protocol version (e.g. 17 for Firebird 4/5)
+    pub protocol_version: i32,
+    pub architecture: i32,
+    /// Packet delivery type ("lazy_send", "batch_send", ...)
+    pub ptype: &'static str,
+    /// Authentication plugin used (Srp256, Srp)
+    pub auth_plugin: String,
+    /// Wire encryption plugin, `None` when traffic is not encrypted
+    pub wire_crypt: Option<String>,
+    /// Wire compression, `None` when not compressed
+    pub compression: Option<String>,
+}
+
+impl HandshakeInfo {
+    pub(crate) fn new(
+        protocol_version: i32,
+        architecture: i32,
+        accept_type: u32,
+        auth_plugin: &str,
+        wire_crypt: Option<String>,
+        compression: Option<String>,
+    ) -> HandshakeInfo {
+        HandshakeInfo {
+            protocol_version,
+            architecture,
+            ptype: ptype_name(accept_type),
+            auth_plugin: auth_plugin.to_string(),
+            wire_crypt,
+            compression,
+        }
+    }
+}
+
+fn ptype_name(accept_type: u32) -> &'static str {
+    match accept_type & PTYPE_MASK {
+        PTYPE_RPC => "rpc",
+        PTYPE_BATCH_SEND => "batch_send",
+        PTYPE_OUT_OF_BAND => "out_of_band",
+        PTYPE_LAZY_SEND => "lazy_send",
+        _ => "unknown",
+    }
+}
+
+impl fmt::Display for HandshakeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "protocol={} arch={} ptype={} auth={} wire_crypt={} compression={}",
+            self.protocol_version,
+            self.architecture,
+            self.ptype,
+            self.auth_plugin,
+            self.wire_crypt.as_deref().unwrap_or("off"),
+            self.compression.as_deref().unwrap_or("off"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_info() {
+        let info = HandshakeInfo::new(
+            17,
+            1,
+            PTYPE_LAZY_SEND | PFLAG_COMPRESS,
+            "Srp256",
+            Some("ChaCha64".to_string()),
+            Some("zlib".to_string()),
+        );
+        assert_eq!(info.ptype, "lazy_send");
+        assert_eq!(
+            info.to_string(),
+            "protocol=17 arch=1 ptype=lazy_send auth=Srp256 wire_crypt=ChaCha64 compression=zlib"
+        );
+        let info = HandshakeInfo::new(16, 1, PTYPE_BATCH_SEND, "Srp", None, None);
+        assert_eq!(
+            info.to_string(),
+            "protocol=16 arch=1 ptype=batch_send auth=Srp wire_crypt=off compression=off"
+        );
+    }
+}
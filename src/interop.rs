@@ -0,0 +1,114 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Protocol-level identifiers, for interop with fbclient code and tools
+//!
+//! Advanced. The server numbers each attachment and transaction;
+//! `Connection::attachment_id` and `Transaction::transaction_id` return
+//! those numbers, the ones `MON$ATTACHMENTS`, `MON$TRANSACTIONS`, the trace
+//! log and `CURRENT_CONNECTION` / `CURRENT_TRANSACTION` show, so a hybrid
+//! application can match what it does through firebirust with what its
+//! fbclient code or a monitoring tool sees.
+//!
+//! `raw_handle` returns the wire protocol handle of the object instead. It
+//! is an index into the objects of this connection only: it is not an
+//! `isc_db_handle` / `isc_tr_handle` of fbclient and must never be passed to
+//! it. It is meant for logs and debugging of the wire protocol.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let trans = conn.transaction()?;
+//! let mut stmt = conn.prepare(
+//!     "SELECT MON$STATE, MON$TIMESTAMP FROM MON$TRANSACTIONS WHERE MON$TRANSACTION_ID = ?",
+//! )?;
+//! let row = stmt.query_row((trans.transaction_id()?,), |row| Ok((row.get::<i16>(0)?, row.get::<String>(1)?)))?;
+//! println!("attachment {} transaction {:?}", conn.attachment_id()?, row);
+//! ```
+
+use super::*;
+
+/// Value of info item `item` of a database or transaction info response,
+/// an unsigned little endian integer of 4 or 8 bytes
+pub(crate) fn parse_info_id(buf: &[u8], item: u8) -> Option<i64> {
+    let mut i = 0;
+    while i + 3 <= buf.len() && buf[i] != ISC_INFO_END {
+        let ln = utils::bytes_to_uint16(&buf[i + 1..i + 3]) as usize;
+        let start = i + 3;
+        if start + ln > buf.len() {
+            return None;
+        }
+        if buf[i] == item {
+            return match ln {
+                1..=4 => {
+                    let mut b = [0u8; 4];
+                    b[..ln].copy_from_slice(&buf[start..start + ln]);
+                    Some(u32::from_le_bytes(b) as i64)
+                }
+                8 => Some(utils::bytes_to_uint64(&buf[start..start + 8]) as i64),
+                _ => None,
+            };
+        }
+        i = start + ln;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_info_id() {
+        #[rustfmt::skip]
+        let buf = [
+            ISC_INFO_ALLOCATION, 2, 0, 0x10, 0x27,
+            ISC_INFO_ATTACHMENT_ID, 4, 0, 0x39, 0x30, 0, 0, // 12345
+            ISC_INFO_END,
+        ];
+        assert_eq!(parse_info_id(&buf, ISC_INFO_ATTACHMENT_ID), Some(12345));
+        assert_eq!(parse_info_id(&buf, ISC_INFO_ALLOCATION), Some(10000));
+        assert_eq!(parse_info_id(&buf, ISC_INFO_TRA_ID as u8), None);
+
+        let buf = [
+            ISC_INFO_TRA_ID as u8,
+            8,
+            0,
+            1,
+            0,
+            0,
+            0,
+            1,
+            0,
+            0,
+            0,
+            ISC_INFO_END,
+        ];
+        assert_eq!(
+            parse_info_id(&buf, ISC_INFO_TRA_ID as u8),
+            Some(0x1_0000_0001)
+        );
+        // truncated
+        assert_eq!(parse_info_id(&buf[..6], ISC_INFO_TRA_ID as u8), None);
+    }
+}
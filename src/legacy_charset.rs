@@ -0,0 +1,250 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Transcoding of text in legacy codepages stored in NONE columns
+//!
+//! Columns with character set NONE hold whatever bytes the writing
+//! application sent, often a Windows or DOS codepage. `LegacyCharsets` maps
+//! tables and columns to the codepage their text was written in, and
+//! CHAR, VARCHAR and text BLOB values of NONE columns are decoded from it
+//! to UTF-8 when read. Columns with a declared character set are left to
+//! the server.
+//!
+//! Data written by both old and new applications mixes codepage text with
+//! UTF-8. By default a value that is valid UTF-8 is kept as it is and only
+//! the rest is decoded from the codepage; `detect_utf8(false)` decodes
+//! every value.
+//!
+//! Needs the `charsets` feature.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{Connection, ConnectOptions, LegacyCharsets};
+//!
+//! let charsets = LegacyCharsets::new("WIN1252")?
+//!     .table("CLIENTES_RU", "WIN1251")?
+//!     .column("ORDERS", "PRINTER_NOTE", "IBM866")?;
+//! let options = ConnectOptions::new().legacy_charsets(charsets);
+//! let conn = Connection::connect_with_options(url, &options)?;
+//! ```
+
+use std::collections::HashMap;
+
+use encoding_rs::Encoding;
+
+use super::ddl::object_name;
+use super::error::{Error, ValueError};
+use super::xsqlvar::XSQLVar;
+use super::*;
+
+/// Codepages of the text in NONE columns, by table and column
+#[derive(Debug, Clone)]
+pub struct LegacyCharsets {
+    default: &'static Encoding,
+    tables: HashMap<String, &'static Encoding>,
+    // (table, column)
+    columns: HashMap<(String, String), &'static Encoding>,
+    detect_utf8: bool,
+}
+
+/// Decoder of the text of one NONE column
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TextDecoder {
+    encoding: &'static Encoding,
+    detect_utf8: bool,
+}
+
+impl LegacyCharsets {
+    /// NONE columns hold text in `codepage`, a Firebird character set name
+    /// ("WIN1252", "ISO8859_2", "DOS866") or a WHATWG encoding label
+    pub fn new(codepage: &str) -> Result<Self, Error> {
+        Ok(LegacyCharsets {
+            default: encoding(codepage)?,
+            tables: HashMap::new(),
+            columns: HashMap::new(),
+            detect_utf8: true,
+        })
+    }
+
+    /// Codepage of the NONE columns of `table`
+    pub fn table(mut self, table: &str, codepage: &str) -> Result<Self, Error> {
+        self.tables.insert(object_name(table), encoding(codepage)?);
+        Ok(self)
+    }
+
+    /// Codepage of one column, over the one of its table
+    pub fn column(mut self, table: &str, column: &str, codepage: &str) -> Result<Self, Error> {
+        self.columns.insert(
+            (object_name(table), object_name(column)),
+            encoding(codepage)?,
+        );
+        Ok(self)
+    }
+
+    /// Keep values that are valid UTF-8 as they are (the default)
+    pub fn detect_utf8(mut self, detect: bool) -> Self {
+        self.detect_utf8 = detect;
+        self
+    }
+
+    /// Codepage of `column` of `table`, the default one for expressions
+    fn encoding_for(&self, table: &str, column: &str) -> &'static Encoding {
+        self.columns
+            .get(&(table.to_string(), column.to_string()))
+            .or_else(|| self.tables.get(table))
+            .copied()
+            .unwrap_or(self.default)
+    }
+
+    /// Set the decoder of the text columns of `xsqlda` declared as NONE
+    pub(crate) fn assign(&self, xsqlda: &mut [XSQLVar]) {
+        for x in xsqlda.iter_mut() {
+            let charset = match x.sqltype {
+                SQL_TYPE_TEXT | SQL_TYPE_VARYING => x.sqlsubtype & 0xff,
+                // text blobs carry their character set in the scale
+                SQL_TYPE_BLOB if x.sqlsubtype == 1 => x.sqlscale & 0xff,
+                _ => continue,
+            };
+            if charset == 0 {
+                x.legacy_decoder = Some(TextDecoder {
+                    encoding: self.encoding_for(&x.relname, &x.fieldname),
+                    detect_utf8: self.detect_utf8,
+                });
+            }
+        }
+    }
+}
+
+impl TextDecoder {
+    pub(crate) fn decode(&self, b: &[u8]) -> String {
+        if self.detect_utf8
+            && let Ok(s) = std::str::from_utf8(b)
+        {
+            return s.to_string();
+        }
+        self.encoding.decode_without_bom_handling(b).0.into_owned()
+    }
+}
+
+/// Encoding of a Firebird character set name or a WHATWG label
+fn encoding(name: &str) -> Result<&'static Encoding, Error> {
+    let label = match name.trim().to_ascii_uppercase().as_str() {
+        "WIN1250" => "windows-1250",
+        "WIN1251" => "windows-1251",
+        "WIN1252" => "windows-1252",
+        "WIN1253" => "windows-1253",
+        "WIN1254" => "windows-1254",
+        "WIN1255" => "windows-1255",
+        "WIN1256" => "windows-1256",
+        "WIN1257" => "windows-1257",
+        "WIN1258" => "windows-1258",
+        "ISO8859_1" => "windows-1252",
+        "ISO8859_2" => "iso-8859-2",
+        "ISO8859_3" => "iso-8859-3",
+        "ISO8859_4" => "iso-8859-4",
+        "ISO8859_5" => "iso-8859-5",
+        "ISO8859_6" => "iso-8859-6",
+        "ISO8859_7" => "iso-8859-7",
+        "ISO8859_8" => "iso-8859-8",
+        "ISO8859_13" => "iso-8859-13",
+        "DOS866" => "ibm866",
+        "KOI8R" => "koi8-r",
+        "KOI8U" => "koi8-u",
+        "TIS620" => "windows-874",
+        "SJIS_0208" | "CP943C" => "shift_jis",
+        "EUCJ_0208" => "euc-jp",
+        "BIG_5" => "big5",
+        "GB_2312" | "GBK" => "gbk",
+        "GB18030" => "gb18030",
+        "KSC_5601" => "euc-kr",
+        _ => name.trim(),
+    };
+    Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| Error::ValueError(ValueError::new(&format!("Unknown codepage {}", name))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(sqltype: u32, sqlsubtype: i32, relname: &str, fieldname: &str) -> XSQLVar {
+        let mut x = XSQLVar::new();
+        x.sqltype = sqltype;
+        x.sqlsubtype = sqlsubtype;
+        x.relname = relname.to_string();
+        x.fieldname = fieldname.to_string();
+        x
+    }
+
+    #[test]
+    fn test_assign() {
+        let charsets = LegacyCharsets::new("WIN1252")
+            .unwrap()
+            .table("clientes_ru", "WIN1251")
+            .unwrap()
+            .column("ORDERS", "note", "DOS866")
+            .unwrap();
+        let mut xsqlda = vec![
+            var(SQL_TYPE_VARYING, 0, "CUSTOMER", "NAME"),
+            var(SQL_TYPE_TEXT, 0, "CLIENTES_RU", "NAME"),
+            var(SQL_TYPE_VARYING, 0, "ORDERS", "NOTE"),
+            // UTF8
+            var(SQL_TYPE_VARYING, 4, "CUSTOMER", "EMAIL"),
+            var(SQL_TYPE_LONG, 0, "CUSTOMER", "ID"),
+        ];
+        charsets.assign(&mut xsqlda);
+        let names: Vec<Option<&str>> = xsqlda
+            .iter()
+            .map(|x| x.legacy_decoder.map(|d| d.encoding.name()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                Some("windows-1252"),
+                Some("windows-1251"),
+                Some("IBM866"),
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let decoder = TextDecoder {
+            encoding: encoding("WIN1252").unwrap(),
+            detect_utf8: true,
+        };
+        assert_eq!(decoder.decode(b"Jos\xe9"), "José");
+        assert_eq!(decoder.decode("José".as_bytes()), "José");
+        let decoder = TextDecoder {
+            detect_utf8: false,
+            ..decoder
+        };
+        assert_eq!(decoder.decode("José".as_bytes()), "JosÃ©");
+
+        assert_eq!(encoding("latin2").unwrap().name(), "ISO-8859-2");
+        assert!(LegacyCharsets::new("EBCDIC").is_err());
+    }
+}
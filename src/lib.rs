@@ -22,43 +22,160 @@
 #![allow(dead_code)]
 
 mod alerter;
+mod array;
+mod auth;
+mod blob;
+mod bufpool;
 mod cellvalue;
+mod checksum;
+mod codegen;
+mod columnar;
+#[cfg(feature = "compat")]
+pub mod compat;
 mod compression;
 mod conn_params;
+mod connect_options;
 mod connection;
 mod crypt_translater;
+mod db_identity;
+mod ddl;
 mod ddl_extractor;
+mod deadline;
 mod decfloat;
+mod diff;
 mod errmsgs;
 mod error;
+mod execute_block;
+mod export;
+mod external;
+#[cfg(feature = "failpoints")]
+mod failpoints;
+mod fbenum;
+mod handshake;
+mod hooks;
+pub mod health;
+#[cfg(feature = "geo")]
+mod geo;
+mod grants;
+mod interop;
+mod kill_timeout;
+#[cfg(feature = "charsets")]
+mod legacy_charset;
+mod limbo;
+mod multidb;
+mod paginator;
 mod param;
 mod params;
+mod pattern;
+mod protocol;
 pub mod pool;
+mod replication;
+mod resources;
+mod retry;
+mod router;
+mod runtime;
 mod row;
+mod sanitize;
+mod script;
+pub mod security;
+mod slow_query;
+pub mod sqlcheck;
+mod sqltype;
 mod srp;
 mod statement;
+mod tenant;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+#[cfg(feature = "time")]
+mod time_map;
+mod timeouts;
 mod transaction;
 mod tz_map;
 mod utils;
+#[cfg(feature = "uuid")]
+mod uuid_map;
+mod versioned;
+#[cfg(feature = "web")]
+mod web;
+#[cfg(feature = "websocket")]
+mod websocket;
 mod wirechannel;
 mod wireprotocol;
+pub mod xdr;
 mod xsqlvar;
 
 mod connection_async;
+mod service_async;
+mod service_port;
 mod statement_async;
 mod transaction_async;
 mod wirechannel_async;
 mod wireprotocol_async;
 
 pub use crate::alerter::{EventAlerter, MAX_EVENTS};
+pub use crate::array::Array;
+pub use crate::auth::AuthPlugin;
+pub use crate::blob::{BlobId, BlobOptions, BlobReader, BlobWriter};
+pub use crate::checksum::{TableChecksum, table_checksum};
+pub use crate::codegen::{ProcedureGenerator, StructGenerator};
+pub use crate::columnar::{ColumnData, ColumnKind, ColumnarBatch};
+pub use crate::compression::WireCodec;
+pub use crate::connect_options::ConnectOptions;
 pub use crate::connection::Connection;
 pub use crate::connection_async::ConnectionAsync;
-pub use crate::error::{Error, ValueError};
+pub use crate::crypt_translater::{CryptPlugin, CryptTranslator};
+pub use crate::db_identity::{BackupInfo, DatabaseIdentity};
+pub use crate::ddl::TruncateMode;
+pub use crate::deadline::DeadlineOutcome;
+pub use crate::decfloat::DecFloat;
+pub use crate::diff::{Diff, DiffReport, diff_tables};
+pub use crate::error::{
+    Contention, ContentionStats, ConversionError, Error, FirebirdError, Limit, PreparedTransaction,
+    TwoPhaseCommitError, ValueError,
+};
+pub use crate::execute_block::ExecuteBlockBuilder;
+pub use crate::export::{ExportReport, parallel_export};
+pub use crate::hooks::{ConnectionHooks, ConnectionInfo};
+pub use crate::external::{ExecuteStatementOnExternal, ExternalDataSource, ExternalTransaction};
+#[cfg(feature = "failpoints")]
+pub use crate::failpoints::Failpoints;
+pub use crate::fbenum::{EnumCode, FbEnum};
+pub use crate::handshake::HandshakeInfo;
+#[cfg(feature = "geo")]
+pub use crate::geo::GeoPoint;
+pub use crate::grants::{GrantEntry, GrantMatrix, audit_grants};
+#[cfg(feature = "charsets")]
+pub use crate::legacy_charset::LegacyCharsets;
+pub use crate::limbo::LimboAction;
+pub use crate::multidb::{MultiDb, MultiTransaction};
+pub use crate::paginator::{Page, PageMode, Paginator};
 pub use crate::param::Param;
 pub use crate::param::ToSqlParam;
-pub use crate::pool::{ConnectionPool, PoolOptions, PoolGuard};
-pub use crate::statement::ColumnInfo;
+pub use crate::pattern::{LIKE_ESCAPE, LikeMatch, containing_predicate, escape_like, like_predicate};
+pub use crate::pool::{ConnectionPool, DrainReport, PinInfo, PinnedConnection, PoolOptions, PoolGuard};
+pub use crate::replication::{Publication, PublicationTable};
+pub use crate::resources::{LeakDetection, ResourceStats};
+pub use crate::retry::{RetryClass, RetryPolicy, classify, retry, retry_async};
+pub use crate::router::{PoolRouter, ReplicaStatus};
+#[cfg(feature = "rt-async-std")]
+pub use crate::runtime::AsyncStdRuntime;
+#[cfg(feature = "rt-tokio")]
+pub use crate::runtime::TokioRuntime;
+pub use crate::runtime::{BoxFuture, LocalBoxFuture, Runtime, Transport};
+pub use crate::row::{FromRow, MappedRows, Row, Rows};
+pub use crate::sanitize::{ParamLogging, RedactionPolicy, sanitize_sql};
+pub use crate::service_async::{ServiceConnectionAsync, ServiceOutput};
+pub use crate::slow_query::SlowQueryLog;
+pub use crate::sqltype::SqlType;
+pub use crate::statement::{ColumnInfo, Cursor, StatementKind};
+pub use crate::tenant::TenantPools;
+pub use crate::timeouts::Timeouts;
 pub use crate::transaction::{IsolationLevel, LockWait, TransactionOptions};
+#[cfg(feature = "web")]
+pub use crate::web::DbTx;
+#[cfg(feature = "websocket")]
+pub use crate::websocket::WebSocketRuntime;
+pub use crate::wirechannel::WireStats;
 
 // Column type
 pub const SQL_TYPE_TEXT: u32 = 452;
@@ -86,7 +203,11 @@ pub const SQL_TYPE_DEC128: u32 = 32762;
 pub const SQL_TYPE_BOOLEAN: u32 = 32764;
 pub const SQL_TYPE_NULL: u32 = 32766;
 
+// Character set id of binary CHAR / VARCHAR
+pub(crate) const CHARSET_OCTETS: u8 = 1;
+
 // Protocol Types (accept_type)
+pub(crate) const PTYPE_RPC: u32 = 2; // Simple remote procedure call
 pub(crate) const PTYPE_BATCH_SEND: u32 = 3; // Batch sends, no asynchrony
 pub(crate) const PTYPE_OUT_OF_BAND: u32 = 4; // Batch sends w/ out of band notification
 pub(crate) const PTYPE_LAZY_SEND: u32 = 5; // Deferred packets delivery
@@ -272,9 +393,11 @@ pub(crate) const OP_DETACH: u32 = 21;
 pub(crate) const OP_TRANSACTION: u32 = 29;
 pub(crate) const OP_COMMIT: u32 = 30;
 pub(crate) const OP_ROLLBACK: u32 = 31;
+pub(crate) const OP_RECONNECT: u32 = 33;
 pub(crate) const OP_OPEN_BLOB: u32 = 35;
 pub(crate) const OP_GET_SEGMENT: u32 = 36;
 pub(crate) const OP_PUT_SEGMENT: u32 = 37;
+pub(crate) const OP_CANCEL_BLOB: u32 = 38;
 pub(crate) const OP_CLOSE_BLOB: u32 = 39;
 pub(crate) const OP_INFO_DATABASE: u32 = 40;
 pub(crate) const OP_INFO_TRANSACTION: u32 = 42;
@@ -282,10 +405,14 @@ pub(crate) const OP_BATCH_SEGMENTS: u32 = 44;
 pub(crate) const OP_QUE_EVENTS: u32 = 48;
 pub(crate) const OP_CANCEL_EVENTS: u32 = 49;
 pub(crate) const OP_COMMIT_RETAINING: u32 = 50;
+pub(crate) const OP_PREPARE2: u32 = 51;
 pub(crate) const OP_EVENT: u32 = 52;
 pub(crate) const OP_CONNECT_REQUEST: u32 = 53;
 pub(crate) const OP_OPEN_BLOB2: u32 = 56;
 pub(crate) const OP_CREATE_BLOB2: u32 = 57;
+pub(crate) const OP_GET_SLICE: u32 = 58;
+pub(crate) const OP_PUT_SLICE: u32 = 59;
+pub(crate) const OP_SLICE: u32 = 60;
 pub(crate) const OP_ALLOCATE_STATEMENT: u32 = 62;
 pub(crate) const OP_EXECUTE: u32 = 63;
 pub(crate) const OP_EXEC_IMMEDIATE: u32 = 64;
@@ -293,6 +420,7 @@ pub(crate) const OP_FETCH: u32 = 65;
 pub(crate) const OP_FETCH_RESPONSE: u32 = 66;
 pub(crate) const OP_FREE_STATEMENT: u32 = 67;
 pub(crate) const OP_PREPARE_STATEMENT: u32 = 68;
+pub(crate) const OP_SET_CURSOR: u32 = 69;
 pub(crate) const OP_INFO_SQL: u32 = 70;
 pub(crate) const OP_DUMMY: u32 = 71;
 pub(crate) const OP_EXECUTE2: u32 = 76;
@@ -317,6 +445,9 @@ pub(crate) const OP_CRYPT: u32 = 96;
 pub(crate) const OP_CRYPT_KEY_CALLBACK: u32 = 97;
 pub(crate) const OP_COND_ACCEPT: u32 = 98;
 
+// op_cancel kinds
+pub(crate) const FB_CANCEL_RAISE: u32 = 3;
+
 pub(crate) const CNCT_USER: u8 = 1;
 pub(crate) const CNCT_PASSWD: u8 = 2;
 pub(crate) const CNCT_HOST: u8 = 4;
@@ -353,6 +484,17 @@ pub const ISC_TPB_RESTART_REQUESTS: u8 = 19;
 pub const ISC_TPB_NO_AUTO_UNDO: u8 = 20;
 pub const ISC_TPB_LOCK_TIMEOUT: u8 = 21;
 pub const ISC_TPB_READ_CONSISTENCY: u8 = 22; // Read consistency (Firebird 4+)
+pub const ISC_TPB_AT_SNAPSHOT_NUMBER: u8 = 23; // Share a snapshot (Firebird 4+)
+
+// Blob Parameter Block (BPB)
+pub(crate) const ISC_BPB_VERSION1: u8 = 1;
+pub(crate) const ISC_BPB_SOURCE_TYPE: u8 = 1;
+pub(crate) const ISC_BPB_TARGET_TYPE: u8 = 2;
+pub(crate) const ISC_BPB_TYPE: u8 = 3;
+pub(crate) const ISC_BPB_SOURCE_INTERP: u8 = 4;
+pub(crate) const ISC_BPB_TARGET_INTERP: u8 = 5;
+pub(crate) const ISC_BPB_FILTER_PARAMETER: u8 = 6;
+pub(crate) const ISC_BPB_STORAGE: u8 = 7;
 
 pub(crate) const ISC_INFO_REQ_SELECT_COUNT: u32 = 13;
 pub(crate) const ISC_INFO_REQ_INSERT_COUNT: u32 = 14;
@@ -379,6 +521,29 @@ pub(crate) const ISC_INFO_SVC_LIMBO_TRANS: u32 = 66;
 pub(crate) const ISC_INFO_SVC_RUNNING: u32 = 67;
 pub(crate) const ISC_INFO_SVC_GET_USERS: u32 = 68;
 
+// Service Parameter Block (SPB)
+pub(crate) const ISC_SPB_VERSION: u8 = 2;
+pub(crate) const ISC_SPB_CURRENT_VERSION: u8 = 2;
+pub(crate) const ISC_SPB_USER_NAME: u8 = 28;
+pub(crate) const ISC_SPB_SPECIFIC_AUTH_DATA: u8 = 84;
+pub(crate) const ISC_SPB_DBNAME: u8 = 106;
+pub(crate) const ISC_SPB_VERBOSE: u8 = 107;
+pub(crate) const ISC_SPB_OPTIONS: u8 = 108;
+pub(crate) const ISC_SPB_BKP_FILE: u8 = 5;
+pub(crate) const ISC_SPB_RES_PAGE_SIZE: u8 = 10;
+pub(crate) const ISC_SPB_RES_CREATE: u32 = 0x2000;
+pub(crate) const ISC_SPB_RES_REPLACE: u32 = 0x1000;
+pub(crate) const ISC_SPB_TRC_ID: u8 = 1;
+pub(crate) const ISC_SPB_TRC_NAME: u8 = 2;
+pub(crate) const ISC_SPB_TRC_CFG: u8 = 3;
+
+// Service actions
+pub(crate) const ISC_ACTION_SVC_BACKUP: u8 = 1;
+pub(crate) const ISC_ACTION_SVC_RESTORE: u8 = 2;
+pub(crate) const ISC_ACTION_SVC_TRACE_START: u8 = 22;
+pub(crate) const ISC_ACTION_SVC_TRACE_STOP: u8 = 23;
+pub(crate) const ISC_ACTION_SVC_TRACE_LIST: u8 = 26;
+
 // Transaction informatino items
 pub(crate) const ISC_INFO_TRA_ID: u32 = 4;
 pub(crate) const ISC_INFO_TRA_OLDEST_INTERESTING: u32 = 5;
@@ -403,3 +568,5 @@ mod test_async;
 mod tests;
 #[cfg(test)]
 mod tests_timezone;
+#[cfg(test)]
+mod tests_transcript;
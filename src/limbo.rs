@@ -0,0 +1,105 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Limbo transaction recovery
+//!
+//! A two-phase commit interrupted between prepare and commit leaves the
+//! transaction "in limbo". Its record versions stay locked until it is
+//! committed or rolled back by id.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::LimboAction;
+//!
+//! for id in conn.limbo_transactions()? {
+//!     conn.recover_limbo(id, LimboAction::Rollback)?;
+//! }
+//! ```
+
+use super::*;
+
+/// What to do with a limbo transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimboAction {
+    Commit,
+    Rollback,
+}
+
+/// Parse the isc_info_limbo items of a database info response
+pub(crate) fn parse_limbo_info(buf: &[u8]) -> Vec<i64> {
+    let mut ids = Vec::new();
+    let mut i = 0;
+    while i + 3 <= buf.len() && buf[i] == ISC_INFO_LIMBO {
+        let ln = utils::bytes_to_uint16(&buf[i + 1..i + 3]) as usize;
+        i += 3;
+        if i + ln > buf.len() {
+            break;
+        }
+        let id = match ln {
+            4 => utils::bytes_to_uint32(&buf[i..i + 4]) as i64,
+            8 => utils::bytes_to_uint64(&buf[i..i + 8]) as i64,
+            _ => 0,
+        };
+        if id != 0 {
+            ids.push(id);
+        }
+        i += ln;
+    }
+    ids
+}
+
+/// Transaction id buffer for op_reconnect (little endian, 4 bytes if it fits)
+pub(crate) fn transaction_id_buffer(transaction_id: i64) -> Vec<u8> {
+    if transaction_id >= 0 && transaction_id <= u32::MAX as i64 {
+        (transaction_id as u32).to_le_bytes().to_vec()
+    } else {
+        transaction_id.to_le_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_limbo_info() {
+        #[rustfmt::skip]
+        let buf = [
+            ISC_INFO_LIMBO, 4, 0, 0x39, 0x30, 0, 0, // 12345
+            ISC_INFO_LIMBO, 8, 0, 1, 0, 0, 0, 1, 0, 0, 0, // 0x1_0000_0001
+            ISC_INFO_END,
+        ];
+        assert_eq!(parse_limbo_info(&buf), vec![12345, 0x1_0000_0001]);
+        assert!(parse_limbo_info(&[ISC_INFO_END]).is_empty());
+    }
+
+    #[test]
+    fn test_transaction_id_buffer() {
+        assert_eq!(transaction_id_buffer(12345), vec![0x39, 0x30, 0, 0]);
+        assert_eq!(
+            transaction_id_buffer(0x1_0000_0001),
+            vec![1, 0, 0, 0, 1, 0, 0, 0]
+        );
+    }
+}
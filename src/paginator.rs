@@ -0,0 +1,381 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Pagination of queries for REST style APIs
+//!
+//! `Paginator` wraps a query, orders it by key columns that are unique
+//! together, and returns one page at a time with a continuation token for
+//! the next one. Keyset pagination (the default) continues after the keys
+//! of the last row, so rows inserted or deleted meanwhile neither repeat nor
+//! go missing, and page 1000 costs as much as page 1. `PageMode::Offset`
+//! uses `ROWS ? TO ?` instead.
+//!
+//! The token is opaque text safe to put in a URL; it carries the key values
+//! with their types, so it is bound as parameters and never pasted into SQL.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::Paginator;
+//!
+//! let pager = Paginator::new("select id, name from customer where country = ?", &["id"])
+//!     .page_size(50);
+//! let page = pager.fetch(&conn, &[&"Brazil"], request.query("after"))?;
+//! for row in &page.rows { /* ... */ }
+//! // hand page.next back to the client, None on the last page
+//! ```
+
+use super::cellvalue::CellValue;
+use super::connection::Connection;
+use super::ddl::{object_name, quote};
+use super::error::{Error, ValueError};
+use super::param::{Param, ToSqlParam};
+use super::row::Row;
+
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
+
+/// How a `Paginator` finds the start of the next page
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageMode {
+    /// Rows after the keys of the last row of the previous page
+    Keyset,
+    /// Rows after a row count, with `ROWS ? TO ?`
+    Offset,
+}
+
+/// Rows of one page and the token of the next one
+pub struct Page {
+    pub rows: Vec<Row>,
+    /// Continuation token, `None` on the last page
+    pub next: Option<String>,
+}
+
+/// Query paging by a set of unique key columns
+#[derive(Debug, Clone)]
+pub struct Paginator {
+    sql: String,
+    // ordering columns, as stored in the system tables
+    keys: Vec<String>,
+    page_size: usize,
+    descending: bool,
+    mode: PageMode,
+}
+
+impl Paginator {
+    /// `sql` is a SELECT without ORDER BY; `keys` are columns of its result
+    /// that identify a row together, e.g. the primary key
+    pub fn new(sql: &str, keys: &[&str]) -> Paginator {
+        Paginator {
+            sql: sql.trim().trim_end_matches(';').to_string(),
+            keys: keys.iter().map(|k| object_name(k)).collect(),
+            page_size: 100,
+            descending: false,
+            mode: PageMode::Keyset,
+        }
+    }
+
+    /// Rows per page, 100 by default
+    pub fn page_size(mut self, page_size: usize) -> Paginator {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Order by the keys descending
+    pub fn descending(mut self, descending: bool) -> Paginator {
+        self.descending = descending;
+        self
+    }
+
+    pub fn mode(mut self, mode: PageMode) -> Paginator {
+        self.mode = mode;
+        self
+    }
+
+    /// SQL and parameters of the page after `token` (the first page for
+    /// `None`); the parameters follow those of the wrapped query. One row
+    /// more than a page is selected, to tell whether another page follows.
+    pub fn query(&self, token: Option<&str>) -> Result<(String, Vec<Param>), Error> {
+        if self.keys.is_empty() {
+            return Err(Error::ValueError(ValueError::new(
+                "Paginator needs at least one key column",
+            )));
+        }
+        let (cmp, dir) = if self.descending {
+            ("<", " DESC")
+        } else {
+            (">", "")
+        };
+        let order: Vec<String> = self
+            .keys
+            .iter()
+            .map(|k| format!("{}{}", quote(k), dir))
+            .collect();
+        let mut sql = format!("SELECT * FROM ({}) PAGED", self.sql);
+        let mut params = Vec::new();
+        match (self.mode, token) {
+            (_, None) => {
+                sql.push_str(&format!(" ORDER BY {} ROWS ?", order.join(", ")));
+                params.push(Param::Int64(self.page_size as i64 + 1));
+            }
+            (PageMode::Keyset, Some(token)) => {
+                let values = decode_keys(token)?;
+                if values.len() != self.keys.len() {
+                    return Err(bad_token());
+                }
+                // (k1 > ?) OR (k1 = ? AND k2 > ?) OR ...
+                let mut terms = Vec::new();
+                for (i, key) in self.keys.iter().enumerate() {
+                    let mut term: Vec<String> = Vec::new();
+                    for (prev, value) in self.keys.iter().zip(&values).take(i) {
+                        term.push(format!("{} = ?", quote(prev)));
+                        params.push(value.clone());
+                    }
+                    term.push(format!("{} {} ?", quote(key), cmp));
+                    params.push(values[i].clone());
+                    terms.push(format!("({})", term.join(" AND ")));
+                }
+                sql.push_str(&format!(
+                    " WHERE {} ORDER BY {} ROWS ?",
+                    terms.join(" OR "),
+                    order.join(", ")
+                ));
+                params.push(Param::Int64(self.page_size as i64 + 1));
+            }
+            (PageMode::Offset, Some(token)) => {
+                let offset: i64 = token
+                    .strip_prefix("o:")
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(bad_token)?;
+                sql.push_str(&format!(" ORDER BY {} ROWS ? TO ?", order.join(", ")));
+                params.push(Param::Int64(offset + 1));
+                params.push(Param::Int64(offset + self.page_size as i64 + 1));
+            }
+        }
+        Ok((sql, params))
+    }
+
+    /// Page of the rows returned by the SQL of `query(token)`
+    pub fn page(&self, mut rows: Vec<Row>, token: Option<&str>) -> Result<Page, Error> {
+        if rows.len() <= self.page_size {
+            return Ok(Page { rows, next: None });
+        }
+        rows.truncate(self.page_size);
+        let next = match self.mode {
+            PageMode::Keyset => encode_keys(&self.keys, rows.last().unwrap())?,
+            PageMode::Offset => {
+                let offset = match token {
+                    Some(token) => token
+                        .strip_prefix("o:")
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .ok_or_else(bad_token)?,
+                    None => 0,
+                };
+                format!("o:{}", offset + self.page_size)
+            }
+        };
+        Ok(Page {
+            rows,
+            next: Some(next),
+        })
+    }
+
+    /// Fetch the page after `token` (the first page for `None`);
+    /// `params` are the parameters of the wrapped query
+    pub fn fetch(
+        &self,
+        conn: &Connection,
+        params: &[&dyn ToSqlParam],
+        token: Option<&str>,
+    ) -> Result<Page, Error> {
+        let (sql, page_params) = self.query(token)?;
+        let mut all: Vec<&dyn ToSqlParam> = params.to_vec();
+        all.extend(page_params.iter().map(|p| p as &dyn ToSqlParam));
+        let mut stmt = conn.prepare(&sql)?;
+        let rows: Vec<Row> = stmt.query(&all[..])?.collect();
+        self.page(rows, token)
+    }
+}
+
+fn bad_token() -> Error {
+    Error::ValueError(ValueError::new("Invalid pagination token"))
+}
+
+/// Token holding the key values of `row`: `tag:value` per key, `;` separated
+fn encode_keys(keys: &[String], row: &Row) -> Result<String, Error> {
+    let mut parts = Vec::new();
+    for key in keys {
+        let idx = row
+            .columns()
+            .iter()
+            .position(|c| &c.name == key)
+            .ok_or_else(|| {
+                Error::ValueError(ValueError::new(&format!(
+                    "Key column {} is not selected",
+                    key
+                )))
+            })?;
+        let (tag, text) = match row.cell(idx).cloned().unwrap_or(CellValue::Null) {
+            CellValue::Short(v) => ("i", v.to_string()),
+            CellValue::Long(v) => ("i", v.to_string()),
+            CellValue::Int64(v) => ("i", v.to_string()),
+            CellValue::Int128(v) => ("I", v.to_string()),
+            CellValue::Text(v) | CellValue::Varying(v) => ("s", v),
+            CellValue::Date(v) => ("d", v.to_string()),
+            CellValue::Time(v) => ("t", v.to_string()),
+            CellValue::TimeStamp(v) => ("ts", v.format(TIMESTAMP_FORMAT).to_string()),
+            // widened first, so the key compares equal to the stored FLOAT
+            CellValue::Float(v) => ("f", (v as f64).to_string()),
+            CellValue::Double(v) => ("f", v.to_string()),
+            CellValue::Decimal(v) => ("m", v.to_string()),
+            CellValue::Boolean(v) => ("b", v.to_string()),
+            CellValue::Null => {
+                return Err(Error::ValueError(ValueError::new(&format!(
+                    "Key column {} is NULL",
+                    key
+                ))));
+            }
+            _ => {
+                return Err(Error::ValueError(ValueError::new(&format!(
+                    "Key column {} has a type pagination does not support",
+                    key
+                ))));
+            }
+        };
+        parts.push(format!("{}:{}", tag, urlencoding::encode(&text)));
+    }
+    Ok(parts.join(";"))
+}
+
+fn decode_keys(token: &str) -> Result<Vec<Param>, Error> {
+    token
+        .split(';')
+        .map(|part| {
+            let (tag, text) = part.split_once(':').ok_or_else(bad_token)?;
+            let text = urlencoding::decode(text).map_err(|_| bad_token())?;
+            let param = match tag {
+                "i" => text.parse().map(Param::Int64).ok(),
+                "I" => text.parse().map(Param::Int128).ok(),
+                "s" => Some(Param::Text(text.into_owned())),
+                "d" => text.parse().map(Param::Date).ok(),
+                "t" => text.parse().map(Param::Time).ok(),
+                "ts" => chrono::NaiveDateTime::parse_from_str(&text, TIMESTAMP_FORMAT)
+                    .map(Param::TimeStamp)
+                    .ok(),
+                "f" => text.parse().map(Param::Double).ok(),
+                "m" => text.parse().map(Param::Decimal).ok(),
+                "b" => text.parse().map(Param::Boolean).ok(),
+                _ => None,
+            };
+            param.ok_or_else(bad_token)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::statement::ColumnInfo;
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+
+    fn rows(ids: &[i64]) -> Vec<Row> {
+        let columns = ["ID", "CREATED"].map(|name| ColumnInfo {
+            name: name.to_string(),
+            type_code: crate::SQL_TYPE_INT64,
+            sub_type: 0,
+            display_size: None,
+            internal_size: 8,
+            precision: None,
+            scale: 0,
+            nullable: false,
+            field_name: name.to_string(),
+            table_name: "T".to_string(),
+            owner_name: "SYSDBA".to_string(),
+        });
+        let created = chrono::NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_milli_opt(12, 30, 0, 250)
+            .unwrap();
+        let cells = ids
+            .iter()
+            .map(|id| vec![CellValue::Int64(*id), CellValue::TimeStamp(created)])
+            .collect::<VecDeque<_>>();
+        crate::row::Rows::new(cells, Arc::from(columns)).collect()
+    }
+
+    #[test]
+    fn test_keyset() {
+        let pager = Paginator::new("select id, created from t where a = ?;", &["created", "id"])
+            .page_size(2);
+        let (sql, params) = pager.query(None).unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM (select id, created from t where a = ?) PAGED ORDER BY \"CREATED\", \"ID\" ROWS ?"
+        );
+        assert_eq!(params, vec![Param::Int64(3)]);
+
+        let page = pager.page(rows(&[1, 2, 3]), None).unwrap();
+        assert_eq!(page.rows.len(), 2);
+        let token = page.next.unwrap();
+        assert_eq!(token, "ts:2024-05-01T12%3A30%3A00.250;i:2");
+
+        let (sql, params) = pager.query(Some(&token)).unwrap();
+        assert!(sql.ends_with(
+            "PAGED WHERE (\"CREATED\" > ?) OR (\"CREATED\" = ? AND \"ID\" > ?) ORDER BY \"CREATED\", \"ID\" ROWS ?"
+        ));
+        let created = chrono::NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_milli_opt(12, 30, 0, 250)
+            .unwrap();
+        assert_eq!(
+            params,
+            vec![
+                Param::TimeStamp(created),
+                Param::TimeStamp(created),
+                Param::Int64(2),
+                Param::Int64(3)
+            ]
+        );
+
+        // the last page has no token
+        assert!(pager.page(rows(&[3]), Some(&token)).unwrap().next.is_none());
+        assert!(pager.query(Some("x:1;i:2")).is_err());
+        assert!(pager.query(Some("i:2")).is_err());
+    }
+
+    #[test]
+    fn test_offset() {
+        let pager = Paginator::new("select id from t", &["id"])
+            .page_size(2)
+            .descending(true)
+            .mode(PageMode::Offset);
+        let page = pager.page(rows(&[5, 4, 3]), None).unwrap();
+        assert_eq!(page.next.as_deref(), Some("o:2"));
+        let (sql, params) = pager.query(Some("o:2")).unwrap();
+        assert!(sql.ends_with("ORDER BY \"ID\" DESC ROWS ? TO ?"));
+        assert_eq!(params, vec![Param::Int64(3), Param::Int64(5)]);
+        let page = pager.page(rows(&[3, 2, 1]), Some("o:2")).unwrap();
+        assert_eq!(page.next.as_deref(), Some("o:4"));
+        assert!(pager.query(Some("o:x")).is_err());
+    }
+}
@@ -42,7 +42,7 @@ pub enum Param {
     Double(f64),
     Blob(Vec<u8>),
     TimeStampTZ(chrono::DateTime<chrono_tz::Tz>),
-    // TimeTz(??),
+    TimeTz(chrono::NaiveTime, chrono_tz::Tz),
     Decimal(Decimal),
     Boolean(bool),
 }
@@ -53,6 +53,28 @@ impl From<&str> for Param {
     }
 }
 
+#[cfg(feature = "json")]
+impl From<serde_json::Value> for Param {
+    /// JSON text; `null` is bound as SQL NULL, the way a NULL column reads
+    fn from(v: serde_json::Value) -> Param {
+        match v {
+            serde_json::Value::Null => Param::Null,
+            v => Param::Text(v.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<&serde_json::Value> for Param {
+    /// JSON text; `null` is bound as SQL NULL, the way a NULL column reads
+    fn from(v: &serde_json::Value) -> Param {
+        match v {
+            serde_json::Value::Null => Param::Null,
+            v => Param::Text(v.to_string()),
+        }
+    }
+}
+
 impl From<String> for Param {
     fn from(v: String) -> Param {
         Param::Text(v)
@@ -125,6 +147,12 @@ impl From<chrono::DateTime<chrono_tz::Tz>> for Param {
     }
 }
 
+impl From<(chrono::NaiveTime, chrono_tz::Tz)> for Param {
+    fn from((t, tz): (chrono::NaiveTime, chrono_tz::Tz)) -> Param {
+        Param::TimeTz(t, tz)
+    }
+}
+
 impl From<Decimal> for Param {
     fn from(v: Decimal) -> Param {
         Param::Decimal(v)
@@ -137,8 +165,88 @@ impl From<bool> for Param {
     }
 }
 
+impl From<Vec<u8>> for Param {
+    fn from(v: Vec<u8>) -> Param {
+        Param::Blob(v)
+    }
+}
+
+impl<T: Into<Param>> From<Option<T>> for Param {
+    fn from(v: Option<T>) -> Param {
+        v.map_or(Param::Null, Into::into)
+    }
+}
+
+/// `HH:MM:SS.ffff`, in the 1/10000 second precision of Firebird
+fn time_literal(t: &chrono::NaiveTime) -> String {
+    // a leap second reads as 1 second or more of nanoseconds
+    let fraction = t.nanosecond() % 1_000_000_000 / 100_000;
+    format!("{}.{:04}", t.format("%H:%M:%S"), fraction)
+}
+
+fn timestamp_literal(dt: &chrono::NaiveDateTime) -> String {
+    format!("{} {}", dt.format("%Y-%m-%d"), time_literal(&dt.time()))
+}
+
+fn time_tz_text(t: &chrono::NaiveTime, tz: &chrono_tz::Tz) -> String {
+    format!("{} {}", time_literal(t), tz.name())
+}
+
+fn timestamp_tz_text(dt: &chrono::DateTime<chrono_tz::Tz>) -> String {
+    format!("{} {}", timestamp_literal(&dt.naive_local()), dt.timezone().name())
+}
+
+/// Encoded TIME/TIMESTAMP WITH TIME ZONE value, or `text` when it can't be
+/// encoded (a zone Firebird has no id for), sent for the server to report
+fn tz_value_or_text(
+    encoded: Result<Vec<u8>, ValueError>,
+    blr_type: u8,
+    text: impl FnOnce() -> String,
+) -> (Vec<u8>, Vec<u8>, bool) {
+    match encoded {
+        Ok(v) => (v, vec![blr_type], false),
+        Err(_) => Param::Text(text()).to_value_blr_isnull(),
+    }
+}
+
+impl Param {
+    /// Render the value as a Firebird SQL literal (for generated PSQL text)
+    pub fn to_sql_literal(&self) -> String {
+        match self {
+            Param::Null => "NULL".to_string(),
+            Param::Text(s) => utils::quote_literal(s),
+            Param::Short(n) => n.to_string(),
+            Param::Long(n) => n.to_string(),
+            Param::Int64(n) => n.to_string(),
+            Param::Int128(n) => n.to_string(),
+            Param::Time(t) => format!("TIME '{}'", time_literal(t)),
+            Param::Date(d) => format!("DATE '{}'", d.format("%Y-%m-%d")),
+            Param::TimeStamp(dt) => {
+                format!("TIMESTAMP '{}'", timestamp_literal(dt))
+            }
+            Param::Float(f) => format!("{:e}", f),
+            Param::Double(d) => format!("{:e}", d),
+            Param::Blob(b) => format!("x'{}'", hex::encode(b)),
+            Param::TimeStampTZ(dt) => format!("TIMESTAMP '{}'", timestamp_tz_text(dt)),
+            Param::TimeTz(t, tz) => format!("TIME '{}'", time_tz_text(t, tz)),
+            Param::Decimal(d) => d.to_string(),
+            Param::Boolean(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        }
+    }
+}
+
 pub trait ToSqlParam {
     fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool);
+
+    /// Fails for a value Firebird can't store, before it is sent
+    fn check_range(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// An ARRAY value, stored as a slice before the statement executes
+    fn array(&self) -> Option<&Array> {
+        None
+    }
 }
 
 impl ToSqlParam for Param {
@@ -219,8 +327,12 @@ impl ToSqlParam for Param {
                 value.write(&v).unwrap();
                 blr.write(&b).unwrap();
             }
-            Param::TimeStampTZ(_dt_tz) => {
-                // TODO:
+            Param::TimeStampTZ(dt) => {
+                let encoded = utils::date_time_tz_to_bytes(dt);
+                return tz_value_or_text(encoded, 29, || timestamp_tz_text(dt));
+            }
+            Param::TimeTz(t, tz) => {
+                return tz_value_or_text(utils::time_tz_to_bytes(t, tz), 28, || time_tz_text(t, tz));
             }
             Param::Decimal(d) => {
                 let s = d.to_string();
@@ -240,6 +352,16 @@ impl ToSqlParam for Param {
         }
         (value, blr, isnull)
     }
+
+    fn check_range(&self) -> Result<(), Error> {
+        let date = match self {
+            Param::Date(d) => *d,
+            Param::TimeStamp(dt) => dt.date(),
+            Param::TimeStampTZ(dt) => dt.naive_utc().date(),
+            _ => return Ok(()),
+        };
+        utils::check_date_range(&date).map_err(Error::ValueError)
+    }
 }
 
 macro_rules! to_sql_param(
@@ -253,17 +375,111 @@ macro_rules! to_sql_param(
     )
 );
 
+// dates are checked against Firebird's 0001-01-01..9999-12-31
+macro_rules! to_sql_param_date(
+    ($t:ty) => (
+        impl ToSqlParam for $t {
+            #[inline]
+            fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+                Param::from(*self).to_value_blr_isnull()
+            }
+
+            #[inline]
+            fn check_range(&self) -> Result<(), Error> {
+                Param::from(*self).check_range()
+            }
+        }
+    )
+);
+
 to_sql_param!(&str);
 to_sql_param!(i16);
 to_sql_param!(i32);
 to_sql_param!(i64);
 to_sql_param!(i128);
 to_sql_param!(chrono::NaiveTime);
-to_sql_param!(chrono::NaiveDate);
-to_sql_param!(chrono::NaiveDateTime);
+to_sql_param_date!(chrono::NaiveDate);
+to_sql_param_date!(chrono::NaiveDateTime);
 to_sql_param!(f32);
 to_sql_param!(f64);
 to_sql_param!(&[u8]);
-to_sql_param!(chrono::DateTime<chrono_tz::Tz>);
+to_sql_param_date!(chrono::DateTime<chrono_tz::Tz>);
+to_sql_param!((chrono::NaiveTime, chrono_tz::Tz));
 to_sql_param!(Decimal);
 to_sql_param!(bool);
+
+impl ToSqlParam for decfloat::DecFloat {
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        match decfloat::decimal_to_decimal64(&self.0) {
+            Some(b) => (b.to_vec(), vec![24], false),
+            None => (decfloat::decimal_to_decimal128(&self.0).to_vec(), vec![25], false),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl ToSqlParam for serde_json::Value {
+    #[inline]
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        Param::from(self).to_value_blr_isnull()
+    }
+}
+
+#[cfg(feature = "json")]
+impl ToSqlParam for &serde_json::Value {
+    #[inline]
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        Param::from(*self).to_value_blr_isnull()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_temporal_literals() {
+        let t = chrono::NaiveTime::from_hms_micro_opt(14, 5, 9, 123_456).unwrap();
+        let d = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(Param::Time(t).to_sql_literal(), "TIME '14:05:09.1234'");
+        assert_eq!(Param::Date(d).to_sql_literal(), "DATE '2024-02-29'");
+        assert_eq!(
+            Param::TimeStamp(d.and_time(t)).to_sql_literal(),
+            "TIMESTAMP '2024-02-29 14:05:09.1234'"
+        );
+        let midnight = chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+        assert_eq!(Param::Time(midnight).to_sql_literal(), "TIME '00:00:00.0000'");
+
+        let tz: chrono_tz::Tz = "Asia/Seoul".parse().unwrap();
+        let dt = tz.from_local_datetime(&d.and_time(t)).unwrap();
+        assert_eq!(
+            Param::TimeStampTZ(dt).to_sql_literal(),
+            "TIMESTAMP '2024-02-29 14:05:09.1234 Asia/Seoul'"
+        );
+        assert_eq!(
+            Param::TimeTz(t, tz).to_sql_literal(),
+            "TIME '14:05:09.1234 Asia/Seoul'"
+        );
+    }
+
+    #[test]
+    fn test_unknown_zone_sent_as_text() {
+        let t = chrono::NaiveTime::from_hms_micro_opt(14, 5, 9, 123_456).unwrap();
+        let tz: chrono_tz::Tz = "Asia/Seoul".parse().unwrap();
+        let (value, blr, isnull) = Param::TimeTz(t, tz).to_value_blr_isnull();
+        assert_eq!((value.len(), blr, isnull), (8, vec![28], false));
+
+        // every zone of chrono-tz has a Firebird id, so fail the encoding
+        let failed = Err(ValueError::new("time zone is unknown to Firebird"));
+        let (value, blr, isnull) = tz_value_or_text(failed, 28, || time_tz_text(&t, &tz));
+        assert_eq!(
+            (value, blr, isnull),
+            Param::Text("14:05:09.1234 Asia/Seoul".to_string()).to_value_blr_isnull()
+        );
+
+        let d = chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        let dt = tz.from_local_datetime(&d.and_time(t)).unwrap();
+        assert_eq!(timestamp_tz_text(&dt), "2024-02-29 14:05:09.1234 Asia/Seoul");
+    }
+}
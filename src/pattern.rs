@@ -0,0 +1,129 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Pattern matching predicates built from user input
+//!
+//! `%` and `_` in user input are wildcards of LIKE. `escape_like` escapes
+//! them, and the predicate helpers bind the input as a parameter so it is
+//! never spliced into the SQL text.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{like_predicate, LikeMatch};
+//!
+//! // matches "100%" literally, not every value starting with "100"
+//! let (predicate, param) = like_predicate("name", LikeMatch::Contains, "100%");
+//! let sql = format!("SELECT id FROM products WHERE {}", predicate);
+//! let rows = conn.prepare(&sql)?.query((param,))?;
+//! ```
+
+use super::param::Param;
+
+/// Escape character used by `escape_like` and the LIKE predicates
+pub const LIKE_ESCAPE: char = '\\';
+
+/// Where the input must appear in the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LikeMatch {
+    /// The whole value
+    Exact,
+    /// At the start (`STARTING WITH`, which can use an index)
+    Prefix,
+    /// At the end
+    Suffix,
+    /// Anywhere
+    Contains,
+}
+
+/// Escape the LIKE wildcards `%` and `_` and the escape character itself,
+/// for use with `ESCAPE '\'`
+pub fn escape_like(input: &str) -> String {
+    let mut escaped = String::with_capacity(input.len());
+    for c in input.chars() {
+        if c == '%' || c == '_' || c == LIKE_ESCAPE {
+            escaped.push(LIKE_ESCAPE);
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Case sensitive predicate matching `input` literally in `column`, and the
+/// parameter to bind to its `?`. The column is inserted as written.
+pub fn like_predicate(column: &str, kind: LikeMatch, input: &str) -> (String, Param) {
+    let (predicate, pattern) = match kind {
+        LikeMatch::Prefix => return (format!("{} STARTING WITH ?", column), Param::from(input)),
+        LikeMatch::Exact => ("LIKE", escape_like(input)),
+        LikeMatch::Suffix => ("LIKE", format!("%{}", escape_like(input))),
+        LikeMatch::Contains => ("LIKE", format!("%{}%", escape_like(input))),
+    };
+    (
+        format!("{} {} ? ESCAPE '{}'", column, predicate, LIKE_ESCAPE),
+        Param::from(pattern),
+    )
+}
+
+/// Case insensitive `CONTAINING` predicate and its parameter. CONTAINING
+/// has no wildcards, so the input is bound as is.
+pub fn containing_predicate(column: &str, input: &str) -> (String, Param) {
+    (format!("{} CONTAINING ?", column), Param::from(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(param: Param) -> String {
+        match param {
+            Param::Text(s) => s,
+            _ => panic!("not a text parameter"),
+        }
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("abc"), "abc");
+        assert_eq!(escape_like("100%"), "100\\%");
+        assert_eq!(escape_like("a_b\\c"), "a\\_b\\\\c");
+        assert_eq!(escape_like(""), "");
+    }
+
+    #[test]
+    fn test_like_predicate() {
+        let (sql, param) = like_predicate("name", LikeMatch::Contains, "5%_off");
+        assert_eq!(sql, "name LIKE ? ESCAPE '\\'");
+        assert_eq!(text(param), "%5\\%\\_off%");
+        let (sql, param) = like_predicate("name", LikeMatch::Suffix, "x");
+        assert_eq!(sql, "name LIKE ? ESCAPE '\\'");
+        assert_eq!(text(param), "%x");
+        let (_, param) = like_predicate("name", LikeMatch::Exact, "a%");
+        assert_eq!(text(param), "a\\%");
+        let (sql, param) = like_predicate("name", LikeMatch::Prefix, "a%");
+        assert_eq!(sql, "name STARTING WITH ?");
+        assert_eq!(text(param), "a%");
+        let (sql, param) = containing_predicate("name", "it's");
+        assert_eq!(sql, "name CONTAINING ?");
+        assert_eq!(text(param), "it's");
+    }
+}
@@ -0,0 +1,382 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Sans-io core of the wire protocol
+//!
+//! Server messages are decoded from bytes already received, with no socket
+//! involved: `decode_message` returns the next complete message and the
+//! number of bytes it took, or `None` while more input is needed. Nothing is
+//! consumed from an incomplete message, so the caller just appends what its
+//! transport reads next and tries again. `WireProtocol` and
+//! `WireProtocolAsync` both drive it this way over their channel's read
+//! buffer, and tests feed it recorded bytes in arbitrary pieces.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut received: Vec<u8> = Vec::new();
+//! let message = loop {
+//!     if let Some((message, n)) = protocol::decode_message(&received, &xsqlda)? {
+//!         received.drain(..n);
+//!         break message;
+//!     }
+//!     received.extend_from_slice(&transport.read_some()?);
+//! };
+//! ```
+
+use super::*;
+use crate::array;
+use crate::cellvalue::CellValue;
+use crate::error::{Error, FirebirdError, ValueError};
+use crate::xdr::{self, XdrRead};
+use crate::xsqlvar::XSQLVar;
+
+/// A message from the server
+#[derive(Debug, PartialEq)]
+pub(crate) enum Message {
+    Response(xdr::Response),
+    /// One op_fetch_response: a row, or `None` closing the batch with its
+    /// status (100 when the cursor is exhausted)
+    Fetch {
+        status: u32,
+        row: Option<Vec<CellValue>>,
+    },
+    /// op_sql_response, empty when the statement returned no row
+    SqlResponse(Vec<CellValue>),
+    /// op_slice, the elements of an array slice
+    Slice(Vec<CellValue>),
+    /// Any other op code; only the op code itself is consumed
+    Other(u32),
+}
+
+impl Message {
+    /// Handle, object id and data of an op_response, its status as an error
+    pub(crate) fn into_response(self) -> Result<(i32, Vec<u8>, Vec<u8>), Error> {
+        match self {
+            Message::Response(r) => match r.error() {
+                Some(e) => Err(Error::FirebirdError(e)),
+                None => Ok((r.handle, r.object_id, r.data)),
+            },
+            other => Err(other.unexpected()),
+        }
+    }
+
+    /// Error for a message the caller was not waiting for. An op_response
+    /// in place of the expected message usually carries the error.
+    pub(crate) fn unexpected(self) -> Error {
+        let opcode = match self {
+            Message::Response(r) => match r.error() {
+                Some(e) => return Error::FirebirdError(e),
+                None => OP_RESPONSE,
+            },
+            Message::Fetch { .. } => OP_FETCH_RESPONSE,
+            Message::SqlResponse(_) => OP_SQL_RESPONSE,
+            Message::Slice(_) => OP_SLICE,
+            Message::Other(opcode) => opcode,
+        };
+        Error::FirebirdError(FirebirdError::new(
+            &format!("Unexpected op code {}", opcode),
+            0,
+        ))
+    }
+}
+
+/// Next message in `buf` and the bytes it took, `None` when `buf` ends
+/// before the message does. op_dummy keepalives are skipped. `xsqlda`
+/// describes the row of an op_fetch_response or op_sql_response, or holds
+/// the element of an op_slice.
+pub(crate) fn decode_message(
+    buf: &[u8],
+    xsqlda: &[XSQLVar],
+) -> Result<Option<(Message, usize)>, Error> {
+    let mut input = Input {
+        buf,
+        pos: 0,
+        short: false,
+    };
+    match decode(&mut input, xsqlda) {
+        Ok(message) => Ok(Some((message, input.pos))),
+        Err(_) if input.short => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// received bytes, telling running out of input apart from malformed data
+struct Input<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    short: bool,
+}
+
+impl XdrRead for Input<'_> {
+    fn read_slice(&mut self, n: usize) -> Result<&[u8], Error> {
+        if n > self.buf.len() - self.pos {
+            self.short = true;
+            return Err(Error::ValueError(ValueError::new("incomplete message")));
+        }
+        let start = self.pos;
+        self.pos += n;
+        Ok(&self.buf[start..start + n])
+    }
+}
+
+fn decode(r: &mut Input, xsqlda: &[XSQLVar]) -> Result<Message, Error> {
+    let mut opcode = r.read_u32()?;
+    loop {
+        match opcode {
+            OP_DUMMY => {}
+            // events are delivered on the auxiliary connection (EventAlerter);
+            // one arriving here is dropped whole so the stream stays in step
+            OP_EVENT => {
+                r.read_u32()?; // database handle
+                r.read_opaque()?; // event counts
+                r.read_slice(12)?; // ast, argument, event id
+            }
+            _ => break,
+        }
+        opcode = r.read_u32()?;
+    }
+    Ok(match opcode {
+        OP_RESPONSE => Message::Response(xdr::decode_response(r)?),
+        OP_FETCH_RESPONSE => {
+            let header = xdr::decode_fetch_header(r)?;
+            let row = if header.count > 0 {
+                Some(decode_row(r, xsqlda)?)
+            } else {
+                None
+            };
+            Message::Fetch {
+                status: header.status,
+                row,
+            }
+        }
+        OP_SQL_RESPONSE => {
+            let count = r.read_u32()?;
+            Message::SqlResponse(if count > 0 {
+                decode_row(r, xsqlda)?
+            } else {
+                Vec::new()
+            })
+        }
+        OP_SLICE => {
+            r.read_u32()?; // slice length
+            let x = xsqlda
+                .first()
+                .ok_or_else(|| Error::ValueError(ValueError::new("op_slice without an element")))?;
+            let n = r.read_u32()? as usize / array::element_length(x);
+            let mut elements = Vec::with_capacity(n);
+            for _ in 0..n {
+                elements.push(decode_value(r, x)?);
+            }
+            Message::Slice(elements)
+        }
+        _ => Message::Other(opcode),
+    })
+}
+
+/// Null bitmap, then each non null value (length prefixed when variable)
+fn decode_row(r: &mut Input, xsqlda: &[XSQLVar]) -> Result<Vec<CellValue>, Error> {
+    let nulls = r.read_padded(xsqlda.len().div_ceil(8))?;
+    let mut row: Vec<CellValue> = Vec::with_capacity(xsqlda.len());
+    for (i, x) in xsqlda.iter().enumerate() {
+        if nulls[i / 8] & (1 << (i % 8)) != 0 {
+            row.push(CellValue::Null);
+            continue;
+        }
+        row.push(decode_value(r, x)?);
+    }
+    Ok(row)
+}
+
+/// A value, length prefixed when variable, then its padding
+fn decode_value(r: &mut Input, x: &XSQLVar) -> Result<CellValue, Error> {
+    let ln = if x.io_length() < 0 {
+        r.read_u32()? as usize
+    } else {
+        x.io_length() as usize
+    };
+    // decode in place from the received bytes
+    let value = x.value(r.read_slice(ln)?)?;
+    r.read_slice(xdr::pad_len(ln))?;
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    fn columns() -> Vec<XSQLVar> {
+        let mut long = XSQLVar::new();
+        long.sqltype = SQL_TYPE_LONG;
+        let mut varying = XSQLVar::new();
+        varying.sqltype = SQL_TYPE_VARYING;
+        vec![long, varying]
+    }
+
+    // op_fetch_response with one row (`None` for the closing message)
+    fn fetch(buf: &mut BytesMut, row: Option<(i32, Option<&str>)>) {
+        xdr::put_u32(buf, OP_FETCH_RESPONSE);
+        match row {
+            Some((n, s)) => {
+                xdr::put_u32(buf, 0);
+                xdr::put_u32(buf, 1);
+                // null bitmap, padded
+                buf.extend_from_slice(&[if s.is_none() { 2 } else { 0 }, 0, 0, 0]);
+                xdr::put_i32(buf, n);
+                if let Some(s) = s {
+                    xdr::put_str(buf, s);
+                }
+            }
+            None => {
+                xdr::put_u32(buf, 100);
+                xdr::put_u32(buf, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fetch_in_pieces() {
+        let xsqlda = columns();
+        let mut buf = BytesMut::new();
+        xdr::put_u32(&mut buf, OP_DUMMY);
+        fetch(&mut buf, Some((1, Some("abcde"))));
+        fetch(&mut buf, Some((2, None)));
+        fetch(&mut buf, None);
+
+        let expected = vec![
+            Message::Fetch {
+                status: 0,
+                row: Some(vec![
+                    CellValue::Long(1),
+                    CellValue::Varying("abcde".to_string()),
+                ]),
+            },
+            Message::Fetch {
+                status: 0,
+                row: Some(vec![CellValue::Long(2), CellValue::Null]),
+            },
+            Message::Fetch {
+                status: 100,
+                row: None,
+            },
+        ];
+
+        // whatever way the bytes arrive, the same messages come out
+        for chunk in [1, 3, 7, buf.len()] {
+            let mut received: Vec<u8> = Vec::new();
+            let mut messages = Vec::new();
+            for piece in buf.chunks(chunk) {
+                received.extend_from_slice(piece);
+                while let Some((message, n)) = decode_message(&received, &xsqlda).unwrap() {
+                    received.drain(..n);
+                    messages.push(message);
+                }
+            }
+            assert_eq!(messages, expected);
+            assert!(received.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_response() {
+        let mut buf = BytesMut::new();
+        xdr::put_u32(&mut buf, OP_RESPONSE);
+        xdr::put_i32(&mut buf, 5);
+        buf.extend_from_slice(&[0u8; 8]);
+        xdr::put_opaque(&mut buf, b"abc");
+        xdr::put_u32(&mut buf, ISC_ARG_GDS);
+        xdr::put_u32(&mut buf, 335544580);
+        xdr::put_u32(&mut buf, ISC_ARG_END);
+        xdr::put_u32(&mut buf, OP_SQL_RESPONSE);
+        xdr::put_u32(&mut buf, 0);
+
+        for n in 0..buf.len() - 8 {
+            assert!(decode_message(&buf[..n], &[]).unwrap().is_none());
+        }
+        let (message, n) = decode_message(&buf, &[]).unwrap().unwrap();
+        assert_eq!(n, buf.len() - 8);
+        match message.into_response() {
+            Err(Error::FirebirdError(e)) => assert!(e.has_gds_code(335544580)),
+            _ => panic!("error status expected"),
+        }
+        let (message, _) = decode_message(&buf[n..], &[]).unwrap().unwrap();
+        assert_eq!(message, Message::SqlResponse(Vec::new()));
+
+        let (message, n) = decode_message(&OP_EXIT.to_be_bytes(), &[])
+            .unwrap()
+            .unwrap();
+        assert_eq!((message, n), (Message::Other(OP_EXIT), 4));
+    }
+
+    #[test]
+    fn test_keepalive_and_event_skipped() {
+        let mut buf = BytesMut::new();
+        xdr::put_u32(&mut buf, OP_DUMMY);
+        xdr::put_u32(&mut buf, OP_EVENT);
+        xdr::put_u32(&mut buf, 0);
+        xdr::put_opaque(&mut buf, b"\x01\x05EVENT\x01\x00\x00\x00");
+        buf.extend_from_slice(&[0u8; 12]);
+        xdr::put_u32(&mut buf, OP_DUMMY);
+        xdr::put_u32(&mut buf, OP_SQL_RESPONSE);
+        xdr::put_u32(&mut buf, 0);
+
+        // incomplete until the whole response arrived
+        for n in 0..buf.len() {
+            assert!(decode_message(&buf[..n], &[]).unwrap().is_none());
+        }
+        let (message, n) = decode_message(&buf, &[]).unwrap().unwrap();
+        assert_eq!((message, n), (Message::SqlResponse(Vec::new()), buf.len()));
+    }
+
+    #[test]
+    fn test_slice() {
+        // VARCHAR(5) elements take 7 bytes each in the native slice
+        let mut varying = XSQLVar::new();
+        varying.sqltype = SQL_TYPE_VARYING;
+        varying.sqllen = 5;
+        let mut buf = BytesMut::new();
+        xdr::put_u32(&mut buf, OP_SLICE);
+        xdr::put_u32(&mut buf, 14);
+        xdr::put_u32(&mut buf, 14);
+        xdr::put_str(&mut buf, "ab");
+        xdr::put_str(&mut buf, "cdefg");
+
+        for n in 0..buf.len() {
+            assert!(
+                decode_message(&buf[..n], &[varying.clone()])
+                    .unwrap()
+                    .is_none()
+            );
+        }
+        let (message, n) = decode_message(&buf, &[varying]).unwrap().unwrap();
+        assert_eq!(n, buf.len());
+        assert_eq!(
+            message,
+            Message::Slice(vec![
+                CellValue::Varying("ab".to_string()),
+                CellValue::Varying("cdefg".to_string()),
+            ])
+        );
+    }
+}
@@ -0,0 +1,147 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Replication setup (Firebird 4.0+)
+//!
+//! Firebird replicates the tables of the database's publication
+//! (RDB$DEFAULT). These helpers run the `ALTER DATABASE` statements that
+//! enable it and choose its tables, and read RDB$PUBLICATIONS and
+//! RDB$PUBLICATION_TABLES, so replication can be set up from code. The
+//! replica itself and the journal are configured in replication.conf.
+//!
+//! # Example
+//!
+//! ```ignore
+//! conn.include_in_publication(&["customer", "orders"])?;
+//! conn.enable_publication()?;
+//! for p in conn.publications()? {
+//!     println!("{} active={} auto_enable={}", p.name, p.active, p.auto_enable);
+//! }
+//! ```
+
+use super::ddl::{object_name, quote};
+use super::error::Error;
+use super::row::{FromRow, Row};
+
+pub(crate) const PUBLICATIONS_SQL: &str = "SELECT RDB$PUBLICATION_NAME, RDB$OWNER_NAME,
+    RDB$ACTIVE_FLAG, RDB$AUTO_ENABLE FROM RDB$PUBLICATIONS ORDER BY RDB$PUBLICATION_NAME";
+
+pub(crate) const PUBLICATION_TABLES_SQL: &str = "SELECT RDB$PUBLICATION_NAME, RDB$TABLE_NAME
+    FROM RDB$PUBLICATION_TABLES ORDER BY RDB$PUBLICATION_NAME, RDB$TABLE_NAME";
+
+/// A row of RDB$PUBLICATIONS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Publication {
+    pub name: String,
+    pub owner: String,
+    /// Changes are being replicated
+    pub active: bool,
+    /// Tables created later join the publication (`INCLUDE ALL`)
+    pub auto_enable: bool,
+}
+
+/// A table of a publication (RDB$PUBLICATION_TABLES)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicationTable {
+    pub publication: String,
+    pub table: String,
+}
+
+impl FromRow for Publication {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        Ok(Publication {
+            name: row.get::<String>(0)?.trim_end().to_string(),
+            owner: row
+                .get_or::<String>(1, String::new())?
+                .trim_end()
+                .to_string(),
+            active: row.get_or::<i16>(2, 0)? != 0,
+            auto_enable: row.get_or::<i16>(3, 0)? != 0,
+        })
+    }
+}
+
+impl FromRow for PublicationTable {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        Ok(PublicationTable {
+            publication: row.get::<String>(0)?.trim_end().to_string(),
+            table: row.get::<String>(1)?.trim_end().to_string(),
+        })
+    }
+}
+
+/// `ALTER DATABASE INCLUDE/EXCLUDE` of `tables`, or of all tables if empty
+pub(crate) fn publication_tables_sql(include: bool, tables: &[&str]) -> String {
+    let (verb, preposition) = if include {
+        ("INCLUDE", "TO")
+    } else {
+        ("EXCLUDE", "FROM")
+    };
+    let target = if tables.is_empty() {
+        "ALL".to_string()
+    } else {
+        let names: Vec<String> = tables.iter().map(|t| quote(&object_name(t))).collect();
+        format!("TABLE {}", names.join(", "))
+    };
+    format!(
+        "ALTER DATABASE {} {} {} PUBLICATION",
+        verb, target, preposition
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cellvalue::CellValue;
+
+    #[test]
+    fn test_publication_tables_sql() {
+        assert_eq!(
+            publication_tables_sql(true, &["customer", "\"Orders\""]),
+            "ALTER DATABASE INCLUDE TABLE \"CUSTOMER\", \"Orders\" TO PUBLICATION"
+        );
+        assert_eq!(
+            publication_tables_sql(false, &[]),
+            "ALTER DATABASE EXCLUDE ALL FROM PUBLICATION"
+        );
+    }
+
+    #[test]
+    fn test_from_row() {
+        let row = Row::from_cells(vec![
+            CellValue::Text("RDB$DEFAULT    ".to_string()),
+            CellValue::Text("SYSDBA ".to_string()),
+            CellValue::Short(1),
+            CellValue::Null,
+        ]);
+        assert_eq!(
+            Publication::from_row(&row).unwrap(),
+            Publication {
+                name: "RDB$DEFAULT".to_string(),
+                owner: "SYSDBA".to_string(),
+                active: true,
+                auto_enable: false,
+            }
+        );
+    }
+}
@@ -0,0 +1,211 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Server handles held by a connection
+//!
+//! The wire protocol counts the statement, transaction and blob handles it
+//! asks the server for and gives back, so `Connection::resource_stats`
+//! shows what a connection holds and the highest counts it reached. The
+//! connection's own autocommit transaction is one of the transactions.
+//!
+//! With a `LeakDetection` set, a warning is logged (target
+//! `firebirust::leaks`) when a count goes over its limit and when a
+//! transaction is dropped without commit or rollback, which points at the
+//! code holding on to handles before the server runs short of them.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::LeakDetection;
+//!
+//! conn.set_leak_detection(Some(LeakDetection::new().max_statements(50)));
+//! run_batch(&conn)?;
+//! let stats = conn.resource_stats();
+//! assert_eq!(stats.statements, 0, "statements left open: {:?}", stats);
+//! ```
+
+/// Handles of a connection, see `Connection::resource_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceStats {
+    /// Statements allocated and not dropped
+    pub statements: u64,
+    /// Transactions started and not committed or rolled back
+    pub transactions: u64,
+    /// Blobs opened or created and not closed
+    pub blobs: u64,
+    pub peak_statements: u64,
+    pub peak_transactions: u64,
+    pub peak_blobs: u64,
+    /// Transactions rolled back because they were dropped unfinished
+    pub implicit_rollbacks: u64,
+}
+
+/// Limits above which open handles are reported as a probable leak
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LeakDetection {
+    max_statements: u64,
+    max_transactions: u64,
+    max_blobs: u64,
+}
+
+impl Default for LeakDetection {
+    fn default() -> Self {
+        Self {
+            max_statements: 100,
+            max_transactions: 10,
+            max_blobs: 10,
+        }
+    }
+}
+
+impl LeakDetection {
+    /// Limits of 100 statements, 10 transactions and 10 blobs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_statements(mut self, max: u64) -> Self {
+        self.max_statements = max;
+        self
+    }
+
+    /// Transactions, the connection's own autocommit transaction included
+    pub fn max_transactions(mut self, max: u64) -> Self {
+        self.max_transactions = max;
+        self
+    }
+
+    pub fn max_blobs(mut self, max: u64) -> Self {
+        self.max_blobs = max;
+        self
+    }
+}
+
+/// Kind of server handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Resource {
+    Statement,
+    Transaction,
+    Blob,
+}
+
+/// Counters and leak detection of one connection
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ResourceTracker {
+    stats: ResourceStats,
+    detection: Option<LeakDetection>,
+}
+
+impl ResourceTracker {
+    pub(crate) fn stats(&self) -> ResourceStats {
+        self.stats
+    }
+
+    pub(crate) fn set_detection(&mut self, detection: Option<LeakDetection>) {
+        self.detection = detection;
+    }
+
+    pub(crate) fn detection(&self) -> Option<&LeakDetection> {
+        self.detection.as_ref()
+    }
+
+    pub(crate) fn opened(&mut self, resource: Resource) {
+        let stats = &mut self.stats;
+        let (count, peak, max, name) = match resource {
+            Resource::Statement => (
+                &mut stats.statements,
+                &mut stats.peak_statements,
+                self.detection.map(|d| d.max_statements),
+                "statements",
+            ),
+            Resource::Transaction => (
+                &mut stats.transactions,
+                &mut stats.peak_transactions,
+                self.detection.map(|d| d.max_transactions),
+                "transactions",
+            ),
+            Resource::Blob => (
+                &mut stats.blobs,
+                &mut stats.peak_blobs,
+                self.detection.map(|d| d.max_blobs),
+                "blobs",
+            ),
+        };
+        *count += 1;
+        *peak = (*peak).max(*count);
+        // once per crossing, not on every further handle
+        if let Some(max) = max
+            && *count == max + 1
+        {
+            log::warn!(target: "firebirust::leaks", "{} open {} (limit {}): handles are not being released", *count, name, max);
+        }
+    }
+
+    pub(crate) fn closed(&mut self, resource: Resource) {
+        let count = match resource {
+            Resource::Statement => &mut self.stats.statements,
+            Resource::Transaction => &mut self.stats.transactions,
+            Resource::Blob => &mut self.stats.blobs,
+        };
+        *count = count.saturating_sub(1);
+    }
+
+    /// A transaction dropped without commit or rollback is being rolled back
+    pub(crate) fn implicit_rollback(&mut self) {
+        self.stats.implicit_rollbacks += 1;
+        if self.detection.is_some() {
+            log::warn!(target: "firebirust::leaks", "transaction dropped without commit or rollback, rolled back");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_tracker() {
+        let mut tracker = ResourceTracker::default();
+        tracker.set_detection(Some(LeakDetection::new().max_statements(1)));
+        tracker.opened(Resource::Transaction);
+        tracker.opened(Resource::Statement);
+        tracker.opened(Resource::Statement);
+        tracker.closed(Resource::Statement);
+        tracker.opened(Resource::Blob);
+        tracker.closed(Resource::Blob);
+        tracker.closed(Resource::Blob);
+        tracker.implicit_rollback();
+        assert_eq!(
+            tracker.stats(),
+            ResourceStats {
+                statements: 1,
+                transactions: 1,
+                blobs: 0,
+                peak_statements: 2,
+                peak_transactions: 1,
+                peak_blobs: 1,
+                implicit_rollbacks: 1,
+            }
+        );
+    }
+}
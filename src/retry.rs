@@ -0,0 +1,417 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Retry with exponential backoff
+//!
+//! `classify` sorts errors by what a retry needs: contention (deadlock,
+//! update or lock conflict, lock timeout) only needs the transaction run
+//! again, a lost connection or a database shutting down needs a new
+//! connection, anything else would fail again. `RetryPolicy` sets how often
+//! and how long to wait between attempts; `retry` and `retry_async` run an
+//! operation under it. `Connection::retry_transaction` reruns a whole
+//! transaction on contention and the pool retries opening connections
+//! with the policy of `PoolOptions::connect_retry`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{RetryPolicy, retry};
+//!
+//! let policy = RetryPolicy::new()
+//!     .max_attempts(5)
+//!     .backoff(Duration::from_millis(50), Duration::from_secs(2))
+//!     .jitter(0.5);
+//!
+//! // a new connection for each attempt
+//! let conn = retry(&policy, || Connection::connect(url))?;
+//!
+//! // the transaction is rolled back and run again on contention
+//! conn.retry_transaction(&policy, |tx| {
+//!     tx.execute("UPDATE stock SET qty = qty - 1 WHERE id = ?", (id,))
+//! })?;
+//! ```
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::error::{Error, ValueError};
+use super::runtime::{self, Runtime};
+
+// gds codes of a lost connection or a database going down
+const ISC_NETWORK_ERROR: u32 = 335544721;
+const ISC_NET_READ_ERR: u32 = 335544726;
+const ISC_NET_WRITE_ERR: u32 = 335544727;
+const ISC_LOST_DB_CONNECTION: u32 = 335544741;
+const ISC_CONN_LOST: u32 = 335544648;
+const ISC_SHUTINPROG: u32 = 335544506;
+const ISC_SHUTDOWN: u32 = 335544528;
+const ISC_ATT_SHUTDOWN: u32 = 335544856;
+
+/// What retrying an error needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryClass {
+    /// Data contention: roll back and run the transaction again
+    Transaction,
+    /// Connection lost, or database or server shutting down: retry on a new connection
+    Connection,
+    /// Retrying would fail the same way
+    Permanent,
+}
+
+/// Default classification of `err`
+///
+/// `Error::Timeout` is permanent: the request was cancelled after running
+/// for the whole timeout and would most likely time out again.
+pub fn classify(err: &Error) -> RetryClass {
+    match err {
+        Error::ConnectionLost | Error::IoError(_) => RetryClass::Connection,
+        Error::PoolTimeout => RetryClass::Connection,
+        Error::FirebirdError(e) if e.contention().is_some() => RetryClass::Transaction,
+        Error::FirebirdError(e)
+            if [
+                ISC_NETWORK_ERROR,
+                ISC_NET_READ_ERR,
+                ISC_NET_WRITE_ERR,
+                ISC_LOST_DB_CONNECTION,
+                ISC_CONN_LOST,
+                ISC_SHUTINPROG,
+                ISC_SHUTDOWN,
+                ISC_ATT_SHUTDOWN,
+            ]
+            .iter()
+            .any(|code| e.has_gds_code(*code)) =>
+        {
+            RetryClass::Connection
+        }
+        _ => RetryClass::Permanent,
+    }
+}
+
+/// Attempts, backoff and error classification of a retried operation
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+    jitter: f64,
+    classifier: fn(&Error) -> RetryClass,
+    runtime: Option<Arc<dyn Runtime>>,
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+            classifier: classify,
+            runtime: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 3 attempts, 100ms backoff doubled up to 10s, 20% jitter, `classify`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attempts in total, the first one included (at least 1)
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, and the longest delay
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Growth of the delay per retry (default: 2.0)
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Each delay varies randomly by up to this fraction, 0.0 to 1.0, so
+    /// clients failing together don't retry together (default: 0.2)
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Classification of errors instead of `classify`
+    pub fn classifier(mut self, classifier: fn(&Error) -> RetryClass) -> Self {
+        self.classifier = classifier;
+        self
+    }
+
+    /// Async runtime `retry_async` waits on instead of the one selected by
+    /// the `rt-*` features
+    pub fn runtime<R: Runtime + 'static>(mut self, runtime: R) -> Self {
+        self.runtime = Some(Arc::new(runtime));
+        self
+    }
+
+    pub fn classify(&self, err: &Error) -> RetryClass {
+        (self.classifier)(err)
+    }
+
+    /// Delay before retry number `retry` (1 for the first), without jitter
+    pub fn base_delay(&self, retry: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .powi(retry.saturating_sub(1).min(i32::MAX as u32) as i32);
+        let secs = self.initial_backoff.as_secs_f64() * factor;
+        Duration::try_from_secs_f64(secs)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    /// Delay before retry number `retry`, with jitter
+    pub fn delay(&self, retry: u32) -> Duration {
+        let base = self.base_delay(retry);
+        if self.jitter == 0.0 {
+            return base;
+        }
+        let spread = self.jitter * (2.0 * rand::random::<f64>() - 1.0);
+        base.mul_f64(1.0 + spread)
+    }
+
+    /// Delay before retrying after `attempt` failed with `err`, `None` if
+    /// the error is not in one of `classes` or no attempt is left
+    fn next_delay(&self, attempt: u32, err: &Error, classes: &[RetryClass]) -> Option<Duration> {
+        if attempt >= self.max_attempts || !classes.contains(&self.classify(err)) {
+            return None;
+        }
+        Some(self.delay(attempt))
+    }
+}
+
+const RETRYABLE: &[RetryClass] = &[RetryClass::Transaction, RetryClass::Connection];
+
+/// Run `op` until it succeeds, fails with an error the policy does not
+/// retry, or runs out of attempts; the last error is returned
+pub fn retry<T, F>(policy: &RetryPolicy, op: F) -> Result<T, Error>
+where
+    F: FnMut() -> Result<T, Error>,
+{
+    retry_on(policy, RETRYABLE, op)
+}
+
+/// `retry` for errors of `classes` only
+pub(crate) fn retry_on<T, F>(
+    policy: &RetryPolicy,
+    classes: &[RetryClass],
+    mut op: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Result<T, Error>,
+{
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(e) => match policy.next_delay(attempt, &e, classes) {
+                Some(delay) => {
+                    log::debug!(target: "firebirust::retry", "attempt {} failed, retrying in {:?}: {:?}", attempt, delay, e);
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                None => return Err(e),
+            },
+            result => return result,
+        }
+    }
+}
+
+/// `retry` for async operations, waiting on `RetryPolicy::runtime` or the
+/// runtime selected by the `rt-*` features
+pub async fn retry_async<T, F>(policy: &RetryPolicy, op: F) -> Result<T, Error>
+where
+    F: AsyncFnMut() -> Result<T, Error>,
+{
+    let runtime = policy
+        .runtime
+        .clone()
+        .or_else(runtime::default_runtime)
+        .ok_or_else(|| {
+            Error::ValueError(ValueError::new(
+                "no async runtime: enable rt-async-std or rt-tokio, or set RetryPolicy::runtime",
+            ))
+        })?;
+    retry_async_on(&runtime, policy, RETRYABLE, op).await
+}
+
+/// `retry_async` on `runtime` for errors of `classes` only
+pub(crate) async fn retry_async_on<T, F>(
+    runtime: &Arc<dyn Runtime>,
+    policy: &RetryPolicy,
+    classes: &[RetryClass],
+    mut op: F,
+) -> Result<T, Error>
+where
+    F: AsyncFnMut() -> Result<T, Error>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) => match policy.next_delay(attempt, &e, classes) {
+                Some(delay) => {
+                    log::debug!(target: "firebirust::retry", "attempt {} failed, retrying in {:?}: {:?}", attempt, delay, e);
+                    runtime.sleep(delay).await;
+                    attempt += 1;
+                }
+                None => return Err(e),
+            },
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{FirebirdError, ISC_DEADLOCK, ISC_UPDATE_CONFLICT};
+
+    fn fb_error(codes: Vec<u32>) -> Error {
+        Error::FirebirdError(FirebirdError::with_gds_codes("error", -901, codes))
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(&Error::ConnectionLost), RetryClass::Connection);
+        assert_eq!(classify(&Error::Timeout), RetryClass::Permanent);
+        assert_eq!(
+            classify(&fb_error(vec![ISC_DEADLOCK, ISC_UPDATE_CONFLICT])),
+            RetryClass::Transaction
+        );
+        assert_eq!(
+            classify(&fb_error(vec![ISC_SHUTINPROG])),
+            RetryClass::Connection
+        );
+        assert_eq!(
+            classify(&fb_error(vec![ISC_NETWORK_ERROR])),
+            RetryClass::Connection
+        );
+        assert_eq!(classify(&fb_error(vec![335544569])), RetryClass::Permanent);
+        assert_eq!(classify(&Error::StaleRow), RetryClass::Permanent);
+    }
+
+    #[test]
+    fn test_delay() {
+        let policy = RetryPolicy::new()
+            .backoff(Duration::from_millis(100), Duration::from_millis(500))
+            .jitter(0.0);
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(3), Duration::from_millis(400));
+        assert_eq!(policy.delay(4), Duration::from_millis(500));
+        assert_eq!(policy.delay(1000), Duration::from_millis(500));
+
+        let policy = policy.jitter(0.5);
+        for _ in 0..100 {
+            let d = policy.delay(2);
+            assert!(d >= Duration::from_millis(100) && d <= Duration::from_millis(300));
+        }
+    }
+
+    #[test]
+    fn test_retry() {
+        let policy = RetryPolicy::new()
+            .max_attempts(3)
+            .backoff(Duration::ZERO, Duration::ZERO);
+
+        let mut calls = 0;
+        let result = retry(&policy, || {
+            calls += 1;
+            if calls < 3 {
+                Err(Error::ConnectionLost)
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        // out of attempts: the last error
+        let mut calls = 0;
+        let result: Result<(), Error> = retry(&policy, || {
+            calls += 1;
+            Err(fb_error(vec![ISC_DEADLOCK]))
+        });
+        assert!(result.unwrap_err().contention().is_some());
+        assert_eq!(calls, 3);
+
+        // not retried
+        let mut calls = 0;
+        let result: Result<(), Error> = retry(&policy, || {
+            calls += 1;
+            Err(Error::StaleRow)
+        });
+        assert!(matches!(result, Err(Error::StaleRow)));
+        assert_eq!(calls, 1);
+
+        // contention only
+        let mut calls = 0;
+        let result: Result<(), Error> = retry_on(&policy, &[RetryClass::Transaction], || {
+            calls += 1;
+            Err(Error::ConnectionLost)
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[cfg(feature = "rt-async-std")]
+    #[test]
+    fn test_retry_async() {
+        // rt-tokio would be the default runtime, and needs a tokio reactor
+        let policy = RetryPolicy::new()
+            .backoff(Duration::from_millis(1), Duration::from_millis(1))
+            .runtime(runtime::AsyncStdRuntime);
+        let mut calls = 0;
+        let result = async_std::task::block_on(retry_async(&policy, async || {
+            calls += 1;
+            if calls < 2 {
+                Err(Error::IoError(std::io::ErrorKind::ConnectionRefused.into()))
+            } else {
+                Ok(calls)
+            }
+        }));
+        assert_eq!(result.unwrap(), 2);
+    }
+}
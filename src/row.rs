@@ -20,17 +20,61 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 use super::cellvalue::{CellValue, CellValueToVal};
-use super::error::{Error, ValueError};
+use super::checksum;
+use super::columnar::{ColumnKind, ColumnarBatch};
+use super::error::{ConversionError, Error, ValueError};
+use super::statement::ColumnInfo;
 use std::collections::VecDeque;
 use std::result::Result;
+use std::sync::Arc;
 
 pub struct Rows {
     rows: VecDeque<Vec<CellValue>>,
+    columns: Arc<[ColumnInfo]>,
+    legacy_bool: bool,
+    strict: bool,
+    // rows handed out so far
+    count: usize,
 }
 
 impl Rows {
-    pub(crate) fn new(rows: VecDeque<Vec<CellValue>>) -> Rows {
-        Rows { rows }
+    pub(crate) fn new(rows: VecDeque<Vec<CellValue>>, columns: Arc<[ColumnInfo]>) -> Rows {
+        Rows {
+            rows,
+            columns,
+            legacy_bool: false,
+            strict: false,
+            count: 0,
+        }
+    }
+
+    /// Metadata of the columns, shared with the statement that produced the rows
+    pub fn columns(&self) -> &[ColumnInfo] {
+        &self.columns
+    }
+
+    /// Let `Row::get::<bool>()` read CHAR(1) / SMALLINT columns
+    pub(crate) fn legacy_bool(mut self, legacy_bool: bool) -> Rows {
+        self.legacy_bool = legacy_bool;
+        self
+    }
+
+    /// Let `Row::get` fail instead of truncating or rounding a value
+    pub(crate) fn strict(mut self, strict: bool) -> Rows {
+        self.strict = strict;
+        self
+    }
+
+    /// The remaining rows as one vector per column, decoded as `schema`
+    /// says (one `ColumnKind` per column) without building a `Row` for each
+    pub fn fetch_columnar(self, schema: &[ColumnKind]) -> Result<ColumnarBatch, Error> {
+        ColumnarBatch::decode(
+            self.rows,
+            &self.columns,
+            schema,
+            self.legacy_bool,
+            self.count,
+        )
     }
 
     pub fn mapped<F, B>(self, f: F) -> MappedRows<F>
@@ -46,7 +90,13 @@ impl Iterator for Rows {
 
     fn next(&mut self) -> Option<Row> {
         match self.rows.pop_front() {
-            Some(row) => Some(Row { row: row }),
+            Some(row) => {
+                self.count += 1;
+                Some(
+                    Row::new(row, self.columns.clone(), self.legacy_bool, self.count)
+                        .strict(self.strict),
+                )
+            }
             None => None,
         }
     }
@@ -54,21 +104,165 @@ impl Iterator for Rows {
 
 pub struct Row {
     row: Vec<CellValue>,
+    columns: Arc<[ColumnInfo]>,
+    legacy_bool: bool,
+    strict: bool,
+    number: usize,
 }
 
 impl Row {
+    pub(crate) fn new(
+        row: Vec<CellValue>,
+        columns: Arc<[ColumnInfo]>,
+        legacy_bool: bool,
+        number: usize,
+    ) -> Row {
+        Row {
+            row,
+            columns,
+            legacy_bool,
+            strict: false,
+            number,
+        }
+    }
+
+    /// Let `get` fail instead of truncating or rounding a value
+    pub(crate) fn strict(mut self, strict: bool) -> Row {
+        self.strict = strict;
+        self
+    }
+
+    #[cfg(test)]
+    pub(crate) fn from_cells(row: Vec<CellValue>) -> Row {
+        Row {
+            row,
+            columns: Arc::new([]),
+            legacy_bool: false,
+            strict: false,
+            number: 1,
+        }
+    }
+
+    /// Metadata of the columns of the row
+    pub fn columns(&self) -> &[ColumnInfo] {
+        &self.columns
+    }
+
+    /// Value of column `idx` as received
+    pub(crate) fn cell(&self, idx: usize) -> Option<&CellValue> {
+        self.row.get(idx)
+    }
+
+    /// Row number in the result set, from 1
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
+    /// Feed the row to `hasher` in a canonical form, column by column in
+    /// the order selected, so equal values of different types (BIGINT 5
+    /// and NUMERIC 5.00, CHAR and VARCHAR) hash alike
+    pub fn digest<D: digest::Update>(&self, hasher: &mut D) {
+        for cell in &self.row {
+            checksum::digest_cell(cell, hasher);
+        }
+    }
+
+    /// Column `idx` as `T`. A value that does not convert fails with
+    /// `Error::Conversion`, naming the column, its SQL type and the row.
+    /// With `strict_types` enabled, so does a value that would be
+    /// truncated or rounded on the way.
     pub fn get<T>(&self, idx: usize) -> Result<T, Error>
     where
         CellValue: CellValueToVal<T>,
     {
         if let Some(cell_value) = self.row.get(idx) {
-            cell_value.clone().to_val()
+            let checked = if self.strict {
+                CellValueToVal::<T>::check_strict(cell_value)
+            } else {
+                Ok(())
+            };
+            let value = checked.and_then(|_| {
+                if self.legacy_bool {
+                    cell_value.clone().to_val_legacy_bool()
+                } else {
+                    cell_value.clone().to_val()
+                }
+            });
+            value.map_err(|e| match e {
+                Error::ValueError(_) if matches!(cell_value, CellValue::Null) => self
+                    .conversion_error::<T>(idx, "value is NULL, use get_nullable or get_or".to_string())
+                    .into(),
+                Error::ValueError(e) => self.conversion_error::<T>(idx, e.message).into(),
+                e => e,
+            })
         } else {
             Err(Error::ValueError(ValueError::new(
                 "This index doesn't exists",
             )))
         }
     }
+
+    /// Column `idx` as `T`, `None` when it is NULL. A value of the wrong
+    /// type still fails with `Error::Conversion`.
+    pub fn get_nullable<T>(&self, idx: usize) -> Result<Option<T>, Error>
+    where
+        CellValue: CellValueToVal<T>,
+    {
+        match self.row.get(idx) {
+            Some(CellValue::Null) => Ok(None),
+            _ => self.get(idx).map(Some),
+        }
+    }
+
+    /// Column `idx` as `T`, `default` when it is NULL. A value of the wrong
+    /// type still fails with `Error::Conversion`.
+    pub fn get_or<T>(&self, idx: usize, default: T) -> Result<T, Error>
+    where
+        CellValue: CellValueToVal<T>,
+    {
+        Ok(self.get_nullable(idx)?.unwrap_or(default))
+    }
+
+    fn conversion_error<T>(&self, idx: usize, message: String) -> ConversionError {
+        let column = self.columns.get(idx);
+        ConversionError {
+            column: column.map(|c| c.name.clone()).unwrap_or_default(),
+            index: idx,
+            sql_type: column.map(|c| c.type_name()).unwrap_or_default(),
+            rust_type: std::any::type_name::<T>(),
+            row: self.number,
+            message,
+        }
+    }
+
+    /// Deserialize a VARCHAR or BLOB SUB_TYPE TEXT column holding JSON.
+    /// NULL deserializes as JSON `null`, so use `Option<T>` for nullable columns.
+    #[cfg(feature = "json")]
+    pub fn get_json<T: serde::de::DeserializeOwned>(&self, idx: usize) -> Result<T, Error> {
+        let result = match self.row.get(idx) {
+            Some(CellValue::Text(s)) | Some(CellValue::Varying(s)) => serde_json::from_str(s),
+            Some(CellValue::BlobText(b)) | Some(CellValue::BlobBinary(b)) => {
+                serde_json::from_slice(b)
+            }
+            Some(CellValue::Null) => serde_json::from_value(serde_json::Value::Null),
+            Some(_) => {
+                return Err(Error::ValueError(ValueError::new(
+                    "Can't convert to json: not a text column",
+                )));
+            }
+            None => {
+                return Err(Error::ValueError(ValueError::new(
+                    "This index doesn't exists",
+                )));
+            }
+        };
+        result.map_err(|e| Error::ValueError(ValueError::new(&format!("Invalid json: {}", e))))
+    }
+}
+
+/// Build a value from a whole row, e.g. `stmt.query_map((), T::from_row)`
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self, Error>;
 }
 
 pub struct MappedRows<F> {
@@ -76,6 +270,13 @@ pub struct MappedRows<F> {
     map: F,
 }
 
+impl<F> MappedRows<F> {
+    /// Metadata of the columns of the underlying rows
+    pub fn columns(&self) -> &[ColumnInfo] {
+        self.rows.columns()
+    }
+}
+
 impl<T, F> Iterator for MappedRows<F>
 where
     F: FnMut(&Row) -> Result<T, Error>,
@@ -91,3 +292,135 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversion_error() {
+        let column = ColumnInfo {
+            name: "NAME".to_string(),
+            type_code: crate::SQL_TYPE_VARYING,
+            sub_type: 4,
+            display_size: Some(20),
+            internal_size: 20,
+            precision: None,
+            scale: 0,
+            nullable: true,
+            field_name: "NAME".to_string(),
+            table_name: "T".to_string(),
+            owner_name: "SYSDBA".to_string(),
+        };
+        let mut rows = Rows::new(
+            VecDeque::from(vec![
+                vec![CellValue::Varying("1".to_string())],
+                vec![CellValue::Varying("one".to_string())],
+            ]),
+            Arc::new([column]),
+        );
+        rows.next().unwrap();
+        let row = rows.next().unwrap();
+        assert_eq!(row.number(), 2);
+        match row.get::<i32>(0) {
+            Err(Error::Conversion(e)) => {
+                assert_eq!((e.column.as_str(), e.index, e.row), ("NAME", 0, 2));
+                assert_eq!(e.sql_type, "VARCHAR CHARACTER SET UTF8");
+                assert_eq!(e.rust_type, "i32");
+                assert!(e.to_string().starts_with("row 2, column 0 `NAME' VARCHAR"));
+            }
+            _ => panic!("conversion should fail"),
+        }
+        assert!(matches!(row.get::<i32>(1), Err(Error::ValueError(_))));
+    }
+
+    #[test]
+    fn test_get_nullable() {
+        let row = Row::from_cells(vec![
+            CellValue::Long(7),
+            CellValue::Null,
+            CellValue::Varying("seven".to_string()),
+        ]);
+        assert_eq!(row.get_nullable::<i32>(0).unwrap(), Some(7));
+        assert_eq!(row.get_nullable::<i32>(1).unwrap(), None);
+        assert!(matches!(row.get_nullable::<i32>(2), Err(Error::Conversion(_))));
+        assert_eq!(row.get_or(0, 0i32).unwrap(), 7);
+        assert_eq!(row.get_or(1, -1i32).unwrap(), -1);
+        assert!(row.get_or(2, 0i32).is_err());
+        match row.get::<i32>(1) {
+            Err(Error::Conversion(e)) => assert!(e.message.contains("NULL")),
+            _ => panic!("NULL is not an i32"),
+        }
+    }
+
+    #[test]
+    fn test_get_i128() {
+        let row = Row::from_cells(vec![
+            CellValue::Int128(i128::MAX),
+            CellValue::Int64(-7),
+            CellValue::Decimal(rust_decimal::Decimal::new(-1234, 2)),
+            CellValue::Int128(1 << 100),
+        ]);
+        assert_eq!(row.get::<i128>(0).unwrap(), i128::MAX);
+        assert_eq!(row.get::<i128>(1).unwrap(), -7);
+        assert_eq!(row.get::<i128>(2).unwrap(), -12);
+        assert_eq!(row.get::<f64>(3).unwrap(), 2f64.powi(100));
+        assert!(matches!(row.strict(true).get::<i128>(2), Err(Error::Conversion(_))));
+    }
+
+    #[test]
+    fn test_strict() {
+        let cells = vec![
+            CellValue::Int64(5_000_000_000),
+            CellValue::Decimal(rust_decimal::Decimal::new(1250, 2)),
+            CellValue::Int64(42),
+            CellValue::Int128(1 << 70),
+            CellValue::Null,
+            CellValue::Short(-3),
+        ];
+        let row = Row::from_cells(cells.clone());
+        assert_eq!(row.get::<i32>(0).unwrap(), 5_000_000_000i64 as i32);
+        assert_eq!(row.get::<i32>(1).unwrap(), 12);
+
+        let row = Row::from_cells(cells).strict(true);
+        match row.get::<i32>(0) {
+            Err(Error::Conversion(e)) => {
+                assert_eq!(e.rust_type, "i32");
+                assert!(e.message.contains("out of range"));
+            }
+            _ => panic!("5000000000 is not an i32"),
+        }
+        assert_eq!(row.get::<i64>(0).unwrap(), 5_000_000_000);
+        match row.get::<i64>(1) {
+            Err(Error::Conversion(e)) => assert!(e.message.contains("scale 2")),
+            _ => panic!("12.50 would be rounded"),
+        }
+        assert_eq!(
+            row.get::<rust_decimal::Decimal>(1).unwrap(),
+            rust_decimal::Decimal::new(1250, 2)
+        );
+        assert_eq!(row.get::<i16>(2).unwrap(), 42);
+        assert!(matches!(row.get::<i64>(3), Err(Error::Conversion(_))));
+        assert_eq!(row.get::<Option<i16>>(4).unwrap(), None);
+        assert!(matches!(row.get::<Option<i16>>(0), Err(Error::Conversion(_))));
+        assert_eq!(row.get::<i16>(5).unwrap(), -3);
+        assert_eq!(row.get::<f64>(2).unwrap(), 42.0);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_get_json() {
+        let row = Row::from_cells(vec![
+            CellValue::BlobText(br#"{"a": [1, 2]}"#.to_vec()),
+            CellValue::Null,
+            CellValue::Varying("not json".to_string()),
+        ]);
+        let v: serde_json::Value = row.get_json(0).unwrap();
+        assert_eq!(v["a"][1], 2);
+        let v: Option<serde_json::Value> = row.get_json(1).unwrap();
+        assert_eq!(v, None);
+        assert!(row.get_json::<serde_json::Value>(1).is_ok()); // Value::Null
+        assert!(row.get_json::<serde_json::Value>(2).is_err());
+        assert!(row.get_json::<serde_json::Value>(3).is_err());
+    }
+}
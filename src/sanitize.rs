@@ -0,0 +1,399 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Masking SQL and parameters before they are logged
+//!
+//! `sanitize_sql` replaces the literals of a statement (strings, numbers,
+//! hex and Q-strings) with `?`, so values written into the SQL text don't end
+//! up in logs. A `RedactionPolicy` decides how the driver's own logging (the
+//! slow query log) shows SQL and parameters, and which parameter values may
+//! be logged at all.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{ParamLogging, RedactionPolicy, sanitize_sql};
+//!
+//! assert_eq!(
+//!     sanitize_sql("SELECT * FROM users WHERE email = 'alice@example.com' AND age > 30"),
+//!     "SELECT * FROM users WHERE email = ? AND age > ?"
+//! );
+//!
+//! // values of the first parameter only, e.g. an id, the others as types
+//! let policy = RedactionPolicy::new()
+//!     .params(ParamLogging::Full)
+//!     .loggable_params(|_sql, index| index == 0);
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use super::decfloat;
+use super::utils;
+
+/// How parameters are logged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamLogging {
+    /// Not at all
+    Hidden,
+    /// Type and size only, e.g. `text(8)` (default)
+    Redacted,
+    /// Values of the parameters `RedactionPolicy::loggable_params` allows,
+    /// text truncated; the others redacted
+    Full,
+}
+
+type LoggableParams = dyn Fn(&str, usize) -> bool + Send + Sync;
+
+/// How SQL and parameters are shown in logs
+#[derive(Clone)]
+pub struct RedactionPolicy {
+    mask_literals: bool,
+    params: ParamLogging,
+    loggable: Option<Arc<LoggableParams>>,
+    max_value_len: usize,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        Self {
+            mask_literals: true,
+            params: ParamLogging::Redacted,
+            loggable: None,
+            max_value_len: 64,
+        }
+    }
+}
+
+impl fmt::Debug for RedactionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedactionPolicy")
+            .field("mask_literals", &self.mask_literals)
+            .field("params", &self.params)
+            .field("loggable_params", &self.loggable.is_some())
+            .field("max_value_len", &self.max_value_len)
+            .finish()
+    }
+}
+
+impl RedactionPolicy {
+    /// Literals masked, parameters redacted
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace literals in the SQL with `?` (default: true)
+    pub fn mask_literals(mut self, mask: bool) -> Self {
+        self.mask_literals = mask;
+        self
+    }
+
+    /// How parameters are logged (default: `ParamLogging::Redacted`)
+    pub fn params(mut self, params: ParamLogging) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// With `ParamLogging::Full`, log the value of a parameter only when
+    /// `f(sql, index)` returns true (default: all values)
+    pub fn loggable_params<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, usize) -> bool + Send + Sync + 'static,
+    {
+        self.loggable = Some(Arc::new(f));
+        self
+    }
+
+    /// Text values longer than this many characters are truncated (0 = unlimited, default: 64)
+    pub fn max_value_len(mut self, len: usize) -> Self {
+        self.max_value_len = len;
+        self
+    }
+
+    /// SQL as it may be logged
+    pub fn sql(&self, sql: &str) -> String {
+        if self.mask_literals {
+            sanitize_sql(sql)
+        } else {
+            sql.to_string()
+        }
+    }
+
+    /// Parameters as they may be logged, `None` with `ParamLogging::Hidden`
+    pub(crate) fn format_params(
+        &self,
+        sql: &str,
+        params: &[(Vec<u8>, Vec<u8>, bool)],
+    ) -> Option<String> {
+        if self.params == ParamLogging::Hidden {
+            return None;
+        }
+        let params: Vec<String> = params
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let full = self.params == ParamLogging::Full
+                    && self.loggable.as_ref().is_none_or(|f| f(sql, i));
+                self.format_param(p, full)
+            })
+            .collect();
+        Some(params.join(", "))
+    }
+
+    /// Parameter as bound on the wire (see `ToSqlParam`)
+    fn format_param(&self, (value, blr, isnull): &(Vec<u8>, Vec<u8>, bool), full: bool) -> String {
+        if *isnull {
+            return "NULL".to_string();
+        }
+        match blr.first() {
+            Some(14) => {
+                let len = blr[1] as usize | (blr[2] as usize) << 8;
+                if full {
+                    let s = String::from_utf8_lossy(&value[..len]);
+                    utils::quote_literal(&truncate(&s, self.max_value_len))
+                } else {
+                    format!("text({})", len)
+                }
+            }
+            Some(7) | Some(8) if full => utils::bytes_to_bint32(value).to_string(),
+            Some(16) if full => utils::bytes_to_bint64(value).to_string(),
+            Some(26) if full => utils::bytes_to_bint128(value).to_string(),
+            Some(10) if full => utils::bytes_to_f32(value).to_string(),
+            Some(27) if full => utils::bytes_to_f64(value).to_string(),
+            Some(23) if full => (value[0] != 0).to_string(),
+            Some(24) if full => {
+                decfloat::decimal64_to_decimal(value).map_or_else(|e| e.message, |d| d.to_string())
+            }
+            Some(25) if full => {
+                decfloat::decimal128_to_decimal(value).map_or_else(|e| e.message, |d| d.to_string())
+            }
+            Some(12) if full => utils::bytes_to_naive_date(value)
+                .map_or("invalid date".to_string(), |d| d.to_string()),
+            Some(13) if full => utils::bytes_to_naive_time(value)
+                .map_or("invalid time".to_string(), |t| t.to_string()),
+            Some(35) if full => utils::bytes_to_naive_date_time(value)
+                .map_or("invalid timestamp".to_string(), |dt| dt.to_string()),
+            Some(28) if full => utils::bytes_to_time_tz(value)
+                .map_or("invalid time".to_string(), |(t, tz)| {
+                    format!("{} {}", t, tz.name())
+                }),
+            Some(29) if full => utils::bytes_to_date_time_tz(value)
+                .map_or("invalid timestamp".to_string(), |dt| dt.to_string()),
+            Some(7) => "smallint".to_string(),
+            Some(8) => "integer".to_string(),
+            Some(16) => "bigint".to_string(),
+            Some(26) => "int128".to_string(),
+            Some(10) => "float".to_string(),
+            Some(27) => "double".to_string(),
+            Some(23) => "boolean".to_string(),
+            Some(12) => "date".to_string(),
+            Some(13) => "time".to_string(),
+            Some(35) => "timestamp".to_string(),
+            Some(28) => "time with time zone".to_string(),
+            Some(29) => "timestamp with time zone".to_string(),
+            Some(24) | Some(25) => "decfloat".to_string(),
+            _ => "?".to_string(),
+        }
+    }
+}
+
+/// First `max` characters of `s` followed by "..." (0 = unlimited)
+pub(crate) fn truncate(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((i, _)) if max > 0 => format!("{}...", &s[..i]),
+        _ => s.to_string(),
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Closing delimiter of a Q-string (`q'{...}'`)
+fn q_close(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '<' => '>',
+        c => c,
+    }
+}
+
+/// Replace string, hex, Q-string and numeric literals with `?`
+///
+/// Quoted identifiers, comments, parameter markers and keywords such as
+/// NULL or TRUE are kept.
+pub fn sanitize_sql(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+    let prev_ident = |i: usize| i > 0 && is_ident_char(chars[i - 1]);
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            // 'text' with '' inside
+            '\'' => {
+                i += 1;
+                while i < chars.len() {
+                    if chars[i] == '\'' {
+                        if chars.get(i + 1) == Some(&'\'') {
+                            i += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+                out.push('?');
+                i += 1;
+            }
+            // q'{text}'
+            'q' | 'Q' if next == Some('\'') && !prev_ident(i) && i + 2 < chars.len() => {
+                let close = q_close(chars[i + 2]);
+                i += 3;
+                while i < chars.len() && !(chars[i] == close && chars.get(i + 1) == Some(&'\'')) {
+                    i += 1;
+                }
+                out.push('?');
+                i += 2;
+            }
+            // x'0A1B', continues as a 'text' literal
+            'x' | 'X' if next == Some('\'') && !prev_ident(i) => {
+                i += 1;
+            }
+            '"' => {
+                let start = i;
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                out.extend(&chars[start..i]);
+            }
+            '-' if next == Some('-') => {
+                let start = i;
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                out.extend(&chars[start..i]);
+            }
+            '/' if next == Some('*') => {
+                let start = i;
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(chars.len());
+                out.extend(&chars[start..i]);
+            }
+            c if c.is_ascii_digit() && !prev_ident(i) => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    // exponent sign, 1.5e-3
+                    if matches!(chars[i], 'e' | 'E')
+                        && matches!(chars.get(i + 1), Some('+') | Some('-'))
+                    {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                out.push('?');
+            }
+            c => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::param::{Param, ToSqlParam};
+
+    #[test]
+    fn test_sanitize_sql() {
+        let cases = [
+            (
+                "SELECT * FROM users WHERE email = 'alice@example.com' AND age > 30",
+                "SELECT * FROM users WHERE email = ? AND age > ?",
+            ),
+            (
+                "UPDATE t SET s = 'it''s', f = 1.5e-3 WHERE id = ?",
+                "UPDATE t SET s = ?, f = ? WHERE id = ?",
+            ),
+            (
+                "SELECT x'0AFF', q'{a 'b' c}', -5 FROM rdb$database",
+                "SELECT ?, ?, -? FROM rdb$database",
+            ),
+            (
+                "SELECT \"Col 1\", t2.c3 FROM t2 -- id 42\n",
+                "SELECT \"Col 1\", t2.c3 FROM t2 -- id 42\n",
+            ),
+            (
+                "SELECT /* 'x' */ NULL, TRUE FROM t WHERE a = :a",
+                "SELECT /* 'x' */ NULL, TRUE FROM t WHERE a = :a",
+            ),
+            ("SELECT 'unterminated", "SELECT ?"),
+        ];
+        for (sql, expected) in cases {
+            assert_eq!(sanitize_sql(sql), expected);
+        }
+    }
+
+    #[test]
+    fn test_format_params() {
+        let params = vec![
+            Param::Long(42).to_value_blr_isnull(),
+            Param::from("alice@example.com").to_value_blr_isnull(),
+            Param::Null.to_value_blr_isnull(),
+        ];
+        let policy = RedactionPolicy::new();
+        assert_eq!(
+            policy.format_params("", &params).unwrap(),
+            "integer, text(17), NULL"
+        );
+        let policy = policy.params(ParamLogging::Full).max_value_len(5);
+        assert_eq!(
+            policy.format_params("", &params).unwrap(),
+            "42, 'alice...', NULL"
+        );
+        let policy = policy.loggable_params(|_, i| i == 0);
+        assert_eq!(
+            policy.format_params("", &params).unwrap(),
+            "42, text(17), NULL"
+        );
+        let policy = policy.params(ParamLogging::Hidden);
+        assert_eq!(policy.format_params("", &params), None);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("äöü", 2), "äö...");
+        assert_eq!(truncate("äöü", 3), "äöü");
+        assert_eq!(truncate("äöü", 0), "äöü");
+    }
+}
@@ -0,0 +1,141 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Scripts of several statements
+//!
+//! `Connection::execute_script` runs a script the way isql would: statements
+//! end with `;`, and `SET TERM` changes the terminator around PSQL bodies
+//! (`EXECUTE BLOCK`, procedures, triggers) whose statements end with `;`
+//! themselves. Terminators inside strings, quoted identifiers and comments
+//! don't count. `ConnectOptions::init_script` runs a script on every new
+//! connection.
+//!
+//! # Example
+//!
+//! ```ignore
+//! conn.execute_script(
+//!     "SET BIND OF DECFLOAT TO DOUBLE PRECISION;
+//!      SET STATEMENT TIMEOUT 30 SECOND;
+//!      SET TERM ^ ;
+//!      EXECUTE BLOCK AS BEGIN
+//!          RDB$SET_CONTEXT('USER_SESSION', 'APP', 'billing');
+//!      END^
+//!      SET TERM ; ^",
+//! )?;
+//! ```
+
+use super::error::{Error, ValueError};
+use super::sqlcheck::{self, TokenKind};
+
+/// Statements of `script`, without their terminators
+pub(crate) fn split_script(script: &str) -> Result<Vec<&str>, Error> {
+    let (tokens, issues) = sqlcheck::tokenize(script);
+    if let Some(issue) = issues.first() {
+        return Err(Error::ValueError(ValueError::new(&issue.to_string())));
+    }
+    let is_word = |i: usize, word: &str| {
+        tokens
+            .get(i)
+            .is_some_and(|t| t.kind == TokenKind::Word && t.text.eq_ignore_ascii_case(word))
+    };
+
+    let mut statements = Vec::new();
+    let mut term = ";".to_string();
+    // byte range of the statement being read
+    let mut current: Option<(usize, usize)> = None;
+    let mut i = 0;
+    while i < tokens.len() {
+        let t = tokens[i];
+        let next = if current.is_none() && is_word(i, "SET") && is_word(i + 1, "TERM") {
+            // SET TERM <new> <current terminator>
+            let from = tokens[i + 1].offset + tokens[i + 1].text.len();
+            let new_term = script[from..]
+                .find(term.as_str())
+                .map(|n| script[from..from + n].trim())
+                .filter(|t| !t.is_empty())
+                .ok_or_else(|| {
+                    Error::ValueError(ValueError::new(&format!(
+                        "SET TERM without a new terminator followed by '{}'",
+                        term
+                    )))
+                })?;
+            let end = script[from..].find(term.as_str()).unwrap() + from + term.len();
+            term = new_term.to_string();
+            end
+        } else if matches!(t.kind, TokenKind::Symbol | TokenKind::Word)
+            && script[t.offset..].starts_with(term.as_str())
+        {
+            if let Some((start, end)) = current.take() {
+                statements.push(&script[start..end]);
+            }
+            t.offset + term.len()
+        } else {
+            let end = t.offset + t.text.len();
+            current = Some((current.map_or(t.offset, |(start, _)| start), end));
+            i += 1;
+            continue;
+        };
+        // skip the tokens of the terminator or directive
+        while i < tokens.len() && tokens[i].offset < next {
+            i += 1;
+        }
+    }
+    if let Some((start, end)) = current {
+        statements.push(&script[start..end]);
+    }
+    Ok(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_script() {
+        let script = "
+            -- session settings; one per line
+            SET BIND OF DECFLOAT TO DOUBLE PRECISION;
+            SELECT RDB$SET_CONTEXT('USER_SESSION', 'A', 'x;y') FROM RDB$DATABASE;
+            SET TERM ^ ;
+            EXECUTE BLOCK AS BEGIN
+                RDB$SET_CONTEXT('USER_SESSION', 'B', 1);
+            END^
+            SET TERM ; ^
+            SET STATEMENT TIMEOUT 30 SECOND";
+        let statements = split_script(script).unwrap();
+        assert_eq!(statements.len(), 4);
+        assert_eq!(statements[0], "SET BIND OF DECFLOAT TO DOUBLE PRECISION");
+        assert_eq!(
+            statements[1],
+            "SELECT RDB$SET_CONTEXT('USER_SESSION', 'A', 'x;y') FROM RDB$DATABASE"
+        );
+        assert!(statements[2].starts_with("EXECUTE BLOCK AS BEGIN"));
+        assert!(statements[2].ends_with("1);\n            END"));
+        assert_eq!(statements[3], "SET STATEMENT TIMEOUT 30 SECOND");
+
+        assert!(split_script("").unwrap().is_empty());
+        assert!(split_script(" ; ;").unwrap().is_empty());
+        assert!(split_script("SELECT 'open FROM RDB$DATABASE;").is_err());
+        assert!(split_script("SET TERM ^").is_err());
+    }
+}
@@ -0,0 +1,177 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Users of the security database through SQL (Firebird 3.0+)
+//!
+//! The SEC$USERS and SEC$USER_ATTRIBUTES virtual tables list the users of
+//! the security database of the attached database. `users` reads them into
+//! `User` values with their tags, and `set_tags` / `drop_tags` change the
+//! tags with `ALTER USER ... TAGS`, without a services connection.
+//!
+//! A non-admin user only sees and alters itself.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::security;
+//!
+//! for user in security::users(&conn)? {
+//!     println!("{} ({}) admin={} tags={:?}", user.name, user.plugin, user.admin, user.tags);
+//! }
+//! security::set_tags(&mut conn, "alice", &[("department", "sales")])?;
+//! ```
+
+use std::collections::BTreeMap;
+
+use super::connection::Connection;
+use super::ddl::{object_name, quote};
+use super::error::{Error, ValueError};
+use super::utils;
+
+const USERS_SQL: &str = "SELECT SEC$USER_NAME, SEC$PLUGIN, SEC$FIRST_NAME, SEC$MIDDLE_NAME,
+    SEC$LAST_NAME, SEC$ACTIVE, SEC$ADMIN, SEC$DESCRIPTION
+    FROM SEC$USERS ORDER BY SEC$USER_NAME, SEC$PLUGIN";
+
+const ATTRIBUTES_SQL: &str = "SELECT SEC$USER_NAME, SEC$PLUGIN, SEC$KEY, SEC$VALUE
+    FROM SEC$USER_ATTRIBUTES";
+
+/// A user of the security database; the same name may exist once per
+/// user manager plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct User {
+    pub name: String,
+    /// User manager plugin, e.g. "Srp"
+    pub plugin: String,
+    pub first_name: Option<String>,
+    pub middle_name: Option<String>,
+    pub last_name: Option<String>,
+    pub active: bool,
+    /// Granted the RDB$ADMIN role in the security database
+    pub admin: bool,
+    pub comment: Option<String>,
+    /// Attributes set with `TAGS`
+    pub tags: BTreeMap<String, String>,
+}
+
+/// All users visible to the current user, with their tags
+pub fn users(conn: &Connection) -> Result<Vec<User>, Error> {
+    let mut stmt = conn.prepare(USERS_SQL)?;
+    let mut users = stmt
+        .query_map((), |row| {
+            Ok(User {
+                name: trimmed(row.get(0)?),
+                plugin: trimmed(row.get(1)?),
+                first_name: row.get::<Option<String>>(2)?.map(trimmed),
+                middle_name: row.get::<Option<String>>(3)?.map(trimmed),
+                last_name: row.get::<Option<String>>(4)?.map(trimmed),
+                active: row.get_or(5, false)?,
+                admin: row.get_or(6, false)?,
+                comment: row.get(7)?,
+                tags: BTreeMap::new(),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    drop(stmt);
+
+    let mut stmt = conn.prepare(ATTRIBUTES_SQL)?;
+    for row in stmt.query(())? {
+        let name = trimmed(row.get(0)?);
+        let plugin = trimmed(row.get(1)?);
+        if let Some(user) = users
+            .iter_mut()
+            .find(|u| u.name == name && u.plugin == plugin)
+        {
+            user.tags
+                .insert(trimmed(row.get(2)?), row.get_or(3, String::new())?);
+        }
+    }
+    Ok(users)
+}
+
+/// The user `name` of any plugin, `None` if it does not exist (or is not
+/// visible to the current user)
+pub fn user(conn: &Connection, name: &str) -> Result<Option<User>, Error> {
+    let name = object_name(name);
+    Ok(users(conn)?.into_iter().find(|u| u.name == name))
+}
+
+/// Set tags of a user, replacing the values of existing keys
+pub fn set_tags(conn: &mut Connection, user: &str, tags: &[(&str, &str)]) -> Result<(), Error> {
+    let items: Vec<String> = tags
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{} = {}",
+                quote(&object_name(key)),
+                utils::quote_literal(value)
+            )
+        })
+        .collect();
+    conn.execute_ddl(&alter_tags_sql(user, &items)?)
+}
+
+/// Remove tags of a user
+pub fn drop_tags(conn: &mut Connection, user: &str, keys: &[&str]) -> Result<(), Error> {
+    let items: Vec<String> = keys
+        .iter()
+        .map(|key| format!("DROP {}", quote(&object_name(key))))
+        .collect();
+    conn.execute_ddl(&alter_tags_sql(user, &items)?)
+}
+
+fn alter_tags_sql(user: &str, items: &[String]) -> Result<String, Error> {
+    if items.is_empty() {
+        return Err(Error::ValueError(ValueError::new("No tags given")));
+    }
+    Ok(format!(
+        "ALTER USER {} TAGS ({})",
+        quote(&object_name(user)),
+        items.join(", ")
+    ))
+}
+
+fn trimmed(s: String) -> String {
+    s.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alter_tags_sql() {
+        let items = vec![
+            format!(
+                "{} = {}",
+                quote(&object_name("dept")),
+                utils::quote_literal("O'Brien's")
+            ),
+            "DROP \"OLD\"".to_string(),
+        ];
+        assert_eq!(
+            alter_tags_sql("alice", &items).unwrap(),
+            "ALTER USER \"ALICE\" TAGS (\"DEPT\" = 'O''Brien''s', DROP \"OLD\")"
+        );
+        assert!(alter_tags_sql("alice", &[]).is_err());
+    }
+}
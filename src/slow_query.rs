@@ -0,0 +1,174 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Slow query log
+//!
+//! A `SlowQueryLog` set on a `Connection` (or on a pool, for every connection
+//! it hands out) logs the statements that took longer than its threshold,
+//! through the `log` crate with target `firebirust::slow_query` at level
+//! `warn`. The time covers execute, fetch of all rows and the automatic
+//! commit. SQL and parameters are masked by its `RedactionPolicy`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{ParamLogging, RedactionPolicy, SlowQueryLog};
+//!
+//! conn.set_slow_query_log(Some(
+//!     SlowQueryLog::new(Duration::from_millis(200))
+//!         .sample_rate(0.1)          // one slow query in ten
+//!         .max_sql_len(500)
+//!         .policy(RedactionPolicy::new().params(ParamLogging::Redacted)),
+//! ));
+//! // WARN firebirust::slow_query: 1.204s SELECT * FROM orders WHERE customer = ? AND state = ? [text(8)]
+//!
+//! conn.set_slow_query_log(None);     // off again
+//! ```
+
+use std::time::Duration;
+
+use super::sanitize::{RedactionPolicy, truncate};
+
+/// Threshold, sampling and formatting of the slow query log
+#[derive(Debug, Clone)]
+pub struct SlowQueryLog {
+    threshold: Duration,
+    sample_rate: f64,
+    max_sql_len: usize,
+    policy: RedactionPolicy,
+}
+
+impl SlowQueryLog {
+    /// Log statements taking `threshold` or longer
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            sample_rate: 1.0,
+            max_sql_len: 1000,
+            policy: RedactionPolicy::default(),
+        }
+    }
+
+    /// Fraction of the slow queries logged, 0.0 to 1.0 (default: 1.0)
+    pub fn sample_rate(mut self, rate: f64) -> Self {
+        self.sample_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// SQL longer than this many characters is truncated (0 = unlimited, default: 1000)
+    pub fn max_sql_len(mut self, len: usize) -> Self {
+        self.max_sql_len = len;
+        self
+    }
+
+    /// How SQL and parameters are masked (default: literals masked, parameters redacted)
+    pub fn policy(mut self, policy: RedactionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Get the threshold
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Log the statement if it was slow and is sampled
+    pub(crate) fn record(&self, sql: &str, params: &[(Vec<u8>, Vec<u8>, bool)], elapsed: Duration) {
+        if elapsed < self.threshold {
+            return;
+        }
+        if self.sample_rate < 1.0 && rand::random::<f64>() >= self.sample_rate {
+            return;
+        }
+        log::warn!(target: "firebirust::slow_query", "{}", self.format(sql, params, elapsed));
+    }
+
+    /// Log line of a slow statement
+    fn format(&self, sql: &str, params: &[(Vec<u8>, Vec<u8>, bool)], elapsed: Duration) -> String {
+        let mut line = format!(
+            "{:.3?} {}",
+            elapsed,
+            truncate(&self.policy.sql(sql), self.max_sql_len)
+        );
+        if !params.is_empty()
+            && let Some(params) = self.policy.format_params(sql, params)
+        {
+            line.push_str(&format!(" [{}]", params));
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::param::{Param, ToSqlParam};
+    use crate::sanitize::ParamLogging;
+
+    fn params() -> Vec<(Vec<u8>, Vec<u8>, bool)> {
+        vec![
+            Param::from("alice@example.com").to_value_blr_isnull(),
+            Param::Long(42).to_value_blr_isnull(),
+            Param::Null.to_value_blr_isnull(),
+        ]
+    }
+
+    #[test]
+    fn test_format_redacted() {
+        let log = SlowQueryLog::new(Duration::from_millis(100));
+        assert_eq!(
+            log.format(
+                "SELECT * FROM users WHERE email = ? AND id = ? OR x = ? AND y = 'x'",
+                &params(),
+                Duration::from_millis(1500)
+            ),
+            "1.500s SELECT * FROM users WHERE email = ? AND id = ? OR x = ? AND y = ? [text(17), integer, NULL]"
+        );
+    }
+
+    #[test]
+    fn test_format_full_and_truncated() {
+        let log = SlowQueryLog::new(Duration::ZERO).max_sql_len(10).policy(
+            RedactionPolicy::new()
+                .params(ParamLogging::Full)
+                .max_value_len(10),
+        );
+        assert_eq!(
+            log.format(
+                "SELECT * FROM users WHERE email = ?",
+                &params(),
+                Duration::from_millis(5)
+            ),
+            "5.000ms SELECT * F... ['alice@exam...', 42, NULL]"
+        );
+        let log = log.policy(
+            RedactionPolicy::new()
+                .params(ParamLogging::Hidden)
+                .mask_literals(false),
+        );
+        assert_eq!(
+            log.format("SELECT 1", &params(), Duration::from_millis(5)),
+            "5.000ms SELECT 1"
+        );
+    }
+}
@@ -0,0 +1,609 @@
+// MIT License
+//
+// Copyright (c) 2021-2024 Hajime Nakagami<nakagami@gmail.com>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Offline syntax pre-check of SQL in the Firebird dialect
+//!
+//! `check` tokenizes a statement without a server and reports what is
+//! certainly wrong before it is sent: unterminated strings, quoted
+//! identifiers and comments, unbalanced parentheses, an empty or unknown
+//! statement, several statements in one string, and syntax of other
+//! databases with its Firebird equivalent (`LIMIT`/`OFFSET`, `TOP`,
+//! backquoted identifiers). The server's "Token unknown" error tells only
+//! where it stopped; an `Issue` says what to write instead. Passing the
+//! check doesn't mean the server will accept the statement.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::sqlcheck;
+//!
+//! let issues = sqlcheck::check("SELECT * FROM orders ORDER BY id LIMIT 10 OFFSET 20");
+//! assert_eq!(issues[0].kind, sqlcheck::IssueKind::LimitOffset);
+//! // line 1, column 34: LIMIT is not Firebird syntax, use
+//! // OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY (or ROWS 21 TO 30)
+//! println!("{}", issues[0]);
+//!
+//! sqlcheck::validate(sql)?; // the first issue as Error::ValueError
+//! ```
+
+use std::fmt;
+
+use super::error::{Error, ValueError};
+
+// first words of the statements DSQL runs
+const STATEMENTS: &[&str] = &[
+    "ALTER",
+    "COMMENT",
+    "COMMIT",
+    "CONNECT",
+    "CREATE",
+    "DECLARE",
+    "DELETE",
+    "DROP",
+    "EXECUTE",
+    "GRANT",
+    "INSERT",
+    "MERGE",
+    "RECREATE",
+    "RELEASE",
+    "REVOKE",
+    "ROLLBACK",
+    "SAVEPOINT",
+    "SELECT",
+    "SET",
+    "UPDATE",
+    "WITH",
+];
+
+/// Kind of a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Keyword or identifier
+    Word,
+    /// `"Name"` (or a backquoted `` `name` ``, reported as an issue)
+    QuotedIdentifier,
+    /// `'text'`, `x'0A'` or `q'{text}'`
+    String,
+    Number,
+    /// `?` or `:name`
+    Parameter,
+    /// Operator or punctuation, e.g. `(`, `,` or `<>`
+    Symbol,
+}
+
+/// A token of a statement and where it starts (in bytes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub offset: usize,
+}
+
+impl Token<'_> {
+    fn is_word(&self, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+
+    // a row count: a number or a parameter
+    fn is_count(&self) -> bool {
+        matches!(self.kind, TokenKind::Number | TokenKind::Parameter)
+    }
+}
+
+/// What is wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    UnterminatedString,
+    UnterminatedIdentifier,
+    UnterminatedComment,
+    UnbalancedParenthesis,
+    /// Nothing but whitespace and comments
+    EmptyStatement,
+    /// Doesn't start with a statement keyword
+    UnknownStatement,
+    /// More than one statement, which DSQL doesn't run at once
+    MultipleStatements,
+    /// `LIMIT n` / `LIMIT m, n` / `OFFSET m` without `ROWS`
+    LimitOffset,
+    /// `SELECT TOP n`
+    Top,
+    /// `` `name` `` instead of `"name"`
+    Backquote,
+}
+
+/// An issue found by `check`, with its position in the statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub kind: IssueKind,
+    /// Byte offset
+    pub offset: usize,
+    /// 1-based line and column (in characters)
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+fn issue(sql: &str, kind: IssueKind, offset: usize, message: String) -> Issue {
+    let before = &sql[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Issue {
+        kind,
+        offset,
+        line,
+        column: before[line_start..].chars().count() + 1,
+        message,
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Closing delimiter of a Q-string (`q'{...}'`)
+fn q_close(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '<' => '>',
+        c => c,
+    }
+}
+
+/// Tokens of `sql`, without whitespace and comments, and the lexical
+/// issues found on the way. An unterminated token runs to the end.
+pub fn tokenize(sql: &str) -> (Vec<Token<'_>>, Vec<Issue>) {
+    let chars: Vec<(usize, char)> = sql.char_indices().collect();
+    let at = |i: usize| chars.get(i).map(|&(_, c)| c);
+    let offset = |i: usize| chars.get(i).map_or(sql.len(), |&(o, _)| o);
+    let mut tokens = Vec::new();
+    let mut issues = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i].1;
+        let next = at(i + 1);
+        let start = i;
+        let kind = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i].1 != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i].1 == '*' && at(i + 1) == Some('/')) {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    issues.push(issue(
+                        sql,
+                        IssueKind::UnterminatedComment,
+                        offset(start),
+                        "comment is not closed with */".to_string(),
+                    ));
+                }
+                i = (i + 2).min(chars.len());
+                continue;
+            }
+            // q'{text}'
+            'q' | 'Q' if next == Some('\'') && i + 2 < chars.len() => {
+                let close = q_close(chars[i + 2].1);
+                i += 3;
+                while i < chars.len() && !(chars[i].1 == close && at(i + 1) == Some('\'')) {
+                    i += 1;
+                }
+                if i >= chars.len() {
+                    issues.push(issue(
+                        sql,
+                        IssueKind::UnterminatedString,
+                        offset(start),
+                        format!("string is not closed with {}'", close),
+                    ));
+                }
+                i = (i + 2).min(chars.len());
+                TokenKind::String
+            }
+            // 'text' with '' inside, or x'0A1B'
+            '\'' | 'x' | 'X' if c == '\'' || next == Some('\'') => {
+                i += if c == '\'' { 1 } else { 2 };
+                loop {
+                    match at(i) {
+                        None => {
+                            issues.push(issue(
+                                sql,
+                                IssueKind::UnterminatedString,
+                                offset(start),
+                                "string is not closed with '".to_string(),
+                            ));
+                            break;
+                        }
+                        Some('\'') if at(i + 1) == Some('\'') => i += 2,
+                        Some('\'') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(_) => i += 1,
+                    }
+                }
+                TokenKind::String
+            }
+            '"' | '`' => {
+                i += 1;
+                loop {
+                    match at(i) {
+                        None => {
+                            issues.push(issue(
+                                sql,
+                                IssueKind::UnterminatedIdentifier,
+                                offset(start),
+                                format!("identifier is not closed with {}", c),
+                            ));
+                            break;
+                        }
+                        Some('"') if c == '"' && at(i + 1) == Some('"') => i += 2,
+                        Some(q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(_) => i += 1,
+                    }
+                }
+                if c == '`' {
+                    issues.push(issue(
+                        sql,
+                        IssueKind::Backquote,
+                        offset(start),
+                        format!(
+                            "identifiers are quoted with double quotes in Firebird, use \"{}\"",
+                            sql[offset(start) + 1..offset(i)].trim_end_matches('`')
+                        ),
+                    ));
+                }
+                TokenKind::QuotedIdentifier
+            }
+            c if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                while i < chars.len() && (chars[i].1.is_ascii_alphanumeric() || chars[i].1 == '.') {
+                    // exponent sign, 1.5e-3
+                    if matches!(chars[i].1, 'e' | 'E') && matches!(at(i + 1), Some('+') | Some('-'))
+                    {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                TokenKind::Number
+            }
+            c if is_ident_char(c) => {
+                while i < chars.len() && is_ident_char(chars[i].1) {
+                    i += 1;
+                }
+                TokenKind::Word
+            }
+            '?' => {
+                i += 1;
+                TokenKind::Parameter
+            }
+            ':' if next.is_some_and(is_ident_char) => {
+                i += 1;
+                while i < chars.len() && is_ident_char(chars[i].1) {
+                    i += 1;
+                }
+                TokenKind::Parameter
+            }
+            _ => {
+                let two = (c, next.unwrap_or(' '));
+                i += match two {
+                    ('|', '|')
+                    | ('<', '=')
+                    | ('>', '=')
+                    | ('<', '>')
+                    | ('!', '=')
+                    | ('^', '=')
+                    | ('~', '=')
+                    | ('!', '<')
+                    | ('!', '>') => 2,
+                    _ => 1,
+                };
+                TokenKind::Symbol
+            }
+        };
+        tokens.push(Token {
+            kind,
+            text: &sql[offset(start)..offset(i)],
+            offset: offset(start),
+        });
+    }
+    (tokens, issues)
+}
+
+/// Issues of `sql`, in the order they appear; empty when none was found
+pub fn check(sql: &str) -> Vec<Issue> {
+    let (tokens, mut issues) = tokenize(sql);
+    let Some(first) = tokens.first() else {
+        issues.push(issue(
+            sql,
+            IssueKind::EmptyStatement,
+            0,
+            "statement is empty".to_string(),
+        ));
+        return issues;
+    };
+    if !STATEMENTS.iter().any(|s| first.is_word(s)) {
+        issues.push(issue(
+            sql,
+            IssueKind::UnknownStatement,
+            first.offset,
+            format!("{} doesn't start a Firebird statement", first.text),
+        ));
+    }
+    // PSQL bodies (EXECUTE BLOCK, CREATE PROCEDURE ...) hold statements
+    let psql = tokens.iter().any(|t| t.is_word("BEGIN"));
+    let mut opened: Vec<usize> = Vec::new();
+    for (n, t) in tokens.iter().enumerate() {
+        let next = |k: usize| tokens.get(n + k);
+        if t.is_symbol("(") {
+            opened.push(t.offset);
+        } else if t.is_symbol(")") && opened.pop().is_none() {
+            issues.push(issue(
+                sql,
+                IssueKind::UnbalancedParenthesis,
+                t.offset,
+                "no ( for this )".to_string(),
+            ));
+        } else if t.is_symbol(";") && !psql && next(1).is_some_and(|t| !t.is_symbol(";")) {
+            issues.push(issue(
+                sql,
+                IssueKind::MultipleStatements,
+                t.offset,
+                "only one statement can be run at a time, split it at ;".to_string(),
+            ));
+        } else if t.is_word("LIMIT") && next(1).is_some_and(|t| t.is_count()) {
+            let count = next(1).unwrap().text;
+            let skip = match (next(2), next(3)) {
+                // LIMIT m, n
+                (Some(c), Some(n)) if c.is_symbol(",") && n.is_count() => Some((count, n.text)),
+                (Some(o), Some(m)) if o.is_word("OFFSET") && m.is_count() => Some((m.text, count)),
+                _ => None,
+            };
+            let suggestion = match skip {
+                Some((skip, count)) => offset_rows(skip, count),
+                None => format!("FETCH FIRST {} ROWS ONLY (or ROWS {})", count, count),
+            };
+            issues.push(issue(
+                sql,
+                IssueKind::LimitOffset,
+                t.offset,
+                format!("LIMIT is not Firebird syntax, use {}", suggestion),
+            ));
+        } else if t.is_word("OFFSET")
+            && next(1).is_some_and(|t| t.is_count())
+            && !next(2).is_some_and(|t| t.is_word("ROW") || t.is_word("ROWS"))
+            && !(n >= 2 && tokens[n - 2].is_word("LIMIT"))
+        {
+            issues.push(issue(
+                sql,
+                IssueKind::LimitOffset,
+                t.offset,
+                format!(
+                    "Firebird needs ROWS after OFFSET: OFFSET {} ROWS",
+                    next(1).unwrap().text
+                ),
+            ));
+        } else if t.is_word("TOP")
+            && n > 0
+            && (tokens[n - 1].is_word("SELECT") || tokens[n - 1].is_word("DISTINCT"))
+            && next(1).is_some_and(|t| t.is_count() || t.is_symbol("("))
+        {
+            let count = next(1).unwrap();
+            let count = if count.is_symbol("(") {
+                next(2).map_or("n", |t| t.text)
+            } else {
+                count.text
+            };
+            issues.push(issue(
+                sql,
+                IssueKind::Top,
+                t.offset,
+                format!(
+                    "TOP is not Firebird syntax, use SELECT FIRST {} or FETCH FIRST {} ROWS ONLY",
+                    count, count
+                ),
+            ));
+        }
+    }
+    for offset in opened {
+        issues.push(issue(
+            sql,
+            IssueKind::UnbalancedParenthesis,
+            offset,
+            "( is not closed".to_string(),
+        ));
+    }
+    issues.sort_by_key(|i| i.offset);
+    issues
+}
+
+/// OFFSET / FETCH equivalent of skipping `skip` rows and taking `count`,
+/// with the ROWS m TO n form when both are numbers
+fn offset_rows(skip: &str, count: &str) -> String {
+    let standard = format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", skip, count);
+    match (skip.parse::<u64>(), count.parse::<u64>()) {
+        (Ok(skip), Ok(count)) if count > 0 => {
+            format!("{} (or ROWS {} TO {})", standard, skip + 1, skip + count)
+        }
+        _ => standard,
+    }
+}
+
+/// `Error::ValueError` describing the first issue of `sql`, if any
+pub fn validate(sql: &str) -> Result<(), Error> {
+    match check(sql).first() {
+        Some(issue) => Err(Error::ValueError(ValueError::new(&issue.to_string()))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<IssueKind> {
+        check(sql).iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let (tokens, issues) = tokenize(
+            "SELECT \"a\"\"b\", 'it''s', x'0A', q'{x'y}', 1.5e-3, :p, ? FROM t -- c\nWHERE a <> b",
+        );
+        assert!(issues.is_empty());
+        let texts: Vec<&str> = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "SELECT",
+                "\"a\"\"b\"",
+                ",",
+                "'it''s'",
+                ",",
+                "x'0A'",
+                ",",
+                "q'{x'y}'",
+                ",",
+                "1.5e-3",
+                ",",
+                ":p",
+                ",",
+                "?",
+                "FROM",
+                "t",
+                "WHERE",
+                "a",
+                "<>",
+                "b",
+            ]
+        );
+        assert_eq!(tokens[1].kind, TokenKind::QuotedIdentifier);
+        assert_eq!(tokens[7].kind, TokenKind::String);
+        assert_eq!(tokens[11].kind, TokenKind::Parameter);
+        assert_eq!(tokens[17].offset, 73);
+    }
+
+    #[test]
+    fn test_unterminated() {
+        assert_eq!(
+            kinds("SELECT 'abc FROM t"),
+            vec![IssueKind::UnterminatedString]
+        );
+        assert_eq!(
+            kinds("SELECT \"abc FROM t"),
+            vec![IssueKind::UnterminatedIdentifier]
+        );
+        assert_eq!(
+            kinds("SELECT 1 FROM t /* x"),
+            vec![IssueKind::UnterminatedComment]
+        );
+        assert_eq!(kinds("SELECT q'{abc}"), vec![IssueKind::UnterminatedString]);
+        assert_eq!(
+            kinds("SELECT (1 FROM t"),
+            vec![IssueKind::UnbalancedParenthesis]
+        );
+        assert_eq!(
+            kinds("SELECT 1) FROM t"),
+            vec![IssueKind::UnbalancedParenthesis]
+        );
+        // quotes and parentheses inside strings and comments don't count
+        assert!(check("SELECT '(\"', \"'\" FROM t -- it's\n").is_empty());
+    }
+
+    #[test]
+    fn test_statements() {
+        assert_eq!(kinds("  -- nothing\n"), vec![IssueKind::EmptyStatement]);
+        assert_eq!(kinds("SELCT 1 FROM t"), vec![IssueKind::UnknownStatement]);
+        assert_eq!(
+            kinds("DELETE FROM a; DELETE FROM b"),
+            vec![IssueKind::MultipleStatements]
+        );
+        assert!(check("DELETE FROM a;").is_empty());
+        assert!(check("EXECUTE BLOCK AS BEGIN DELETE FROM a; DELETE FROM b; END").is_empty());
+        assert!(check("with x as (select 1 a from rdb$database) select a from x").is_empty());
+    }
+
+    #[test]
+    fn test_other_dialects() {
+        let issues = check("SELECT * FROM orders\nORDER BY id LIMIT 10 OFFSET 20");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].to_string(),
+            "line 2, column 13: LIMIT is not Firebird syntax, use \
+             OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY (or ROWS 21 TO 30)"
+        );
+        assert_eq!(
+            check("SELECT * FROM t LIMIT ?")[0].message,
+            "LIMIT is not Firebird syntax, use FETCH FIRST ? ROWS ONLY (or ROWS ?)"
+        );
+        assert_eq!(
+            check("SELECT * FROM t LIMIT 5, 10")[0].message,
+            "LIMIT is not Firebird syntax, use OFFSET 5 ROWS FETCH NEXT 10 ROWS ONLY (or ROWS 6 TO 15)"
+        );
+        assert_eq!(
+            kinds("SELECT * FROM t OFFSET 5"),
+            vec![IssueKind::LimitOffset]
+        );
+        assert!(check("SELECT * FROM t OFFSET 5 ROWS FETCH NEXT 10 ROWS ONLY").is_empty());
+        // a column named limit
+        assert!(check("SELECT limit FROM quota WHERE limit > 5").is_empty());
+
+        assert_eq!(kinds("SELECT TOP 10 * FROM t"), vec![IssueKind::Top]);
+        assert_eq!(
+            kinds("SELECT DISTINCT TOP (5) a FROM t"),
+            vec![IssueKind::Top]
+        );
+        let issues = check("SELECT `name` FROM `users`");
+        assert_eq!(issues.len(), 2);
+        assert_eq!(
+            issues[0].message,
+            "identifiers are quoted with double quotes in Firebird, use \"name\""
+        );
+        assert!(validate("SELECT a FROM t WHERE b = ?").is_ok());
+        assert!(validate("SELECT a FROM t LIMIT 1").is_err());
+    }
+}
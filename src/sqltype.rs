@@ -0,0 +1,288 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! SQL types of columns and parameters
+//!
+//! `SqlType` is the type of a described column, so tools can match on it
+//! instead of comparing type names. Its `Display` is the type as written in
+//! DDL, with the character set of text types and the sub type of blobs.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::SqlType;
+//!
+//! for col in stmt.description() {
+//!     match col.sql_type() {
+//!         SqlType::Blob { sub_type: 1 } => println!("{} is a text blob", col.name),
+//!         t => println!("{}: {}", col.name, t), // e.g. "VARCHAR CHARACTER SET UTF8"
+//!     }
+//! }
+//! ```
+
+use super::*;
+use std::fmt;
+
+/// Type of a column or parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlType {
+    /// CHAR with its character set id
+    Char {
+        charset: u8,
+    },
+    /// VARCHAR with its character set id
+    Varchar {
+        charset: u8,
+    },
+    SmallInt,
+    Integer,
+    BigInt,
+    Int128,
+    Float,
+    DoublePrecision,
+    Date,
+    Time,
+    Timestamp,
+    TimeWithTimeZone,
+    TimestampWithTimeZone,
+    /// BLOB with its sub type: 0 binary, 1 text, negative for user defined
+    Blob {
+        sub_type: i32,
+    },
+    Boolean,
+    DecFloat16,
+    DecFloat34,
+    /// NUMERIC stored as a 128 bit fixed point decimal
+    DecFixed,
+    Array,
+    /// The type of a `? IS NULL` parameter
+    Null,
+    /// A type code this crate does not know
+    Unknown(u32),
+}
+
+impl SqlType {
+    /// The type of an XSQLDA entry: `type_code` without its nullable bit,
+    /// `sub_type` as described (the character set id for text types)
+    pub fn new(type_code: u32, sub_type: i32) -> SqlType {
+        match type_code & !1 {
+            SQL_TYPE_TEXT => SqlType::Char {
+                charset: sub_type as u8,
+            },
+            SQL_TYPE_VARYING => SqlType::Varchar {
+                charset: sub_type as u8,
+            },
+            SQL_TYPE_SHORT => SqlType::SmallInt,
+            SQL_TYPE_LONG => SqlType::Integer,
+            SQL_TYPE_INT64 => SqlType::BigInt,
+            SQL_TYPE_INT128 => SqlType::Int128,
+            SQL_TYPE_FLOAT => SqlType::Float,
+            SQL_TYPE_DOUBLE | SQL_TYPE_D_FLOAT => SqlType::DoublePrecision,
+            SQL_TYPE_DATE => SqlType::Date,
+            SQL_TYPE_TIME => SqlType::Time,
+            SQL_TYPE_TIMESTAMP => SqlType::Timestamp,
+            SQL_TYPE_TIME_TZ | SQL_TYPE_TIME_TZ_EX => SqlType::TimeWithTimeZone,
+            SQL_TYPE_TIMESTAMP_TZ | SQL_TYPE_TIMESTAMP_TZ_EX => SqlType::TimestampWithTimeZone,
+            SQL_TYPE_BLOB => SqlType::Blob { sub_type },
+            SQL_TYPE_BOOLEAN => SqlType::Boolean,
+            SQL_TYPE_DEC64 => SqlType::DecFloat16,
+            SQL_TYPE_DEC128 => SqlType::DecFloat34,
+            SQL_TYPE_DEC_FIXED => SqlType::DecFixed,
+            SQL_TYPE_ARRAY => SqlType::Array,
+            SQL_TYPE_NULL => SqlType::Null,
+            code => SqlType::Unknown(code),
+        }
+    }
+
+    /// Type name without character set or sub type ("VARCHAR", "BLOB")
+    pub fn name(&self) -> &'static str {
+        match self {
+            SqlType::Char { .. } => "CHAR",
+            SqlType::Varchar { .. } => "VARCHAR",
+            SqlType::SmallInt => "SMALLINT",
+            SqlType::Integer => "INTEGER",
+            SqlType::BigInt => "BIGINT",
+            SqlType::Int128 => "INT128",
+            SqlType::Float => "FLOAT",
+            SqlType::DoublePrecision => "DOUBLE PRECISION",
+            SqlType::Date => "DATE",
+            SqlType::Time => "TIME",
+            SqlType::Timestamp => "TIMESTAMP",
+            SqlType::TimeWithTimeZone => "TIME WITH TIME ZONE",
+            SqlType::TimestampWithTimeZone => "TIMESTAMP WITH TIME ZONE",
+            SqlType::Blob { .. } => "BLOB",
+            SqlType::Boolean => "BOOLEAN",
+            SqlType::DecFloat16 => "DECFLOAT(16)",
+            SqlType::DecFloat34 => "DECFLOAT(34)",
+            SqlType::DecFixed => "NUMERIC",
+            SqlType::Array => "ARRAY",
+            SqlType::Null => "NULL",
+            SqlType::Unknown(_) => "UNKNOWN",
+        }
+    }
+
+    /// Character set of CHAR and VARCHAR, `None` for other types or an
+    /// unknown id
+    pub fn charset(&self) -> Option<&'static str> {
+        match self {
+            SqlType::Char { charset } | SqlType::Varchar { charset } => charset_name(*charset),
+            _ => None,
+        }
+    }
+
+    pub fn is_text(&self) -> bool {
+        matches!(
+            self,
+            SqlType::Char { .. } | SqlType::Varchar { .. } | SqlType::Blob { sub_type: 1 }
+        )
+    }
+}
+
+impl fmt::Display for SqlType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())?;
+        match self {
+            SqlType::Blob { sub_type } => match blob_sub_type_name(*sub_type) {
+                Some(name) => write!(f, " SUB_TYPE {}", name),
+                None => write!(f, " SUB_TYPE {}", sub_type),
+            },
+            SqlType::Unknown(code) => write!(f, "({})", code),
+            _ => match self.charset() {
+                Some(charset) => write!(f, " CHARACTER SET {}", charset),
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// Name of a predefined blob sub type
+pub(crate) fn blob_sub_type_name(sub_type: i32) -> Option<&'static str> {
+    Some(match sub_type {
+        0 => "BINARY",
+        1 => "TEXT",
+        2 => "BLR",
+        3 => "ACL",
+        4 => "RANGES",
+        5 => "SUMMARY",
+        6 => "FORMAT",
+        7 => "TRANSACTION_DESCRIPTION",
+        8 => "EXTERNAL_FILE_DESCRIPTION",
+        9 => "DEBUG_INFORMATION",
+        _ => return None,
+    })
+}
+
+/// Name of a character set id (RDB$CHARACTER_SETS)
+pub(crate) fn charset_name(id: u8) -> Option<&'static str> {
+    Some(match id {
+        0 => "NONE",
+        1 => "OCTETS",
+        2 => "ASCII",
+        3 => "UNICODE_FSS",
+        4 => "UTF8",
+        5 => "SJIS_0208",
+        6 => "EUCJ_0208",
+        9 => "DOS737",
+        10 => "DOS437",
+        11 => "DOS850",
+        12 => "DOS865",
+        13 => "DOS860",
+        14 => "DOS863",
+        15 => "DOS775",
+        16 => "DOS858",
+        17 => "DOS862",
+        18 => "DOS864",
+        19 => "NEXT",
+        21 => "ISO8859_1",
+        22 => "ISO8859_2",
+        23 => "ISO8859_3",
+        34 => "ISO8859_4",
+        35 => "ISO8859_5",
+        36 => "ISO8859_6",
+        37 => "ISO8859_7",
+        38 => "ISO8859_8",
+        39 => "ISO8859_9",
+        40 => "ISO8859_13",
+        44 => "KSC_5601",
+        45 => "DOS852",
+        46 => "DOS857",
+        47 => "DOS861",
+        48 => "DOS866",
+        49 => "DOS869",
+        50 => "CYRL",
+        51 => "WIN1250",
+        52 => "WIN1251",
+        53 => "WIN1252",
+        54 => "WIN1253",
+        55 => "WIN1254",
+        56 => "BIG_5",
+        57 => "GB_2312",
+        58 => "WIN1255",
+        59 => "WIN1256",
+        60 => "WIN1257",
+        63 => "KOI8R",
+        64 => "KOI8U",
+        65 => "WIN1258",
+        66 => "TIS620",
+        67 => "GBK",
+        68 => "CP943C",
+        69 => "GB18030",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_type() {
+        assert_eq!(SqlType::new(SQL_TYPE_LONG + 1, 0), SqlType::Integer);
+        assert_eq!(
+            SqlType::new(SQL_TYPE_TIMESTAMP_TZ_EX, 0),
+            SqlType::TimestampWithTimeZone
+        );
+        assert_eq!(SqlType::new(SQL_TYPE_DEC128, 0).to_string(), "DECFLOAT(34)");
+        assert_eq!(SqlType::new(SQL_TYPE_INT128, 0).to_string(), "INT128");
+        assert_eq!(
+            SqlType::new(SQL_TYPE_VARYING, 4).to_string(),
+            "VARCHAR CHARACTER SET UTF8"
+        );
+        // collation id in the high byte
+        assert_eq!(
+            SqlType::new(SQL_TYPE_TEXT, (1 << 8) | 53).to_string(),
+            "CHAR CHARACTER SET WIN1252"
+        );
+        assert_eq!(SqlType::new(SQL_TYPE_TEXT, 200).to_string(), "CHAR");
+        assert_eq!(
+            SqlType::new(SQL_TYPE_BLOB, 1).to_string(),
+            "BLOB SUB_TYPE TEXT"
+        );
+        assert_eq!(
+            SqlType::new(SQL_TYPE_BLOB, -10).to_string(),
+            "BLOB SUB_TYPE -10"
+        );
+        assert!(SqlType::new(SQL_TYPE_BLOB, 1).is_text());
+        assert_eq!(SqlType::new(1234, 0).to_string(), "UNKNOWN(1234)");
+    }
+}
@@ -21,16 +21,22 @@
 // SOFTWARE.
 #![allow(dead_code)]
 use super::Connection;
+use super::connection::FetchBatch;
 use super::Error;
+use super::error::{Limit, ParamError};
+use super::array::ArrayDesc;
 use super::cellvalue::CellValue;
 use super::param::ToSqlParam;
 use super::params::Params;
 use super::row::{MappedRows, Row, Rows};
-use super::xsqlvar::XSQLVar;
+use super::sqltype::SqlType;
+use super::transaction::Transaction;
+use super::xsqlvar::{XSQLVar, calc_blr};
 use super::*;
 
-use maplit::hashmap;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Instant;
 
 const DSQL_CLOSE: i32 = 1;
 const DSQL_DROP: i32 = 2;
@@ -42,6 +48,9 @@ pub struct ColumnInfo {
     pub name: String,
     /// SQL type code
     pub type_code: u32,
+    /// Sub type: character set id of text types, blob sub type, or
+    /// NUMERIC (1) / DECIMAL (2) of scaled integers
+    pub sub_type: i32,
     /// Display size (for character types)
     pub display_size: Option<i32>,
     /// Internal storage size in bytes
@@ -61,34 +70,107 @@ pub struct ColumnInfo {
 }
 
 impl ColumnInfo {
-    /// Get a human-readable type name
-    pub fn type_name(&self) -> &'static str {
-        match self.type_code {
-            super::SQL_TYPE_TEXT => "CHAR",
-            super::SQL_TYPE_VARYING => "VARCHAR",
-            super::SQL_TYPE_SHORT => "SMALLINT",
-            super::SQL_TYPE_LONG => "INTEGER",
-            super::SQL_TYPE_INT64 => "BIGINT",
-            super::SQL_TYPE_INT128 => "INT128",
-            super::SQL_TYPE_FLOAT => "FLOAT",
-            super::SQL_TYPE_DOUBLE => "DOUBLE PRECISION",
-            super::SQL_TYPE_DATE => "DATE",
-            super::SQL_TYPE_TIME => "TIME",
-            super::SQL_TYPE_TIMESTAMP => "TIMESTAMP",
-            super::SQL_TYPE_TIME_TZ => "TIME WITH TIME ZONE",
-            super::SQL_TYPE_TIMESTAMP_TZ => "TIMESTAMP WITH TIME ZONE",
-            super::SQL_TYPE_BLOB => "BLOB",
-            super::SQL_TYPE_BOOLEAN => "BOOLEAN",
-            super::SQL_TYPE_DEC64 => "DECFLOAT(16)",
-            super::SQL_TYPE_DEC128 => "DECFLOAT(34)",
-            super::SQL_TYPE_DEC_FIXED => "NUMERIC",
-            _ => "UNKNOWN",
+    /// Type of the column, to match on instead of comparing type names
+    pub fn sql_type(&self) -> SqlType {
+        SqlType::new(self.type_code, self.sub_type)
+    }
+
+    /// Type as written in DDL, e.g. "VARCHAR CHARACTER SET UTF8" or
+    /// "BLOB SUB_TYPE TEXT"
+    pub fn type_name(&self) -> String {
+        self.sql_type().to_string()
+    }
+}
+
+impl From<&XSQLVar> for ColumnInfo {
+    fn from(x: &XSQLVar) -> ColumnInfo {
+        // Calculate display size for character types
+        let display_size = match x.sqltype {
+            super::SQL_TYPE_TEXT | super::SQL_TYPE_VARYING => Some(x.sqllen),
+            _ => None,
+        };
+
+        // Calculate precision for numeric types
+        let precision = match x.sqltype {
+            super::SQL_TYPE_SHORT => Some(5),
+            super::SQL_TYPE_LONG => Some(10),
+            super::SQL_TYPE_INT64 => Some(19),
+            super::SQL_TYPE_INT128 => Some(38),
+            super::SQL_TYPE_DEC64 => Some(16),
+            super::SQL_TYPE_DEC128 => Some(34),
+            super::SQL_TYPE_DEC_FIXED => Some(x.sqllen),
+            _ => None,
+        };
+
+        ColumnInfo {
+            name: if x.aliasname.is_empty() {
+                x.fieldname.clone()
+            } else {
+                x.aliasname.clone()
+            },
+            type_code: x.sqltype,
+            sub_type: x.sqlsubtype,
+            display_size,
+            internal_size: x.sqllen,
+            precision,
+            scale: x.sqlscale,
+            nullable: x.null_ok,
+            field_name: x.fieldname.clone(),
+            table_name: x.relname.clone(),
+            owner_name: x.ownname.clone(),
         }
     }
 }
 
+/// What a prepared statement does, from isc_info_sql_stmt_type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatementKind {
+    Select,
+    /// SELECT ... FOR UPDATE
+    SelectForUpdate,
+    Insert,
+    Update,
+    Delete,
+    /// CREATE, ALTER, DROP, GRANT, ...
+    Ddl,
+    /// EXECUTE PROCEDURE, EXECUTE BLOCK and DML with RETURNING
+    ExecProcedure,
+    SetGenerator,
+    /// SET TRANSACTION, COMMIT, ROLLBACK and savepoints
+    Transaction,
+    /// Any other type the server reports
+    Other(u32),
+}
+
+impl StatementKind {
+    pub(crate) fn from_stmt_type(stmt_type: u32) -> StatementKind {
+        match stmt_type {
+            ISC_INFO_SQL_STMT_SELECT => StatementKind::Select,
+            ISC_INFO_SQL_STMT_SELECT_FOR_UPD => StatementKind::SelectForUpdate,
+            ISC_INFO_SQL_STMT_INSERT => StatementKind::Insert,
+            ISC_INFO_SQL_STMT_UPDATE => StatementKind::Update,
+            ISC_INFO_SQL_STMT_DELETE => StatementKind::Delete,
+            ISC_INFO_SQL_STMT_DDL => StatementKind::Ddl,
+            ISC_INFO_SQL_STMT_EXEC_PROCEDURE => StatementKind::ExecProcedure,
+            ISC_INFO_SQL_STMT_SET_GENERATOR => StatementKind::SetGenerator,
+            ISC_INFO_SQL_STMT_START_TRANS
+            | ISC_INFO_SQL_STMT_COMMIT
+            | ISC_INFO_SQL_STMT_ROLLBACK
+            | ISC_INFO_SQL_STMT_SAVEPOINT => StatementKind::Transaction,
+            other => StatementKind::Other(other),
+        }
+    }
+
+    /// Whether the statement only reads. A SELECT from a selectable
+    /// procedure counts as reading, whatever the procedure does.
+    pub fn is_read_only(&self) -> bool {
+        *self == StatementKind::Select
+    }
+}
+
 pub struct Statement<'conn> {
     conn: &'conn Connection,
+    sql: String,
     pub(crate) trans_handle: i32,
     pub(crate) stmt_handle: i32,
     stmt_type: u32,
@@ -96,19 +178,33 @@ pub struct Statement<'conn> {
     autocommit: bool,
     params: Vec<(Vec<u8>, Vec<u8>, bool)>,
     last_rowcount: usize,
+    description: Arc<[ColumnInfo]>,
+    input_description: Option<Vec<ColumnInfo>>,
+    blob_options: HashMap<usize, BlobOptions>,
+    // sent with op_set_cursor before each open_cursor
+    cursor_name: Option<String>,
+    // ARRAY parameters by position, stored before executing
+    arrays: Vec<(usize, Array)>,
+    // ARRAY columns by (relation, field)
+    array_descs: HashMap<(String, String), ArrayDesc>,
+    // original position of each parameter when `compat` moved them
+    param_order: Option<Vec<usize>>,
 }
 
-impl Statement<'_> {
-    pub(super) fn new(
-        conn: &Connection,
+impl<'conn> Statement<'conn> {
+    pub(super) fn new<'a>(
+        conn: &'a Connection,
+        sql: &str,
         trans_handle: i32,
         stmt_handle: i32,
         stmt_type: u32,
         xsqlda: Vec<XSQLVar>,
         autocommit: bool,
-    ) -> Statement<'_> {
+    ) -> Statement<'a> {
+        let description = xsqlda.iter().map(ColumnInfo::from).collect();
         Statement {
             conn,
+            sql: sql.to_string(),
             trans_handle,
             stmt_handle,
             stmt_type,
@@ -116,13 +212,45 @@ impl Statement<'_> {
             autocommit,
             params: Vec::new(),
             last_rowcount: 0,
+            description,
+            input_description: None,
+            blob_options: HashMap::new(),
+            cursor_name: None,
+            arrays: Vec::new(),
+            array_descs: HashMap::new(),
+            param_order: None,
         }
     }
 
+    /// Bind parameters in `order` (see `compat::Translation::param_order`)
+    pub(crate) fn with_param_order(mut self, order: Option<Vec<usize>>) -> Self {
+        self.param_order = order;
+        self
+    }
+
+    /// Move the parameters to where the translated statement expects them
+    fn reorder_params(&mut self) -> Result<(), Error> {
+        let Some(order) = &self.param_order else {
+            return Ok(());
+        };
+        check_param_order(order, self.params.len())?;
+        let mut params = std::mem::take(&mut self.params);
+        self.params = order.iter().map(|&i| std::mem::take(&mut params[i])).collect();
+        for (i, _) in self.arrays.iter_mut() {
+            *i = order.iter().position(|&o| o == *i).unwrap_or(*i);
+        }
+        Ok(())
+    }
+
     #[inline]
     pub(crate) fn bind_parameters(&mut self, params: &[&dyn ToSqlParam]) -> Result<(), Error> {
         self.params = Vec::new();
+        self.arrays = Vec::new();
         for p in params.iter() {
+            p.check_range()?;
+            if let Some(array) = p.array() {
+                self.arrays.push((self.params.len(), array.clone()));
+            }
             self.params.push(p.to_value_blr_isnull());
         }
         Ok(())
@@ -130,64 +258,272 @@ impl Statement<'_> {
 
     pub(crate) fn reset_parameter(&mut self, _capacity: usize) -> Result<(), Error> {
         self.params = Vec::new();
+        self.arrays = Vec::new();
         Ok(())
     }
 
     pub(crate) fn put_parameter<T: ToSqlParam>(&mut self, param: T) -> Result<(), Error> {
+        param.check_range()?;
+        if let Some(array) = param.array() {
+            self.arrays.push((self.params.len(), array.clone()));
+        }
         self.params.push(param.to_value_blr_isnull());
         Ok(())
     }
 
-    fn fetch_records(&self, trans_handle: i32) -> Result<VecDeque<Vec<CellValue>>, Error> {
+    /// Fetch all rows; `first` is a batch already fetched by a pipelined execute
+    fn fetch_records(
+        &self,
+        trans_handle: i32,
+        first: Option<FetchBatch>,
+    ) -> Result<VecDeque<Vec<CellValue>>, Error> {
         let mut rows = VecDeque::new();
-        let blr = self.calc_blr();
+        let blr = calc_blr(&self.xsqlda);
 
-        loop {
-            let (rows_segment, more_data) =
-                self.conn._fetch(self.stmt_handle, &blr, &self.xsqlda)?;
-            rows.extend(rows_segment);
-            if !more_data {
-                break;
+        let mut more_data = match first {
+            Some((rows_segment, more_data)) => {
+                rows.extend(rows_segment);
+                more_data
             }
+            None => true,
+        };
+        let max_rows = self.conn.max_rows();
+        while more_data {
+            check_rows(rows.len(), max_rows)?;
+            let (rows_segment, more) = self.conn._fetch(self.stmt_handle, &blr, &self.xsqlda)?;
+            rows.extend(rows_segment);
+            more_data = more;
         }
+        check_rows(rows.len(), max_rows)?;
 
+        let bpbs = blob::column_bpbs(&self.xsqlda, &self.blob_options);
         for row in rows.iter_mut() {
-            for cell in row.iter_mut() {
-                match cell {
-                    CellValue::BlobBinary(blob_id) => {
-                        let blob = self.conn._get_blob_segments(&blob_id, trans_handle);
-                        *cell = CellValue::BlobBinary(blob.unwrap());
-                    }
-                    CellValue::BlobText(blob_id) => {
-                        let blob = self.conn._get_blob_segments(&blob_id, trans_handle);
-                        *cell = CellValue::BlobText(blob.unwrap());
-                    }
-                    _ => {}
+            self.load_blobs(trans_handle, row, &bpbs)?;
+        }
+
+        Ok(rows)
+    }
+
+    /// Replace the blob ids of `row` by the blob contents
+    fn load_blobs(&self, trans_handle: i32, row: &mut [CellValue], bpbs: &[Vec<u8>]) -> Result<(), Error> {
+        for (i, cell) in row.iter_mut().enumerate() {
+            match cell {
+                CellValue::BlobBinary(blob_id) | CellValue::BlobText(blob_id)
+                    if self.blob_options.get(&i).is_some_and(|o| o.streamed) =>
+                {
+                    *cell = CellValue::BlobId(BlobId::new(blob_id.clone(), trans_handle, bpbs[i].clone()));
+                }
+                CellValue::BlobBinary(blob_id) => {
+                    let blob = self.conn._get_blob_segments(blob_id, trans_handle, &bpbs[i])?;
+                    *cell = CellValue::BlobBinary(blob);
                 }
+                CellValue::BlobText(blob_id) => {
+                    let blob = self.conn._get_blob_segments(blob_id, trans_handle, &bpbs[i])?;
+                    *cell = CellValue::BlobText(self.xsqlda[i].blob_text(blob));
+                }
+                CellValue::Array(array) => {
+                    let Some(array_id) = array.id() else {
+                        continue;
+                    };
+                    let x = &self.xsqlda[i];
+                    let desc = self
+                        .array_descs
+                        .get(&(x.relname.clone(), x.fieldname.clone()))
+                        .ok_or_else(|| {
+                            Error::ValueError(ValueError::new(&format!(
+                                "ARRAY column {} is not described",
+                                x.fieldname
+                            )))
+                        })?;
+                    *cell = CellValue::Array(self.conn._get_slice(array_id, trans_handle, desc)?);
+                }
+                _ => {}
             }
         }
+        Ok(())
+    }
 
-        Ok(rows)
+    /// Element type and bounds of an ARRAY column, looked up once
+    fn array_desc(&mut self, trans_handle: i32, relation: &str, field: &str) -> Result<&ArrayDesc, Error> {
+        let key = (relation.to_string(), field.to_string());
+        if !self.array_descs.contains_key(&key) {
+            let desc = ArrayDesc::lookup(self.conn, trans_handle, relation, field)?;
+            self.array_descs.insert(key.clone(), desc);
+        }
+        Ok(&self.array_descs[&key])
+    }
+
+    /// Describe the ARRAY columns of the result, whose slices are fetched
+    /// with the rows
+    fn describe_arrays(&mut self, trans_handle: i32) -> Result<(), Error> {
+        let columns: Vec<(String, String)> = self
+            .xsqlda
+            .iter()
+            .filter(|x| x.sqltype == SQL_TYPE_ARRAY)
+            .map(|x| (x.relname.clone(), x.fieldname.clone()))
+            .collect();
+        for (relation, field) in columns {
+            self.array_desc(trans_handle, &relation, &field)?;
+        }
+        Ok(())
+    }
+
+    /// Store the slices of ARRAY parameters and bind their ids instead
+    fn bind_arrays(&mut self, trans_handle: i32) -> Result<(), Error> {
+        if self.arrays.is_empty() {
+            return Ok(());
+        }
+        let inputs = self.describe_input()?;
+        for (i, array) in std::mem::take(&mut self.arrays) {
+            let (relation, field) = match (array.column_name(), inputs.get(i)) {
+                (Some(column), _) => column.clone(),
+                (None, Some(c)) if !c.table_name.is_empty() && !c.field_name.is_empty() => {
+                    (c.table_name.clone(), c.field_name.clone())
+                }
+                _ => {
+                    return Err(Error::ValueError(ValueError::new(&format!(
+                        "no column for ARRAY parameter {}, see Array::column",
+                        i + 1
+                    ))));
+                }
+            };
+            let conn = self.conn;
+            let desc = self.array_desc(trans_handle, &relation, &field)?;
+            let array_id = conn._put_slice(&array, trans_handle, desc)?;
+            self.params[i] = (array_id, vec![9, 0], false);
+        }
+        Ok(())
+    }
+
+    /// With `legacy_bool` enabled, rebind bool parameters aimed at
+    /// CHAR(1) / SMALLINT columns as 'T'/'F' or 1/0
+    fn bind_legacy_bool(&mut self) -> Result<(), Error> {
+        let (t, f) = match self.conn.legacy_bool() {
+            Some(chars) => chars,
+            None => return Ok(()),
+        };
+        let is_bool = |p: &(Vec<u8>, Vec<u8>, bool)| !p.2 && p.1 == [23];
+        if !self.params.iter().any(is_bool) {
+            return Ok(());
+        }
+        let inputs = self.describe_input()?;
+        for (i, p) in self.params.iter_mut().enumerate() {
+            if !is_bool(p) {
+                continue;
+            }
+            let b = p.0[0] != 0;
+            let param = match inputs.get(i).map(|c| c.type_code) {
+                Some(SQL_TYPE_TEXT) | Some(SQL_TYPE_VARYING) => {
+                    Param::Text(if b { t } else { f }.to_string())
+                }
+                Some(SQL_TYPE_SHORT) | Some(SQL_TYPE_LONG) | Some(SQL_TYPE_INT64) => {
+                    Param::Short(b as i16)
+                }
+                _ => continue,
+            };
+            *p = param.to_value_blr_isnull();
+        }
+        Ok(())
     }
 
     pub fn query<P: Params>(&mut self, params: P) -> Result<Rows, Error> {
+        self.watched(|stmt| stmt._query(params))
+    }
+
+    /// Run `f` under the watchdog of `Connection::set_kill_timeout`
+    fn watched<'s, T>(
+        &'s mut self,
+        f: impl FnOnce(&'s mut Self) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let watchdog = self.conn.kill_watchdog();
+        if let Some(watchdog) = watchdog {
+            watchdog.arm();
+        }
+        let result = f(self);
+        // killed by the watchdog
+        let killed = watchdog.is_some_and(|w| w.disarm());
+        match result {
+            Err(Error::FirebirdError(e)) if killed && e.is_cancelled() => Err(Error::Timeout),
+            result => result,
+        }
+    }
+
+    fn _query<P: Params>(&mut self, params: P) -> Result<Rows, Error> {
+        check_read_only(self.conn.is_read_only(), self.kind())?;
         params.__bind_in(self)?;
-        self.last_rowcount = self.conn._execute_statement(
-            self.trans_handle,
-            self.stmt_handle,
-            self.stmt_type,
-            self.params.as_slice(),
-        )?;
+        check_params(self.params.len(), self.conn.max_params())?;
+        self.reorder_params()?;
+        self.bind_legacy_bool()?;
+        self.bind_arrays(self.trans_handle)?;
+        self.describe_arrays(self.trans_handle)?;
+        let started = Instant::now();
+        let first_batch = self.execute_bound(self.trans_handle, self.conn.pipeline())?;
         let mut rows: VecDeque<Vec<CellValue>> = VecDeque::new();
         if self.stmt_type == ISC_INFO_SQL_STMT_SELECT {
-            rows = self.fetch_records(self.trans_handle)?;
-            self.conn._free_statement(self.stmt_handle, DSQL_CLOSE);
+            let fetched = self.fetch_records(self.trans_handle, first_batch);
+            // also after a limit stopped the fetch, so the statement can run again
+            if matches!(fetched, Ok(_) | Err(Error::LimitExceeded(..))) {
+                self.conn._free_statement(self.stmt_handle, DSQL_CLOSE);
+            }
+            rows = fetched?;
         } else if self.autocommit {
             // commit automatically
             self.conn.commit()?;
         }
+        if let Some(log) = self.conn.slow_query_log() {
+            log.record(&self.sql, &self.params, started.elapsed());
+        }
 
-        Ok(Rows::new(rows))
+        Ok(Rows::new(rows, self.description.clone())
+            .legacy_bool(self.conn.legacy_bool().is_some())
+            .strict(self.conn.strict_types()))
+    }
+
+    /// Execute with the bound parameters under `trans_handle`, preparing
+    /// again and retrying when DDL made the statement stale. With
+    /// `pipeline` the first rows of a SELECT come back with the execute.
+    fn execute_bound(&mut self, trans_handle: i32, pipeline: bool) -> Result<Option<FetchBatch>, Error> {
+        let mut retries = self.conn.reprepare_retries();
+        let mut first_batch = None;
+        self.last_rowcount = loop {
+            let start = Instant::now();
+            let mut result = if pipeline {
+                let blr = calc_blr(&self.xsqlda);
+                let fetch = (self.stmt_type == ISC_INFO_SQL_STMT_SELECT)
+                    .then_some((&blr, self.xsqlda.as_slice()));
+                self.conn
+                    ._execute_statement_pipelined(
+                        trans_handle,
+                        self.stmt_handle,
+                        self.stmt_type,
+                        self.params.as_slice(),
+                        fetch,
+                    )
+                    .map(|(rowcount, batch)| {
+                        first_batch = batch;
+                        rowcount
+                    })
+            } else {
+                self.conn._execute_statement(
+                    trans_handle,
+                    self.stmt_handle,
+                    self.stmt_type,
+                    self.params.as_slice(),
+                )
+            };
+            if let Err(e) = &mut result {
+                self.conn.record_contention(e, start.elapsed());
+            }
+            match result {
+                // metadata changed by DDL: prepare again and retry
+                Err(Error::FirebirdError(ref e)) if retries > 0 && e.is_stale_statement() => {
+                    retries -= 1;
+                    self.redescribe()?;
+                }
+                _ => break result?,
+            }
+        };
+        Ok(first_batch)
     }
 
     pub fn query_map<T, P, F>(&mut self, params: P, f: F) -> Result<MappedRows<F>, Error>
@@ -198,11 +534,46 @@ impl Statement<'_> {
         self.query(params).map(|rows| rows.mapped(f))
     }
 
+    /// Run the query and map its first row; an error if no row was returned
+    pub fn query_row<T, P, F>(&mut self, params: P, f: F) -> Result<T, Error>
+    where
+        P: Params,
+        F: FnOnce(&Row) -> Result<T, Error>,
+    {
+        match self.query(params)?.next() {
+            Some(row) => f(&row),
+            None => Err(no_rows()),
+        }
+    }
+
     pub fn execute<P: Params>(&mut self, params: P) -> Result<(), Error> {
         self.query(params)?;
         Ok(())
     }
 
+    /// Run the query under `tx` instead of the transaction it was prepared in.
+    /// A prepared statement outlives commits, so hot statements need not be
+    /// prepared again for each transaction. Nothing is committed automatically.
+    pub fn query_in<P: Params>(&mut self, tx: &mut Transaction<'_>, params: P) -> Result<Rows, Error> {
+        if !tx.is_on(self.conn) {
+            return Err(Error::ValueError(ValueError::new(
+                "Transaction belongs to another connection",
+            )));
+        }
+        let trans_handle = std::mem::replace(&mut self.trans_handle, tx.trans_handle()?);
+        let autocommit = std::mem::replace(&mut self.autocommit, false);
+        let result = self.query(params);
+        self.trans_handle = trans_handle;
+        self.autocommit = autocommit;
+        result
+    }
+
+    /// Execute under `tx` (see `query_in`)
+    pub fn execute_in<P: Params>(&mut self, tx: &mut Transaction<'_>, params: P) -> Result<(), Error> {
+        self.query_in(tx, params)?;
+        Ok(())
+    }
+
     pub fn column_count(&self) -> usize {
         self.xsqlda.len()
     }
@@ -211,6 +582,76 @@ impl Statement<'_> {
         self.xsqlda.iter().map(|x| x.aliasname.as_str()).collect()
     }
 
+    /// Name the cursor of this SELECT, so that other statements of its
+    /// transaction can refer to the current row with `WHERE CURRENT OF name`.
+    /// Takes effect from the next `open_cursor`.
+    pub fn set_cursor_name(&mut self, name: &str) {
+        self.cursor_name = Some(name.to_string());
+    }
+
+    /// Execute the SELECT and return its open cursor, which fetches one row
+    /// per round trip instead of `fetch_size` rows. With `FOR UPDATE WITH
+    /// LOCK` a row is locked when `Cursor::next_row` returns it, not before.
+    /// Opening it is prepared again when stale, logged when slow and timed by
+    /// `Connection::set_kill_timeout` as `query` is; fetching is not.
+    pub fn open_cursor<P: Params>(&mut self, params: P) -> Result<Cursor<'_, 'conn>, Error> {
+        let trans_handle = self.trans_handle;
+        self.watched(|stmt| stmt._open_cursor(trans_handle, params))
+    }
+
+    /// Open the cursor under `tx` (see `query_in` and `open_cursor`). The
+    /// cursor does not borrow `tx`, so `tx` stays usable while it is open.
+    pub fn open_cursor_in<P: Params>(
+        &mut self,
+        tx: &mut Transaction<'_>,
+        params: P,
+    ) -> Result<Cursor<'_, 'conn>, Error> {
+        if !tx.is_on(self.conn) {
+            return Err(Error::ValueError(ValueError::new(
+                "Transaction belongs to another connection",
+            )));
+        }
+        let trans_handle = tx.trans_handle()?;
+        self.watched(|stmt| stmt._open_cursor(trans_handle, params))
+    }
+
+    fn _open_cursor<P: Params>(
+        &mut self,
+        trans_handle: i32,
+        params: P,
+    ) -> Result<Cursor<'_, 'conn>, Error> {
+        params.__bind_in(self)?;
+        check_params(self.params.len(), self.conn.max_params())?;
+        self.reorder_params()?;
+        self.bind_legacy_bool()?;
+        if self.stmt_type != ISC_INFO_SQL_STMT_SELECT {
+            return Err(Error::ValueError(ValueError::new(
+                "open_cursor needs a SELECT statement",
+            )));
+        }
+        self.bind_arrays(trans_handle)?;
+        self.describe_arrays(trans_handle)?;
+        if let Some(name) = &self.cursor_name {
+            self.conn._set_cursor(self.stmt_handle, name)?;
+        }
+        let started = Instant::now();
+        // rows are fetched one at a time by the cursor, never pipelined
+        self.execute_bound(trans_handle, false)?;
+        if let Some(log) = self.conn.slow_query_log() {
+            log.record(&self.sql, &self.params, started.elapsed());
+        }
+        Ok(Cursor {
+            trans_handle,
+            blr: calc_blr(&self.xsqlda),
+            bpbs: blob::column_bpbs(&self.xsqlda, &self.blob_options),
+            fetch_size: 1,
+            buffered: VecDeque::new(),
+            fetched: 0,
+            exhausted: false,
+            stmt: self,
+        })
+    }
+
     /// Get the number of rows affected by the last execute/query operation.
     /// For SELECT statements, returns the number of rows fetched.
     /// For INSERT/UPDATE/DELETE, returns the number of affected rows.
@@ -220,46 +661,52 @@ impl Statement<'_> {
 
     /// Get column metadata for all columns (DB-API 2.0 style description).
     /// Returns a vector of ColumnInfo structs with detailed metadata for each column.
+    /// The metadata is taken from the prepare response, no server round trip is needed.
     pub fn description(&self) -> Vec<ColumnInfo> {
-        self.xsqlda
-            .iter()
-            .map(|x| {
-                // Calculate display size for character types
-                let display_size = match x.sqltype {
-                    super::SQL_TYPE_TEXT | super::SQL_TYPE_VARYING => Some(x.sqllen),
-                    _ => None,
-                };
-
-                // Calculate precision for numeric types
-                let precision = match x.sqltype {
-                    super::SQL_TYPE_SHORT => Some(5),
-                    super::SQL_TYPE_LONG => Some(10),
-                    super::SQL_TYPE_INT64 => Some(19),
-                    super::SQL_TYPE_INT128 => Some(38),
-                    super::SQL_TYPE_DEC64 => Some(16),
-                    super::SQL_TYPE_DEC128 => Some(34),
-                    super::SQL_TYPE_DEC_FIXED => Some(x.sqllen),
-                    _ => None,
-                };
-
-                ColumnInfo {
-                    name: if x.aliasname.is_empty() {
-                        x.fieldname.clone()
-                    } else {
-                        x.aliasname.clone()
-                    },
-                    type_code: x.sqltype,
-                    display_size,
-                    internal_size: x.sqllen,
-                    precision,
-                    scale: x.sqlscale,
-                    nullable: x.null_ok,
-                    field_name: x.fieldname.clone(),
-                    table_name: x.relname.clone(),
-                    owner_name: x.ownname.clone(),
-                }
-            })
-            .collect()
+        self.description.to_vec()
+    }
+
+    /// Get metadata for the input parameters (`?` placeholders).
+    /// Requested from the server on first use and cached.
+    pub fn describe_input(&mut self) -> Result<Vec<ColumnInfo>, Error> {
+        if self.input_description.is_none() {
+            let xsqlda = self.conn._describe_input(self.stmt_handle)?;
+            self.input_description = Some(xsqlda.iter().map(ColumnInfo::from).collect());
+        }
+        Ok(self.input_description.clone().unwrap())
+    }
+
+    /// Prepare the statement again and refresh the cached metadata.
+    /// Use after DDL (e.g. `ALTER TABLE`) changed the tables the statement refers to.
+    pub fn redescribe(&mut self) -> Result<(), Error> {
+        let (stmt_type, xsqlda) =
+            self.conn._reprepare(&self.sql, self.trans_handle, self.stmt_handle)?;
+        self.stmt_type = stmt_type;
+        self.description = xsqlda.iter().map(ColumnInfo::from).collect();
+        self.xsqlda = xsqlda;
+        self.input_description = None;
+        self.array_descs.clear();
+        Ok(())
+    }
+
+    /// Commit the statement's transaction, keeping it open
+    pub(crate) fn commit_retaining(&self) -> Result<(), Error> {
+        self.conn._commit(self.trans_handle)
+    }
+
+    /// Open the blobs of column `col` with `options` (e.g. through a server BLOB filter)
+    pub fn set_blob_options(&mut self, col: usize, options: BlobOptions) {
+        self.blob_options.insert(col, options);
+    }
+
+    /// Whether the statement selects, changes data or changes metadata
+    pub fn kind(&self) -> StatementKind {
+        StatementKind::from_stmt_type(self.stmt_type)
+    }
+
+    /// Get the SQL text of the statement
+    pub fn sql(&self) -> &str {
+        &self.sql
     }
 
     pub fn column_metadata(
@@ -292,41 +739,125 @@ impl Statement<'_> {
             None
         }
     }
+}
+
+pub(crate) fn no_rows() -> Error {
+    Error::ValueError(ValueError::new("Query returned no rows"))
+}
+
+/// `Err` once a query fetched more than `max_rows` rows
+pub(crate) fn check_rows(fetched: usize, max_rows: Option<usize>) -> Result<(), Error> {
+    match max_rows {
+        Some(max) if fetched > max => Err(Error::LimitExceeded(Limit::Rows, max)),
+        _ => Ok(()),
+    }
+}
 
-    fn calc_blr(&self) -> Vec<u8> {
-        let ln = self.xsqlda.len() * 2;
-        let mut blr: Vec<u8> = vec![5, 2, 4, 0, (ln & 255) as u8, (ln >> 8) as u8];
-
-        for x in &self.xsqlda {
-            let map = hashmap! {
-                SQL_TYPE_VARYING => vec![37, (x.sqllen & 255) as u8, (x.sqllen >> 8) as u8],
-                SQL_TYPE_TEXT => vec![14, (x.sqllen & 255) as u8, (x.sqllen >> 8) as u8],
-                SQL_TYPE_LONG => vec![8, x.sqlscale as u8],
-                SQL_TYPE_SHORT => vec![7, x.sqlscale as u8],
-                SQL_TYPE_INT64 => vec![16, x.sqlscale as u8],
-                SQL_TYPE_INT128 => vec![26, x.sqlscale as u8],
-                SQL_TYPE_QUAD => vec![9, x.sqlscale as u8],
-                SQL_TYPE_DEC_FIXED => vec![26, x.sqlscale as u8],
-                SQL_TYPE_DOUBLE => vec![27],
-                SQL_TYPE_FLOAT => vec![10],
-                SQL_TYPE_D_FLOAT => vec![11],
-                SQL_TYPE_DATE => vec![12],
-                SQL_TYPE_TIME => vec![13],
-                SQL_TYPE_TIMESTAMP => vec![35],
-                SQL_TYPE_BLOB => vec![9, 0],
-                SQL_TYPE_ARRAY => vec![9, 0],
-                SQL_TYPE_BOOLEAN => vec![23],
-                SQL_TYPE_DEC64 => vec![24],
-                SQL_TYPE_DEC128 => vec![25],
-                SQL_TYPE_TIME_TZ => vec![28],
-                SQL_TYPE_TIMESTAMP_TZ => vec![29],
-            };
-            blr.extend(&map[&x.sqltype]);
-            blr.extend(vec![7, 0]);
+/// `Err` if more than `max_params` parameters are bound
+/// On a read-only connection only statements that read may run
+pub(crate) fn check_read_only(read_only: bool, kind: StatementKind) -> Result<(), Error> {
+    if read_only && !kind.is_read_only() {
+        return Err(Error::ValueError(ValueError::new(&format!(
+            "{:?} statement on a read-only connection",
+            kind
+        ))));
+    }
+    Ok(())
+}
+
+/// `Err` unless `count` parameters were bound for a statement whose
+/// markers were moved in `order` by the `compat` translation
+pub(crate) fn check_param_order(order: &[usize], count: usize) -> Result<(), Error> {
+    if order.len() == count {
+        return Ok(());
+    }
+    Err(Error::ParamError(ParamError::new(&format!(
+        "{} parameters bound, the translated statement takes {}",
+        count,
+        order.len()
+    ))))
+}
+
+pub(crate) fn check_params(count: usize, max_params: Option<usize>) -> Result<(), Error> {
+    match max_params {
+        Some(max) if count > max => Err(Error::LimitExceeded(Limit::Params, max)),
+        _ => Ok(()),
+    }
+}
+
+/// Open cursor of a SELECT, see `Statement::open_cursor`. Rows are fetched
+/// one at a time by `next_row`; the cursor is closed when dropped.
+///
+/// Locks taken by `FOR UPDATE WITH LOCK` last until the transaction ends,
+/// closing the cursor does not release them.
+pub struct Cursor<'s, 'conn> {
+    stmt: &'s mut Statement<'conn>,
+    trans_handle: i32,
+    blr: Vec<u8>,
+    bpbs: Vec<Vec<u8>>,
+    fetch_size: u32,
+    // rows fetched but not returned yet
+    buffered: VecDeque<Vec<CellValue>>,
+    fetched: usize,
+    // the server has no more rows
+    exhausted: bool,
+}
+
+impl Cursor<'_, '_> {
+    /// Fetch the next row, `None` after the last one
+    pub fn next_row(&mut self) -> Result<Option<Row>, Error> {
+        let stmt = &*self.stmt;
+        if self.buffered.is_empty() && !self.exhausted {
+            let start = Instant::now();
+            let mut result =
+                stmt.conn
+                    ._fetch_rows(stmt.stmt_handle, &self.blr, &stmt.xsqlda, self.fetch_size);
+            if let Err(e) = &mut result {
+                // a row locked by another transaction fails its fetch
+                stmt.conn.record_contention(e, start.elapsed());
+            }
+            let (rows, more) = result?;
+            self.exhausted = !more || rows.is_empty();
+            self.buffered.extend(rows);
         }
-        blr.extend(vec![255, 76]);
+        let Some(mut row) = self.buffered.pop_front() else {
+            return Ok(None);
+        };
+        self.fetched += 1;
+        check_rows(self.fetched, stmt.conn.max_rows())?;
+        stmt.load_blobs(self.trans_handle, &mut row, &self.bpbs)?;
+        Ok(Some(Row::new(
+            row,
+            stmt.description.clone(),
+            stmt.conn.legacy_bool().is_some(),
+            self.fetched,
+        )
+        .strict(stmt.conn.strict_types())))
+    }
+
+    /// Rows fetched per round trip, 1 by default so that a `WITH LOCK`
+    /// row is locked only when returned. Larger values suit streaming a
+    /// big result set without holding it in memory.
+    pub fn set_fetch_size(&mut self, rows: u32) {
+        self.fetch_size = rows.max(1);
+    }
+
+    /// Metadata of the columns
+    pub fn columns(&self) -> &[ColumnInfo] {
+        &self.stmt.description
+    }
 
-        blr
+    /// Run `sql` in the cursor's transaction without committing, typically
+    /// `UPDATE ... WHERE CURRENT OF <cursor name>` on the row just fetched
+    pub fn execute<P: Params>(&mut self, sql: &str, params: P) -> Result<(), Error> {
+        let conn = self.stmt.conn;
+        conn._prepare(sql, self.trans_handle, false)?.execute(params)
+    }
+}
+
+impl Drop for Cursor<'_, '_> {
+    fn drop(&mut self) {
+        self.stmt.conn._free_statement(self.stmt.stmt_handle, DSQL_CLOSE);
     }
 }
 
@@ -26,50 +26,92 @@ use super::cellvalue::CellValue;
 use super::param::ToSqlParam;
 use super::params::Params;
 use super::row::{MappedRows, Row, Rows};
-use super::xsqlvar::XSQLVar;
+use super::statement::{
+    StatementKind, check_param_order, check_params, check_read_only, check_rows, no_rows,
+};
+use super::xsqlvar::{XSQLVar, calc_blr};
 use super::*;
 
-use async_std::task;
-use maplit::hashmap;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
 
 const DSQL_CLOSE: i32 = 1;
 const DSQL_DROP: i32 = 2;
 
 pub struct StatementAsync<'conn> {
     conn: &'conn ConnectionAsync,
+    sql: String,
     pub(crate) trans_handle: i32,
     pub(crate) stmt_handle: i32,
     stmt_type: u32,
     pub(crate) xsqlda: Vec<XSQLVar>,
     autocommit: bool,
     params: Vec<(Vec<u8>, Vec<u8>, bool)>,
+    last_rowcount: usize,
+    description: Arc<[ColumnInfo]>,
+    input_description: Option<Vec<ColumnInfo>>,
+    blob_options: HashMap<usize, BlobOptions>,
+    // a query future dropped while fetching leaves the cursor open
+    cursor_open: bool,
+    // sent with op_set_cursor before each open_cursor
+    cursor_name: Option<String>,
+    // original position of each parameter when `compat` moved them
+    param_order: Option<Vec<usize>>,
 }
 
-impl StatementAsync<'_> {
-    pub(super) fn new(
-        conn: &ConnectionAsync,
+impl<'conn> StatementAsync<'conn> {
+    pub(super) fn new<'a>(
+        conn: &'a ConnectionAsync,
+        sql: &str,
         trans_handle: i32,
         stmt_handle: i32,
         stmt_type: u32,
         xsqlda: Vec<XSQLVar>,
         autocommit: bool,
-    ) -> StatementAsync<'_> {
+    ) -> StatementAsync<'a> {
+        let description = xsqlda.iter().map(ColumnInfo::from).collect();
         StatementAsync {
             conn,
+            sql: sql.to_string(),
             trans_handle,
             stmt_handle,
             stmt_type,
             xsqlda,
             autocommit,
             params: Vec::new(),
+            last_rowcount: 0,
+            description,
+            input_description: None,
+            blob_options: HashMap::new(),
+            cursor_open: false,
+            cursor_name: None,
+            param_order: None,
         }
     }
 
+    /// See `Statement::with_param_order`
+    pub(crate) fn with_param_order(mut self, order: Option<Vec<usize>>) -> Self {
+        self.param_order = order;
+        self
+    }
+
+    /// See `Statement::reorder_params`
+    fn reorder_params(&mut self) -> Result<(), Error> {
+        let Some(order) = &self.param_order else {
+            return Ok(());
+        };
+        check_param_order(order, self.params.len())?;
+        let mut params = std::mem::take(&mut self.params);
+        self.params = order.iter().map(|&i| std::mem::take(&mut params[i])).collect();
+        Ok(())
+    }
+
     #[inline]
     pub(crate) fn bind_parameters(&mut self, params: &[&dyn ToSqlParam]) -> Result<(), Error> {
         self.params = Vec::new();
         for p in params.iter() {
+            p.check_range()?;
+            check_not_array(p.array())?;
             self.params.push(p.to_value_blr_isnull());
         }
         Ok(())
@@ -81,47 +123,84 @@ impl StatementAsync<'_> {
     }
 
     pub(crate) fn put_parameter<T: ToSqlParam>(&mut self, param: T) -> Result<(), Error> {
+        param.check_range()?;
+        check_not_array(param.array())?;
         self.params.push(param.to_value_blr_isnull());
         Ok(())
     }
 
     async fn fetch_records(&self, trans_handle: i32) -> Result<VecDeque<Vec<CellValue>>, Error> {
         let mut rows = VecDeque::new();
-        let blr = self.calc_blr();
+        let blr = calc_blr(&self.xsqlda);
 
+        let max_rows = self.conn.max_rows();
         loop {
             let (rows_segment, more_data) = self
                 .conn
                 ._fetch(self.stmt_handle, &blr, &self.xsqlda)
                 .await?;
             rows.extend(rows_segment);
+            check_rows(rows.len(), max_rows)?;
             if !more_data {
                 break;
             }
         }
 
+        let bpbs = blob::column_bpbs(&self.xsqlda, &self.blob_options);
         for row in rows.iter_mut() {
-            for cell in row.iter_mut() {
-                match cell {
-                    CellValue::BlobBinary(blob_id) => {
-                        let blob = self.conn._get_blob_segments(&blob_id, trans_handle).await;
-                        *cell = CellValue::BlobBinary(blob.unwrap());
-                    }
-                    CellValue::BlobText(blob_id) => {
-                        let blob = self.conn._get_blob_segments(&blob_id, trans_handle).await;
-                        *cell = CellValue::BlobText(blob.unwrap());
-                    }
-                    _ => {}
+            self.load_blobs(trans_handle, row, &bpbs).await?;
+        }
+
+        Ok(rows)
+    }
+
+    /// Replace the blob ids of `row` by the blob contents
+    async fn load_blobs(
+        &self,
+        trans_handle: i32,
+        row: &mut [CellValue],
+        bpbs: &[Vec<u8>],
+    ) -> Result<(), Error> {
+        for (i, cell) in row.iter_mut().enumerate() {
+            match cell {
+                CellValue::BlobBinary(blob_id) => {
+                    let blob = self
+                        .conn
+                        ._get_blob_segments(blob_id, trans_handle, &bpbs[i])
+                        .await?;
+                    *cell = CellValue::BlobBinary(blob);
+                }
+                CellValue::BlobText(blob_id) => {
+                    let blob = self
+                        .conn
+                        ._get_blob_segments(blob_id, trans_handle, &bpbs[i])
+                        .await?;
+                    *cell = CellValue::BlobText(self.xsqlda[i].blob_text(blob));
                 }
+                _ => {}
             }
         }
+        Ok(())
+    }
 
-        Ok(rows)
+    /// Close a cursor left open by a dropped future or cursor
+    async fn close_stale_cursor(&mut self) {
+        if self.cursor_open {
+            self.conn
+                ._free_statement(self.stmt_handle, DSQL_CLOSE)
+                .await;
+            self.cursor_open = false;
+        }
     }
 
     pub async fn query<P: Params>(&mut self, params: P) -> Result<Rows, Error> {
+        check_read_only(self.conn.is_read_only(), self.kind())?;
         params.__bind_in_async(self)?;
-        self.conn
+        check_params(self.params.len(), self.conn.max_params())?;
+        self.reorder_params()?;
+        self.close_stale_cursor().await;
+        self.last_rowcount = self
+            .conn
             ._execute_statement(
                 self.trans_handle,
                 self.stmt_handle,
@@ -131,16 +210,18 @@ impl StatementAsync<'_> {
             .await?;
         let mut rows: VecDeque<Vec<CellValue>> = VecDeque::new();
         if self.stmt_type == ISC_INFO_SQL_STMT_SELECT {
+            self.cursor_open = true;
             rows = self.fetch_records(self.trans_handle).await?;
             self.conn
                 ._free_statement(self.stmt_handle, DSQL_CLOSE)
                 .await;
+            self.cursor_open = false;
         } else if self.autocommit {
             // commit automatically
             self.conn.commit().await?;
         }
 
-        Ok(Rows::new(rows))
+        Ok(Rows::new(rows, self.description.clone()).strict(self.conn.strict_types()))
     }
 
     pub async fn query_map<T, P, F>(&mut self, params: P, f: F) -> Result<MappedRows<F>, Error>
@@ -151,11 +232,66 @@ impl StatementAsync<'_> {
         self.query(params).await.map(|rows| rows.mapped(f))
     }
 
+    /// Run the query and map its first row; an error if no row was returned
+    pub async fn query_row<T, P, F>(&mut self, params: P, f: F) -> Result<T, Error>
+    where
+        P: Params,
+        F: FnOnce(&Row) -> Result<T, Error>,
+    {
+        match self.query(params).await?.next() {
+            Some(row) => f(&row),
+            None => Err(no_rows()),
+        }
+    }
+
     pub async fn execute<P: Params>(&mut self, params: P) -> Result<(), Error> {
         self.query(params).await?;
         Ok(())
     }
 
+    /// Name the cursor of this SELECT for `WHERE CURRENT OF name`, from
+    /// the next `open_cursor` (see `Statement::set_cursor_name`)
+    pub fn set_cursor_name(&mut self, name: &str) {
+        self.cursor_name = Some(name.to_string());
+    }
+
+    /// Execute the SELECT and return its open cursor, fetching one row per
+    /// round trip (see `Statement::open_cursor`)
+    pub async fn open_cursor<P: Params>(
+        &mut self,
+        params: P,
+    ) -> Result<CursorAsync<'_, 'conn>, Error> {
+        params.__bind_in_async(self)?;
+        check_params(self.params.len(), self.conn.max_params())?;
+        self.reorder_params()?;
+        if self.stmt_type != ISC_INFO_SQL_STMT_SELECT {
+            return Err(Error::ValueError(ValueError::new(
+                "open_cursor needs a SELECT statement",
+            )));
+        }
+        self.close_stale_cursor().await;
+        if let Some(name) = &self.cursor_name {
+            self.conn._set_cursor(self.stmt_handle, name).await?;
+        }
+        self.last_rowcount = self
+            .conn
+            ._execute_statement(
+                self.trans_handle,
+                self.stmt_handle,
+                self.stmt_type,
+                self.params.as_slice(),
+            )
+            .await?;
+        self.cursor_open = true;
+        Ok(CursorAsync {
+            blr: calc_blr(&self.xsqlda),
+            bpbs: blob::column_bpbs(&self.xsqlda, &self.blob_options),
+            fetched: 0,
+            exhausted: false,
+            stmt: self,
+        })
+    }
+
     pub fn column_count(&self) -> usize {
         self.xsqlda.len()
     }
@@ -164,6 +300,56 @@ impl StatementAsync<'_> {
         self.xsqlda.iter().map(|x| x.aliasname.as_str()).collect()
     }
 
+    /// Get the number of rows affected by the last execute/query operation.
+    /// For SELECT statements, returns the number of rows fetched.
+    /// For INSERT/UPDATE/DELETE, returns the number of affected rows.
+    pub fn rowcount(&self) -> usize {
+        self.last_rowcount
+    }
+
+    /// Get column metadata for all columns, taken from the prepare response
+    pub fn description(&self) -> Vec<ColumnInfo> {
+        self.description.to_vec()
+    }
+
+    /// Get metadata for the input parameters (`?` placeholders).
+    /// Requested from the server on first use and cached.
+    pub async fn describe_input(&mut self) -> Result<Vec<ColumnInfo>, Error> {
+        if self.input_description.is_none() {
+            let xsqlda = self.conn._describe_input(self.stmt_handle).await?;
+            self.input_description = Some(xsqlda.iter().map(ColumnInfo::from).collect());
+        }
+        Ok(self.input_description.clone().unwrap())
+    }
+
+    /// Prepare the statement again and refresh the cached metadata
+    pub async fn redescribe(&mut self) -> Result<(), Error> {
+        let (stmt_type, xsqlda) = self
+            .conn
+            ._reprepare(&self.sql, self.trans_handle, self.stmt_handle)
+            .await?;
+        self.stmt_type = stmt_type;
+        self.description = xsqlda.iter().map(ColumnInfo::from).collect();
+        self.xsqlda = xsqlda;
+        self.input_description = None;
+        Ok(())
+    }
+
+    /// Open the blobs of column `col` with `options` (e.g. through a server BLOB filter)
+    pub fn set_blob_options(&mut self, col: usize, options: BlobOptions) {
+        self.blob_options.insert(col, options);
+    }
+
+    /// See `Statement::kind`
+    pub fn kind(&self) -> StatementKind {
+        StatementKind::from_stmt_type(self.stmt_type)
+    }
+
+    /// Get the SQL text of the statement
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
     pub fn column_metadata(
         &self,
         col: usize,
@@ -194,46 +380,84 @@ impl StatementAsync<'_> {
             None
         }
     }
+}
 
-    fn calc_blr(&self) -> Vec<u8> {
-        let ln = self.xsqlda.len() * 2;
-        let mut blr: Vec<u8> = vec![5, 2, 4, 0, (ln & 255) as u8, (ln >> 8) as u8];
-
-        for x in &self.xsqlda {
-            let map = hashmap! {
-                SQL_TYPE_VARYING => vec![37, (x.sqllen & 255) as u8, (x.sqllen >> 8) as u8],
-                SQL_TYPE_TEXT => vec![14, (x.sqllen & 255) as u8, (x.sqllen >> 8) as u8],
-                SQL_TYPE_LONG => vec![8, x.sqlscale as u8],
-                SQL_TYPE_SHORT => vec![7, x.sqlscale as u8],
-                SQL_TYPE_INT64 => vec![16, x.sqlscale as u8],
-                SQL_TYPE_INT128 => vec![26, x.sqlscale as u8],
-                SQL_TYPE_QUAD => vec![9, x.sqlscale as u8],
-                SQL_TYPE_DEC_FIXED => vec![26, x.sqlscale as u8],
-                SQL_TYPE_DOUBLE => vec![27],
-                SQL_TYPE_FLOAT => vec![10],
-                SQL_TYPE_D_FLOAT => vec![11],
-                SQL_TYPE_DATE => vec![12],
-                SQL_TYPE_TIME => vec![13],
-                SQL_TYPE_TIMESTAMP => vec![35],
-                SQL_TYPE_BLOB => vec![9, 0],
-                SQL_TYPE_ARRAY => vec![9, 0],
-                SQL_TYPE_BOOLEAN => vec![23],
-                SQL_TYPE_DEC64 => vec![24],
-                SQL_TYPE_DEC128 => vec![25],
-                SQL_TYPE_TIME_TZ => vec![28],
-                SQL_TYPE_TIMESTAMP_TZ => vec![29],
-            };
-            blr.extend(&map[&x.sqltype]);
-            blr.extend(vec![7, 0]);
+/// Open cursor of a SELECT, see `StatementAsync::open_cursor` and `Cursor`
+pub struct CursorAsync<'s, 'conn> {
+    stmt: &'s mut StatementAsync<'conn>,
+    blr: Vec<u8>,
+    bpbs: Vec<Vec<u8>>,
+    fetched: usize,
+    exhausted: bool,
+}
+
+impl CursorAsync<'_, '_> {
+    /// Fetch the next row, `None` after the last one
+    pub async fn next_row(&mut self) -> Result<Option<Row>, Error> {
+        if self.exhausted {
+            return Ok(None);
         }
-        blr.extend(vec![255, 76]);
+        let stmt = &*self.stmt;
+        let fetched = stmt
+            .conn
+            ._fetch_one(stmt.stmt_handle, &self.blr, &stmt.xsqlda)
+            .await?;
+        let Some(mut row) = fetched else {
+            self.exhausted = true;
+            return Ok(None);
+        };
+        self.fetched += 1;
+        check_rows(self.fetched, stmt.conn.max_rows())?;
+        stmt.load_blobs(stmt.trans_handle, &mut row, &self.bpbs).await?;
+        Ok(Some(
+            Row::new(row, stmt.description.clone(), false, self.fetched)
+                .strict(stmt.conn.strict_types()),
+        ))
+    }
+
+    /// Metadata of the columns
+    pub fn columns(&self) -> &[ColumnInfo] {
+        &self.stmt.description
+    }
+
+    /// Run `sql` in the cursor's transaction without committing, typically
+    /// `UPDATE ... WHERE CURRENT OF <cursor name>` on the row just fetched
+    pub async fn execute<P: Params>(&mut self, sql: &str, params: P) -> Result<(), Error> {
+        let conn = self.stmt.conn;
+        let mut stmt = conn._prepare(sql, self.stmt.trans_handle).await?;
+        stmt.autocommit = false;
+        stmt.execute(params).await
+    }
 
-        blr
+    /// Close the cursor now instead of from `Drop`
+    pub async fn close(self) {
+        self.stmt.close_stale_cursor().await;
+    }
+}
+
+impl Drop for CursorAsync<'_, '_> {
+    fn drop(&mut self) {
+        // stays marked open, for the next query to close, if the runtime
+        // cannot block here
+        let conn = self.stmt.conn;
+        conn.block_on(Box::pin(self.stmt.close_stale_cursor()));
     }
 }
 
 impl Drop for StatementAsync<'_> {
     fn drop(&mut self) {
-        task::block_on(self.conn._free_statement(self.stmt_handle, DSQL_DROP));
+        self.conn
+            .block_on(Box::pin(self.conn._free_statement(self.stmt_handle, DSQL_DROP)));
+    }
+}
+
+// storing an ARRAY takes metadata queries and slice ops only the blocking
+// Statement makes
+fn check_not_array(array: Option<&Array>) -> Result<(), Error> {
+    match array {
+        Some(_) => Err(Error::ValueError(ValueError::new(
+            "ARRAY parameters need the blocking Connection",
+        ))),
+        None => Ok(()),
     }
 }
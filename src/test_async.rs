@@ -20,7 +20,6 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 use super::*;
-use async_std::task;
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -41,6 +40,23 @@ struct Foo {
     j: f32,
 }
 
+impl FromRow for Foo {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        Ok(Foo {
+            a: row.get(0)?,
+            b: row.get(1)?,
+            c: row.get(2)?,
+            d: row.get(3)?,
+            e: row.get(4)?,
+            f: row.get(5)?,
+            g: row.get(6)?,
+            h: row.get(7)?,
+            i: row.get(8)?,
+            j: row.get(9)?,
+        })
+    }
+}
+
 async fn test_connnect_async() {
     let user = match env::var("ISC_USER") {
         Ok(val) => val,
@@ -259,6 +275,16 @@ async fn test_connnect_async() {
         for (i, foo) in foo_iter.enumerate() {
             assert_eq!(foo.unwrap(), expects[i]);
         }
+
+        let foo = stmt.query_row((2,), Foo::from_row).await.unwrap();
+        assert_eq!(foo, expects[0]);
+        assert!(stmt.query_row((-1,), Foo::from_row).await.is_err());
+        assert_eq!(stmt.sql(), "select * from foo where a=?");
+        assert_eq!(stmt.description().len(), 10);
+        assert_eq!(stmt.description()[0].name, "A");
+        let inputs = stmt.describe_input().await.unwrap();
+        assert_eq!(inputs.len(), 1);
+        assert_eq!(inputs[0].type_code, SQL_TYPE_LONG);
     }
 
     // Transction
@@ -308,7 +334,19 @@ async fn test_connnect_async() {
     }
 }
 
+// on the runtime ConnectionAsync picks: tokio when rt-tokio is enabled
+#[cfg(not(feature = "rt-tokio"))]
+#[test]
+fn test_connnect() {
+    async_std::task::block_on(test_connnect_async())
+}
+
+#[cfg(feature = "rt-tokio")]
 #[test]
 fn test_connnect() {
-    task::block_on(test_connnect_async())
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(test_connnect_async())
 }
@@ -0,0 +1,577 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Round-trip test harness (feature `testing`)
+//!
+//! `proptest` strategies for values of every supported type, fixed test
+//! vectors at the edges of their ranges, and two harnesses that bind a
+//! value as a parameter and read it back as a column:
+//!
+//! - `wire_round_trip` encodes the parameter message and decodes it as a
+//!   fetched row, with no server. A CHAR parameter comes back as a VARCHAR
+//!   of its length; BLOB data has a protocol of its own and is left to
+//!   `server_round_trip`.
+//! - `server_round_trip` has a live server `CAST` the parameter to an SQL
+//!   type and fetches the result, so the server's conversions are covered
+//!   too. INT128, NUMERIC(38) and DECFLOAT need Firebird 4.0+.
+//!
+//! Both are generic over `ToSqlParam` and `CellValueToVal`, so a type
+//! implemented outside the crate is tested the same way as `SqlValue`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // Cargo.toml:
+//! // [dev-dependencies]
+//! // firebirust = { version = "0.5", features = ["testing"] }
+//! use firebirust::testing::{self, SqlValue};
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn my_type_round_trips(v in my_type_strategy()) {
+//!         prop_assert_eq!(testing::wire_round_trip::<_, MyType>(&v).unwrap(), v);
+//!     }
+//!
+//!     #[test]
+//!     fn builtin_types_round_trip(v in testing::wire_values()) {
+//!         prop_assert_eq!(v.wire_round_trip().unwrap(), v);
+//!     }
+//! }
+//! ```
+
+use super::cellvalue::{CellValue, CellValueToVal};
+use super::decfloat::DecFloat;
+use super::error::{Error, ValueError};
+use super::param::{Param, ToSqlParam};
+use super::protocol::{self, Message};
+use super::row::Row;
+use super::xsqlvar::XSQLVar;
+use super::*;
+use bytes::BytesMut;
+use chrono::TimeZone;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use proptest::prelude::*;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+
+/// Character set of a `SqlValue::Text` column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Ascii,
+    Iso8859_1,
+    Win1251,
+    Utf8,
+}
+
+impl Charset {
+    /// Name in `CHARACTER SET` clauses
+    pub fn name(&self) -> &'static str {
+        match self {
+            Charset::Ascii => "ASCII",
+            Charset::Iso8859_1 => "ISO8859_1",
+            Charset::Win1251 => "WIN1251",
+            Charset::Utf8 => "UTF8",
+        }
+    }
+
+    /// Characters the character set can store
+    pub fn chars(&self) -> BoxedStrategy<char> {
+        match self {
+            Charset::Ascii => (0x20u8..0x7f).prop_map(char::from).boxed(),
+            Charset::Iso8859_1 => prop_oneof![0x20u8..0x7f, 0xa0u8..=0xff]
+                .prop_map(char::from)
+                .boxed(),
+            Charset::Win1251 => prop_oneof![
+                proptest::char::range(' ', '~'),
+                proptest::char::range('\u{410}', '\u{44f}'),
+            ]
+            .boxed(),
+            Charset::Utf8 => any::<char>().prop_filter("NUL", |c| *c != '\0').boxed(),
+        }
+    }
+}
+
+/// A value of one of the supported types, with the SQL type that stores it
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Short(i16),
+    Long(i32),
+    Int64(i64),
+    Int128(i128),
+    Float(f32),
+    Double(f64),
+    /// NUMERIC(18, scale), or NUMERIC(38, scale) when it doesn't fit
+    Numeric(Decimal),
+    /// DECFLOAT(34), bound as `DecFloat`
+    DecFloat(Decimal),
+    /// VARCHAR of the string's length
+    Text(String, Charset),
+    /// BLOB SUB_TYPE BINARY
+    Blob(Vec<u8>),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    TimeStamp(NaiveDateTime),
+    TimeTz(NaiveTime, Tz),
+    TimeStampTz(DateTime<Tz>),
+    Boolean(bool),
+}
+
+impl SqlValue {
+    /// SQL type of a column holding the value
+    pub fn sql_type(&self) -> String {
+        match self {
+            SqlValue::Short(_) => "SMALLINT".to_string(),
+            SqlValue::Long(_) => "INTEGER".to_string(),
+            SqlValue::Int64(_) => "BIGINT".to_string(),
+            SqlValue::Int128(_) => "INT128".to_string(),
+            SqlValue::Float(_) => "FLOAT".to_string(),
+            SqlValue::Double(_) => "DOUBLE PRECISION".to_string(),
+            SqlValue::Numeric(d) => {
+                let fits_int64 = i64::try_from(d.mantissa()).is_ok();
+                let precision = if fits_int64 && d.scale() <= 18 {
+                    18
+                } else {
+                    38
+                };
+                format!("NUMERIC({}, {})", precision, d.scale())
+            }
+            SqlValue::DecFloat(_) => "DECFLOAT(34)".to_string(),
+            SqlValue::Text(s, charset) => format!(
+                "VARCHAR({}) CHARACTER SET {}",
+                s.chars().count().max(1),
+                charset.name()
+            ),
+            SqlValue::Blob(_) => "BLOB SUB_TYPE BINARY".to_string(),
+            SqlValue::Date(_) => "DATE".to_string(),
+            SqlValue::Time(_) => "TIME".to_string(),
+            SqlValue::TimeStamp(_) => "TIMESTAMP".to_string(),
+            SqlValue::TimeTz(..) => "TIME WITH TIME ZONE".to_string(),
+            SqlValue::TimeStampTz(_) => "TIMESTAMP WITH TIME ZONE".to_string(),
+            SqlValue::Boolean(_) => "BOOLEAN".to_string(),
+        }
+    }
+
+    /// The value after `wire_round_trip`
+    pub fn wire_round_trip(&self) -> Result<SqlValue, Error> {
+        self.read(&wire_row(self)?)
+    }
+
+    /// The value after `server_round_trip` to its `sql_type`
+    pub fn server_round_trip(&self, conn: &Connection) -> Result<SqlValue, Error> {
+        self.read(&server_row(conn, &self.sql_type(), self)?)
+    }
+
+    /// Column 0 of `row` read as the same kind of value
+    fn read(&self, row: &Row) -> Result<SqlValue, Error> {
+        Ok(match self {
+            SqlValue::Short(_) => SqlValue::Short(row.get(0)?),
+            SqlValue::Long(_) => SqlValue::Long(row.get(0)?),
+            SqlValue::Int64(_) => SqlValue::Int64(row.get(0)?),
+            SqlValue::Int128(_) => SqlValue::Int128(row.get(0)?),
+            SqlValue::Float(_) => SqlValue::Float(row.get(0)?),
+            SqlValue::Double(_) => SqlValue::Double(row.get(0)?),
+            // NUMERIC parameters are bound as text, which is what the wire
+            // harness gets back
+            SqlValue::Numeric(_) => match row.get::<String>(0) {
+                Ok(s) => SqlValue::Numeric(s.parse().map_err(|_| {
+                    Error::ValueError(ValueError::new(&format!("{} is not a NUMERIC", s)))
+                })?),
+                Err(_) => SqlValue::Numeric(row.get(0)?),
+            },
+            SqlValue::DecFloat(_) => SqlValue::DecFloat(row.get(0)?),
+            SqlValue::Text(_, charset) => SqlValue::Text(row.get(0)?, *charset),
+            SqlValue::Blob(_) => SqlValue::Blob(row.get(0)?),
+            SqlValue::Date(_) => SqlValue::Date(row.get(0)?),
+            SqlValue::Time(_) => SqlValue::Time(row.get(0)?),
+            SqlValue::TimeStamp(_) => SqlValue::TimeStamp(row.get(0)?),
+            SqlValue::TimeTz(..) => {
+                let (t, tz) = row.get(0)?;
+                SqlValue::TimeTz(t, tz)
+            }
+            SqlValue::TimeStampTz(_) => SqlValue::TimeStampTz(row.get(0)?),
+            SqlValue::Boolean(_) => SqlValue::Boolean(row.get(0)?),
+        })
+    }
+
+    fn param(&self) -> Param {
+        match self {
+            SqlValue::Short(v) => Param::Short(*v),
+            SqlValue::Long(v) => Param::Long(*v),
+            SqlValue::Int64(v) => Param::Int64(*v),
+            SqlValue::Int128(v) => Param::Int128(*v),
+            SqlValue::Float(v) => Param::Float(*v),
+            SqlValue::Double(v) => Param::Double(*v),
+            SqlValue::Numeric(v) | SqlValue::DecFloat(v) => Param::Decimal(*v),
+            SqlValue::Text(v, _) => Param::Text(v.clone()),
+            SqlValue::Blob(v) => Param::Blob(v.clone()),
+            SqlValue::Date(v) => Param::Date(*v),
+            SqlValue::Time(v) => Param::Time(*v),
+            SqlValue::TimeStamp(v) => Param::TimeStamp(*v),
+            SqlValue::TimeTz(t, tz) => Param::TimeTz(*t, *tz),
+            SqlValue::TimeStampTz(v) => Param::TimeStampTZ(*v),
+            SqlValue::Boolean(v) => Param::Boolean(*v),
+        }
+    }
+}
+
+impl ToSqlParam for SqlValue {
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        match self {
+            SqlValue::DecFloat(v) => DecFloat(*v).to_value_blr_isnull(),
+            _ => self.param().to_value_blr_isnull(),
+        }
+    }
+
+    fn check_range(&self) -> Result<(), Error> {
+        self.param().check_range()
+    }
+}
+
+/// `param` bound the way a statement binds it, then decoded the way a
+/// fetched row is decoded, read as `T`. No server is involved.
+pub fn wire_round_trip<P, T>(param: &P) -> Result<T, Error>
+where
+    P: ToSqlParam + ?Sized,
+    CellValue: CellValueToVal<T>,
+{
+    wire_row(param)?.get(0)
+}
+
+/// `param` cast to `sql_type` by the server the connection is attached to,
+/// then fetched and read as `T`
+pub fn server_round_trip<P, T>(conn: &Connection, sql_type: &str, param: &P) -> Result<T, Error>
+where
+    P: ToSqlParam,
+    CellValue: CellValueToVal<T>,
+{
+    server_row(conn, sql_type, param)?.get(0)
+}
+
+/// op_sql_response carrying the parameter message of `param` as its row
+fn wire_row<P: ToSqlParam + ?Sized>(param: &P) -> Result<Row, Error> {
+    param.check_range()?;
+    let (value, blr, isnull) = param.to_value_blr_isnull();
+    let mut x = XSQLVar::new();
+    x.null_ok = true;
+    let mut buf = BytesMut::new();
+    xdr::put_u32(&mut buf, OP_SQL_RESPONSE);
+    xdr::put_u32(&mut buf, 1);
+    // null bitmap, padded to 4 bytes
+    buf.extend_from_slice(&[isnull as u8, 0, 0, 0]);
+    let scale = || blr.get(1).map_or(0, |s| *s as i8 as i32);
+    match blr.first() {
+        Some(14) => {
+            let len = blr[1] as usize | (blr[2] as usize) << 8;
+            x.sqltype = SQL_TYPE_VARYING;
+            x.sqllen = len as i32;
+            if !isnull {
+                xdr::put_opaque(&mut buf, &value[..len]);
+            }
+        }
+        Some(code) => {
+            (x.sqltype, x.sqlscale) = match code {
+                7 => (SQL_TYPE_SHORT, scale()),
+                8 => (SQL_TYPE_LONG, scale()),
+                16 => (SQL_TYPE_INT64, scale()),
+                26 => (SQL_TYPE_INT128, scale()),
+                10 => (SQL_TYPE_FLOAT, 0),
+                27 => (SQL_TYPE_DOUBLE, 0),
+                12 => (SQL_TYPE_DATE, 0),
+                13 => (SQL_TYPE_TIME, 0),
+                35 => (SQL_TYPE_TIMESTAMP, 0),
+                28 => (SQL_TYPE_TIME_TZ, 0),
+                29 => (SQL_TYPE_TIMESTAMP_TZ, 0),
+                23 => (SQL_TYPE_BOOLEAN, 0),
+                24 => (SQL_TYPE_DEC64, 0),
+                25 => (SQL_TYPE_DEC128, 0),
+                _ => {
+                    return Err(Error::ValueError(ValueError::new(&format!(
+                        "no column type for parameter BLR {:?}",
+                        blr
+                    ))));
+                }
+            };
+            x.sqllen = x.io_length() as i32;
+            if !isnull {
+                buf.extend_from_slice(&value);
+            }
+        }
+        None => {
+            return Err(Error::ValueError(ValueError::new("parameter without BLR")));
+        }
+    }
+    let xsqlda = [x];
+    // the message is incomplete until its last byte arrived
+    for n in [0, buf.len() / 2, buf.len() - 1] {
+        if protocol::decode_message(&buf[..n], &xsqlda)?.is_some() {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "{} of {} bytes decoded as a whole message",
+                n,
+                buf.len()
+            ))));
+        }
+    }
+    match protocol::decode_message(&buf, &xsqlda)? {
+        Some((Message::SqlResponse(row), n)) if n == buf.len() => {
+            Ok(Row::new(row, Arc::new([]), false, 1))
+        }
+        other => Err(Error::ValueError(ValueError::new(&format!(
+            "unexpected message {:?}",
+            other
+        )))),
+    }
+}
+
+fn server_row<P: ToSqlParam + ?Sized>(
+    conn: &Connection,
+    sql_type: &str,
+    param: &P,
+) -> Result<Row, Error> {
+    let sql = format!("select cast(? as {}) from rdb$database", sql_type);
+    let mut stmt = conn.prepare(&sql)?;
+    let params: &[&dyn ToSqlParam] = &[&ParamRef(param)];
+    let row = stmt.query(params)?.next();
+    row.ok_or_else(|| Error::ValueError(ValueError::new("no row")))
+}
+
+// `&P` for `&[&dyn ToSqlParam]`, which needs a sized parameter
+struct ParamRef<'a, P: ?Sized>(&'a P);
+
+impl<P: ToSqlParam + ?Sized> ToSqlParam for ParamRef<'_, P> {
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        self.0.to_value_blr_isnull()
+    }
+
+    fn check_range(&self) -> Result<(), Error> {
+        self.0.check_range()
+    }
+
+    fn array(&self) -> Option<&Array> {
+        self.0.array()
+    }
+}
+
+/// Firebird dates, 0001-01-01 to 9999-12-31
+pub fn dates() -> impl Strategy<Value = NaiveDate> {
+    let first = NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
+    (0i64..=3_652_058).prop_map(move |d| first + chrono::Duration::days(d))
+}
+
+/// Times of day in Firebird's 1/10000 second units
+pub fn times() -> impl Strategy<Value = NaiveTime> {
+    (0u32..86_400, 0u32..10_000)
+        .prop_map(|(s, n)| NaiveTime::from_num_seconds_from_midnight_opt(s, n * 100_000).unwrap())
+}
+
+/// Region and offset time zones
+pub fn time_zones() -> impl Strategy<Value = Tz> {
+    prop_oneof![
+        Just(chrono_tz::UTC),
+        Just(chrono_tz::Asia::Seoul),
+        Just(chrono_tz::America::New_York),
+        Just(chrono_tz::Australia::Adelaide),
+        Just(chrono_tz::Etc::GMTMinus3),
+        Just(chrono_tz::Etc::GMTPlus11),
+    ]
+}
+
+/// Any `Decimal`: up to 96 bit coefficients, scales 0 to 28
+pub fn decimals() -> impl Strategy<Value = Decimal> {
+    let max = (1i128 << 96) - 1;
+    prop_oneof![
+        (any::<i64>(), 0u32..=18).prop_map(|(m, s)| Decimal::new(m, s)),
+        (-max..=max, 0u32..=28).prop_map(|(m, s)| Decimal::from_i128_with_scale(m, s)),
+        Just(Decimal::MAX),
+        Just(Decimal::MIN),
+        Just(Decimal::new(1, 28)),
+    ]
+}
+
+/// Strings of up to `max_len` characters `charset` can store
+pub fn texts(charset: Charset, max_len: usize) -> impl Strategy<Value = String> {
+    proptest::collection::vec(charset.chars(), 0..=max_len).prop_map(|v| v.into_iter().collect())
+}
+
+fn charsets() -> impl Strategy<Value = Charset> {
+    prop_oneof![
+        Just(Charset::Ascii),
+        Just(Charset::Iso8859_1),
+        Just(Charset::Win1251),
+        Just(Charset::Utf8),
+    ]
+}
+
+fn finite_f32() -> impl Strategy<Value = f32> {
+    use proptest::num::f32;
+    f32::POSITIVE | f32::NEGATIVE | f32::NORMAL | f32::SUBNORMAL | f32::ZERO
+}
+
+fn finite_f64() -> impl Strategy<Value = f64> {
+    use proptest::num::f64;
+    f64::POSITIVE | f64::NEGATIVE | f64::NORMAL | f64::SUBNORMAL | f64::ZERO
+}
+
+/// Values `wire_round_trip` returns unchanged: all types but BLOB
+pub fn wire_values() -> BoxedStrategy<SqlValue> {
+    prop_oneof![
+        any::<i16>().prop_map(SqlValue::Short),
+        any::<i32>().prop_map(SqlValue::Long),
+        any::<i64>().prop_map(SqlValue::Int64),
+        any::<i128>().prop_map(SqlValue::Int128),
+        finite_f32().prop_map(SqlValue::Float),
+        finite_f64().prop_map(SqlValue::Double),
+        decimals().prop_map(SqlValue::Numeric),
+        decimals().prop_map(SqlValue::DecFloat),
+        charsets().prop_flat_map(|c| texts(c, 2000).prop_map(move |s| SqlValue::Text(s, c))),
+        dates().prop_map(SqlValue::Date),
+        times().prop_map(SqlValue::Time),
+        (dates(), times()).prop_map(|(d, t)| SqlValue::TimeStamp(d.and_time(t))),
+        (times(), time_zones()).prop_map(|(t, tz)| SqlValue::TimeTz(t, tz)),
+        (dates(), times(), time_zones())
+            .prop_map(|(d, t, tz)| SqlValue::TimeStampTz(tz.from_utc_datetime(&d.and_time(t)))),
+        any::<bool>().prop_map(SqlValue::Boolean),
+    ]
+    .boxed()
+}
+
+/// Values of all types, BLOBs up to the 32765 bytes a parameter holds
+pub fn sql_values() -> BoxedStrategy<SqlValue> {
+    prop_oneof![
+        12 => wire_values(),
+        1 => proptest::collection::vec(any::<u8>(), 0..=32_765).prop_map(SqlValue::Blob),
+    ]
+    .boxed()
+}
+
+/// Fixed values at the edges of each type's range
+pub fn test_vectors() -> Vec<SqlValue> {
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+    let last_time = NaiveTime::from_hms_micro_opt(23, 59, 59, 999_900).unwrap();
+    let bytes: Vec<u8> = (0..=255).collect();
+    vec![
+        SqlValue::Short(i16::MIN),
+        SqlValue::Short(i16::MAX),
+        SqlValue::Long(i32::MIN),
+        SqlValue::Long(i32::MAX),
+        SqlValue::Int64(i64::MIN),
+        SqlValue::Int64(i64::MAX),
+        SqlValue::Int128(i128::MIN),
+        SqlValue::Int128(i128::MAX),
+        SqlValue::Float(f32::MAX),
+        SqlValue::Float(f32::MIN_POSITIVE),
+        SqlValue::Float(-f32::from_bits(1)),
+        SqlValue::Double(f64::MIN),
+        SqlValue::Double(f64::from_bits(1)),
+        SqlValue::Double(-0.0),
+        SqlValue::Numeric(Decimal::new(i64::MAX, 4)),
+        SqlValue::Numeric(Decimal::new(i64::MIN, 18)),
+        SqlValue::Numeric(Decimal::new(-5, 1)),
+        SqlValue::Numeric(Decimal::MAX),
+        SqlValue::Numeric(Decimal::new(-1, 28)),
+        SqlValue::DecFloat(Decimal::MIN),
+        SqlValue::DecFloat(Decimal::new(1, 28)),
+        SqlValue::DecFloat(Decimal::new(8_000_000_000_000_000, 0)),
+        SqlValue::Text(String::new(), Charset::Utf8),
+        SqlValue::Text("  padded  ".to_string(), Charset::Ascii),
+        SqlValue::Text("\u{1d11e}".repeat(8191), Charset::Utf8),
+        SqlValue::Text("ÿ¡Ñ".to_string(), Charset::Iso8859_1),
+        SqlValue::Text("Съешь же ещё".to_string(), Charset::Win1251),
+        SqlValue::Blob(Vec::new()),
+        SqlValue::Blob(bytes.repeat(128)),
+        SqlValue::Date(date(1, 1, 1)),
+        SqlValue::Date(date(1858, 11, 16)),
+        SqlValue::Date(date(1858, 11, 17)),
+        SqlValue::Date(date(9999, 12, 31)),
+        SqlValue::Time(NaiveTime::MIN),
+        SqlValue::Time(last_time),
+        SqlValue::TimeStamp(date(1, 1, 1).and_time(NaiveTime::MIN)),
+        SqlValue::TimeStamp(date(9999, 12, 31).and_time(last_time)),
+        SqlValue::TimeTz(NaiveTime::MIN, chrono_tz::Asia::Seoul),
+        SqlValue::TimeTz(last_time, chrono_tz::Etc::GMTPlus11),
+        SqlValue::TimeStampTz(
+            chrono_tz::America::New_York.from_utc_datetime(&date(1, 1, 1).and_time(NaiveTime::MIN)),
+        ),
+        SqlValue::TimeStampTz(
+            chrono_tz::UTC.from_utc_datetime(&date(9999, 12, 31).and_time(last_time)),
+        ),
+        SqlValue::Boolean(true),
+        SqlValue::Boolean(false),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_wire_vectors() {
+        for v in test_vectors() {
+            if matches!(v, SqlValue::Blob(_)) {
+                continue;
+            }
+            assert_eq!(v.wire_round_trip().unwrap(), v);
+        }
+        assert_eq!(
+            wire_round_trip::<_, Option<i32>>(&Param::Null).unwrap(),
+            None
+        );
+        let d: Decimal = wire_round_trip(&DecFloat(Decimal::new(-15, 1))).unwrap();
+        assert_eq!(d, Decimal::new(-15, 1));
+        let date = NaiveDate::from_ymd_opt(10000, 1, 1).unwrap();
+        assert!(wire_round_trip::<_, NaiveDate>(&date).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_wire_round_trip(v in wire_values()) {
+            prop_assert_eq!(v.wire_round_trip().unwrap(), v);
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn test_server_round_trip() {
+        let user = env::var("ISC_USER").unwrap_or("sysdba".to_string());
+        let password = env::var("ISC_PASSWORD").unwrap_or("masterkey".to_string());
+        let conn = Connection::create_database(&format!(
+            "firebird://{}:{}@localhost/tmp/rust-firebird-test-roundtrip.fdb",
+            &user,
+            urlencoding::encode(&password)
+        ))
+        .unwrap();
+
+        for v in test_vectors() {
+            assert_eq!(v.server_round_trip(&conn).unwrap(), v);
+        }
+        let mut runner = proptest::test_runner::TestRunner::default();
+        runner
+            .run(&sql_values(), |v| {
+                prop_assert_eq!(v.server_round_trip(&conn).unwrap(), v);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
@@ -164,6 +164,8 @@ fn test_connnect() {
         assert_eq!(stmt.column_metadata(1), (
             Some((SQL_TYPE_VARYING, 0, 4, 120, false, "B", "FOO", "SYSDBA"))
         ));
+        stmt.redescribe().unwrap();
+        assert_eq!(stmt.description()[0].name, "A");
 
         for (i, row) in stmt.query(()).unwrap().enumerate() {
             let foo = Foo {
@@ -350,7 +352,7 @@ fn test_connnect() {
     assert_eq!(stmt.query(()).unwrap().count(), 1);
 
     // Transction
-    let mut conn = Connection::connect(&conn_string).unwrap();
+    let conn = Connection::connect(&conn_string).unwrap();
     let expects: [Foo; 1] = [Foo {
         a: 2,
         b: "A".to_string(),
@@ -392,4 +394,54 @@ fn test_connnect() {
     for (i, foo) in foo_iter.enumerate() {
         assert_eq!(foo.unwrap(), expects[i]);
     }
+    drop(stmt);
+    trans.commit().unwrap();
+
+    // one prepared statement, executed under successive transactions
+    let mut stmt = conn.prepare("select count(*) from foo").unwrap();
+    for _ in 0..2 {
+        let mut tx = conn.transaction().unwrap();
+        let count: i64 = stmt.query_in(&mut tx, ()).unwrap().next().unwrap().get(0).unwrap();
+        assert_eq!(count, expects.len() as i64);
+        tx.commit().unwrap();
+    }
+
+    // a reporting transaction running next to a writing one
+    let mut report = conn
+        .begin_named_transaction("report", TransactionOptions::snapshot_read_only())
+        .unwrap();
+    let mut write = conn
+        .begin_named_transaction("write", TransactionOptions::new())
+        .unwrap();
+    assert_eq!(report.name(), Some("report"));
+    let mut ids = conn
+        .prepare("select current_connection, current_transaction from rdb$database")
+        .unwrap();
+    let (attachment_id, transaction_id): (i64, i64) = ids
+        .query_in(&mut report, ())
+        .unwrap()
+        .next()
+        .map(|row| (row.get(0).unwrap(), row.get(1).unwrap()))
+        .unwrap();
+    assert_eq!(conn.attachment_id().unwrap(), attachment_id);
+    assert_eq!(report.transaction_id().unwrap(), transaction_id);
+    assert_ne!(write.transaction_id().unwrap(), transaction_id);
+    drop(ids);
+    write.execute("delete from foo", ()).unwrap();
+    let count: i64 = stmt.query_in(&mut report, ()).unwrap().next().unwrap().get(0).unwrap();
+    assert_eq!(count, expects.len() as i64);
+    write.rollback().unwrap();
+    report.commit().unwrap();
+
+    // session configuration run on every new connection
+    let options = ConnectOptions::new().init_script(
+        "SELECT RDB$SET_CONTEXT('USER_SESSION', 'APP', 'tests') FROM RDB$DATABASE;
+         SET TERM ^ ;
+         EXECUTE BLOCK AS BEGIN RDB$SET_CONTEXT('USER_SESSION', 'N', 1); END^",
+    );
+    let conn2 = Connection::connect_with_options(&conn_string, &options).unwrap();
+    let app: Option<String> = conn2.get_context("USER_SESSION", "APP").unwrap();
+    assert_eq!(app.as_deref(), Some("tests"));
+    let n: Option<i32> = conn2.get_context("USER_SESSION", "N").unwrap();
+    assert_eq!(n, Some(1));
 }
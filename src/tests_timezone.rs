@@ -148,4 +148,21 @@ fn test_timezone() {
         };
         assert_eq!(r, expects[i]);
     }
+
+    drop(stmt);
+
+    // bound as parameters
+    let seoul = chrono_tz::Tz::from_str("Asia/Seoul").unwrap();
+    let t = (NaiveTime::from_hms_milli_opt(23, 59, 59, 500).unwrap(), seoul);
+    let ts = expects[1].ts;
+    conn.execute("insert into tz_test (id, t, ts) values (?, ?, ?)", (4, t, ts))
+        .unwrap();
+    let mut stmt = conn
+        .prepare("select t, ts, t = ?, ts = ? from tz_test where id = 4")
+        .unwrap();
+    let row = stmt.query((t, ts)).unwrap().next().unwrap();
+    assert_eq!(row.get::<(NaiveTime, chrono_tz::Tz)>(0).unwrap(), t);
+    assert_eq!(row.get::<chrono::DateTime<chrono_tz::Tz>>(1).unwrap(), ts);
+    assert!(row.get::<bool>(2).unwrap());
+    assert!(row.get::<bool>(3).unwrap());
 }
@@ -0,0 +1,195 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `time` crate values (feature `time`)
+//!
+//! `time::Date`, `time::Time` and `time::PrimitiveDateTime` read from and
+//! bind to DATE, TIME and TIMESTAMP like their chrono counterparts, for
+//! projects standardized on the `time` crate. Times keep Firebird's
+//! precision of 100 microseconds; finer fractions are truncated when bound.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use time::macros::{date, datetime};
+//!
+//! conn.execute(
+//!     "INSERT INTO orders (id, due, created) VALUES (?, ?, ?)",
+//!     (1, date!(2026 - 10 - 31), datetime!(2026-10-16 9:30)),
+//! )?;
+//! let due: time::Date = conn
+//!     .prepare("SELECT due FROM orders WHERE id = ?")?
+//!     .query_row((1,), |row| row.get(0))?;
+//! ```
+
+use super::cellvalue::{CellValue, CellValueToVal};
+use super::error::{Error, ValueError};
+use super::param::{Param, ToSqlParam};
+
+fn to_chrono_date(d: time::Date) -> chrono::NaiveDate {
+    // every time::Date (years -9999..=9999) is a valid chrono date
+    chrono::NaiveDate::from_ymd_opt(d.year(), d.month() as u32, d.day() as u32).unwrap()
+}
+
+fn to_chrono_time(t: time::Time) -> chrono::NaiveTime {
+    chrono::NaiveTime::from_hms_nano_opt(
+        t.hour() as u32,
+        t.minute() as u32,
+        t.second() as u32,
+        t.nanosecond(),
+    )
+    .unwrap()
+}
+
+fn from_chrono_date(d: chrono::NaiveDate) -> Result<time::Date, Error> {
+    use chrono::Datelike;
+    time::Month::try_from(d.month() as u8)
+        .and_then(|month| time::Date::from_calendar_date(d.year(), month, d.day() as u8))
+        .map_err(|e| Error::ValueError(ValueError::new(&format!("Can't convert date: {}", e))))
+}
+
+fn from_chrono_time(t: chrono::NaiveTime) -> Result<time::Time, Error> {
+    use chrono::Timelike;
+    // chrono represents a leap second as a nanosecond past 1_000_000_000
+    time::Time::from_hms_nano(
+        t.hour() as u8,
+        t.minute() as u8,
+        t.second() as u8,
+        t.nanosecond().min(999_999_999),
+    )
+    .map_err(|e| Error::ValueError(ValueError::new(&format!("Can't convert time: {}", e))))
+}
+
+impl From<time::Date> for Param {
+    fn from(v: time::Date) -> Param {
+        Param::Date(to_chrono_date(v))
+    }
+}
+
+impl From<time::Time> for Param {
+    fn from(v: time::Time) -> Param {
+        Param::Time(to_chrono_time(v))
+    }
+}
+
+impl From<time::PrimitiveDateTime> for Param {
+    fn from(v: time::PrimitiveDateTime) -> Param {
+        Param::TimeStamp(to_chrono_date(v.date()).and_time(to_chrono_time(v.time())))
+    }
+}
+
+// dates are checked against Firebird's 0001-01-01..9999-12-31
+macro_rules! to_sql_param_time(
+    ($t:ty) => (
+        impl ToSqlParam for $t {
+            #[inline]
+            fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+                Param::from(*self).to_value_blr_isnull()
+            }
+
+            #[inline]
+            fn check_range(&self) -> Result<(), Error> {
+                Param::from(*self).check_range()
+            }
+        }
+    )
+);
+
+to_sql_param_time!(time::Date);
+to_sql_param_time!(time::Time);
+to_sql_param_time!(time::PrimitiveDateTime);
+
+impl CellValueToVal<time::Date> for CellValue {
+    fn to_val(self) -> Result<time::Date, Error> {
+        match self {
+            CellValue::Date(v) => from_chrono_date(v),
+            _ => Err(Error::ValueError(ValueError::new("Can't convert date"))),
+        }
+    }
+}
+
+impl CellValueToVal<time::Time> for CellValue {
+    fn to_val(self) -> Result<time::Time, Error> {
+        match self {
+            CellValue::Time(v) => from_chrono_time(v),
+            _ => Err(Error::ValueError(ValueError::new("Can't convert time"))),
+        }
+    }
+}
+
+impl CellValueToVal<time::PrimitiveDateTime> for CellValue {
+    fn to_val(self) -> Result<time::PrimitiveDateTime, Error> {
+        match self {
+            CellValue::TimeStamp(v) => Ok(time::PrimitiveDateTime::new(
+                from_chrono_date(v.date())?,
+                from_chrono_time(v.time())?,
+            )),
+            _ => Err(Error::ValueError(ValueError::new(
+                "Can't convert timestamp",
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_values() {
+        let date = time::Date::from_calendar_date(1967, time::Month::August, 11).unwrap();
+        let t = time::Time::from_hms_micro(23, 45, 1, 123_400).unwrap();
+        let dt = time::PrimitiveDateTime::new(date, t);
+
+        // bound exactly like the chrono values
+        let chrono_date = chrono::NaiveDate::from_ymd_opt(1967, 8, 11).unwrap();
+        let chrono_time = chrono::NaiveTime::from_hms_micro_opt(23, 45, 1, 123_400).unwrap();
+        assert_eq!(
+            date.to_value_blr_isnull(),
+            chrono_date.to_value_blr_isnull()
+        );
+        assert_eq!(t.to_value_blr_isnull(), chrono_time.to_value_blr_isnull());
+        assert_eq!(
+            dt.to_value_blr_isnull(),
+            chrono_date.and_time(chrono_time).to_value_blr_isnull()
+        );
+
+        let v: time::Date = CellValue::Date(chrono_date).to_val().unwrap();
+        assert_eq!(v, date);
+        let v: time::Time = CellValue::Time(chrono_time).to_val().unwrap();
+        assert_eq!(v, t);
+        let v: time::PrimitiveDateTime = CellValue::TimeStamp(chrono_date.and_time(chrono_time))
+            .to_val()
+            .unwrap();
+        assert_eq!(v, dt);
+        let v: Option<time::Date> = CellValue::Null.to_val().unwrap();
+        assert_eq!(v, None);
+        let r: Result<time::Date, Error> = CellValue::Long(1).to_val();
+        assert!(r.is_err());
+
+        // year 0 is outside Firebird's range
+        let early = time::Date::from_calendar_date(0, time::Month::January, 1).unwrap();
+        assert!(early.check_range().is_err());
+        assert!(date.check_range().is_ok());
+    }
+}
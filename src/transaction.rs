@@ -21,9 +21,12 @@
 // SOFTWARE.
 
 use super::Connection;
-use super::error::Error;
+use super::blob::BlobWriter;
+use super::error::{Error, ValueError};
 use super::params::Params;
+use super::param::Param;
 use super::statement::Statement;
+use super::versioned;
 
 /// Transaction isolation level
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -70,6 +73,16 @@ pub struct TransactionOptions {
     pub lock_wait: LockWait,
     /// Read-only flag (overrides isolation level setting if true)
     pub read_only: bool,
+    /// Don't keep the undo log (faster bulk loads, rollback marks the changes dead instead)
+    pub no_auto_undo: bool,
+    /// Ignore records of transactions in limbo
+    pub ignore_limbo: bool,
+    /// Restart requests left active by other transactions
+    pub restart_requests: bool,
+    /// Label of the code path starting the transaction, see `tag()`
+    pub tag: Option<String>,
+    /// Snapshot to start at, see `at_snapshot()`
+    pub at_snapshot: Option<i64>,
 }
 
 impl Default for TransactionOptions {
@@ -78,6 +91,11 @@ impl Default for TransactionOptions {
             isolation_level: IsolationLevel::ReadCommitted,
             lock_wait: LockWait::Wait,
             read_only: false,
+            no_auto_undo: false,
+            ignore_limbo: false,
+            restart_requests: false,
+            tag: None,
+            at_snapshot: None,
         }
     }
 }
@@ -106,12 +124,57 @@ impl TransactionOptions {
         self
     }
 
+    /// Disable the undo log (isc_tpb_no_auto_undo)
+    pub fn no_auto_undo(mut self, no_auto_undo: bool) -> Self {
+        self.no_auto_undo = no_auto_undo;
+        self
+    }
+
+    /// Ignore limbo transactions (isc_tpb_ignore_limbo)
+    pub fn ignore_limbo(mut self, ignore_limbo: bool) -> Self {
+        self.ignore_limbo = ignore_limbo;
+        self
+    }
+
+    /// Restart active requests (isc_tpb_restart_requests)
+    pub fn restart_requests(mut self, restart_requests: bool) -> Self {
+        self.restart_requests = restart_requests;
+        self
+    }
+
+    /// Record `tag` as the context variable `TAG` of the transaction
+    /// (namespace `USER_TRANSACTION`) when it starts, so monitoring can tell
+    /// which code path opened it:
+    /// `SELECT MON$TRANSACTION_ID, MON$VARIABLE_VALUE FROM MON$CONTEXT_VARIABLES
+    /// WHERE MON$VARIABLE_NAME = 'TAG'`. Costs one round trip per transaction.
+    pub fn tag(mut self, tag: &str) -> Self {
+        self.tag = Some(tag.to_string());
+        self
+    }
+
+    /// Start at snapshot `number` of another transaction still running
+    /// (`Transaction::snapshot_number`), so several connections see the same
+    /// data, as `SET TRANSACTION SNAPSHOT AT NUMBER` does. Only snapshot
+    /// isolation can share a snapshot: other isolation levels are changed to
+    /// `Snapshot`. Firebird 4+.
+    pub fn at_snapshot(mut self, number: i64) -> Self {
+        if !matches!(
+            self.isolation_level,
+            IsolationLevel::Snapshot | IsolationLevel::SnapshotReadOnly
+        ) {
+            self.isolation_level = IsolationLevel::Snapshot;
+        }
+        self.at_snapshot = Some(number);
+        self
+    }
+
     /// Create options for snapshot isolation
     pub fn snapshot() -> Self {
         Self {
             isolation_level: IsolationLevel::Snapshot,
             lock_wait: LockWait::Wait,
             read_only: false,
+            ..Self::default()
         }
     }
 
@@ -121,6 +184,7 @@ impl TransactionOptions {
             isolation_level: IsolationLevel::Serializable,
             lock_wait: LockWait::Wait,
             read_only: false,
+            ..Self::default()
         }
     }
 
@@ -130,26 +194,51 @@ impl TransactionOptions {
             isolation_level: IsolationLevel::SnapshotReadOnly,
             lock_wait: LockWait::Wait,
             read_only: true,
+            ..Self::default()
         }
     }
 }
 
+/// Records the tag of a transaction, see `TransactionOptions::tag`
+pub(crate) const SET_TAG_SQL: &str =
+    "SELECT RDB$SET_CONTEXT('USER_TRANSACTION', 'TAG', ?) FROM RDB$DATABASE";
+
+const SNAPSHOT_NUMBER_SQL: &str = "SELECT RDB$GET_CONTEXT('SYSTEM', 'SNAPSHOT_NUMBER') FROM RDB$DATABASE";
+
 pub struct Transaction<'conn> {
-    conn: &'conn mut Connection,
+    conn: &'conn Connection,
     trans_handle: i32,
     finished: bool,  // true if commit() or rollback() was called
+    savepoint_seq: u32, // for unique savepoint names
+    name: Option<String>,
 }
 
 impl Transaction<'_> {
-    pub fn new(conn: &mut Connection) -> Result<Transaction<'_>, Error> {
+    pub fn new(conn: &Connection) -> Result<Transaction<'_>, Error> {
         let trans_handle = conn._begin_trans()?;
-        Ok(Transaction { conn, trans_handle, finished: false })
+        Ok(Transaction { conn, trans_handle, finished: false, savepoint_seq: 0, name: None })
     }
 
     /// Create a new transaction with custom options (isolation level, lock wait, etc.)
-    pub fn with_options(conn: &mut Connection, options: TransactionOptions) -> Result<Transaction<'_>, Error> {
+    pub fn with_options(conn: &Connection, options: TransactionOptions) -> Result<Transaction<'_>, Error> {
         let trans_handle = conn._begin_trans_with_options(&options)?;
-        Ok(Transaction { conn, trans_handle, finished: false })
+        Ok(Transaction { conn, trans_handle, finished: false, savepoint_seq: 0, name: None })
+    }
+
+    /// Begin a transaction known by `name`, see `Connection::begin_named_transaction`
+    pub fn named<'c>(conn: &'c Connection, name: &str, options: TransactionOptions) -> Result<Transaction<'c>, Error> {
+        if name.is_empty() {
+            return Err(Error::ValueError(ValueError::new("Transaction name is empty")));
+        }
+        let options = if options.tag.is_none() { options.tag(name) } else { options };
+        let mut tx = Transaction::with_options(conn, options)?;
+        tx.name = Some(name.to_string());
+        Ok(tx)
+    }
+
+    /// Name given by `Connection::begin_named_transaction`
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
     }
 
     pub fn execute_batch(&mut self, query: &str) -> Result<(), Error> {
@@ -179,6 +268,109 @@ impl Transaction<'_> {
     pub fn prepare(&mut self, query: &str) -> Result<Statement<'_>, Error> {
         self.conn._prepare(query, self.trans_handle, false) // autocommit=false in transaction
     }
+
+    /// Create a blob in the transaction, see `Connection::blob_writer`
+    pub fn blob_writer(&self) -> Result<BlobWriter<'_>, Error> {
+        BlobWriter::create(self.conn, self.trans_handle()?)
+    }
+
+    pub(crate) fn trans_handle(&self) -> Result<i32, Error> {
+        if self.finished {
+            return Err(Error::ValueError(ValueError::new(
+                "Transaction already committed or rolled back",
+            )));
+        }
+        Ok(self.trans_handle)
+    }
+
+    /// Id the server gave the transaction, as `MON$TRANSACTION_ID` and
+    /// `CURRENT_TRANSACTION` show it, e.g. to find it in `MON$TRANSACTIONS`
+    /// or the trace log. Advanced: see
+    /// "Interop with fbclient Code" in the README.
+    pub fn transaction_id(&self) -> Result<i64, Error> {
+        self.conn._transaction_id(self.trans_handle()?)
+    }
+
+    /// Wire protocol handle of the transaction. Advanced: it is only
+    /// meaningful on its connection and is NOT an fbclient `isc_tr_handle`;
+    /// never pass it to fbclient.
+    pub fn raw_handle(&self) -> i32 {
+        self.trans_handle
+    }
+
+    /// Number of the snapshot the transaction reads, to start other
+    /// transactions at with `TransactionOptions::at_snapshot`. Snapshot
+    /// isolation, Firebird 4+.
+    pub fn snapshot_number(&self) -> Result<i64, Error> {
+        let mut stmt = self.conn._prepare(SNAPSHOT_NUMBER_SQL, self.trans_handle()?, false)?;
+        let number: Option<String> = stmt.query_row((), |row| row.get_nullable(0))?;
+        number
+            .and_then(|n| n.trim().parse().ok())
+            .ok_or_else(|| {
+                Error::ValueError(ValueError::new(
+                    "no snapshot number: the transaction is not a snapshot (or the server is older than Firebird 4)",
+                ))
+            })
+    }
+
+    /// Whether the transaction was started on `conn`
+    pub(crate) fn is_on(&self, conn: &Connection) -> bool {
+        std::ptr::eq(self.conn, conn)
+    }
+
+    /// Run `f` inside a savepoint.
+    /// The savepoint is released if `f` returns Ok and rolled back to if it returns Err,
+    /// leaving the rest of the transaction intact. Calls can be nested.
+    pub fn with_savepoint<T, F>(&mut self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        self.savepoint_seq += 1;
+        let name = format!("FIREBIRUST_SP_{}", self.savepoint_seq);
+        savepoint(self, &name, |tx, sql| tx.conn._execute_immediate(sql, tx.trans_handle), f)
+    }
+
+    /// Optimistic locking update (see `Connection::update_versioned`)
+    pub fn update_versioned<K: Into<Param>>(
+        &mut self,
+        table: &str,
+        key_column: &str,
+        key: K,
+        version_column: &str,
+        version: i64,
+        values: &[(&str, Param)],
+    ) -> Result<i64, Error> {
+        let columns: Vec<&str> = values.iter().map(|(c, _)| *c).collect();
+        let query = versioned::update_versioned_sql(table, key_column, version_column, &columns);
+        let mut stmt = self.prepare(&query)?;
+        versioned::execute_versioned(&mut stmt, key.into(), version, values)
+    }
+}
+
+/// Run `f` inside savepoint `name`, released if `f` returns Ok and rolled
+/// back to if it returns Err; `execute` runs a statement in the transaction
+pub(crate) fn savepoint<S, T, F>(
+    s: &mut S,
+    name: &str,
+    execute: impl Fn(&mut S, &str) -> Result<(), Error>,
+    f: F,
+) -> Result<T, Error>
+where
+    F: FnOnce(&mut S) -> Result<T, Error>,
+{
+    execute(s, &format!("SAVEPOINT {}", name))?;
+    match f(s) {
+        Ok(v) => {
+            execute(s, &format!("RELEASE SAVEPOINT {}", name))?;
+            Ok(v)
+        }
+        Err(e) => {
+            // the error of `f` is the one worth reporting
+            let _ = execute(s, &format!("ROLLBACK TO SAVEPOINT {}", name))
+                .and_then(|()| execute(s, &format!("RELEASE SAVEPOINT {}", name)));
+            Err(e)
+        }
+    }
 }
 
 impl Drop for Transaction<'_> {
@@ -189,3 +381,51 @@ impl Drop for Transaction<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_savepoint() {
+        let execute = |log: &mut Vec<String>, sql: &str| {
+            log.push(sql.to_string());
+            Ok(())
+        };
+        let mut log = Vec::new();
+        let v = savepoint(&mut log, "SP_1", execute, |_| Ok(1)).unwrap();
+        assert_eq!(v, 1);
+        assert_eq!(log, vec!["SAVEPOINT SP_1", "RELEASE SAVEPOINT SP_1"]);
+
+        let mut log = Vec::new();
+        let r: Result<(), Error> = savepoint(&mut log, "SP_2", execute, |log| {
+            log.push("UPDATE".to_string());
+            Err(Error::StaleRow)
+        });
+        assert!(matches!(r, Err(Error::StaleRow)));
+        assert_eq!(
+            log,
+            vec![
+                "SAVEPOINT SP_2",
+                "UPDATE",
+                "ROLLBACK TO SAVEPOINT SP_2",
+                "RELEASE SAVEPOINT SP_2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_savepoint_keeps_error() {
+        let execute = |log: &mut Vec<String>, sql: &str| {
+            if sql.starts_with("ROLLBACK") {
+                return Err(Error::ConnectionLost);
+            }
+            log.push(sql.to_string());
+            Ok(())
+        };
+        let mut log = Vec::new();
+        let r: Result<(), Error> = savepoint(&mut log, "SP_3", execute, |_| Err(Error::StaleRow));
+        assert!(matches!(r, Err(Error::StaleRow)));
+        assert_eq!(log, vec!["SAVEPOINT SP_3"]);
+    }
+}
@@ -25,8 +25,6 @@ use super::error::Error;
 use super::params::Params;
 use super::statement_async::StatementAsync;
 
-use async_std::task;
-
 pub struct TransactionAsync<'conn> {
     conn: &'conn mut ConnectionAsync,
     trans_handle: i32,
@@ -61,6 +59,7 @@ impl TransactionAsync<'_> {
 
 impl Drop for TransactionAsync<'_> {
     fn drop(&mut self) {
-        task::block_on(self.conn.drop_transaction(self.trans_handle));
+        self.conn
+            .block_on(Box::pin(self.conn.drop_transaction(self.trans_handle)));
     }
 }
@@ -3,7 +3,7 @@
 use maplit::hashmap;
 
 #[allow(dead_code)]
-pub fn timezone_id_by_name(s: &str) -> u16 {
+pub fn timezone_id_by_name(s: &str) -> Option<u16> {
     let map = hashmap! {
         "GMT" => 65535,
         "ACT" => 65534,
@@ -641,10 +641,10 @@ pub fn timezone_id_by_name(s: &str) -> u16 {
         "Asia/Qostanay" => 64902,
         "Pacific/Kanton" => 64901,
     };
-    map[s]
+    map.get(s).copied()
 }
 
-pub fn timezone_name_by_id(id: u16) -> &'static str {
+pub fn timezone_name_by_id(id: u16) -> Option<&'static str> {
     let map = hashmap! {
         65535 => "GMT",
         65534 => "ACT",
@@ -1282,5 +1282,5 @@ pub fn timezone_name_by_id(id: u16) -> &'static str {
         64902 => "Asia/Qostanay",
         64901 => "Pacific/Kanton",
     };
-    map[&id]
+    map.get(&id).copied()
 }
@@ -26,48 +26,49 @@ use std::io::prelude::*;
 use std::str;
 
 use chrono;
-use chrono::TimeZone;
+use chrono::{Datelike, TimeZone, Timelike};
 use chrono_tz;
 use digest::Digest;
 use sha1::Sha1;
 use hex;
 use num_bigint::{BigInt, BigUint, Sign};
 
+use super::error::ValueError;
 use super::tz_map;
 
 pub fn int32_to_bytes(i: i32) -> [u8; 4] {
     // little endian i32 to Vec<u8>
-    i32::to_ne_bytes(i.to_le())
+    i.to_le_bytes()
 }
 
 pub fn uint32_to_bytes(i: u32) -> [u8; 4] {
     // little endian u32 to Vec<u8>
-    u32::to_ne_bytes(i.to_le())
+    i.to_le_bytes()
 }
 
 pub fn bint128_to_bytes(i: i128) -> [u8; 16] {
     // big endian i128 to Vec<u8>
-    i128::to_ne_bytes(i.to_be())
+    i.to_be_bytes()
 }
 
 pub fn bint64_to_bytes(i: i64) -> [u8; 8] {
     // big endian i64 to Vec<u8>
-    i64::to_ne_bytes(i.to_be())
+    i.to_be_bytes()
 }
 
 pub fn bint32_to_bytes(i: i32) -> [u8; 4] {
     // big endian i32 to Vec<u8>
-    i32::to_ne_bytes(i.to_be())
+    i.to_be_bytes()
 }
 
 pub fn ubint32_to_bytes(i: u32) -> [u8; 4] {
     // big endian u32 to Vec<u8>
-    u32::to_ne_bytes(i.to_be())
+    i.to_be_bytes()
 }
 
 pub fn int16_to_bytes(i: u16) -> [u8; 2] {
     // little endian u16 to Vec<u8>
-    u16::to_ne_bytes(i.to_le())
+    i.to_le_bytes()
 }
 
 pub fn f32_to_bytes(f: f32) -> [u8; 4] {
@@ -80,124 +81,113 @@ pub fn f64_to_bytes(f: f64) -> [u8; 8] {
     f.to_be_bytes()
 }
 
+// Invalid UTF-8 (e.g. codepage text in a NONE column) is replaced by U+FFFD
+
 pub fn bytes_to_str(b: &[u8]) -> String {
-    str::from_utf8(b).unwrap().to_string()
+    String::from_utf8_lossy(b).into_owned()
 }
 
 pub fn bytes_to_rtrim_str(b: &[u8]) -> String {
-    str::from_utf8(b).unwrap().trim_end().to_string()
+    String::from_utf8_lossy(b).trim_end().to_string()
+}
+
+// The decoders below read the first N bytes of `b` in the stated byte order,
+// whatever the byte order of the host ("b" prefix: big endian, the XDR and
+// BLR order; none: little endian, the VAX order of info and parameter blocks)
+
+fn array<const N: usize>(b: &[u8]) -> [u8; N] {
+    b[..N].try_into().unwrap()
 }
 
 pub fn bytes_to_int32(b: &[u8]) -> i32 {
-    let tmp: [u8; 4] = [b[0], b[1], b[2], b[3]];
-    i32::from_ne_bytes(tmp)
+    i32::from_le_bytes(array(b))
 }
 
 pub fn bytes_to_bint32(b: &[u8]) -> i32 {
-    let tmp: [u8; 4] = [b[3], b[2], b[1], b[0]];
-    i32::from_ne_bytes(tmp)
+    i32::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_uint32(b: &[u8]) -> u32 {
-    // little endian u32
-    ((b[0] as u32) << 0) + ((b[1] as u32) << 8) + ((b[2] as u32) << 16) + ((b[3] as u32) << 24)
+    u32::from_le_bytes(array(b))
 }
 
 pub fn bytes_to_buint32(b: &[u8]) -> u32 {
-    // big endian u32
-    ((b[0] as u32) << 24) + ((b[1] as u32) << 16) + ((b[2] as u32) << 8) + ((b[3] as u32) << 0)
+    u32::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_int16(b: &[u8]) -> i16 {
-    let tmp: [u8; 2] = [b[0], b[1]];
-    i16::from_ne_bytes(tmp)
+    i16::from_le_bytes(array(b))
 }
 
 pub fn bytes_to_bint16(b: &[u8]) -> i16 {
-    let tmp: [u8; 2] = [b[1], b[0]];
-    i16::from_ne_bytes(tmp)
+    i16::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_uint16(b: &[u8]) -> u16 {
-    // little endian u16
-    ((b[0] as u16) << 0) + ((b[1] as u16) << 8)
+    u16::from_le_bytes(array(b))
 }
 
 pub fn bytes_to_buint16(b: &[u8]) -> u16 {
-    // big endian u16
-    ((b[0] as u16) << 8) + ((b[1] as u16) << 0)
+    u16::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_int64(b: &[u8]) -> i64 {
-    let tmp: [u8; 8] = [b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]];
-    i64::from_ne_bytes(tmp)
+    i64::from_le_bytes(array(b))
 }
 
 pub fn bytes_to_bint64(b: &[u8]) -> i64 {
-    let tmp: [u8; 8] = [b[7], b[6], b[5], b[4], b[3], b[2], b[1], b[0]];
-    i64::from_ne_bytes(tmp)
+    i64::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_uint128(b: &[u8]) -> u128 {
-    let tmp: [u8; 16] = [
-        b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8], b[9], b[10], b[11], b[12], b[13],
-        b[14], b[15],
-    ];
-    u128::from_ne_bytes(tmp)
+    u128::from_le_bytes(array(b))
+}
+
+pub fn bytes_to_buint128(b: &[u8]) -> u128 {
+    u128::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_int128(b: &[u8]) -> i128 {
-    let tmp: [u8; 16] = [
-        b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8], b[9], b[10], b[11], b[12], b[13],
-        b[14], b[15],
-    ];
-    i128::from_ne_bytes(tmp)
+    i128::from_le_bytes(array(b))
 }
 
 pub fn bytes_to_bint128(b: &[u8]) -> i128 {
-    let tmp: [u8; 16] = [
-        b[15], b[14], b[13], b[12], b[11], b[10], b[9], b[8], b[7], b[6], b[5], b[4], b[3], b[2],
-        b[1], b[0],
-    ];
-    i128::from_ne_bytes(tmp)
+    i128::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_uint64(b: &[u8]) -> u64 {
-    // little endian u64
-    ((b[0] as u64) << 0)
-        + ((b[1] as u64) << 8)
-        + ((b[2] as u64) << 16)
-        + ((b[3] as u64) << 24)
-        + ((b[4] as u64) << 32)
-        + ((b[5] as u64) << 40)
-        + ((b[6] as u64) << 48)
-        + ((b[7] as u64) << 56)
+    u64::from_le_bytes(array(b))
 }
 
 pub fn bytes_to_buint64(b: &[u8]) -> u64 {
-    // big endian u64
-    ((b[0] as u64) << 56)
-        + ((b[1] as u64) << 48)
-        + ((b[2] as u64) << 40)
-        + ((b[3] as u64) << 32)
-        + ((b[4] as u64) << 24)
-        + ((b[5] as u64) << 16)
-        + ((b[6] as u64) << 8)
-        + ((b[7] as u64) << 0)
+    u64::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_f32(b: &[u8]) -> f32 {
-    let tmp: [u8; 4] = [b[3], b[2], b[1], b[0]];
-    f32::from_ne_bytes(tmp)
+    f32::from_be_bytes(array(b))
 }
 
 pub fn bytes_to_f64(b: &[u8]) -> f64 {
-    let tmp: [u8; 8] = [b[7], b[6], b[5], b[4], b[3], b[2], b[1], b[0]];
-    f64::from_ne_bytes(tmp)
+    f64::from_be_bytes(array(b))
 }
 
-pub fn bytes_to_naive_date(b: &[u8]) -> chrono::NaiveDate {
-    let mut nday = bytes_to_buint32(b) + 678882;
+/// Firebird dates are days from 1858-11-17, its range is
+/// 0001-01-01 (-678575) to 9999-12-31 (2973483)
+const MIN_DATE_DAYS: i32 = -678575;
+const MAX_DATE_DAYS: i32 = 2973483;
+
+/// TIME is in 1/10000 of a second (deci-milliseconds) since midnight
+const TIME_UNITS_PER_DAY: u32 = 24 * 3600 * 10000;
+
+pub fn bytes_to_naive_date(b: &[u8]) -> Result<chrono::NaiveDate, ValueError> {
+    let days = bytes_to_bint32(b);
+    if !(MIN_DATE_DAYS..=MAX_DATE_DAYS).contains(&days) {
+        return Err(ValueError::new(&format!(
+            "DATE {} days from 1858-11-17 is outside 0001-01-01..9999-12-31",
+            days
+        )));
+    }
+    let mut nday = (days + 678882) as u32;
     let century = (4 * nday - 1) / 146097;
     nday = 4 * nday - 1 - 146097 * century;
     let mut day = nday / 4;
@@ -217,68 +207,127 @@ pub fn bytes_to_naive_date(b: &[u8]) -> chrono::NaiveDate {
         year += 1;
     }
 
-    chrono::NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    chrono::NaiveDate::from_ymd_opt(year, month, day)
+        .ok_or_else(|| ValueError::new(&format!("Invalid DATE {}-{}-{}", year, month, day)))
 }
 
-pub fn bytes_to_naive_time(b: &[u8]) -> chrono::NaiveTime {
+pub fn bytes_to_naive_time(b: &[u8]) -> Result<chrono::NaiveTime, ValueError> {
     let n = bytes_to_buint32(b);
+    if n >= TIME_UNITS_PER_DAY {
+        return Err(ValueError::new(&format!(
+            "TIME {} (1/10000 s) is past the end of the day",
+            n
+        )));
+    }
     let mut s = n / 10000;
     let mut m = s / 60;
     let h = m / 60;
     m = m % 60;
     s = s % 60;
-    chrono::NaiveTime::from_hms_nano_opt(h, m, s, (n % 10000) * 100000).unwrap()
-}
-
-pub fn bytes_to_time_tz(b: &[u8]) -> (chrono::NaiveTime, chrono_tz::Tz) {
-    // https://stackoverflow.com/questions/56050292/is-there-a-way-to-parse-a-timezone-abbreviation-into-a-timezone-offset-in-rust
-    let time = bytes_to_naive_time(&b[..4]);
-    let timezone: chrono_tz::Tz;
-    let offset: chrono_tz::Tz;
-    if &b[4..6] == b"\x00\x00" {
-        timezone = "UTC".parse().unwrap();
-        offset = timezone;
+    chrono::NaiveTime::from_hms_nano_opt(h, m, s, (n % 10000) * 100000)
+        .ok_or_else(|| ValueError::new(&format!("Invalid TIME {}", n)))
+}
+
+pub fn bytes_to_time_tz(b: &[u8]) -> Result<(chrono::NaiveTime, chrono_tz::Tz), ValueError> {
+    // UTC time, then the zone id as an XDR short
+    let utc_time = bytes_to_naive_time(&b[..4])?;
+    let timezone = timezone_by_id(bytes_to_buint16(&b[6..8]))?;
+    let utc = chrono::NaiveDateTime::new(time_tz_date(), utc_time);
+    Ok((timezone.from_utc_datetime(&utc).time(), timezone))
+}
+
+/// TIME WITH TIME ZONE as bound on the wire: UTC time and zone id
+pub fn time_tz_to_bytes(
+    time: &chrono::NaiveTime,
+    timezone: &chrono_tz::Tz,
+) -> Result<Vec<u8>, ValueError> {
+    let local = chrono::NaiveDateTime::new(time_tz_date(), *time);
+    let utc = local_date_time(timezone, &local)?.naive_utc();
+    let mut v = naive_time_to_bytes(&utc.time()).to_vec();
+    v.extend_from_slice(&zone_to_bytes(timezone)?);
+    Ok(v)
+}
+
+pub fn bytes_to_naive_date_time(b: &[u8]) -> Result<chrono::NaiveDateTime, ValueError> {
+    let date = bytes_to_naive_date(&b[..4])?;
+    let time = bytes_to_naive_time(&b[4..])?;
+
+    Ok(chrono::NaiveDateTime::new(date, time))
+}
+
+pub fn bytes_to_date_time_tz(b: &[u8]) -> Result<chrono::DateTime<chrono_tz::Tz>, ValueError> {
+    // UTC date and time, then the zone id as an XDR short
+    let utc = bytes_to_naive_date_time(&b[..8])?;
+    let timezone = timezone_by_id(bytes_to_buint16(&b[10..12]))?;
+    Ok(timezone.from_utc_datetime(&utc))
+}
+
+/// TIMESTAMP WITH TIME ZONE as bound on the wire: UTC date, time and zone id
+pub fn date_time_tz_to_bytes(
+    dt: &chrono::DateTime<chrono_tz::Tz>,
+) -> Result<Vec<u8>, ValueError> {
+    let utc = dt.naive_utc();
+    let mut v = convert_date(utc.year(), utc.month(), utc.day()).to_vec();
+    v.extend_from_slice(&naive_time_to_bytes(&utc.time()));
+    v.extend_from_slice(&zone_to_bytes(&dt.timezone())?);
+    Ok(v)
+}
+
+/// Date Firebird converts TIME WITH TIME ZONE values at, so that a time
+/// in a region keeps its offset whatever the current date
+fn time_tz_date() -> chrono::NaiveDate {
+    chrono::NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()
+}
+
+// zone ids up to 2878 are offsets: 1439 + minutes east of UTC
+const MAX_OFFSET_ZONE_ID: u16 = 2 * 1439;
+
+/// Time zone of a Firebird zone id. chrono_tz has fixed offsets only for
+/// whole hours (Etc/GMT-3 is +03:00), other offsets are an error.
+fn timezone_by_id(id: u16) -> Result<chrono_tz::Tz, ValueError> {
+    let name = if id <= MAX_OFFSET_ZONE_ID {
+        let minutes = id as i32 - 1439;
+        if minutes % 60 != 0 {
+            return Err(ValueError::new(&format!(
+                "time zone offset {:+03}:{:02} has no chrono_tz time zone",
+                minutes / 60,
+                minutes.abs() % 60
+            )));
+        }
+        match minutes / 60 {
+            0 => "UTC".to_string(),
+            h => format!("Etc/GMT{:+}", -h),
+        }
     } else {
-        timezone = tz_map::timezone_name_by_id(bytes_to_buint16(&b[4..6]))
-            .parse()
-            .unwrap();
-        offset = tz_map::timezone_name_by_id(bytes_to_buint16(&b[6..8]))
-            .parse()
-            .unwrap();
-    }
-    let date = chrono::Utc::now().date_naive();
-    let dt = chrono::NaiveDateTime::new(date, time);
-    let tz_aware = timezone
-        .from_local_datetime(&dt)
-        .unwrap()
-        .with_timezone(&offset);
-    (tz_aware.time(), offset)
+        tz_map::timezone_name_by_id(id)
+            .ok_or_else(|| ValueError::new(&format!("unknown time zone id {}", id)))?
+            .to_string()
+    };
+    name.parse()
+        .map_err(|_| ValueError::new(&format!("unknown time zone {}", name)))
 }
 
-pub fn bytes_to_naive_date_time(b: &[u8]) -> chrono::NaiveDateTime {
-    let date = bytes_to_naive_date(&b[..4]);
-    let time = bytes_to_naive_time(&b[4..]);
-
-    chrono::NaiveDateTime::new(date, time)
+/// Zone id of a time zone as an XDR short
+fn zone_to_bytes(timezone: &chrono_tz::Tz) -> Result<[u8; 4], ValueError> {
+    let id = tz_map::timezone_id_by_name(timezone.name()).ok_or_else(|| {
+        ValueError::new(&format!("time zone {} is unknown to Firebird", timezone.name()))
+    })?;
+    Ok(bint32_to_bytes(id as i16 as i32))
 }
 
-pub fn bytes_to_date_time_tz(b: &[u8]) -> chrono::DateTime<chrono_tz::Tz> {
-    let dt = bytes_to_naive_date_time(&b[..8]);
-    if &b[8..10] == b"\x00\x00" {
-        let timezone: chrono_tz::Tz = "UTC".parse().unwrap();
-        return timezone.from_local_datetime(&dt).unwrap();
-    }
-    let timezone: chrono_tz::Tz = tz_map::timezone_name_by_id(bytes_to_buint16(&b[8..10]))
-        .parse()
-        .unwrap();
-    let offset: chrono_tz::Tz = tz_map::timezone_name_by_id(bytes_to_buint16(&b[10..12]))
-        .parse()
-        .unwrap();
+fn naive_time_to_bytes(t: &chrono::NaiveTime) -> [u8; 4] {
+    convert_time(t.hour(), t.minute(), t.second(), t.nanosecond())
+}
 
-    timezone
-        .from_local_datetime(&dt)
-        .unwrap()
-        .with_timezone(&offset)
+/// `dt` in `timezone`, an error for a local time skipped or repeated by
+/// a daylight saving change
+fn local_date_time(
+    timezone: &chrono_tz::Tz,
+    dt: &chrono::NaiveDateTime,
+) -> Result<chrono::DateTime<chrono_tz::Tz>, ValueError> {
+    timezone.from_local_datetime(dt).single().ok_or_else(|| {
+        ValueError::new(&format!("{} is not a single local time in {}", dt, timezone))
+    })
 }
 
 pub fn big_int_from_hex_string(s: &[u8]) -> BigInt {
@@ -343,24 +392,45 @@ pub fn bytes_to_blr(b: &[u8]) -> (Vec<u8>, Vec<u8>) {
     (blr, v)
 }
 
+pub fn quote_literal(s: &str) -> String {
+    // SQL string literal, single quotes doubled
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Fails for a date outside Firebird's range, 0001-01-01 to 9999-12-31
+pub fn check_date_range(d: &chrono::NaiveDate) -> Result<(), ValueError> {
+    if (1..=9999).contains(&d.year()) {
+        Ok(())
+    } else {
+        Err(ValueError::new(&format!(
+            "DATE {} is outside 0001-01-01..9999-12-31",
+            d
+        )))
+    }
+}
+
 pub fn convert_date(year: i32, month: u32, day: u32) -> [u8; 4] {
-    // Convert date to BLR format data
-    let i = month + 9;
-    let jy = year as u32 + (i / 12) - 1;
+    // Convert date to BLR format data, days before 1858-11-17 are negative
+    let i = month as i64 + 9;
+    let jy = year as i64 + (i / 12) - 1;
     let jm = i % 12;
     let c = jy / 100;
-    let j = (146097 * c) / 4 + (1461 * (jy - 100 * c)) / 4 + (153 * jm + 2) / 5 + day - 678882;
+    let j = (146097 * c) / 4 + (1461 * (jy - 100 * c)) / 4 + (153 * jm + 2) / 5 + day as i64
+        - 678882;
     bint32_to_bytes(j as i32)
 }
 
 pub fn convert_time(hour: u32, minute: u32, second: u32, nanosecond: u32) -> [u8; 4] {
     // Convert time to BLR format time (1/10000 of a second units)
     // nanoseconds need to be converted to these units: ns / 100000
-    let n = (hour * 3600 + minute * 60 + second) * 10000 + nanosecond / 100000;
+    // (a leap second, nanosecond >= 1_000_000_000, is kept in its second)
+    let n = (hour * 3600 + minute * 60 + second) * 10000 + nanosecond.min(999_999_999) / 100000;
     bint32_to_bytes(n as i32)
 }
 
-pub fn guess_wire_crypt(buf: &[u8]) -> (Vec<u8>, Vec<u8>) {
+/// Crypt plugin and its specific data from the server's op_cont_auth
+/// response. `preferred` (registered plugins) win over the built in ones.
+pub fn guess_wire_crypt(buf: &[u8], preferred: &[&str]) -> (Vec<u8>, Vec<u8>) {
     let mut available_plugins = vec![];
     let mut plugin_nonce = vec![];
 
@@ -379,15 +449,25 @@ pub fn guess_wire_crypt(buf: &[u8]) -> (Vec<u8>, Vec<u8>) {
             plugin_nonce.push(v);
         }
     }
+    for name in preferred {
+        if available_plugins.contains(name) {
+            let prefix = [name.as_bytes(), b"\x00"].concat();
+            let data = plugin_nonce
+                .iter()
+                .find(|nonce| nonce.starts_with(&prefix))
+                .map_or(vec![], |nonce| nonce[prefix.len()..].to_vec());
+            return (name.as_bytes().to_vec(), data);
+        }
+    }
     if available_plugins.contains(&"ChaCha64") {
         for nonce in &plugin_nonce {
-            if &nonce[0..9] == b"ChaCha64\x00" {
+            if nonce.starts_with(b"ChaCha64\x00") {
                 return (b"ChaCha64".to_vec(), nonce[9..].to_vec());
             }
         }
     } else if available_plugins.contains(&"ChaCha") {
         for nonce in &plugin_nonce {
-            if &nonce[0..7] == b"ChaCha\x00" {
+            if nonce.starts_with(b"ChaCha\x00") {
                 return (b"ChaCha".to_vec(), nonce[7..(7 + 12)].to_vec());
             }
         }
@@ -396,3 +476,213 @@ pub fn guess_wire_crypt(buf: &[u8]) -> (Vec<u8>, Vec<u8>) {
     }
     return (vec![], vec![]);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_encode_byte_order() {
+        assert_eq!(int32_to_bytes(0x01020304), [4, 3, 2, 1]);
+        assert_eq!(uint32_to_bytes(0xfffffffe), [0xfe, 0xff, 0xff, 0xff]);
+        assert_eq!(int16_to_bytes(0x0102), [2, 1]);
+        assert_eq!(bint32_to_bytes(-2), [0xff, 0xff, 0xff, 0xfe]);
+        assert_eq!(ubint32_to_bytes(0x01020304), [1, 2, 3, 4]);
+        assert_eq!(bint64_to_bytes(0x0102030405060708), [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(
+            bint128_to_bytes(-1 << 120),
+            [0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(f32_to_bytes(1.0), [0x3f, 0x80, 0, 0]);
+        assert_eq!(f64_to_bytes(-2.0), [0xc0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_decode_byte_order() {
+        let b = [1u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 0xff];
+        assert_eq!(bytes_to_int16(&b), 0x0201);
+        assert_eq!(bytes_to_uint16(&b), 0x0201);
+        assert_eq!(bytes_to_bint16(&b), 0x0102);
+        assert_eq!(bytes_to_buint16(&b), 0x0102);
+        assert_eq!(bytes_to_int32(&b), 0x04030201);
+        assert_eq!(bytes_to_uint32(&b), 0x04030201);
+        assert_eq!(bytes_to_bint32(&b), 0x01020304);
+        assert_eq!(bytes_to_buint32(&b), 0x01020304);
+        assert_eq!(bytes_to_int64(&b), 0x0807060504030201);
+        assert_eq!(bytes_to_uint64(&b), 0x0807060504030201);
+        assert_eq!(bytes_to_bint64(&b), 0x0102030405060708);
+        assert_eq!(bytes_to_buint64(&b), 0x0102030405060708);
+        assert_eq!(bytes_to_uint128(&b), 0x100f0e0d0c0b0a090807060504030201);
+        assert_eq!(bytes_to_int128(&b), 0x100f0e0d0c0b0a090807060504030201);
+        assert_eq!(bytes_to_buint128(&b), 0x0102030405060708090a0b0c0d0e0f10);
+        assert_eq!(bytes_to_bint128(&b), 0x0102030405060708090a0b0c0d0e0f10);
+
+        assert_eq!(bytes_to_int32(&[0xfe, 0xff, 0xff, 0xff]), -2);
+        assert_eq!(bytes_to_bint32(&[0xff, 0xff, 0xff, 0xfe]), -2);
+        assert_eq!(bytes_to_bint16(&[0x80, 0x00]), i16::MIN);
+        assert_eq!(bytes_to_f32(&[0x3f, 0x80, 0, 0]), 1.0);
+        assert_eq!(bytes_to_f64(&[0xc0, 0, 0, 0, 0, 0, 0, 0]), -2.0);
+    }
+
+    #[test]
+    fn test_date_time_round_trip() {
+        let b = convert_date(2024, 2, 29);
+        assert_eq!(b, [0x00, 0x00, 0xeb, 0xd1]);
+        assert_eq!(
+            bytes_to_naive_date(&b).unwrap(),
+            chrono::NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()
+        );
+
+        let b = convert_time(12, 34, 56, 789_000_000);
+        assert_eq!(bytes_to_buint32(&b), 452967890);
+        assert_eq!(
+            bytes_to_naive_time(&b).unwrap(),
+            chrono::NaiveTime::from_hms_milli_opt(12, 34, 56, 789).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_date_time_range() {
+        let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(convert_date(1858, 11, 17), [0, 0, 0, 0]);
+        assert_eq!(bytes_to_bint32(&convert_date(1858, 11, 16)), -1);
+        assert_eq!(bytes_to_bint32(&convert_date(1, 1, 1)), MIN_DATE_DAYS);
+        assert_eq!(bytes_to_bint32(&convert_date(9999, 12, 31)), MAX_DATE_DAYS);
+        assert_eq!(
+            bytes_to_naive_date(&bint32_to_bytes(MIN_DATE_DAYS)).unwrap(),
+            date(1, 1, 1)
+        );
+        assert_eq!(
+            bytes_to_naive_date(&bint32_to_bytes(MAX_DATE_DAYS)).unwrap(),
+            date(9999, 12, 31)
+        );
+        assert_eq!(
+            bytes_to_naive_date(&bint32_to_bytes(-1)).unwrap(),
+            date(1858, 11, 16)
+        );
+        assert!(bytes_to_naive_date(&bint32_to_bytes(MIN_DATE_DAYS - 1)).is_err());
+        assert!(bytes_to_naive_date(&bint32_to_bytes(MAX_DATE_DAYS + 1)).is_err());
+        assert!(bytes_to_naive_date(&bint32_to_bytes(i32::MIN)).is_err());
+
+        assert!(check_date_range(&date(1, 1, 1)).is_ok());
+        assert!(check_date_range(&date(9999, 12, 31)).is_ok());
+        assert!(check_date_range(&date(0, 12, 31)).is_err());
+        assert!(check_date_range(&date(10000, 1, 1)).is_err());
+
+        // deci-milliseconds: the last unit of the day and no further
+        let last = chrono::NaiveTime::from_hms_micro_opt(23, 59, 59, 999_900).unwrap();
+        let b = convert_time(23, 59, 59, 999_999_999);
+        assert_eq!(bytes_to_buint32(&b), TIME_UNITS_PER_DAY - 1);
+        assert_eq!(bytes_to_naive_time(&b).unwrap(), last);
+        assert!(bytes_to_naive_time(&ubint32_to_bytes(TIME_UNITS_PER_DAY)).is_err());
+        // a leap second stays in 23:59:59
+        let b = convert_time(23, 59, 59, 1_500_000_000);
+        assert_eq!(bytes_to_buint32(&b), TIME_UNITS_PER_DAY - 1);
+
+        let mut b = convert_date(2024, 2, 29).to_vec();
+        b.extend(ubint32_to_bytes(TIME_UNITS_PER_DAY));
+        assert!(bytes_to_naive_date_time(&b).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_date_round_trip(days in MIN_DATE_DAYS..=MAX_DATE_DAYS) {
+            let d = bytes_to_naive_date(&bint32_to_bytes(days)).unwrap();
+            let epoch = chrono::NaiveDate::from_ymd_opt(1858, 11, 17).unwrap();
+            prop_assert_eq!((d - epoch).num_days(), days as i64);
+            let b = convert_date(d.year(), d.month(), d.day());
+            prop_assert_eq!(bytes_to_bint32(&b), days);
+        }
+
+        #[test]
+        fn prop_time_round_trip(n in 0..TIME_UNITS_PER_DAY) {
+            let t = bytes_to_naive_time(&ubint32_to_bytes(n)).unwrap();
+            let b = convert_time(t.hour(), t.minute(), t.second(), t.nanosecond());
+            prop_assert_eq!(bytes_to_buint32(&b), n);
+        }
+
+        #[test]
+        fn prop_time_truncates_to_deci_milliseconds(
+            secs in 0u32..86400,
+            nano in 0u32..1_000_000_000,
+        ) {
+            let t = chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, nano).unwrap();
+            let b = convert_time(t.hour(), t.minute(), t.second(), t.nanosecond());
+            let back = bytes_to_naive_time(&b).unwrap();
+            prop_assert_eq!(back.num_seconds_from_midnight(), secs);
+            prop_assert_eq!(back.nanosecond(), nano / 100_000 * 100_000);
+        }
+
+        #[test]
+        fn prop_timestamp_round_trip(
+            days in MIN_DATE_DAYS..=MAX_DATE_DAYS,
+            n in 0..TIME_UNITS_PER_DAY,
+        ) {
+            let mut b = bint32_to_bytes(days).to_vec();
+            b.extend(ubint32_to_bytes(n));
+            let dt = bytes_to_naive_date_time(&b).unwrap();
+            let mut back = convert_date(dt.year(), dt.month(), dt.day()).to_vec();
+            back.extend(convert_time(dt.hour(), dt.minute(), dt.second(), dt.nanosecond()));
+            prop_assert_eq!(back, b);
+        }
+
+        #[test]
+        fn prop_out_of_range_is_error(days in any::<i32>(), n in any::<u32>()) {
+            let date = bytes_to_naive_date(&bint32_to_bytes(days));
+            let in_range = (MIN_DATE_DAYS..=MAX_DATE_DAYS).contains(&days);
+            prop_assert_eq!(date.is_ok(), in_range);
+            let time = bytes_to_naive_time(&ubint32_to_bytes(n));
+            prop_assert_eq!(time.is_ok(), n < TIME_UNITS_PER_DAY);
+        }
+    }
+
+    #[test]
+    fn test_time_zones() {
+        let seoul: chrono_tz::Tz = "Asia/Seoul".parse().unwrap();
+        let t = chrono::NaiveTime::from_hms_opt(8, 30, 0).unwrap();
+        let b = time_tz_to_bytes(&t, &seoul).unwrap();
+        // 23:30 UTC, then the zone id as an XDR short
+        assert_eq!(b[..4], convert_time(23, 30, 0, 0));
+        assert_eq!(b[4..], bint32_to_bytes(65222u16 as i16 as i32));
+        assert_eq!(bytes_to_time_tz(&b).unwrap(), (t, seoul));
+
+        // regions convert at 2020-01-01, not the current date
+        let new_york: chrono_tz::Tz = "America/New_York".parse().unwrap();
+        let b = time_tz_to_bytes(&t, &new_york).unwrap();
+        assert_eq!(b[..4], convert_time(13, 30, 0, 0));
+
+        let dt = seoul.with_ymd_and_hms(1967, 8, 11, 23, 45, 1).unwrap();
+        let b = date_time_tz_to_bytes(&dt).unwrap();
+        assert_eq!(b[..4], convert_date(1967, 8, 11));
+        assert_eq!(b[4..8], convert_time(14, 45, 1, 0));
+        assert_eq!(bytes_to_date_time_tz(&b).unwrap(), dt);
+
+        // offset zones: 1439 + minutes
+        let mut b = convert_time(0, 0, 0, 0).to_vec();
+        b.extend_from_slice(&bint32_to_bytes(1439 + 180));
+        let plus3: chrono_tz::Tz = "Etc/GMT-3".parse().unwrap();
+        assert_eq!(bytes_to_time_tz(&b).unwrap(), (chrono::NaiveTime::from_hms_opt(3, 0, 0).unwrap(), plus3));
+        b[4..].copy_from_slice(&bint32_to_bytes(1439));
+        assert_eq!(bytes_to_time_tz(&b).unwrap().1, chrono_tz::UTC);
+        b[4..].copy_from_slice(&bint32_to_bytes(1439 + 330));
+        assert!(bytes_to_time_tz(&b).is_err());
+        b[4..].copy_from_slice(&bint32_to_bytes(5000));
+        assert!(bytes_to_time_tz(&b).is_err());
+    }
+
+    #[test]
+    fn test_xdr_bytes() {
+        assert_eq!(xdr_bytes(b"abcde"), b"\x00\x00\x00\x05abcde\x00\x00\x00");
+        let (blr, v) = bytes_to_blr(&[0u8; 300]);
+        assert_eq!(blr, [14, 0x2c, 0x01]);
+        assert_eq!(v.len(), 300);
+    }
+
+    #[test]
+    fn test_bytes_to_str() {
+        assert_eq!(bytes_to_rtrim_str("José  ".as_bytes()), "José");
+        // WIN1252 text of a NONE column
+        assert_eq!(bytes_to_str(b"Jos\xe9"), "Jos\u{fffd}");
+    }
+}
@@ -0,0 +1,112 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `uuid::Uuid` values (feature `uuid`)
+//!
+//! Firebird stores UUIDs as CHAR(16) CHARACTER SET OCTETS, the type
+//! GEN_UUID() returns. The 16 bytes are in the order of the text form, the
+//! same as UUID_TO_CHAR and CHAR_TO_UUID use, so a `Uuid` round-trips with
+//! both. A `Uuid` is bound as 16 OCTETS bytes; text columns holding the
+//! 36 character form can be read as well.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use uuid::Uuid;
+//!
+//! // CREATE TABLE users (id CHAR(16) CHARACTER SET OCTETS PRIMARY KEY, name VARCHAR(50))
+//! let id: Uuid = conn
+//!     .prepare("INSERT INTO users (id, name) VALUES (GEN_UUID(), ?) RETURNING id")?
+//!     .query_row(("ann",), |row| row.get(0))?;
+//!
+//! let mut stmt = conn.prepare("SELECT name, UUID_TO_CHAR(id) FROM users WHERE id = ?")?;
+//! let text: String = stmt.query_row((id,), |row| row.get(1))?;
+//! assert_eq!(text, id.hyphenated().to_string().to_uppercase());
+//! ```
+
+use super::cellvalue::{CellValue, CellValueToVal};
+use super::error::{Error, ValueError};
+use super::param::ToSqlParam;
+use super::*;
+use uuid::Uuid;
+
+impl ToSqlParam for Uuid {
+    /// blr_text2 with the OCTETS character set
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        (
+            self.as_bytes().to_vec(),
+            vec![15, CHARSET_OCTETS, 0, 16, 0],
+            false,
+        )
+    }
+}
+
+impl ToSqlParam for &Uuid {
+    fn to_value_blr_isnull(&self) -> (Vec<u8>, Vec<u8>, bool) {
+        (*self).to_value_blr_isnull()
+    }
+}
+
+impl CellValueToVal<Uuid> for CellValue {
+    fn to_val(self) -> Result<Uuid, Error> {
+        match self {
+            CellValue::Octets(v) => Uuid::from_slice(&v).map_err(|_| {
+                Error::ValueError(ValueError::new(&format!(
+                    "Can't convert {} OCTETS bytes to UUID, 16 expected",
+                    v.len()
+                )))
+            }),
+            CellValue::Text(s) | CellValue::Varying(s) => Uuid::parse_str(s.trim()).map_err(|_| {
+                Error::ValueError(ValueError::new(&format!("Can't convert '{}' to UUID", s)))
+            }),
+            _ => Err(Error::ValueError(ValueError::new("Can't convert to UUID"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uuid() {
+        let id = Uuid::parse_str("0b1cf2a4-6b3e-4d8f-9a21-3c5d7e9f0a1b").unwrap();
+        let (value, blr, isnull) = id.to_value_blr_isnull();
+        assert_eq!(value, id.as_bytes().to_vec());
+        assert_eq!(blr, vec![15, 1, 0, 16, 0]);
+        assert!(!isnull);
+
+        // as GEN_UUID() returns it, and as UUID_TO_CHAR() shows it
+        let v: Uuid = CellValue::Octets(id.as_bytes().to_vec()).to_val().unwrap();
+        assert_eq!(v, id);
+        let v: Uuid = CellValue::Text("0B1CF2A4-6B3E-4D8F-9A21-3C5D7E9F0A1B".to_string())
+            .to_val()
+            .unwrap();
+        assert_eq!(v, id);
+
+        let r: Result<Uuid, Error> = CellValue::Octets(vec![1, 2, 3]).to_val();
+        assert!(r.is_err());
+        let r: Result<Uuid, Error> = CellValue::Long(1).to_val();
+        assert!(r.is_err());
+    }
+}
@@ -0,0 +1,103 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Optimistic locking helper
+//!
+//! Rows carry an integer version column. An update only succeeds when the
+//! version read earlier is still current, and the version is bumped in the
+//! same statement. Zero affected rows are reported as `Error::StaleRow`.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::{Error, Param};
+//!
+//! match conn.update_versioned(
+//!     "customers", "id", 42, "version", 7,
+//!     &[("name", Param::from("ACME"))],
+//! ) {
+//!     Ok(new_version) => println!("saved, version {}", new_version),
+//!     Err(Error::StaleRow) => println!("modified by someone else, reload"),
+//!     Err(e) => return Err(e),
+//! }
+//! ```
+
+use super::error::Error;
+use super::param::{Param, ToSqlParam};
+use super::statement::Statement;
+
+/// Build `UPDATE table SET c1 = ?, ..., version = version + 1 WHERE key = ? AND version = ?`.
+/// Identifiers are inserted as written.
+pub(crate) fn update_versioned_sql(
+    table: &str,
+    key_column: &str,
+    version_column: &str,
+    columns: &[&str],
+) -> String {
+    let mut sets: Vec<String> = columns.iter().map(|c| format!("{} = ?", c)).collect();
+    sets.push(format!("{} = {} + 1", version_column, version_column));
+    format!(
+        "UPDATE {} SET {} WHERE {} = ? AND {} = ?",
+        table,
+        sets.join(", "),
+        key_column,
+        version_column
+    )
+}
+
+/// Execute the prepared versioned update and return the new version
+pub(crate) fn execute_versioned(
+    stmt: &mut Statement<'_>,
+    key: Param,
+    version: i64,
+    values: &[(&str, Param)],
+) -> Result<i64, Error> {
+    let new_version = version + 1;
+    let version = Param::Int64(version);
+    let mut params: Vec<&dyn ToSqlParam> =
+        values.iter().map(|(_, v)| v as &dyn ToSqlParam).collect();
+    params.push(&key);
+    params.push(&version);
+    stmt.execute(params.as_slice())?;
+    if stmt.rowcount() == 0 {
+        return Err(Error::StaleRow);
+    }
+    Ok(new_version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_versioned_sql() {
+        assert_eq!(
+            update_versioned_sql("customers", "id", "version", &["name", "city"]),
+            "UPDATE customers SET name = ?, city = ?, version = version + 1 WHERE id = ? AND version = ?"
+        );
+        assert_eq!(
+            update_versioned_sql("t", "id", "rev", &[]),
+            "UPDATE t SET rev = rev + 1 WHERE id = ? AND rev = ?"
+        );
+    }
+}
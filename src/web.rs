@@ -0,0 +1,184 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Transaction per request for web handlers (`web` feature)
+//!
+//! `DbTx` checks a connection out of a `ConnectionPool` and begins a
+//! transaction on it. `finish` commits when the handler's result is Ok and
+//! rolls back when it is Err; a `DbTx` dropped without finishing (an early
+//! `?` return or a panic) rolls back too. Either way the connection goes
+//! back to the pool. `with_savepoint` lets part of a request fail without
+//! losing the rest of its work.
+//!
+//! Nothing here depends on a web framework: build the `DbTx` at the start of
+//! an axum or actix-web handler (or in an extractor of your own) and pass
+//! the handler's result through `finish`, or let `DbTx::run` do both.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::DbTx;
+//!
+//! async fn create_order(State(pool): State<Arc<ConnectionPool>>, Json(order): Json<Order>)
+//!     -> Result<Json<i64>, AppError>
+//! {
+//!     let mut tx = DbTx::begin(&pool)?;
+//!     let id: i64 = tx
+//!         .prepare("INSERT INTO orders (customer) VALUES (?) RETURNING id")?
+//!         .query_row((order.customer,), |row| row.get(0))?;
+//!     // a failed audit entry doesn't cancel the order
+//!     let _ = tx.with_savepoint(|tx| tx.execute("INSERT INTO audit (order_id) VALUES (?)", (id,)));
+//!     tx.finish(Ok(Json(id)))
+//! }
+//!
+//! let id = DbTx::run(&pool, |tx| {
+//!     tx.execute("UPDATE stock SET qty = qty - 1 WHERE item = ?", (item,))
+//! })?;
+//! ```
+
+use std::sync::Arc;
+
+use super::error::Error;
+use super::params::Params;
+use super::pool::{ConnectionPool, PoolGuard};
+use super::statement::Statement;
+use super::transaction::{TransactionOptions, savepoint};
+
+/// Transaction on a pooled connection, committed or rolled back with the
+/// result of a request
+pub struct DbTx {
+    guard: PoolGuard,
+    trans_handle: i32,
+    finished: bool, // true if commit() or rollback() was called
+    savepoint_seq: u32,
+}
+
+impl DbTx {
+    /// Check out a connection and begin a transaction with the default
+    /// options of the connection
+    pub fn begin(pool: &Arc<ConnectionPool>) -> Result<DbTx, Error> {
+        let guard = pool.get()?;
+        let trans_handle = guard._begin_trans()?;
+        Ok(DbTx::new(guard, trans_handle))
+    }
+
+    /// Check out a connection and begin a transaction with `options`
+    pub fn begin_with(
+        pool: &Arc<ConnectionPool>,
+        options: TransactionOptions,
+    ) -> Result<DbTx, Error> {
+        let guard = pool.get()?;
+        let trans_handle = guard._begin_trans_with_options(&options)?;
+        Ok(DbTx::new(guard, trans_handle))
+    }
+
+    fn new(guard: PoolGuard, trans_handle: i32) -> DbTx {
+        DbTx {
+            guard,
+            trans_handle,
+            finished: false,
+            savepoint_seq: 0,
+        }
+    }
+
+    /// Run `f` in a new `DbTx` and finish it with the result of `f`
+    pub fn run<T, E, F>(pool: &Arc<ConnectionPool>, f: F) -> Result<T, E>
+    where
+        E: From<Error>,
+        F: FnOnce(&mut DbTx) -> Result<T, E>,
+    {
+        let mut tx = DbTx::begin(pool)?;
+        let result = f(&mut tx);
+        tx.finish(result)
+    }
+
+    pub fn execute_batch(&mut self, query: &str) -> Result<(), Error> {
+        self.guard._execute_batch(query, self.trans_handle)
+    }
+
+    pub fn execute<P: Params>(&mut self, query: &str, params: P) -> Result<(), Error> {
+        self.guard._execute(query, params, self.trans_handle)
+    }
+
+    pub fn prepare(&mut self, query: &str) -> Result<Statement<'_>, Error> {
+        self.guard._prepare(query, self.trans_handle, false)
+    }
+
+    /// Commit if `result` is Ok, roll back if it is Err, and return
+    /// `result`; a failed commit is returned instead
+    pub fn finish<T, E: From<Error>>(mut self, result: Result<T, E>) -> Result<T, E> {
+        match result {
+            Ok(v) => {
+                self.end(true)?;
+                Ok(v)
+            }
+            Err(e) => {
+                // the handler's error is the one worth reporting
+                let _ = self.end(false);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn commit(mut self) -> Result<(), Error> {
+        self.end(true)
+    }
+
+    pub fn rollback(mut self) -> Result<(), Error> {
+        self.end(false)
+    }
+
+    fn end(&mut self, commit: bool) -> Result<(), Error> {
+        let result = if commit {
+            self.guard._commit_final(self.trans_handle)
+        } else {
+            self.guard._rollback_final(self.trans_handle)
+        };
+        // otherwise drop rolls back
+        self.finished = result.is_ok();
+        result
+    }
+
+    /// Run `f` inside a savepoint, see `Transaction::with_savepoint`
+    pub fn with_savepoint<T, F>(&mut self, f: F) -> Result<T, Error>
+    where
+        F: FnOnce(&mut Self) -> Result<T, Error>,
+    {
+        self.savepoint_seq += 1;
+        let name = format!("FIREBIRUST_SP_{}", self.savepoint_seq);
+        savepoint(
+            self,
+            &name,
+            |tx, sql| tx.guard._execute_immediate(sql, tx.trans_handle),
+            f,
+        )
+    }
+}
+
+impl Drop for DbTx {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.guard.drop_transaction(self.trans_handle);
+        }
+    }
+}
@@ -21,55 +21,131 @@
 // SOFTWARE.
 
 use super::compression::WireCompressor;
-use super::crypt_translater::{Arc4, ChaCha, CryptTranslator};
+use super::crypt_translater::{CryptPlugin, CryptTranslator};
 use super::error::Error;
-use digest::Digest;
-use sha2::Sha256;
-use std::collections::VecDeque;
-use std::io::{BufReader, BufWriter, Read, Write};
-use std::net::TcpStream;
+#[cfg(feature = "failpoints")]
+use super::failpoints::{self, Failpoints};
+use super::hooks::ErrorReporter;
+use super::xdr::XdrRead;
+use std::borrow::Cow;
+use std::io::{BufRead, BufReader, IoSlice, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Socket level counters of a connection
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WireStats {
+    /// Times the client sent packets and then waited for the server
+    pub round_trips: u64,
+    /// Logical operations (or deferred batches) sent
+    pub packets_sent: u64,
+    /// write syscalls
+    pub write_calls: u64,
+    /// read syscalls
+    pub read_calls: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
 
 pub struct WireChannel {
     stream: TcpStream,  // Keep reference for timeout control
     reader: BufReader<TcpStream>,
-    writer: BufWriter<TcpStream>,
-    read_buf: VecDeque<u8>,  // VecDeque for O(1) pop_front
+    // contiguous buffer of received data; bytes before read_pos are consumed
+    read_buf: Vec<u8>,
+    read_pos: usize,
     read_trans: Option<Box<dyn CryptTranslator>>,
     write_trans: Option<Box<dyn CryptTranslator>>,
     compressor: Option<WireCompressor>,
     compressed: bool,
+    stats: WireStats,
+    // packets were sent since the last read
+    awaiting_response: bool,
+    on_error: Option<ErrorReporter>,
+    #[cfg(feature = "failpoints")]
+    failpoints: Failpoints,
 }
 
 impl WireChannel {
-    pub fn new(host: &str, port: u16) -> Result<WireChannel, Error> {
-        let stream = TcpStream::connect(format!("{}:{}", host, port))?;
+    /// `buffer_size` is the socket read buffer (the `buffer_size` URL parameter,
+    /// 32KB by default, matching fbclient's MAX_DATA_HW). Each address of
+    /// `host` is tried for at most `connect_timeout`.
+    pub fn new(
+        host: &str,
+        port: u16,
+        buffer_size: usize,
+        connect_timeout: Option<Duration>,
+    ) -> Result<WireChannel, Error> {
+        let stream = match connect_timeout {
+            Some(timeout) => connect_timeout_any(host, port, timeout)?,
+            None => TcpStream::connect(format!("{}:{}", host, port))?,
+        };
         // CRITICAL: Disable Nagle's algorithm for low-latency operations
         stream.set_nodelay(true)?;
-        // Buffer size matching fbclient's MAX_DATA_HW (32KB)
-        const BUFFER_SIZE: usize = 32768;
-        let reader = BufReader::with_capacity(BUFFER_SIZE, stream.try_clone()?);
-        let writer = BufWriter::with_capacity(BUFFER_SIZE, stream.try_clone()?);
+        let reader = BufReader::with_capacity(buffer_size, stream.try_clone()?);
         Ok(WireChannel {
             stream,
             reader,
-            writer,
-            read_buf: VecDeque::with_capacity(BUFFER_SIZE),
+            read_buf: Vec::with_capacity(buffer_size),
+            read_pos: 0,
             read_trans: None,
             write_trans: None,
             compressor: None,
             compressed: false,
+            stats: WireStats::default(),
+            awaiting_response: false,
+            on_error: None,
+            #[cfg(feature = "failpoints")]
+            failpoints: Failpoints::default(),
         })
     }
 
-    /// Set read timeout for the underlying socket
-    pub fn set_read_timeout(&self, timeout: Option<std::time::Duration>) -> Result<(), Error> {
+    pub fn stats(&self) -> WireStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = WireStats::default();
+    }
+
+    /// Wait at most `timeout` for data (`None` waits forever); reads past
+    /// it fail with `Error::Timeout`
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> Result<(), Error> {
         self.stream.set_read_timeout(timeout)?;
         Ok(())
     }
 
+    pub fn read_timeout(&self) -> Result<Option<Duration>, Error> {
+        Ok(self.stream.read_timeout()?)
+    }
+
+    /// Called with the error of each failed read or send
+    pub(crate) fn set_error_reporter(&mut self, reporter: Option<ErrorReporter>) {
+        self.on_error = reporter;
+    }
+
+    fn reported(&self, err: Error) -> Error {
+        if let Some(report) = &self.on_error {
+            report(&err);
+        }
+        err
+    }
+
+    #[cfg(feature = "failpoints")]
+    pub(crate) fn set_failpoints(&mut self, failpoints: Failpoints) {
+        self.failpoints = failpoints;
+    }
+
+    /// Wait before an op_fetch if a failpoint asks for it
+    #[cfg(feature = "failpoints")]
+    pub(crate) fn delay_fetch(&self) {
+        if let Some(delay) = self.failpoints.fetch_delay() {
+            std::thread::sleep(delay);
+        }
+    }
+
     /// Enable wire compression
-    pub fn enable_compression(&mut self) {
-        self.compressor = Some(WireCompressor::new());
+    pub fn enable_compression(&mut self, compressor: WireCompressor) {
+        self.compressor = Some(compressor);
         self.compressed = true;
     }
 
@@ -78,80 +154,305 @@ impl WireChannel {
         self.compressed
     }
 
-    pub fn set_crypt_key(&mut self, plugin: &[u8], key: &[u8], nonce: &[u8]) {
-        if plugin == b"ChaCha64" || plugin == b"ChaCha" {
-            let mut hasher = Sha256::new();
-            hasher.update(&key);
-            let key = &hasher.finalize().to_vec();
-            self.read_trans = Some(Box::new(ChaCha::new(key, nonce)));
-            self.write_trans = Some(Box::new(ChaCha::new(key, nonce)));
-        } else if plugin == b"Arc4" {
-            self.read_trans = Some(Box::new(Arc4::new(key)));
-            self.write_trans = Some(Box::new(Arc4::new(key)));
-        }
+    /// Name of the compression in use, `None` when off
+    pub fn compression_name(&self) -> Option<&str> {
+        self.compressor.as_ref().map(|c| c.name())
+    }
+
+    pub fn set_crypt_key(
+        &mut self,
+        plugin: &dyn CryptPlugin,
+        key: &[u8],
+        nonce: &[u8],
+    ) -> Result<(), Error> {
+        self.read_trans = Some(plugin.translator(key, nonce)?);
+        self.write_trans = Some(plugin.translator(key, nonce)?);
+        Ok(())
     }
 
     pub fn read(&mut self, n: usize) -> Result<Vec<u8>, Error> {
-        // Fill buffer if needed
+        Ok(self.read_exact_slice(n)?.to_vec())
+    }
+
+    /// Fill `out` with the next received bytes
+    pub fn read_into(&mut self, out: &mut [u8]) -> Result<(), Error> {
+        out.copy_from_slice(self.read_exact_slice(out.len())?);
+        Ok(())
+    }
+
+    /// Next `n` received bytes, borrowed from the read buffer (no copy)
+    pub fn read_exact_slice(&mut self, n: usize) -> Result<&[u8], Error> {
+        self.fill(n)?;
+        let start = self.read_pos;
+        self.read_pos += n;
+        Ok(&self.read_buf[start..start + n])
+    }
+
+    /// Received bytes not consumed yet
+    pub(crate) fn buffered(&self) -> &[u8] {
+        &self.read_buf[self.read_pos..]
+    }
+
+    pub(crate) fn consume(&mut self, n: usize) {
+        self.read_pos += n;
+    }
+
+    /// Wait for at least one more byte
+    pub(crate) fn fill_more(&mut self) -> Result<(), Error> {
+        self.fill(self.available() + 1)
+    }
+
+    fn available(&self) -> usize {
+        self.read_buf.len() - self.read_pos
+    }
+
+    /// Move unread bytes to the front so the buffer does not grow without bound
+    fn compact(&mut self) {
+        if self.read_pos == self.read_buf.len() {
+            self.read_buf.clear();
+        } else {
+            self.read_buf.copy_within(self.read_pos.., 0);
+            self.read_buf.truncate(self.available());
+        }
+        self.read_pos = 0;
+    }
+
+    fn fill(&mut self, n: usize) -> Result<(), Error> {
+        if self.available() >= n {
+            return Ok(());
+        }
+        self.fill_from_socket(n).map_err(|e| self.reported(e))
+    }
+
+    fn fill_from_socket(&mut self, n: usize) -> Result<(), Error> {
+        self.compact();
+        #[cfg(feature = "failpoints")]
+        self.failpoints.check()?;
         while self.read_buf.len() < n {
-            let mut input_buf = [0u8; 8192];
-            let ln = self.reader.read(&mut input_buf)?;
+            if self.awaiting_response {
+                self.awaiting_response = false;
+                self.stats.round_trips += 1;
+            }
+            if self.reader.buffer().is_empty() {
+                self.stats.read_calls += 1;
+            }
+            // up to buffer_size bytes per read syscall, taken from the BufReader without a copy
+            let input = self.reader.fill_buf().map_err(Error::from_socket)?;
+            let ln = input.len();
+            self.stats.bytes_received += ln as u64;
             if ln == 0 {
-                return Err(Error::IoError(std::io::Error::new(
-                    std::io::ErrorKind::UnexpectedEof,
-                    "Connection closed",
-                )));
+                return Err(Error::ConnectionLost);
             }
 
             // Apply decryption first if enabled
-            let decrypted = if let Some(ref mut trans) = self.read_trans {
-                trans.translate(&input_buf[..ln]).to_vec()
+            #[allow(unused_mut)]
+            let mut decrypted = if let Some(ref mut trans) = self.read_trans {
+                Cow::Owned(trans.translate(input).to_vec())
             } else {
-                input_buf[..ln].to_vec()
+                Cow::Borrowed(input)
             };
 
             // Then apply decompression if enabled
-            let data = if self.compressed {
-                if let Some(ref mut comp) = self.compressor {
-                    comp.decompress(&decrypted)?
-                } else {
-                    decrypted
+            match self.compressor {
+                Some(ref mut comp) if self.compressed => {
+                    #[cfg(feature = "failpoints")]
+                    if self.failpoints.on_compressed_frame() {
+                        failpoints::corrupt(decrypted.to_mut());
+                    }
+                    let data = comp.decompress(&decrypted)?;
+                    self.read_buf.extend_from_slice(&data);
                 }
-            } else {
-                decrypted
-            };
-
-            self.read_buf.extend(&data);
+                _ => self.read_buf.extend_from_slice(&decrypted),
+            }
+            self.reader.consume(ln);
         }
+        Ok(())
+    }
 
-        // Efficient extraction using drain - O(n) instead of O(n²)
-        let v: Vec<u8> = self.read_buf.drain(..n).collect();
-        Ok(v)
+    /// Send one logical operation. The parts are written with a single
+    /// vectored write (when the socket accepts it all), without copying them
+    /// into an intermediate buffer unless compression needs a contiguous input.
+    pub fn send(&mut self, parts: &[&[u8]]) -> Result<(), Error> {
+        self.send_to_socket(parts).map_err(|e| self.reported(e))
     }
 
-    pub fn write(&mut self, buf: &[u8]) -> Result<(), Error> {
-        // Apply compression first if enabled
-        let compressed = if self.compressed {
-            if let Some(ref mut comp) = self.compressor {
-                comp.compress(buf)?
-            } else {
-                buf.to_vec()
+    fn send_to_socket(&mut self, parts: &[&[u8]]) -> Result<(), Error> {
+        #[cfg(feature = "failpoints")]
+        if let Err(e) = self.failpoints.on_send() {
+            let _ = self.stream.shutdown(std::net::Shutdown::Both);
+            return Err(e);
+        }
+        // Apply compression first if enabled, then encryption
+        let owned: Vec<Vec<u8>> = if self.compressed
+            && let Some(ref mut comp) = self.compressor
+        {
+            let compressed = comp.compress(&parts.concat())?;
+            match self.write_trans {
+                Some(ref mut trans) => vec![trans.translate(&compressed).to_vec()],
+                None => vec![compressed],
             }
+        } else if let Some(ref mut trans) = self.write_trans {
+            parts.iter().map(|p| trans.translate(p).to_vec()).collect()
         } else {
-            buf.to_vec()
+            Vec::new()
         };
 
-        // Then apply encryption if enabled
-        if let Some(ref mut trans) = self.write_trans {
-            self.writer.write_all(&*trans.translate(&compressed))?;
+        if owned.is_empty() {
+            self.write_all_vectored(parts)?;
         } else {
-            self.writer.write_all(&compressed)?;
+            let borrowed: Vec<&[u8]> = owned.iter().map(|v| v.as_slice()).collect();
+            self.write_all_vectored(&borrowed)?;
         }
+        self.stats.packets_sent += 1;
+        self.awaiting_response = true;
         Ok(())
     }
 
-    pub fn flush(&mut self) -> Result<(), Error> {
-        self.writer.flush()?;
+    fn write_all_vectored(&mut self, parts: &[&[u8]]) -> Result<(), Error> {
+        let mut slices: Vec<IoSlice> = parts
+            .iter()
+            .filter(|p| !p.is_empty())
+            .map(|p| IoSlice::new(p))
+            .collect();
+        let mut slices = slices.as_mut_slice();
+        while !slices.is_empty() {
+            let n = self
+                .stream
+                .write_vectored(slices)
+                .map_err(Error::from_socket)?;
+            self.stats.write_calls += 1;
+            if n == 0 {
+                return Err(Error::ConnectionLost);
+            }
+            self.stats.bytes_sent += n as u64;
+            IoSlice::advance_slices(&mut slices, n);
+        }
         Ok(())
     }
 }
+
+impl XdrRead for WireChannel {
+    fn read_slice(&mut self, n: usize) -> Result<&[u8], Error> {
+        self.read_exact_slice(n)
+    }
+}
+
+/// Connect to the first address of `host` answering within `timeout`
+fn connect_timeout_any(host: &str, port: u16, timeout: Duration) -> Result<TcpStream, Error> {
+    let mut last_err = None;
+    for addr in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(match last_err {
+        Some(e) if e.kind() == std::io::ErrorKind::TimedOut => Error::Timeout,
+        Some(e) => Error::IoError(e),
+        None => Error::IoError(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "host has no address",
+        )),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_send_stats() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let echo = std::thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 8];
+            sock.read_exact(&mut buf).unwrap();
+            sock.write_all(&buf).unwrap();
+        });
+
+        let mut channel = WireChannel::new("127.0.0.1", port, 32768, None).unwrap();
+        channel.send(&[&[0, 0, 0, 1], &[], &[2, 3, 4, 5]]).unwrap();
+        assert_eq!(channel.read(8).unwrap(), vec![0, 0, 0, 1, 2, 3, 4, 5]);
+        echo.join().unwrap();
+
+        let stats = channel.stats();
+        assert_eq!(stats.packets_sent, 1);
+        assert_eq!(stats.round_trips, 1);
+        assert_eq!(stats.bytes_sent, 8);
+        assert_eq!(stats.bytes_received, 8);
+        assert!(stats.write_calls >= 1);
+        channel.reset_stats();
+        assert_eq!(channel.stats(), WireStats::default());
+    }
+
+    #[cfg(feature = "failpoints")]
+    #[test]
+    fn test_failpoint_drop_after_packets() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            sock.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let mut channel = WireChannel::new("127.0.0.1", port, 32768, None).unwrap();
+        channel.set_failpoints(Failpoints::new().drop_after_packets(1));
+        channel.send(&[&[0, 0, 0, 1]]).unwrap();
+        assert!(matches!(channel.send(&[&[0, 0, 0, 2]]), Err(Error::ConnectionLost)));
+        assert!(matches!(channel.read(4), Err(Error::ConnectionLost)));
+        // the server saw the first packet, then the socket close
+        assert_eq!(server.join().unwrap(), vec![0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_read_buffer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            sock.write_all(&[1, 2, 3, 4, 5, 6]).unwrap();
+            rx.recv().unwrap();
+            sock.write_all(&[7, 8]).unwrap();
+        });
+
+        let mut channel = WireChannel::new("127.0.0.1", port, 32768, None).unwrap();
+        assert_eq!(channel.read_exact_slice(2).unwrap(), &[1, 2]);
+        let mut out = [0u8; 3];
+        channel.read_into(&mut out).unwrap();
+        assert_eq!(out, [3, 4, 5]);
+        tx.send(()).unwrap();
+        // spans the second segment: unread byte is moved to the front first
+        assert_eq!(channel.read(3).unwrap(), vec![6, 7, 8]);
+        assert_eq!(channel.read_pos, 3);
+        assert_eq!(channel.read_buf, vec![6, 7, 8]);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_timeout_and_connection_lost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let server = std::thread::spawn(move || {
+            let (mut sock, _) = listener.accept().unwrap();
+            rx.recv().unwrap();
+            sock.write_all(&[1, 2]).unwrap();
+            // closed with the response incomplete
+        });
+
+        let mut channel = WireChannel::new("127.0.0.1", port, 32768, None).unwrap();
+        channel
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        assert!(matches!(channel.read(4), Err(Error::Timeout)));
+        tx.send(()).unwrap();
+        server.join().unwrap();
+        assert!(matches!(channel.read(4), Err(Error::ConnectionLost)));
+    }
+}
@@ -20,71 +20,169 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
-use super::crypt_translater::{Arc4, ChaCha, CryptTranslator};
+use super::crypt_translater::{CryptPlugin, CryptTranslator};
 use super::error::Error;
-use async_std::io::prelude::*;
-use async_std::net::TcpStream;
-use digest::Digest;
-use sha2::Sha256;
+#[cfg(feature = "failpoints")]
+use super::failpoints::Failpoints;
+use super::hooks::ErrorReporter;
+use super::runtime::{self, Runtime, Transport};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Socket operation a dropped future left unfinished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Interrupted {
+    Read,
+    Write,
+}
 
 pub struct WireChannelAsync {
-    stream: TcpStream,
+    runtime: Arc<dyn Runtime>,
+    stream: Box<dyn Transport>,
     read_buf: Vec<u8>,
     read_trans: Option<Box<dyn CryptTranslator>>,
     write_trans: Option<Box<dyn CryptTranslator>>,
+    read_timeout: Option<Duration>,
+    in_flight: Option<Interrupted>,
+    on_error: Option<ErrorReporter>,
+    #[cfg(feature = "failpoints")]
+    failpoints: Failpoints,
 }
 
 impl WireChannelAsync {
-    pub async fn new(host: &str, port: u16) -> Result<WireChannelAsync, Error> {
-        let stream = TcpStream::connect(format!("{}:{}", host, port)).await?;
+    /// Connect to `host`, giving up with `Error::Timeout` after `connect_timeout`
+    pub async fn new(
+        runtime: Arc<dyn Runtime>,
+        host: &str,
+        port: u16,
+        connect_timeout: Option<Duration>,
+    ) -> Result<WireChannelAsync, Error> {
+        let stream = match connect_timeout {
+            Some(timeout) => runtime::timeout(&*runtime, timeout, runtime.connect(host, port)).await??,
+            None => runtime.connect(host, port).await?,
+        };
         Ok(WireChannelAsync {
+            runtime,
             stream,
             read_buf: Vec::new(),
             read_trans: None,
             write_trans: None,
+            read_timeout: None,
+            in_flight: None,
+            on_error: None,
+            #[cfg(feature = "failpoints")]
+            failpoints: Failpoints::default(),
         })
     }
 
-    pub fn set_crypt_key(&mut self, plugin: &[u8], key: &[u8], nonce: &[u8]) {
-        if plugin == b"ChaCha64" || plugin == b"ChaCha" {
-            let mut hasher = Sha256::new();
-            hasher.update(&key);
-            let key = &hasher.finalize().to_vec();
-            self.read_trans = Some(Box::new(ChaCha::new(key, nonce)));
-            self.write_trans = Some(Box::new(ChaCha::new(key, nonce)));
-        } else if plugin == b"Arc4" {
-            self.read_trans = Some(Box::new(Arc4::new(key)));
-            self.write_trans = Some(Box::new(Arc4::new(key)));
+    pub(crate) fn runtime(&self) -> Arc<dyn Runtime> {
+        self.runtime.clone()
+    }
+
+    pub fn set_crypt_key(
+        &mut self,
+        plugin: &dyn CryptPlugin,
+        key: &[u8],
+        nonce: &[u8],
+    ) -> Result<(), Error> {
+        self.read_trans = Some(plugin.translator(key, nonce)?);
+        self.write_trans = Some(plugin.translator(key, nonce)?);
+        Ok(())
+    }
+
+    /// Called with the error of each failed read or write
+    pub(crate) fn set_error_reporter(&mut self, reporter: Option<ErrorReporter>) {
+        self.on_error = reporter;
+    }
+
+    fn reported(&self, err: Error) -> Error {
+        if let Some(report) = &self.on_error {
+            report(&err);
         }
+        err
     }
 
-    pub async fn read(&mut self, n: usize) -> Result<Vec<u8>, Error> {
-        let mut input_buf = [0u8; 4096];
+    #[cfg(feature = "failpoints")]
+    pub(crate) fn set_failpoints(&mut self, failpoints: Failpoints) {
+        self.failpoints = failpoints;
+    }
 
-        while self.read_buf.len() < n {
-            let ln = self.stream.read(&mut input_buf).await?;
-            self.read_buf.extend(input_buf[..ln].iter().copied());
+    /// Wait before an op_fetch if a failpoint asks for it
+    #[cfg(feature = "failpoints")]
+    pub(crate) async fn delay_fetch(&self) {
+        if let Some(delay) = self.failpoints.fetch_delay() {
+            self.runtime.sleep(delay).await;
         }
+    }
 
-        let mut v: Vec<u8> = Vec::new();
-        for _ in 0..n {
-            v.push(self.read_buf.remove(0));
+    /// Wait at most `timeout` for data (`None` waits forever); reads past
+    /// it fail with `Error::Timeout`
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    pub async fn read(&mut self, n: usize) -> Result<Vec<u8>, Error> {
+        while self.read_buf.len() < n {
+            self.fill_more().await?;
         }
+        Ok(self.read_buf.drain(..n).collect())
+    }
 
-        if let Some(ref mut trans) = self.read_trans {
-            let translated: Vec<u8> = (*trans.translate(&v)).to_vec();
-            Ok(translated)
-        } else {
-            Ok(v)
+    /// Received bytes not consumed yet
+    pub(crate) fn buffered(&self) -> &[u8] {
+        &self.read_buf
+    }
+
+    pub(crate) fn consume(&mut self, n: usize) {
+        self.read_buf.drain(..n);
+    }
+
+    /// Wait for the next chunk of data, decrypted as it arrives
+    pub(crate) async fn fill_more(&mut self) -> Result<(), Error> {
+        self.read_from_socket().await.map_err(|e| self.reported(e))
+    }
+
+    async fn read_from_socket(&mut self) -> Result<(), Error> {
+        #[cfg(feature = "failpoints")]
+        self.failpoints.check()?;
+        let mut input_buf = [0u8; 4096];
+        self.in_flight = Some(Interrupted::Read);
+        let read = self.stream.read(&mut input_buf);
+        let ln = match self.read_timeout {
+            Some(timeout) => runtime::timeout(&*self.runtime, timeout, read).await,
+            None => Ok(read.await),
+        };
+        self.in_flight = None;
+        let ln = ln?.map_err(Error::from_socket)?;
+        if ln == 0 {
+            return Err(Error::ConnectionLost);
         }
+        match self.read_trans {
+            Some(ref mut trans) => self.read_buf.extend_from_slice(&trans.translate(&input_buf[..ln])),
+            None => self.read_buf.extend_from_slice(&input_buf[..ln]),
+        }
+        Ok(())
     }
 
     pub async fn write(&mut self, buf: &[u8]) -> Result<(), Error> {
-        if let Some(ref mut trans) = self.write_trans {
-            self.stream.write(&*trans.translate(buf)).await?;
+        self.write_to_socket(buf).await.map_err(|e| self.reported(e))
+    }
+
+    async fn write_to_socket(&mut self, buf: &[u8]) -> Result<(), Error> {
+        #[cfg(feature = "failpoints")]
+        self.failpoints.on_send()?;
+        self.in_flight = Some(Interrupted::Write);
+        let written = if let Some(ref mut trans) = self.write_trans {
+            self.stream.write_all(&trans.translate(buf)).await
         } else {
-            self.stream.write(buf).await?;
-        }
-        Ok(())
+            self.stream.write_all(buf).await
+        };
+        self.in_flight = None;
+        written.map_err(Error::from_socket)
+    }
+
+    /// The read or write a dropped future was waiting on, cleared by the call
+    pub(crate) fn take_interrupted(&mut self) -> Option<Interrupted> {
+        self.in_flight.take()
     }
 }
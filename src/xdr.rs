@@ -0,0 +1,440 @@
+// MIT License
+//
+// Copyright (c) 2021 Hajime Nakagami<nakagami@gmail.com>
+// Copyright (c) 2026 Roberto
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! XDR encoding and decoding of the Firebird wire protocol
+//!
+//! Integers are 4 byte big endian, opaque data (strings, buffers) is a length
+//! followed by the bytes, padded to a multiple of 4. The decoders work on
+//! anything implementing `XdrRead`: the connection's socket, or a byte slice
+//! through `Decoder`, which is what a tool parsing captured traffic needs.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use firebirust::xdr::{self, Decoder, XdrRead};
+//!
+//! // body of a packet after its op code
+//! let mut d = Decoder::new(&captured[4..]);
+//! let response = xdr::decode_response(&mut d)?;
+//! if let Some(e) = response.error() {
+//!     println!("{:?}", e);
+//! }
+//! ```
+
+use super::*;
+use crate::error::{Error, FirebirdError, ValueError};
+use bytes::{BufMut, BytesMut};
+
+/// Padding after `n` bytes of opaque data
+pub fn pad_len(n: usize) -> usize {
+    (4 - n % 4) & 3
+}
+
+pub fn put_u32(buf: &mut BytesMut, n: u32) {
+    buf.put_u32(n);
+}
+
+pub fn put_i32(buf: &mut BytesMut, n: i32) {
+    buf.put_i32(n);
+}
+
+/// Length, bytes and padding
+pub fn put_opaque(buf: &mut BytesMut, b: &[u8]) {
+    buf.put_u32(b.len() as u32);
+    buf.extend_from_slice(b);
+    buf.put_bytes(0, pad_len(b.len()));
+}
+
+pub fn put_str(buf: &mut BytesMut, s: &str) {
+    put_opaque(buf, s.as_bytes());
+}
+
+/// Source of XDR data
+pub trait XdrRead {
+    /// Next `n` bytes
+    fn read_slice(&mut self, n: usize) -> Result<&[u8], Error>;
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        let b = self.read_slice(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn read_i32(&mut self) -> Result<i32, Error> {
+        Ok(self.read_u32()? as i32)
+    }
+
+    /// `n` bytes followed by their padding
+    fn read_padded(&mut self, n: usize) -> Result<Vec<u8>, Error> {
+        let v = self.read_slice(n)?.to_vec();
+        self.read_slice(pad_len(n))?;
+        Ok(v)
+    }
+
+    /// Length prefixed opaque data
+    fn read_opaque(&mut self) -> Result<Vec<u8>, Error> {
+        let n = self.read_u32()? as usize;
+        self.read_padded(n)
+    }
+
+    fn read_string(&mut self) -> Result<String, Error> {
+        Ok(String::from_utf8_lossy(&self.read_opaque()?).into_owned())
+    }
+}
+
+/// XDR reader over a byte slice
+pub struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Decoder<'a> {
+        Decoder { buf, pos: 0 }
+    }
+
+    /// Bytes consumed so far
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+}
+
+impl XdrRead for Decoder<'_> {
+    fn read_slice(&mut self, n: usize) -> Result<&[u8], Error> {
+        if n > self.remaining() {
+            return Err(Error::ValueError(ValueError::new(&format!(
+                "xdr: {} bytes needed, {} remaining",
+                n,
+                self.remaining()
+            ))));
+        }
+        let start = self.pos;
+        self.pos += n;
+        Ok(&self.buf[start..start + n])
+    }
+}
+
+/// One item of a status vector
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusArg {
+    Gds(u32),
+    Number(u32),
+    String(String),
+    Interpreted(String),
+    SqlState(String),
+    /// isc_arg_warning: the items up to the end belong to warnings
+    Warning(u32),
+    /// Other item types (OS error codes); their value is a number on the wire
+    Other(u32, u32),
+}
+
+/// Status vector items up to isc_arg_end
+pub fn decode_status_vector<R: XdrRead + ?Sized>(r: &mut R) -> Result<Vec<StatusArg>, Error> {
+    let mut args = Vec::new();
+    loop {
+        let arg = match r.read_u32()? {
+            ISC_ARG_END => break,
+            ISC_ARG_GDS => StatusArg::Gds(r.read_u32()?),
+            ISC_ARG_NUMBER => StatusArg::Number(r.read_u32()?),
+            ISC_ARG_STRING => StatusArg::String(r.read_string()?),
+            ISC_ARG_INTERPRETED => StatusArg::Interpreted(r.read_string()?),
+            ISC_ARG_SQL_STATE => StatusArg::SqlState(r.read_string()?),
+            ISC_ARG_WARNING => StatusArg::Warning(r.read_u32()?),
+            // the server sends C strings as isc_arg_string, everything else as a number
+            kind => StatusArg::Other(kind, r.read_u32()?),
+        };
+        args.push(arg);
+    }
+    Ok(args)
+}
+
+/// The error described by a status vector, `None` if it reports success
+/// (warnings included)
+pub fn status_error(args: &[StatusArg]) -> Option<FirebirdError> {
+    let mut sql_code: i32 = 0;
+    let mut gds_code: u32 = 0;
+    let mut gds_codes: Vec<u32> = Vec::new();
+    let mut num_arg = 0;
+    let mut message = String::new();
+
+    for arg in args {
+        match arg {
+            StatusArg::Warning(_) => break,
+            StatusArg::Gds(code) => {
+                gds_code = *code;
+                if gds_code != 0 {
+                    if !gds_codes.contains(&gds_code) {
+                        gds_codes.push(gds_code);
+                    }
+                    match errmsgs::error_message_by_id(gds_code) {
+                        "" => message.push_str(&format!("error {}\n", gds_code)),
+                        m => message.push_str(m),
+                    }
+                    num_arg = 0;
+                }
+            }
+            StatusArg::Number(num) => {
+                if gds_code == 335544436 {
+                    sql_code = *num as i32;
+                }
+                num_arg += 1;
+                message = message.replace(&format!("@{}", num_arg), &num.to_string());
+            }
+            StatusArg::String(s) => {
+                num_arg += 1;
+                message = message.replace(&format!("@{}", num_arg), s);
+            }
+            StatusArg::Interpreted(s) => message.push_str(s),
+            StatusArg::SqlState(_) | StatusArg::Other(..) => {}
+        }
+    }
+
+    if !gds_codes.is_empty() || sql_code != 0 {
+        Some(FirebirdError::with_gds_codes(&message, sql_code, gds_codes))
+    } else {
+        None
+    }
+}
+
+/// Messages of the warnings of a status vector
+pub fn status_warnings(args: &[StatusArg]) -> Vec<String> {
+    let mut warnings: Vec<String> = Vec::new();
+    let mut num_arg = 0;
+    for arg in args {
+        match (arg, warnings.last_mut()) {
+            (StatusArg::Warning(code), _) => {
+                warnings.push(match errmsgs::error_message_by_id(*code) {
+                    "" => format!("warning {}", code),
+                    m => m.trim_end_matches("\\n").to_string(),
+                });
+                num_arg = 0;
+            }
+            (StatusArg::Number(num), Some(message)) => {
+                num_arg += 1;
+                *message = message.replace(&format!("@{}", num_arg), &num.to_string());
+            }
+            (StatusArg::String(s), Some(message)) => {
+                num_arg += 1;
+                *message = message.replace(&format!("@{}", num_arg), s);
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+/// op_response
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub handle: i32,
+    pub object_id: Vec<u8>,
+    pub data: Vec<u8>,
+    pub status: Vec<StatusArg>,
+}
+
+impl Response {
+    pub fn error(&self) -> Option<FirebirdError> {
+        status_error(&self.status)
+    }
+
+    /// Warnings sent along with a successful (or failed) request
+    pub fn warnings(&self) -> Vec<String> {
+        status_warnings(&self.status)
+    }
+}
+
+/// op_response body (after the op code)
+pub fn decode_response<R: XdrRead + ?Sized>(r: &mut R) -> Result<Response, Error> {
+    let handle = r.read_i32()?;
+    let object_id = r.read_slice(8)?.to_vec();
+    let data = r.read_opaque()?;
+    let status = decode_status_vector(r)?;
+    Ok(Response {
+        handle,
+        object_id,
+        data,
+        status,
+    })
+}
+
+/// Status and row count heading each row of an op_fetch_response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchHeader {
+    /// 0 while rows follow, 100 when the cursor is exhausted
+    pub status: u32,
+    pub count: u32,
+}
+
+/// op_fetch_response header (after the op code)
+pub fn decode_fetch_header<R: XdrRead + ?Sized>(r: &mut R) -> Result<FetchHeader, Error> {
+    Ok(FetchHeader {
+        status: r.read_u32()?,
+        count: r.read_u32()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response_bytes(status: &[StatusArg]) -> BytesMut {
+        let mut buf = BytesMut::new();
+        put_i32(&mut buf, 7);
+        buf.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 9]);
+        put_opaque(&mut buf, b"abcde");
+        for arg in status {
+            match arg {
+                StatusArg::Gds(n) => {
+                    put_u32(&mut buf, ISC_ARG_GDS);
+                    put_u32(&mut buf, *n);
+                }
+                StatusArg::Number(n) => {
+                    put_u32(&mut buf, ISC_ARG_NUMBER);
+                    put_u32(&mut buf, *n);
+                }
+                StatusArg::String(s) => {
+                    put_u32(&mut buf, ISC_ARG_STRING);
+                    put_str(&mut buf, s);
+                }
+                StatusArg::Interpreted(s) => {
+                    put_u32(&mut buf, ISC_ARG_INTERPRETED);
+                    put_str(&mut buf, s);
+                }
+                StatusArg::SqlState(s) => {
+                    put_u32(&mut buf, ISC_ARG_SQL_STATE);
+                    put_str(&mut buf, s);
+                }
+                StatusArg::Warning(n) => {
+                    put_u32(&mut buf, ISC_ARG_WARNING);
+                    put_u32(&mut buf, *n);
+                }
+                StatusArg::Other(kind, n) => {
+                    put_u32(&mut buf, *kind);
+                    put_u32(&mut buf, *n);
+                }
+            }
+        }
+        put_u32(&mut buf, ISC_ARG_END);
+        buf
+    }
+
+    #[test]
+    fn test_opaque() {
+        for (b, len) in [(&b""[..], 4), (b"a", 8), (b"abcd", 8), (b"abcde", 12)] {
+            let mut buf = BytesMut::new();
+            put_opaque(&mut buf, b);
+            assert_eq!(buf.len(), len);
+            let mut d = Decoder::new(&buf);
+            assert_eq!(d.read_opaque().unwrap(), b);
+            assert_eq!(d.remaining(), 0);
+        }
+        assert!(Decoder::new(&[0, 0, 0, 9, 1]).read_opaque().is_err());
+    }
+
+    #[test]
+    fn test_decode_response() {
+        let buf = response_bytes(&[]);
+        let mut d = Decoder::new(&buf);
+        let r = decode_response(&mut d).unwrap();
+        assert_eq!(r.handle, 7);
+        assert_eq!(r.object_id, vec![0, 0, 0, 0, 0, 0, 0, 9]);
+        assert_eq!(r.data, b"abcde");
+        assert!(r.error().is_none());
+        assert_eq!(d.remaining(), 0);
+
+        let status = vec![
+            StatusArg::Gds(335544569), // Dynamic SQL Error
+            StatusArg::Gds(335544436), // SQL error code = @1
+            StatusArg::Number(-204i32 as u32),
+            StatusArg::Gds(335544580), // Table unknown
+            StatusArg::Gds(335544882),
+            StatusArg::String("FOO".to_string()),
+            StatusArg::SqlState("42S02".to_string()),
+        ];
+        let buf = response_bytes(&status);
+        let r = decode_response(&mut Decoder::new(&buf)).unwrap();
+        assert_eq!(r.status, status);
+        let e = r.error().unwrap();
+        assert_eq!(e.sql_code, -204);
+        assert!(e.has_gds_code(335544580));
+    }
+
+    #[test]
+    fn test_decode_warnings() {
+        // success with a warning, then an OS error code: all of it is consumed
+        let status = vec![
+            StatusArg::Gds(0),
+            StatusArg::Warning(335544807), // SQL warning code = @1
+            StatusArg::Number(301),
+            StatusArg::Other(ISC_ARG_UNIX, 2),
+        ];
+        let mut buf = response_bytes(&status);
+        put_u32(&mut buf, 0xdead);
+        let mut d = Decoder::new(&buf);
+        let r = decode_response(&mut d).unwrap();
+        assert_eq!(r.status, status);
+        assert_eq!(d.read_u32().unwrap(), 0xdead);
+        assert!(r.error().is_none());
+        assert_eq!(r.warnings(), vec!["SQL warning code = 301"]);
+
+        // an error followed by warnings keeps its own message
+        let status = vec![
+            StatusArg::Gds(335544580),
+            StatusArg::Warning(335544807),
+            StatusArg::Number(1),
+        ];
+        let r = decode_response(&mut Decoder::new(&response_bytes(&status))).unwrap();
+        let e = r.error().unwrap();
+        assert_eq!(e.gds_codes, vec![335544580]);
+        assert!(!e.message.contains("warning"));
+    }
+
+    #[test]
+    fn test_decode_truncated() {
+        let buf = response_bytes(&[StatusArg::Gds(335544569)]);
+        for n in 0..buf.len() {
+            assert!(decode_response(&mut Decoder::new(&buf[..n])).is_err());
+        }
+        // unknown gds codes do not panic
+        let e = status_error(&[StatusArg::Gds(1)]).unwrap();
+        assert_eq!(e.message, "error 1\n");
+    }
+
+    #[test]
+    fn test_decode_fetch_header() {
+        let mut buf = BytesMut::new();
+        put_u32(&mut buf, 0);
+        put_u32(&mut buf, 1);
+        let h = decode_fetch_header(&mut Decoder::new(&buf)).unwrap();
+        assert_eq!(
+            h,
+            FetchHeader {
+                status: 0,
+                count: 1
+            }
+        );
+    }
+}
@@ -20,13 +20,17 @@
 // SOFTWARE.
 
 #![allow(dead_code)]
+use super::array::Array;
 use super::cellvalue::CellValue;
 use super::decfloat;
 use super::error::ValueError;
 use super::*;
+#[cfg(feature = "charsets")]
+use super::legacy_charset::TextDecoder;
 use maplit::hashmap;
 use rust_decimal;
 
+#[derive(Clone)]
 pub(crate) struct XSQLVar {
     pub sqltype: u32,
     pub sqlscale: i32,
@@ -37,6 +41,9 @@ pub(crate) struct XSQLVar {
     pub relname: String,
     pub ownname: String,
     pub aliasname: String,
+    // text of a NONE column in a legacy codepage
+    #[cfg(feature = "charsets")]
+    pub legacy_decoder: Option<TextDecoder>,
 }
 
 impl XSQLVar {
@@ -51,6 +58,8 @@ impl XSQLVar {
             relname: "".to_string(),
             ownname: "".to_string(),
             aliasname: "".to_string(),
+            #[cfg(feature = "charsets")]
+            legacy_decoder: None,
         }
     }
     pub fn io_length(&self) -> isize {
@@ -85,10 +94,31 @@ impl XSQLVar {
         }
     }
 
+    fn text(&self, raw_value: &[u8]) -> String {
+        #[cfg(feature = "charsets")]
+        if let Some(decoder) = &self.legacy_decoder {
+            return decoder.decode(raw_value);
+        }
+        utils::bytes_to_str(raw_value)
+    }
+
+    /// Contents of a text blob of this column, as UTF-8
+    pub fn blob_text(&self, blob: Vec<u8>) -> Vec<u8> {
+        #[cfg(feature = "charsets")]
+        if let Some(decoder) = &self.legacy_decoder {
+            return decoder.decode(&blob).into_bytes();
+        }
+        blob
+    }
+
     pub fn value(&self, raw_value: &[u8]) -> Result<CellValue, ValueError> {
         match self.sqltype {
-            SQL_TYPE_TEXT => Ok(CellValue::Text(utils::bytes_to_rtrim_str(raw_value))),
-            SQL_TYPE_VARYING => Ok(CellValue::Varying(utils::bytes_to_str(raw_value))),
+            // binary, e.g. the CHAR(16) of GEN_UUID()
+            SQL_TYPE_TEXT | SQL_TYPE_VARYING if self.sqlsubtype as u8 == CHARSET_OCTETS => {
+                Ok(CellValue::Octets(raw_value.to_vec()))
+            }
+            SQL_TYPE_TEXT => Ok(CellValue::Text(self.text(raw_value).trim_end().to_string())),
+            SQL_TYPE_VARYING => Ok(CellValue::Varying(self.text(raw_value))),
             SQL_TYPE_SHORT => Ok(CellValue::Short(utils::bytes_to_bint32(raw_value) as i16)),
             SQL_TYPE_LONG => Ok(CellValue::Long(utils::bytes_to_bint32(raw_value))),
             SQL_TYPE_INT64 => Ok(if self.sqlscale < 0 {
@@ -104,28 +134,23 @@ impl XSQLVar {
             } else {
                 CellValue::Int64(utils::bytes_to_bint64(raw_value))
             }),
-            SQL_TYPE_INT128 => Ok(if self.sqlscale < 0 {
-                CellValue::Decimal(rust_decimal::Decimal::new(
-                    utils::bytes_to_bint64(raw_value),
-                    (self.sqlscale * -1) as u32,
-                ))
-            } else if self.sqlscale > 0 {
-                CellValue::Decimal(rust_decimal::Decimal::new(
-                    (utils::bytes_to_bint128(raw_value) as i64) * (self.sqlscale as i64),
-                    0,
-                ))
-            } else {
-                CellValue::Int128(utils::bytes_to_bint128(raw_value))
-            }),
-            SQL_TYPE_DATE => Ok(CellValue::Date(utils::bytes_to_naive_date(raw_value))),
-            SQL_TYPE_TIME => Ok(CellValue::Time(utils::bytes_to_naive_time(raw_value))),
+            SQL_TYPE_INT128 => {
+                let v = utils::bytes_to_bint128(raw_value);
+                if self.sqlscale == 0 {
+                    Ok(CellValue::Int128(v))
+                } else {
+                    int128_to_decimal(v, self.sqlscale).map(CellValue::Decimal)
+                }
+            }
+            SQL_TYPE_DATE => Ok(CellValue::Date(utils::bytes_to_naive_date(raw_value)?)),
+            SQL_TYPE_TIME => Ok(CellValue::Time(utils::bytes_to_naive_time(raw_value)?)),
             SQL_TYPE_TIMESTAMP => Ok(CellValue::TimeStamp(utils::bytes_to_naive_date_time(
                 raw_value,
-            ))),
-            SQL_TYPE_TIME_TZ | SQL_TYPE_TIME_TZ_EX => Ok(CellValue::TimeTz(utils::bytes_to_time_tz(raw_value))),
+            )?)),
+            SQL_TYPE_TIME_TZ | SQL_TYPE_TIME_TZ_EX => Ok(CellValue::TimeTz(utils::bytes_to_time_tz(raw_value)?)),
             SQL_TYPE_TIMESTAMP_TZ | SQL_TYPE_TIMESTAMP_TZ_EX => Ok(CellValue::TimeStampTz(utils::bytes_to_date_time_tz(
                 raw_value,
-            ))),
+            )?)),
             SQL_TYPE_FLOAT => Ok(CellValue::Float(utils::bytes_to_f32(raw_value))),
             SQL_TYPE_DOUBLE => Ok(CellValue::Double(utils::bytes_to_f64(raw_value))),
             SQL_TYPE_BOOLEAN => Ok(CellValue::Boolean(raw_value[0] != 0)),
@@ -134,6 +159,7 @@ impl XSQLVar {
             } else {
                 CellValue::BlobBinary(raw_value.to_vec())
             }),
+            SQL_TYPE_ARRAY => Ok(CellValue::Array(Array::unloaded(raw_value.to_vec()))),
             SQL_TYPE_DEC_FIXED => Ok(CellValue::Decimal(decfloat::decimal_fixed_to_decimal(
                 raw_value,
                 self.sqlscale,
@@ -151,3 +177,101 @@ impl XSQLVar {
         }
     }
 }
+
+/// NUMERIC(38, -`scale`) as a `Decimal`, an error when it needs more than
+/// the 96 bit coefficient of a `Decimal`
+fn int128_to_decimal(v: i128, scale: i32) -> Result<rust_decimal::Decimal, ValueError> {
+    let (v, scale) = if scale > 0 {
+        let m = 10i128.checked_pow(scale as u32);
+        (m.and_then(|m| v.checked_mul(m)), 0)
+    } else {
+        (Some(v), -scale as u32)
+    };
+    v.and_then(|v| rust_decimal::Decimal::try_from_i128_with_scale(v, scale).ok())
+        .ok_or_else(|| {
+            ValueError::new(&format!(
+                "NUMERIC(38, {}) value is out of the range of Decimal",
+                scale
+            ))
+        })
+}
+
+/// Output message BLR for fetching rows described by `xsqlda`
+pub(crate) fn calc_blr(xsqlda: &[XSQLVar]) -> Vec<u8> {
+    let ln = xsqlda.len() * 2;
+    let mut blr: Vec<u8> = vec![5, 2, 4, 0, (ln & 255) as u8, (ln >> 8) as u8];
+
+    for x in xsqlda {
+        let map = hashmap! {
+            SQL_TYPE_VARYING => vec![37, (x.sqllen & 255) as u8, (x.sqllen >> 8) as u8],
+            SQL_TYPE_TEXT => vec![14, (x.sqllen & 255) as u8, (x.sqllen >> 8) as u8],
+            SQL_TYPE_LONG => vec![8, x.sqlscale as u8],
+            SQL_TYPE_SHORT => vec![7, x.sqlscale as u8],
+            SQL_TYPE_INT64 => vec![16, x.sqlscale as u8],
+            SQL_TYPE_INT128 => vec![26, x.sqlscale as u8],
+            SQL_TYPE_QUAD => vec![9, x.sqlscale as u8],
+            SQL_TYPE_DEC_FIXED => vec![26, x.sqlscale as u8],
+            SQL_TYPE_DOUBLE => vec![27],
+            SQL_TYPE_FLOAT => vec![10],
+            SQL_TYPE_D_FLOAT => vec![11],
+            SQL_TYPE_DATE => vec![12],
+            SQL_TYPE_TIME => vec![13],
+            SQL_TYPE_TIMESTAMP => vec![35],
+            SQL_TYPE_BLOB => vec![9, 0],
+            SQL_TYPE_ARRAY => vec![9, 0],
+            SQL_TYPE_BOOLEAN => vec![23],
+            SQL_TYPE_DEC64 => vec![24],
+            SQL_TYPE_DEC128 => vec![25],
+            SQL_TYPE_TIME_TZ => vec![28],
+            SQL_TYPE_TIMESTAMP_TZ => vec![29],
+        };
+        blr.extend(&map[&x.sqltype]);
+        blr.extend(vec![7, 0]);
+    }
+    blr.extend(vec![255, 76]);
+
+    blr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_int128_value() {
+        let mut x = XSQLVar::new();
+        x.sqltype = SQL_TYPE_INT128;
+        x.sqllen = 16;
+        let big = i128::MAX;
+        let b = utils::bint128_to_bytes(big);
+        assert_eq!(x.value(&b).unwrap(), CellValue::Int128(big));
+        let b = utils::bint128_to_bytes(-12345678901234567890123);
+        assert_eq!(
+            x.value(&b).unwrap(),
+            CellValue::Int128(-12345678901234567890123)
+        );
+
+        // NUMERIC(38, 4)
+        x.sqlscale = -4;
+        let b = utils::bint128_to_bytes(-123456789012345678901234);
+        assert_eq!(
+            x.value(&b).unwrap(),
+            CellValue::Decimal("-12345678901234567890.1234".parse().unwrap())
+        );
+        // past the 96 bits of a Decimal
+        let b = utils::bint128_to_bytes(big);
+        assert!(x.value(&b).is_err());
+    }
+
+    #[test]
+    fn test_octets_value() {
+        let mut x = XSQLVar::new();
+        x.sqltype = SQL_TYPE_TEXT;
+        x.sqllen = 16;
+        x.sqlsubtype = 1; // OCTETS
+        let b = [0xff, 0x20, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 0x20, 0x20];
+        assert_eq!(x.value(&b).unwrap(), CellValue::Octets(b.to_vec()));
+        x.sqlsubtype = 4; // UTF8
+        assert_eq!(x.value(b"abc  ").unwrap(), CellValue::Text("abc".to_string()));
+    }
+}